@@ -3,6 +3,7 @@ mod inputs;
 mod mutation;
 mod outputs;
 mod query;
+mod scalars;
 
 use crate::graph::mutation::MutationRoot;
 use crate::graph::query::QueryRoot;
@@ -10,7 +11,7 @@ use app::application;
 use app::dataloader;
 use app::domain::user::AuthUser;
 use app::internal_api;
-use app::AppError;
+use app::{AppError, AppResult};
 use convert_case::{Case, Casing};
 use juniper::{EmptySubscription, FieldError, RootNode};
 use strum_macros::Display as StrumDisplay;
@@ -19,6 +20,7 @@ pub struct Context {
     pub auth_user: AuthUser,
     pub work_app: application::work::Application,
     pub nft_app: application::nft::Application,
+    pub sales_app: application::sales::Application,
     pub user_app: application::user::Application,
     pub thumbnail_by_work_loader: dataloader::thumbnail_by_work::Loader,
     pub asset721_by_work_loader: dataloader::asset721_by_work::Loader,
@@ -29,16 +31,19 @@ pub struct Context {
 impl juniper::Context for Context {}
 
 impl Context {
-    pub async fn new(auth_user: AuthUser) -> Self {
+    pub async fn new(auth_user: AuthUser) -> AppResult<Self> {
         let work_app =
             application::work::Application::new(auth_user.user_id().clone().unwrap_or_default())
                 .await;
         let nft_app =
             application::nft::Application::new(auth_user.user_id().clone().unwrap_or_default())
-                .await;
+                .await?;
+        let sales_app =
+            application::sales::Application::new(auth_user.user_id().clone().unwrap_or_default())
+                .await?;
         let user_app =
             application::user::Application::new(auth_user.user_id().clone().unwrap_or_default())
-                .await;
+                .await?;
 
         let thumbnail_by_work_loader: dataloader::thumbnail_by_work::Loader =
             dataloader::thumbnail_by_work::Batcher::new_loader();
@@ -49,16 +54,17 @@ impl Context {
 
         let internal_api = internal_api::Client::new();
 
-        Self {
+        Ok(Self {
             auth_user,
             work_app,
             nft_app,
+            sales_app,
             user_app,
             thumbnail_by_work_loader,
             asset721_by_work_loader,
             asset1155_by_work_loader,
             internal_api,
-        }
+        })
     }
 }
 
@@ -75,6 +81,7 @@ pub enum FieldErrorCode {
     NotFound,
     Forbidden,
     Internal,
+    RateLimited,
 }
 
 pub struct FieldErrorWithCode {
@@ -92,6 +99,7 @@ impl From<AppError> for FieldErrorWithCode {
                 AppError::Forbidden => FieldErrorCode::Forbidden,
                 AppError::NotFound => FieldErrorCode::NotFound,
                 AppError::Internal(_) => FieldErrorCode::Internal,
+                AppError::RateLimited(_) => FieldErrorCode::RateLimited,
             },
         }
     }
@@ -5,6 +5,7 @@ pub enum WorkStatus {
     Prepare,
     PublishNFT,
     SellOrder,
+    Sold,
 }
 
 impl WorkStatus {
@@ -13,6 +14,7 @@ impl WorkStatus {
             WorkStatus::Prepare => domain::work::WorkStatus::Prepare,
             WorkStatus::PublishNFT => domain::work::WorkStatus::PublishNFT,
             WorkStatus::SellOrder => domain::work::WorkStatus::SellOrder,
+            WorkStatus::Sold => domain::work::WorkStatus::Sold,
         }
     }
 }
@@ -23,6 +25,103 @@ impl From<domain::work::WorkStatus> for WorkStatus {
             domain::work::WorkStatus::Prepare => WorkStatus::Prepare,
             domain::work::WorkStatus::PublishNFT => WorkStatus::PublishNFT,
             domain::work::WorkStatus::SellOrder => WorkStatus::SellOrder,
+            domain::work::WorkStatus::Sold => WorkStatus::Sold,
+        }
+    }
+}
+
+#[derive(Debug, GraphQLEnum)]
+pub enum OfferStatus {
+    Open,
+    Accepted,
+    Rejected,
+    Closed,
+}
+
+impl From<domain::offer::OfferStatus> for OfferStatus {
+    fn from(data: domain::offer::OfferStatus) -> Self {
+        match data {
+            domain::offer::OfferStatus::Open => OfferStatus::Open,
+            domain::offer::OfferStatus::Accepted => OfferStatus::Accepted,
+            domain::offer::OfferStatus::Rejected => OfferStatus::Rejected,
+            domain::offer::OfferStatus::Closed => OfferStatus::Closed,
+        }
+    }
+}
+
+#[derive(Debug, GraphQLEnum)]
+pub enum PinKind {
+    Image,
+    Animation,
+    Metadata,
+}
+
+impl From<domain::pin::PinKind> for PinKind {
+    fn from(data: domain::pin::PinKind) -> Self {
+        match data {
+            domain::pin::PinKind::Image => PinKind::Image,
+            domain::pin::PinKind::Animation => PinKind::Animation,
+            domain::pin::PinKind::Metadata => PinKind::Metadata,
+        }
+    }
+}
+
+#[derive(Debug, GraphQLEnum)]
+pub enum PinStatus {
+    Queued,
+    Pinning,
+    Pinned,
+    Failed,
+}
+
+impl From<domain::pin::PinStatus> for PinStatus {
+    fn from(data: domain::pin::PinStatus) -> Self {
+        match data {
+            domain::pin::PinStatus::Queued => PinStatus::Queued,
+            domain::pin::PinStatus::Pinning => PinStatus::Pinning,
+            domain::pin::PinStatus::Pinned => PinStatus::Pinned,
+            domain::pin::PinStatus::Failed => PinStatus::Failed,
+        }
+    }
+}
+
+#[derive(Debug, GraphQLEnum)]
+pub enum RevenueGroup {
+    Work,
+    Publisher,
+    Month,
+}
+
+impl RevenueGroup {
+    pub fn domain(&self) -> domain::sale::RevenueGroup {
+        match self {
+            RevenueGroup::Work => domain::sale::RevenueGroup::Work,
+            RevenueGroup::Publisher => domain::sale::RevenueGroup::Publisher,
+            RevenueGroup::Month => domain::sale::RevenueGroup::Month,
+        }
+    }
+}
+
+#[derive(Debug, GraphQLEnum)]
+pub enum TokenSchema {
+    ERC721,
+    ERC1155,
+}
+
+impl TokenSchema {
+    pub fn domain(&self) -> domain::market::TokenSchema {
+        match self {
+            TokenSchema::ERC721 => domain::market::TokenSchema::Erc721,
+            TokenSchema::ERC1155 => domain::market::TokenSchema::Erc1155,
+        }
+    }
+}
+
+impl From<domain::market::TokenSchema> for TokenSchema {
+    fn from(data: domain::market::TokenSchema) -> Self {
+        match data {
+            domain::market::TokenSchema::Erc721 => TokenSchema::ERC721,
+            domain::market::TokenSchema::Erc1155 => TokenSchema::ERC1155,
         }
     }
 }
@@ -1,3 +1,7 @@
+use crate::graph::enums::TokenSchema;
+use crate::graph::scalars::TokenAmount;
+use app::domain;
+
 #[derive(Debug, GraphQLInputObject)]
 pub struct RegisterUserInput {
     pub email: String,
@@ -16,17 +20,19 @@ pub struct ImportThumbnailInput {
     pub file_name: String,
 }
 
+/// gs_pathを省くと、作品の動画からポスターフレームとプレビューを作る
 #[derive(Debug, GraphQLInputObject)]
 pub struct CreateERC721Input {
     pub work_id: String,
-    pub gs_path: String,
+    pub gs_path: Option<String>,
     pub use_ipfs: bool,
 }
 
+/// gs_pathを省くと、作品の動画からポスターフレームとプレビューを作る
 #[derive(Debug, GraphQLInputObject)]
 pub struct CreateERC1155Input {
     pub work_id: String,
-    pub gs_path: String,
+    pub gs_path: Option<String>,
     pub amount: i32,
     pub use_ipfs: bool,
 }
@@ -34,16 +40,29 @@ pub struct CreateERC1155Input {
 #[derive(Debug, GraphQLInputObject)]
 pub struct SellERC721Input {
     pub work_id: String,
-    pub ether: f64,
+    pub ether: TokenAmount,
 }
 
 #[derive(Debug, GraphQLInputObject)]
 pub struct SellERC1155Input {
     pub work_id: String,
-    pub ether: f64,
+    pub ether: TokenAmount,
     pub amount: i32,
 }
 
+#[derive(Debug, GraphQLInputObject)]
+pub struct CancelSellOrderInput {
+    pub work_id: String,
+}
+
+#[derive(Debug, GraphQLInputObject)]
+pub struct UpdateSellOrderInput {
+    pub work_id: String,
+    pub ether: TokenAmount,
+    /// 出品の期限（unix時間の秒）。未指定ならマーケットプレイスのデフォルト
+    pub expires_at: Option<f64>,
+}
+
 #[derive(Debug, GraphQLInputObject)]
 pub struct TransferERC721Input {
     pub work_id: String,
@@ -56,3 +75,113 @@ pub struct TransferERC1155Input {
     pub to_address: String,
     pub amount: i32,
 }
+
+#[derive(Debug, GraphQLInputObject)]
+pub struct BuyNFTInput {
+    pub contract_address: String,
+    pub token_id: String,
+    /// 支払ってよい上限（注文全体の価格）
+    pub max_price: TokenAmount,
+}
+
+#[derive(Debug, GraphQLInputObject)]
+pub struct AcceptOfferInput {
+    pub offer_id: String,
+}
+
+#[derive(Debug, GraphQLInputObject)]
+pub struct RejectOfferInput {
+    pub offer_id: String,
+}
+
+#[derive(Debug, GraphQLInputObject)]
+pub struct UpdateWorkRoyaltyInput {
+    pub work_id: String,
+    pub receiver: String,
+    /// 1/10000単位（500で5%）
+    pub basis_points: i32,
+}
+
+#[derive(Debug, GraphQLInputObject)]
+pub struct UpdateCollectionRoyaltyInput {
+    pub schema: TokenSchema,
+    pub receiver: String,
+    /// 1/10000単位（500で5%）
+    pub basis_points: i32,
+}
+
+#[derive(Debug, GraphQLInputObject)]
+pub struct WorkAttributeInput {
+    pub trait_type: String,
+    pub value: String,
+    pub display_type: Option<String>,
+}
+
+impl WorkAttributeInput {
+    pub fn domain(self) -> domain::work::WorkAttribute {
+        domain::work::WorkAttribute {
+            trait_type: self.trait_type,
+            value: self.value,
+            display_type: self.display_type,
+        }
+    }
+}
+
+/// Handlebarsの書式で `{{work.title}}` などの項目が使える
+#[derive(Debug, GraphQLInputObject)]
+pub struct UpdateMetadataTemplateInput {
+    pub schema: TokenSchema,
+    pub name: String,
+    pub description: String,
+    pub external_url: String,
+    pub attributes: Vec<WorkAttributeInput>,
+}
+
+impl UpdateMetadataTemplateInput {
+    pub fn domain(self) -> domain::template::MetadataTemplate {
+        domain::template::MetadataTemplate {
+            name: self.name,
+            description: self.description,
+            external_url: self.external_url,
+            attributes: self
+                .attributes
+                .into_iter()
+                .map(WorkAttributeInput::domain)
+                .collect(),
+        }
+    }
+}
+
+/// amountはERC1155の発行数。gs_pathを省くと作品の動画から画像を作る
+#[derive(Debug, GraphQLInputObject)]
+pub struct StageRevealInput {
+    pub schema: TokenSchema,
+    pub work_id: String,
+    pub gs_path: Option<String>,
+    pub amount: Option<i32>,
+    pub use_ipfs: bool,
+}
+
+#[derive(Debug, GraphQLInputObject)]
+pub struct UpdateRevealPlaceholderInput {
+    pub schema: TokenSchema,
+    pub name: String,
+    pub description: String,
+    pub image_url: String,
+    pub attributes: Vec<WorkAttributeInput>,
+}
+
+impl UpdateRevealPlaceholderInput {
+    pub fn domain(self) -> domain::reveal::Placeholder {
+        domain::reveal::Placeholder {
+            name: self.name,
+            description: self.description,
+            image_url: self.image_url,
+            attributes: self
+                .attributes
+                .into_iter()
+                .map(WorkAttributeInput::domain)
+                .collect(),
+        }
+    }
+}
@@ -1,9 +1,14 @@
+use crate::graph::enums::TokenSchema;
 use crate::graph::inputs::{
-    CreateERC1155Input, CreateERC721Input, ImportThumbnailInput, ImportWorkInput,
-    RegisterUserInput, SellERC1155Input, SellERC721Input, TransferERC1155Input,
-    TransferERC721Input,
+    AcceptOfferInput, BuyNFTInput, CancelSellOrderInput, CreateERC1155Input, CreateERC721Input,
+    ImportThumbnailInput, ImportWorkInput, RegisterUserInput, RejectOfferInput, SellERC1155Input,
+    SellERC721Input, StageRevealInput, TransferERC1155Input, TransferERC721Input,
+    UpdateCollectionRoyaltyInput, UpdateMetadataTemplateInput, UpdateRevealPlaceholderInput,
+    UpdateSellOrderInput, UpdateWorkRoyaltyInput,
+};
+use crate::graph::outputs::{
+    MetadataTemplate, Placeholder, PreSignUploadUrl, Royalty, StagedMetadata,
 };
-use crate::graph::outputs::PreSignUploadUrl;
 use crate::graph::Context;
 use crate::graph::FieldErrorWithCode;
 use app::AppError;
@@ -163,7 +168,7 @@ impl MutationRoot {
 
         context
             .nft_app
-            .sell_erc721(input.work_id, input.ether)
+            .sell_erc721(input.work_id, input.ether.0)
             .await
             .map_err(FieldErrorWithCode::from)?;
 
@@ -178,7 +183,51 @@ impl MutationRoot {
 
         context
             .nft_app
-            .sell_erc1155(input.work_id, input.ether)
+            .sell_erc1155(
+                input.work_id,
+                input.ether.0,
+                u64::try_from(input.amount).unwrap_or_default(),
+            )
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(true)
+    }
+
+    async fn cancel_sell_order(
+        context: &Context,
+        input: CancelSellOrderInput,
+    ) -> FieldResult<bool> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        context
+            .nft_app
+            .cancel_sell_order(input.work_id)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(true)
+    }
+
+    async fn update_sell_order(
+        context: &Context,
+        input: UpdateSellOrderInput,
+    ) -> FieldResult<bool> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        context
+            .nft_app
+            .update_sell_order(
+                input.work_id,
+                input.ether.0,
+                input.expires_at.map(|v| v as i64),
+            )
             .await
             .map_err(FieldErrorWithCode::from)?;
 
@@ -208,10 +257,241 @@ impl MutationRoot {
 
         context
             .nft_app
-            .transfer_erc1155(input.work_id, input.to_address)
+            .transfer_erc1155(
+                input.work_id,
+                input.to_address,
+                u64::try_from(input.amount).unwrap_or_default(),
+            )
             .await
             .map_err(FieldErrorWithCode::from)?;
 
         Ok(true)
     }
+
+    async fn buy_nft(context: &Context, input: BuyNFTInput) -> FieldResult<bool> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        context
+            .nft_app
+            .buy_nft(input.contract_address, input.token_id, input.max_price.0)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(true)
+    }
+
+    async fn accept_offer(context: &Context, input: AcceptOfferInput) -> FieldResult<bool> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        context
+            .nft_app
+            .accept_offer(input.offer_id)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(true)
+    }
+
+    async fn reject_offer(context: &Context, input: RejectOfferInput) -> FieldResult<bool> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        context
+            .nft_app
+            .reject_offer(input.offer_id)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(true)
+    }
+
+    async fn update_work_royalty(
+        context: &Context,
+        input: UpdateWorkRoyaltyInput,
+    ) -> FieldResult<Royalty> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let basis_points = u64::try_from(input.basis_points).map_err(|_e| {
+            FieldErrorWithCode::from(AppError::BadRequest(format!(
+                "invalid basis points: {}",
+                input.basis_points
+            )))
+        })?;
+        let royalty = context
+            .nft_app
+            .update_work_royalty(input.work_id, input.receiver, basis_points)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(Royalty::from(royalty))
+    }
+
+    async fn update_collection_royalty(
+        context: &Context,
+        input: UpdateCollectionRoyaltyInput,
+    ) -> FieldResult<Royalty> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let basis_points = u64::try_from(input.basis_points).map_err(|_e| {
+            FieldErrorWithCode::from(AppError::BadRequest(format!(
+                "invalid basis points: {}",
+                input.basis_points
+            )))
+        })?;
+        let royalty = context
+            .nft_app
+            .update_collection_royalty(input.schema.domain(), input.receiver, basis_points)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(Royalty::from(royalty))
+    }
+
+    async fn update_metadata_template(
+        context: &Context,
+        input: UpdateMetadataTemplateInput,
+    ) -> FieldResult<MetadataTemplate> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let schema = input.schema.domain();
+        let template = input.domain();
+        context
+            .nft_app
+            .update_metadata_template(schema, template.clone())
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(MetadataTemplate::from(template))
+    }
+
+    /// 公開前にすると、APIから配信するmetadataがプレースホルダーになる
+    async fn update_collection_visibility(
+        context: &Context,
+        schema: TokenSchema,
+        hidden: bool,
+    ) -> FieldResult<bool> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        context
+            .nft_app
+            .update_collection_visibility(schema.domain(), hidden)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(hidden)
+    }
+
+    /// 公開前のコレクションで、mintの前に公開後のmetadataを準備する
+    async fn stage_reveal(
+        context: &Context,
+        input: StageRevealInput,
+    ) -> FieldResult<StagedMetadata> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let item = context
+            .nft_app
+            .stage_reveal(
+                input.work_id,
+                input.schema.domain(),
+                input.gs_path,
+                input.amount.unwrap_or(1) as u32,
+                input.use_ipfs,
+            )
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(StagedMetadata::from(item))
+    }
+
+    /// 準備した公開後のmetadataから証明用のハッシュを確定し、コントラクトに書き込んで返す
+    /// 以降は作品を追加できず、公開前のコレクションのmintはこの後に行う
+    async fn commit_provenance(context: &Context, schema: TokenSchema) -> FieldResult<String> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let hash = context
+            .nft_app
+            .commit_provenance(schema.domain())
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(hash)
+    }
+
+    async fn update_reveal_placeholder(
+        context: &Context,
+        input: UpdateRevealPlaceholderInput,
+    ) -> FieldResult<Placeholder> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let schema = input.schema.domain();
+        let placeholder = input.domain();
+        context
+            .nft_app
+            .update_reveal_placeholder(schema, placeholder.clone())
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(Placeholder::from(placeholder))
+    }
+
+    /// コントラクトのtokenURIをAPIのmetadataの配信に向け、設定したURIを返す
+    async fn use_hosted_metadata(context: &Context, schema: TokenSchema) -> FieldResult<String> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let base_uri = context
+            .nft_app
+            .use_hosted_metadata(schema.domain())
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(base_uri)
+    }
+
+    /// 売上の台帳のCSVをS3に出力し、ダウンロード用のURLを返す
+    async fn export_sales_csv(context: &Context, month: Option<String>) -> FieldResult<String> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let url = context
+            .sales_app
+            .export_csv(month)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(url)
+    }
 }
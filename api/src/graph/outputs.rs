@@ -2,6 +2,11 @@ pub mod asset;
 pub mod user;
 pub mod work;
 
+use crate::graph::enums::TokenSchema;
+use crate::graph::outputs::work::WorkAttribute;
+use app::domain;
+use app::open_sea::metadata::Metadata;
+
 #[derive(Debug, GraphQLObject)]
 pub struct PreSignUploadUrl {
     pub url: String,
@@ -13,3 +18,182 @@ pub struct OwnNft {
     pub erc721: bool,
     pub erc1155: bool,
 }
+
+/// 金額は通貨の単位の10進数の文字列
+#[derive(Debug, GraphQLObject)]
+pub struct Revenue {
+    pub key: String,
+    pub currency: String,
+    pub sales_count: i32,
+    pub volume: String,
+    pub fees: String,
+    pub royalties: String,
+    pub proceeds: String,
+}
+
+impl From<domain::sale::Revenue> for Revenue {
+    fn from(data: domain::sale::Revenue) -> Self {
+        Self {
+            key: data.key,
+            currency: data.currency,
+            sales_count: TryFrom::try_from(data.sales_count).unwrap_or_default(),
+            volume: data.volume,
+            fees: data.fees,
+            royalties: data.royalties,
+            proceeds: data.proceeds,
+        }
+    }
+}
+
+/// 作品のトークンの保有者。amountはERC721では1
+#[derive(Debug, GraphQLObject)]
+pub struct Ownership {
+    pub schema: TokenSchema,
+    pub contract_address: String,
+    pub token_id: String,
+    /// チェックサム付きのアドレス
+    pub owner_address: String,
+    pub amount: i32,
+    pub updated_at: f64,
+}
+
+impl From<domain::ownership::Ownership> for Ownership {
+    fn from(data: domain::ownership::Ownership) -> Self {
+        Self {
+            schema: TokenSchema::from(data.schema),
+            contract_address: data.contract_address,
+            token_id: data.token_id,
+            owner_address: data.owner_address,
+            amount: TryFrom::try_from(data.amount).unwrap_or(i32::MAX),
+            updated_at: data.updated_at as f64,
+        }
+    }
+}
+
+#[derive(Debug, GraphQLObject)]
+pub struct Royalty {
+    /// チェックサム付きのアドレス
+    pub receiver: String,
+    pub basis_points: i32,
+}
+
+impl From<domain::royalty::Royalty> for Royalty {
+    fn from(data: domain::royalty::Royalty) -> Self {
+        Self {
+            receiver: data.receiver,
+            basis_points: TryFrom::try_from(data.basis_points).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, GraphQLObject)]
+pub struct MetadataTemplate {
+    pub name: String,
+    pub description: String,
+    pub external_url: String,
+    pub attributes: Vec<WorkAttribute>,
+}
+
+impl From<domain::template::MetadataTemplate> for MetadataTemplate {
+    fn from(data: domain::template::MetadataTemplate) -> Self {
+        Self {
+            name: data.name,
+            description: data.description,
+            external_url: data.external_url,
+            attributes: data
+                .attributes
+                .into_iter()
+                .map(WorkAttribute::from)
+                .collect(),
+        }
+    }
+}
+
+/// mint時に生成されるmetadata。imageとanimation_urlはアップロード時に決まるため含まない
+#[derive(Debug, GraphQLObject)]
+pub struct MetadataPreview {
+    pub name: String,
+    pub description: String,
+    pub external_url: String,
+    pub background_color: Option<String>,
+    pub attributes: Vec<WorkAttribute>,
+    pub seller_fee_basis_points: Option<i32>,
+    pub fee_recipient: Option<String>,
+    /// metadataのJSONそのもの
+    pub json: String,
+}
+
+impl From<Metadata> for MetadataPreview {
+    fn from(data: Metadata) -> Self {
+        let json = data.to_json().unwrap_or_default();
+        Self {
+            name: data.name,
+            description: data.description,
+            external_url: data.external_url,
+            background_color: data.background_color,
+            attributes: data
+                .attributes
+                .into_iter()
+                .map(|v| WorkAttribute {
+                    value: v.value_string(),
+                    trait_type: v.trait_type,
+                    display_type: v.display_type,
+                })
+                .collect(),
+            seller_fee_basis_points: data
+                .seller_fee_basis_points
+                .and_then(|v| TryFrom::try_from(v).ok()),
+            fee_recipient: data.fee_recipient,
+            json,
+        }
+    }
+}
+
+#[derive(Debug, GraphQLObject)]
+pub struct Placeholder {
+    pub name: String,
+    pub description: String,
+    pub image_url: String,
+    pub attributes: Vec<WorkAttribute>,
+}
+
+impl From<domain::reveal::Placeholder> for Placeholder {
+    fn from(data: domain::reveal::Placeholder) -> Self {
+        Self {
+            name: data.name,
+            description: data.description,
+            image_url: data.image_url,
+            attributes: data
+                .attributes
+                .into_iter()
+                .map(WorkAttribute::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, GraphQLObject)]
+pub struct RevealState {
+    pub hidden: bool,
+    pub placeholder: Option<Placeholder>,
+    /// 公開前に確定した、公開後のmetadata全体のハッシュ
+    pub provenance_hash: Option<String>,
+    pub revealed_at: Option<f64>,
+    /// 公開されているmetadataがハッシュと一致するか。公開前はnull
+    pub verified: Option<bool>,
+}
+
+#[derive(Debug, GraphQLObject)]
+pub struct StagedMetadata {
+    pub work_id: String,
+    pub metadata_hash: String,
+}
+
+impl From<domain::reveal::RevealItem> for StagedMetadata {
+    fn from(data: domain::reveal::RevealItem) -> Self {
+        Self {
+            work_id: data.work_id,
+            metadata_hash: data.metadata_hash,
+        }
+    }
+}
@@ -1,5 +1,9 @@
+use crate::graph::scalars::TokenAmount;
 use crate::graph::Context;
+use crate::FieldErrorWithCode;
 use app::domain;
+use core::convert::TryFrom;
+use juniper::FieldResult;
 
 #[derive(Debug, Clone)]
 pub struct Asset721 {
@@ -12,6 +16,10 @@ impl Asset721 {
         self.data.work_id.to_owned()
     }
 
+    fn chain_id(&self) -> i32 {
+        TryFrom::try_from(self.data.chain_id.to_owned()).unwrap_or_default()
+    }
+
     fn address(&self) -> String {
         self.data.contract_address.to_owned()
     }
@@ -40,12 +48,40 @@ impl Asset721 {
         self.data.permalink.to_owned()
     }
 
-    fn usd_price(&self) -> f64 {
+    fn usd_price(&self) -> String {
         self.data.usd_price.to_owned()
     }
 
-    fn eth_price(&self) -> f64 {
-        self.data.eth_price.to_owned()
+    fn eth_price(&self) -> TokenAmount {
+        self.data.eth_price.into()
+    }
+
+    fn currency(&self) -> String {
+        self.data.currency.to_owned()
+    }
+
+    fn listing_price(&self) -> Option<String> {
+        self.data.listing_price.to_owned()
+    }
+
+    fn last_sale_price(&self) -> Option<String> {
+        self.data.last_sale_price.to_owned()
+    }
+
+    async fn price_history(
+        &self,
+        context: &Context,
+        limit: Option<i32>,
+    ) -> FieldResult<Vec<PriceSnapshot>> {
+        let snapshots = context
+            .nft_app
+            .price_history(self.data.work_id.to_owned(), limit)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+        Ok(snapshots
+            .iter()
+            .map(|v| PriceSnapshot::from(v.to_owned()))
+            .collect())
     }
 }
 
@@ -66,6 +102,10 @@ impl Asset1155 {
         self.data.work_id.to_owned()
     }
 
+    fn chain_id(&self) -> i32 {
+        TryFrom::try_from(self.data.chain_id.to_owned()).unwrap_or_default()
+    }
+
     fn address(&self) -> String {
         self.data.contract_address.to_owned()
     }
@@ -94,12 +134,45 @@ impl Asset1155 {
         self.data.permalink.to_owned()
     }
 
-    fn usd_price(&self) -> f64 {
+    fn usd_price(&self) -> String {
         self.data.usd_price.to_owned()
     }
 
-    fn eth_price(&self) -> f64 {
-        self.data.eth_price.to_owned()
+    fn eth_price(&self) -> TokenAmount {
+        self.data.eth_price.into()
+    }
+
+    fn currency(&self) -> String {
+        self.data.currency.to_owned()
+    }
+
+    fn listing_price(&self) -> Option<String> {
+        self.data.listing_price.to_owned()
+    }
+
+    fn last_sale_price(&self) -> Option<String> {
+        self.data.last_sale_price.to_owned()
+    }
+
+    /// 発行者が保有している残りの数量
+    fn balance(&self) -> Option<i32> {
+        self.data.balance.map(|v| v as i32)
+    }
+
+    async fn price_history(
+        &self,
+        context: &Context,
+        limit: Option<i32>,
+    ) -> FieldResult<Vec<PriceSnapshot>> {
+        let snapshots = context
+            .nft_app
+            .price_history(self.data.work_id.to_owned(), limit)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+        Ok(snapshots
+            .iter()
+            .map(|v| PriceSnapshot::from(v.to_owned()))
+            .collect())
     }
 }
 
@@ -108,3 +181,41 @@ impl From<domain::asset::Asset1155> for Asset1155 {
         Self { data }
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct PriceSnapshot {
+    data: domain::asset::PriceSnapshot,
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl PriceSnapshot {
+    fn timestamp(&self) -> f64 {
+        self.data.timestamp as f64
+    }
+
+    fn currency(&self) -> String {
+        self.data.price.currency.to_owned()
+    }
+
+    fn listing_price(&self) -> Option<String> {
+        self.data.price.listing_price.to_owned()
+    }
+
+    fn last_sale_price(&self) -> Option<String> {
+        self.data.price.last_sale_price.to_owned()
+    }
+
+    fn usd_price(&self) -> String {
+        self.data.price.usd_price.to_owned()
+    }
+
+    fn eth_price(&self) -> TokenAmount {
+        self.data.price.eth_price.into()
+    }
+}
+
+impl From<domain::asset::PriceSnapshot> for PriceSnapshot {
+    fn from(data: domain::asset::PriceSnapshot) -> Self {
+        Self { data }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::graph::scalars::TokenAmount;
 use crate::graph::Context;
 use app::domain;
 use core::convert::TryFrom;
@@ -17,8 +18,8 @@ impl User {
         self.data.wallet_address.to_owned()
     }
 
-    fn balance(&self) -> f64 {
-        self.data.balance.to_owned()
+    fn balance(&self) -> TokenAmount {
+        TokenAmount::from(self.data.balance.to_owned())
     }
 
     fn nft_721_num(&self) -> i32 {
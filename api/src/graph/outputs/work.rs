@@ -1,5 +1,6 @@
-use crate::graph::enums::WorkStatus;
+use crate::graph::enums::{OfferStatus, PinKind, PinStatus, WorkStatus};
 use crate::graph::outputs::asset::{Asset1155, Asset721};
+use crate::graph::outputs::Royalty;
 use crate::graph::Context;
 use crate::FieldErrorWithCode;
 use app::{domain, AppError};
@@ -20,6 +21,27 @@ impl Work {
         self.data.video_path.to_owned()
     }
 
+    /// 未設定ならID
+    fn title(&self) -> String {
+        self.data.display_name()
+    }
+
+    fn description(&self) -> String {
+        self.data.description.to_owned()
+    }
+
+    fn attributes(&self) -> Vec<WorkAttribute> {
+        self.data
+            .attributes
+            .iter()
+            .map(|v| WorkAttribute::from(v.to_owned()))
+            .collect()
+    }
+
+    fn background_color(&self) -> Option<String> {
+        self.data.background_color.to_owned()
+    }
+
     async fn signed_video_url(&self, context: &Context) -> FieldResult<String> {
         let urls = context
             .internal_api
@@ -33,6 +55,11 @@ impl Work {
         WorkStatus::from(self.data.status.to_owned())
     }
 
+    /// 作品ごとの設定。未設定ならコレクションの設定が使われる
+    fn royalty(&self) -> Option<Royalty> {
+        self.data.royalty.to_owned().map(Royalty::from)
+    }
+
     async fn thumbnails(&self, context: &Context) -> FieldResult<Vec<Thumbnail>> {
         let thumbnails = context
             .thumbnail_by_work_loader
@@ -75,6 +102,24 @@ impl Work {
 
         Ok(Some(Asset1155::from(asset.ok().unwrap().to_owned())))
     }
+
+    async fn offers(&self, context: &Context) -> FieldResult<Vec<Offer>> {
+        let offers = context
+            .nft_app
+            .offers(self.data.id.to_owned())
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+        Ok(offers.iter().map(|v| Offer::from(v.to_owned())).collect())
+    }
+
+    async fn pins(&self, context: &Context) -> FieldResult<Vec<Pin>> {
+        let pins = context
+            .nft_app
+            .pins(self.data.id.to_owned())
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+        Ok(pins.iter().map(|v| Pin::from(v.to_owned())).collect())
+    }
 }
 
 impl From<domain::work::Work> for Work {
@@ -151,3 +196,115 @@ impl From<domain::work::Thumbnail> for Thumbnail {
         Self { data }
     }
 }
+
+#[derive(Debug, Clone, GraphQLObject)]
+pub struct WorkAttribute {
+    pub trait_type: String,
+    pub value: String,
+    pub display_type: Option<String>,
+}
+
+impl From<domain::work::WorkAttribute> for WorkAttribute {
+    fn from(data: domain::work::WorkAttribute) -> Self {
+        Self {
+            trait_type: data.trait_type,
+            value: data.value,
+            display_type: data.display_type,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Offer {
+    data: domain::offer::Offer,
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl Offer {
+    fn id(&self) -> String {
+        self.data.id.to_owned()
+    }
+
+    fn maker(&self) -> String {
+        self.data.maker.to_owned()
+    }
+
+    fn currency(&self) -> String {
+        self.data.currency.to_owned()
+    }
+
+    fn price(&self) -> String {
+        self.data.price.to_owned()
+    }
+
+    fn quantity(&self) -> i32 {
+        TryFrom::try_from(self.data.quantity).unwrap_or_default()
+    }
+
+    fn expires_at(&self) -> Option<f64> {
+        if self.data.expires_at == 0 {
+            return None;
+        }
+        Some(self.data.expires_at as f64)
+    }
+
+    fn status(&self) -> OfferStatus {
+        OfferStatus::from(self.data.status.to_owned())
+    }
+
+    fn created_at(&self) -> f64 {
+        self.data.created_at as f64
+    }
+}
+
+impl From<domain::offer::Offer> for Offer {
+    fn from(data: domain::offer::Offer) -> Self {
+        Self { data }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pin {
+    data: domain::pin::Pin,
+}
+
+#[juniper::graphql_object(Context = Context)]
+impl Pin {
+    fn cid(&self) -> String {
+        self.data.cid.to_owned()
+    }
+
+    fn kind(&self) -> PinKind {
+        PinKind::from(self.data.kind)
+    }
+
+    fn provider(&self) -> String {
+        self.data.provider.to_owned()
+    }
+
+    fn name(&self) -> String {
+        self.data.name.to_owned()
+    }
+
+    fn size(&self) -> f64 {
+        self.data.size as f64
+    }
+
+    fn status(&self) -> PinStatus {
+        PinStatus::from(self.data.status)
+    }
+
+    fn url(&self) -> String {
+        format!("ipfs://{}", self.data.cid)
+    }
+
+    fn updated_at(&self) -> f64 {
+        self.data.updated_at as f64
+    }
+}
+
+impl From<domain::pin::Pin> for Pin {
+    fn from(data: domain::pin::Pin) -> Self {
+        Self { data }
+    }
+}
@@ -1,12 +1,14 @@
-use crate::graph::enums::WorkStatus;
+use crate::graph::enums::{RevenueGroup, TokenSchema, WorkStatus};
 use crate::graph::outputs::user::User;
 use crate::graph::outputs::work::{Work, WorkConnection, WorkEdge};
-use crate::graph::outputs::OwnNft;
+use crate::graph::outputs::{
+    MetadataPreview, MetadataTemplate, OwnNft, Ownership, Placeholder, RevealState, Revenue,
+    Royalty,
+};
 use crate::graph::Context;
 use crate::graph::FieldErrorWithCode;
 use app::AppError;
 use juniper::FieldResult;
-use std::{thread, time};
 
 pub struct QueryRoot;
 
@@ -98,9 +100,6 @@ impl QueryRoot {
             .await
             .map_err(FieldErrorWithCode::from)?;
 
-        // OpenSeaのレートリミット対応
-        thread::sleep(time::Duration::from_millis(1000));
-
         let is_own_erc1155 = context
             .nft_app
             .is_own_erc1155(work_id.clone())
@@ -112,4 +111,130 @@ impl QueryRoot {
             erc1155: is_own_erc1155,
         })
     }
+
+    /// 記録している作品のトークンの保有者
+    async fn owners(context: &Context, work_id: String) -> FieldResult<Vec<Ownership>> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let owners = context
+            .nft_app
+            .owners_of(work_id)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(owners.into_iter().map(Ownership::from).collect())
+    }
+
+    /// コントラクトの既定のロイヤリティ
+    async fn collection_royalty(
+        context: &Context,
+        schema: TokenSchema,
+    ) -> FieldResult<Option<Royalty>> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let royalty = context
+            .nft_app
+            .collection_royalty(schema.domain())
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(royalty.map(Royalty::from))
+    }
+
+    async fn metadata_template(
+        context: &Context,
+        schema: TokenSchema,
+    ) -> FieldResult<MetadataTemplate> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let template = context
+            .nft_app
+            .metadata_template(schema.domain())
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(MetadataTemplate::from(template))
+    }
+
+    /// 公開前・公開後の状態と、証明用のハッシュの検証結果
+    async fn reveal_state(context: &Context, schema: TokenSchema) -> FieldResult<RevealState> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let collection = context
+            .nft_app
+            .collection(schema.domain())
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+        let verified = context
+            .nft_app
+            .verify_provenance(schema.domain())
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(RevealState {
+            hidden: collection.hidden,
+            placeholder: collection.placeholder.map(Placeholder::from),
+            provenance_hash: collection.provenance_hash,
+            revealed_at: collection.revealed_at.map(|v| v as f64),
+            verified,
+        })
+    }
+
+    /// mint前にコレクションのテンプレートで展開したmetadataを確認する
+    /// schemaの既定はERC721、amountはERC1155の発行数
+    async fn preview_metadata(
+        context: &Context,
+        work_id: String,
+        schema: Option<TokenSchema>,
+        amount: Option<i32>,
+    ) -> FieldResult<MetadataPreview> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let metadata = context
+            .nft_app
+            .preview_metadata(
+                work_id,
+                schema.unwrap_or(TokenSchema::ERC721).domain(),
+                u64::try_from(amount.unwrap_or(1)).unwrap_or(1),
+            )
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(MetadataPreview::from(metadata))
+    }
+
+    /// 売上の台帳をgroupの単位で集計する。monthは "2022-08" の形式
+    async fn revenue(
+        context: &Context,
+        group: RevenueGroup,
+        month: Option<String>,
+    ) -> FieldResult<Vec<Revenue>> {
+        let auth_user = context.auth_user.to_owned();
+        if !auth_user.is_publisher() {
+            return Err(FieldErrorWithCode::from(AppError::UnAuthenticate).into());
+        }
+
+        let revenue = context
+            .sales_app
+            .revenue(group.domain(), month)
+            .await
+            .map_err(FieldErrorWithCode::from)?;
+
+        Ok(revenue.into_iter().map(Revenue::from).collect())
+    }
 }
@@ -0,0 +1,68 @@
+use app::domain::amount::Wei;
+use juniper::{ParseScalarResult, ParseScalarValue, Value};
+
+/// ether単位の10進数文字列でやり取りする金額（例: "0.015"）
+/// 浮動小数を経由しないため、weiの精度のまま入出力できる
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenAmount(pub Wei);
+
+#[juniper::graphql_scalar(description = "ether単位の10進数文字列で表す金額")]
+impl<S> GraphQLScalar for TokenAmount
+where
+    S: ScalarValue,
+{
+    fn resolve(&self) -> Value {
+        Value::scalar(self.0.to_ether())
+    }
+
+    fn from_input_value(v: &InputValue) -> Option<TokenAmount> {
+        v.as_string_value()
+            .and_then(|v| Wei::from_ether(v).ok())
+            .map(TokenAmount)
+    }
+
+    fn from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a, S> {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+}
+
+impl From<Wei> for TokenAmount {
+    fn from(data: Wei) -> Self {
+        Self(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use juniper::{DefaultScalarValue, FromInputValue, InputValue, ToInputValue};
+
+    fn parse(value: InputValue) -> Option<TokenAmount> {
+        <TokenAmount as FromInputValue<DefaultScalarValue>>::from_input_value(&value)
+    }
+
+    #[test]
+    fn parses_ether_strings() {
+        let amount = parse(InputValue::scalar("0.015")).unwrap();
+        assert_eq!(amount.0, Wei::from_ether("0.015").unwrap());
+
+        // 1weiの精度まで保つ
+        let amount = parse(InputValue::scalar("1.000000000000000001")).unwrap();
+        assert_eq!(
+            ToInputValue::<DefaultScalarValue>::to_input_value(&amount),
+            InputValue::scalar("1.000000000000000001")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert_eq!(parse(InputValue::scalar("")), None);
+        assert_eq!(parse(InputValue::scalar("-1")), None);
+        assert_eq!(parse(InputValue::scalar("0.0000000000000000001")), None);
+        assert_eq!(parse(InputValue::scalar("1e18")), None);
+        // 浮動小数は受け付けない
+        assert_eq!(parse(InputValue::scalar(0.5)), None);
+        assert_eq!(parse(InputValue::scalar(1)), None);
+        assert_eq!(parse(InputValue::Null), None);
+    }
+}
@@ -6,16 +6,32 @@ extern crate juniper;
 use crate::graph::*;
 use actix_web::http::header::HeaderMap;
 use actix_web::web::Data;
+use app::application;
+use app::domain::market::TokenSchema;
 use app::domain::user::AuthUser;
+use app::{AppError, AppResult};
+use async_trait::async_trait;
 use juniper_actix::{graphql_handler, playground_handler};
 use lambda_web::actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
 use lambda_web::{is_running_on_lambda, run_actix_on_lambda, LambdaError};
 use std::env;
 use std::str::FromStr;
+use tokio::sync::OnceCell;
+
+// 公開後もサムネイルの変更などで内容が変わるので、CDNでも長くは持たない
+const METADATA_CACHE_CONTROL: &str = "public, max-age=300, s-maxage=3600";
+// 公開のタイミングですぐに切り替わるようにする
+const HIDDEN_METADATA_CACHE_CONTROL: &str = "public, max-age=60";
 
 #[actix_web::main]
 async fn main() -> Result<(), LambdaError> {
     app::aws::ssm::load_env().await;
+    app::ethereum::validate_chain()
+        .await
+        .expect("should connect to configured chain");
+
+    // metadataの配信ではリクエストごとに作らず使い回す。起動時には作らず、最初のリクエストで作る
+    let nft_app: Data<NftApp> = Data::new(OnceCell::new());
 
     let app = move || {
         let schema = create_schema();
@@ -25,6 +41,7 @@ async fn main() -> Result<(), LambdaError> {
         App::new()
             .app_data(Data::new(schema))
             .app_data(Data::new(cognito_verifier))
+            .app_data(nft_app.clone())
             .service(
                 web::resource("/default/graphql")
                     .route(web::post().to(graphql_route))
@@ -34,6 +51,7 @@ async fn main() -> Result<(), LambdaError> {
             .service(
                 web::resource("/default/health_check").route(web::get().to(health_check_route)),
             )
+            .configure(metadata_services::<NftApp>)
     };
 
     if is_running_on_lambda() {
@@ -49,10 +67,109 @@ async fn main() -> Result<(), LambdaError> {
     Ok(())
 }
 
+type NftApp = OnceCell<application::nft::Application>;
+
+/// 作れなかったとき（コントラクトが未デプロイなど）はそのリクエストだけ失敗させ、次のリクエストで作り直す
+async fn get_nft_app(cell: &NftApp) -> AppResult<&application::nft::Application> {
+    cell.get_or_try_init(|| application::nft::Application::new("".to_string()))
+        .await
+}
+
+/// 配信するmetadataのJSONと、非公開のプレースホルダーかどうかを返す
+#[async_trait]
+trait MetadataSource: Send + Sync + 'static {
+    async fn metadata(&self, contract: String, token_id: String) -> AppResult<(String, bool)>;
+
+    async fn metadata_of(&self, schema: TokenSchema, token_id: String)
+        -> AppResult<(String, bool)>;
+}
+
+#[async_trait]
+impl MetadataSource for NftApp {
+    async fn metadata(&self, contract: String, token_id: String) -> AppResult<(String, bool)> {
+        get_nft_app(self)
+            .await?
+            .hosted_metadata(contract, token_id)
+            .await
+    }
+
+    async fn metadata_of(
+        &self,
+        schema: TokenSchema,
+        token_id: String,
+    ) -> AppResult<(String, bool)> {
+        get_nft_app(self)
+            .await?
+            .hosted_metadata_of(schema, token_id)
+            .await
+    }
+}
+
+fn metadata_services<S: MetadataSource>(cfg: &mut web::ServiceConfig) {
+    // {contract}/{token_id}より先に登録する
+    cfg.service(
+        web::resource("/default/metadata/1155/{id}.json")
+            .route(web::get().to(metadata_1155_route::<S>)),
+    )
+    .service(
+        web::resource("/default/metadata/{contract}/{token_id}")
+            .route(web::get().to(metadata_route::<S>)),
+    );
+}
+
 async fn health_check_route() -> actix_web::Result<HttpResponse> {
     Ok(HttpResponse::Ok().body("ok"))
 }
 
+async fn metadata_route<S: MetadataSource>(
+    path: web::Path<(String, String)>,
+    source: web::Data<S>,
+) -> actix_web::Result<HttpResponse> {
+    let (contract, token_id) = path.into_inner();
+    Ok(metadata_response(source.metadata(contract, token_id).await))
+}
+
+/// ERC1155のuriの{id}を64桁の16進数で置き換えたURL
+async fn metadata_1155_route<S: MetadataSource>(
+    path: web::Path<String>,
+    source: web::Data<S>,
+) -> actix_web::Result<HttpResponse> {
+    Ok(metadata_response(
+        source
+            .metadata_of(TokenSchema::Erc1155, path.into_inner())
+            .await,
+    ))
+}
+
+fn metadata_response(result: AppResult<(String, bool)>) -> HttpResponse {
+    match result {
+        Ok((json, hidden)) => HttpResponse::Ok()
+            .content_type("application/json")
+            .insert_header(("Access-Control-Allow-Origin", "*"))
+            .insert_header((
+                "Cache-Control",
+                if hidden {
+                    HIDDEN_METADATA_CACHE_CONTROL
+                } else {
+                    METADATA_CACHE_CONTROL
+                },
+            ))
+            .body(json),
+        Err(AppError::NotFound) => HttpResponse::NotFound()
+            .insert_header(("Cache-Control", "no-store"))
+            .finish(),
+        Err(AppError::BadRequest(msg)) => HttpResponse::BadRequest()
+            .insert_header(("Cache-Control", "no-store"))
+            .body(msg),
+        Err(e) => {
+            println!("metadata error: {:?}", e);
+            HttpResponse::InternalServerError()
+                .insert_header(("Cache-Control", "no-store"))
+                .finish()
+        }
+    }
+}
+
 async fn playground_route() -> actix_web::Result<HttpResponse> {
     playground_handler("/default/graphql", None).await
 }
@@ -64,7 +181,13 @@ async fn graphql_route(
     cognito_verifier: web::Data<app::aws::cognito::Verifier>,
 ) -> actix_web::Result<HttpResponse> {
     let auth_user = authenticate(&req, cognito_verifier.get_ref()).await;
-    let context = Context::new(auth_user).await;
+    let context = match Context::new(auth_user).await {
+        Ok(v) => v,
+        Err(e) => {
+            println!("context error: {:?}", e);
+            return Ok(HttpResponse::ServiceUnavailable().body(e.to_string()));
+        }
+    };
     graphql_handler(&schema, &context, req, payload).await
 }
 
@@ -105,3 +228,125 @@ where
 fn get<'a, 'b>(headers: &'a HeaderMap, key: &'b str) -> Option<&'a str> {
     headers.get(key)?.to_str().ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test;
+    use std::sync::Mutex;
+
+    const TOKEN_ID: &str = "000000000000000000000000000000000000000000000000000000000000000a";
+
+    /// token_idが"missing"ならNotFound、"bad"ならBadRequestを返し、呼ばれた引数を記録する
+    #[derive(Default)]
+    struct FakeSource {
+        hidden: bool,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl FakeSource {
+        fn respond(&self, call: String, token_id: &str) -> AppResult<(String, bool)> {
+            self.calls.lock().unwrap().push(call);
+            match token_id {
+                "missing" => Err(AppError::NotFound),
+                "bad" => Err(AppError::BadRequest("bad token id".to_string())),
+                _ => Ok((r#"{"name":"work"}"#.to_string(), self.hidden)),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl MetadataSource for FakeSource {
+        async fn metadata(&self, contract: String, token_id: String) -> AppResult<(String, bool)> {
+            self.respond(format!("{}/{}", contract, token_id), &token_id)
+        }
+
+        async fn metadata_of(
+            &self,
+            schema: TokenSchema,
+            token_id: String,
+        ) -> AppResult<(String, bool)> {
+            self.respond(format!("{:?}:{}", schema, token_id), &token_id)
+        }
+    }
+
+    async fn get(source: &Data<FakeSource>, uri: &str) -> actix_web::dev::ServiceResponse {
+        let service = test::init_service(
+            App::new()
+                .app_data(source.clone())
+                .configure(metadata_services::<FakeSource>),
+        )
+        .await;
+        test::call_service(&service, test::TestRequest::get().uri(uri).to_request()).await
+    }
+
+    fn header<'a>(res: &'a actix_web::dev::ServiceResponse, key: &str) -> &'a str {
+        res.headers().get(key).unwrap().to_str().unwrap()
+    }
+
+    #[actix_web::test]
+    async fn serves_metadata_by_contract() {
+        let source = Data::new(FakeSource::default());
+
+        let res = get(&source, "/default/metadata/0xabc/10").await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(header(&res, "Cache-Control"), METADATA_CACHE_CONTROL);
+        assert_eq!(header(&res, "Access-Control-Allow-Origin"), "*");
+        assert_eq!(header(&res, "Content-Type"), "application/json");
+        assert_eq!(*source.calls.lock().unwrap(), vec!["0xabc/10"]);
+        assert_eq!(test::read_body(res).await, r#"{"name":"work"}"#.as_bytes());
+    }
+
+    #[actix_web::test]
+    async fn serves_erc1155_uri_before_contract_route() {
+        let source = Data::new(FakeSource::default());
+
+        let res = get(
+            &source,
+            &format!("/default/metadata/1155/{}.json", TOKEN_ID),
+        )
+        .await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(header(&res, "Cache-Control"), METADATA_CACHE_CONTROL);
+        assert_eq!(
+            *source.calls.lock().unwrap(),
+            vec![format!("Erc1155:{}", TOKEN_ID)]
+        );
+    }
+
+    #[actix_web::test]
+    async fn shortens_cache_while_hidden() {
+        let source = Data::new(FakeSource {
+            hidden: true,
+            ..Default::default()
+        });
+
+        let res = get(
+            &source,
+            &format!("/default/metadata/1155/{}.json", TOKEN_ID),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(header(&res, "Cache-Control"), HIDDEN_METADATA_CACHE_CONTROL);
+
+        let res = get(&source, "/default/metadata/0xabc/10").await;
+        assert_eq!(header(&res, "Cache-Control"), HIDDEN_METADATA_CACHE_CONTROL);
+    }
+
+    #[actix_web::test]
+    async fn does_not_cache_errors() {
+        let source = Data::new(FakeSource::default());
+
+        let res = get(&source, "/default/metadata/0xabc/missing").await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(header(&res, "Cache-Control"), "no-store");
+
+        let res = get(&source, "/default/metadata/1155/bad.json").await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(header(&res, "Cache-Control"), "no-store");
+        assert_eq!(test::read_body(res).await, "bad token id".as_bytes());
+    }
+}
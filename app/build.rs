@@ -1,5 +1,9 @@
+use serde_json::Value;
+use std::collections::HashMap;
 use std::env;
-use std::path::PathBuf;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
@@ -9,5 +13,337 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .file_descriptor_set_path(out_dir.join("proto_descriptor.bin"))
         .compile(&["proto/api.proto"], &["proto"])
         .unwrap();
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=proto");
+    generate_contract_bindings(&out_dir, "Canvas721", "canvas_erc721")?;
+    generate_contract_bindings(&out_dir, "Canvas1155", "canvas_erc1155")?;
     Ok(())
 }
+
+struct Param {
+    name: String,
+    kind: String,
+}
+
+impl Param {
+    fn list(value: &Value) -> Vec<Param> {
+        value
+            .as_array()
+            .map(|v| {
+                v.iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        let name = snake_case(v["name"].as_str().unwrap_or_default());
+                        Param {
+                            name: if name.is_empty() {
+                                format!("arg{}", i)
+                            } else {
+                                name
+                            },
+                            kind: v["type"].as_str().unwrap().to_string(),
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// src/ethereum/{file_name}.abi.json から型付きのバインディングを OUT_DIR/{file_name}.rs に生成する
+fn generate_contract_bindings(
+    out_dir: &Path,
+    struct_name: &str,
+    file_name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let abi_path = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap())
+        .join("src/ethereum")
+        .join(format!("{}.abi.json", file_name));
+    println!("cargo:rerun-if-changed={}", abi_path.display());
+    let abi: Vec<Value> = serde_json::from_slice(&fs::read(&abi_path)?)?;
+
+    let mut out = String::new();
+    writeln!(out, "// generated by build.rs from {}.abi.json", file_name)?;
+    writeln!(out, "#[allow(unused_imports)]")?;
+    writeln!(
+        out,
+        "use crate::ethereum::bindings::{{next_param, ContractInstance}};"
+    )?;
+    writeln!(out, "#[allow(unused_imports)]")?;
+    writeln!(out, "use crate::AppResult;")?;
+    writeln!(out, "#[allow(unused_imports)]")?;
+    writeln!(out, "use web3::contract::{{tokens::Tokenizable, Options}};")?;
+    writeln!(out, "#[allow(unused_imports)]")?;
+    writeln!(
+        out,
+        "use web3::types::{{Address, Log, TransactionReceipt, U256}};"
+    )?;
+    writeln!(out, "use web3::{{signing::Key, Transport, Web3}};")?;
+    writeln!(out)?;
+
+    writeln!(out, "#[derive(Clone, Debug)]")?;
+    writeln!(out, "pub struct {}<T: Transport> {{", struct_name)?;
+    writeln!(out, "    instance: ContractInstance<T>,")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(out, "#[allow(clippy::too_many_arguments)]")?;
+    writeln!(out, "impl<T: Transport> {}<T> {{", struct_name)?;
+    writeln!(
+        out,
+        "    pub fn new(web3: Web3<T>, address: Address, confirmations: usize) -> AppResult<Self> {{"
+    )?;
+    writeln!(out, "        Ok(Self {{")?;
+    writeln!(
+        out,
+        "            instance: ContractInstance::new(web3, address, include_bytes!({:?}), confirmations)?,",
+        abi_path.display().to_string()
+    )?;
+    writeln!(out, "        }})")?;
+    writeln!(out, "    }}")?;
+    writeln!(out)?;
+    writeln!(out, "    pub fn address(&self) -> Address {{")?;
+    writeln!(out, "        self.instance.address()")?;
+    writeln!(out, "    }}")?;
+
+    // オーバーロードは宣言順のindexで区別し、2つ目以降は追加の引数名を付けたメソッドにする
+    let mut overloads: HashMap<String, Vec<Vec<Param>>> = HashMap::new();
+    for item in abi.iter().filter(|v| v["type"] == "function") {
+        let name = item["name"].as_str().unwrap().to_string();
+        let inputs = Param::list(&item["inputs"]);
+        let outputs = Param::list(&item["outputs"]);
+        let view = matches!(
+            item["stateMutability"].as_str(),
+            Some("view") | Some("pure")
+        ) || item["constant"] == true;
+
+        let previous = overloads.entry(name.clone()).or_default();
+        let index = previous.len();
+        let method = match previous.first() {
+            None => snake_case(&name),
+            Some(first) if inputs.len() > first.len() => format!(
+                "{}_with_{}",
+                snake_case(&name),
+                inputs[first.len()..]
+                    .iter()
+                    .map(|v| v.name.clone())
+                    .collect::<Vec<String>>()
+                    .join("_and_")
+            ),
+            Some(_) => format!("{}_{}", snake_case(&name), index),
+        };
+
+        let args = inputs
+            .iter()
+            .map(|v| format!("{}: {}", v.name, rust_type(&v.kind)))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let tokens = format!(
+            "vec![{}]",
+            inputs
+                .iter()
+                .map(|v| format!("{}.into_token()", v.name))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        let signature = format!(
+            "{}({})",
+            name,
+            inputs
+                .iter()
+                .map(|v| v.kind.clone())
+                .collect::<Vec<String>>()
+                .join(",")
+        );
+        let comma = if args.is_empty() { "" } else { ", " };
+
+        writeln!(out)?;
+        writeln!(out, "    /// `{}`", signature)?;
+        if view {
+            let output = match outputs.len() {
+                0 => "()".to_string(),
+                1 => rust_type(&outputs[0].kind),
+                _ => format!(
+                    "({},)",
+                    outputs
+                        .iter()
+                        .map(|v| rust_type(&v.kind))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+            };
+            writeln!(
+                out,
+                "    pub async fn {}(&self{}{}) -> AppResult<{}> {{",
+                method, comma, args, output
+            )?;
+            writeln!(
+                out,
+                "        self.instance.query({:?}, {}, {}).await",
+                name, index, tokens
+            )?;
+            writeln!(out, "    }}")?;
+            writeln!(out)?;
+            writeln!(
+                out,
+                "    pub fn decode_{}(&self, data: &[u8]) -> AppResult<{}> {{",
+                method, output
+            )?;
+            writeln!(
+                out,
+                "        self.instance.decode({:?}, {}, data)",
+                name, index
+            )?;
+            writeln!(out, "    }}")?;
+        } else {
+            writeln!(
+                out,
+                "    pub async fn {}<K: Key>(&self, key: K, options: Options{}{}) -> AppResult<TransactionReceipt> {{",
+                method, comma, args
+            )?;
+            writeln!(
+                out,
+                "        self.instance.send({:?}, {}, {}, options, key).await",
+                name, index, tokens
+            )?;
+            writeln!(out, "    }}")?;
+        }
+        writeln!(out)?;
+        writeln!(
+            out,
+            "    pub fn encode_{}(&self{}{}) -> AppResult<Vec<u8>> {{",
+            method, comma, args
+        )?;
+        writeln!(
+            out,
+            "        self.instance.encode({:?}, {}, {})",
+            name, index, tokens
+        )?;
+        writeln!(out, "    }}")?;
+
+        previous.push(inputs);
+    }
+
+    let events: Vec<(String, Vec<Param>)> = abi
+        .iter()
+        .filter(|v| v["type"] == "event")
+        .map(|v| {
+            (
+                v["name"].as_str().unwrap().to_string(),
+                Param::list(&v["inputs"]),
+            )
+        })
+        .collect();
+    let event_enum = format!("{}Event", struct_name);
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "    /// このコントラクトのイベントのログであればデコードする"
+    )?;
+    writeln!(
+        out,
+        "    pub fn decode_event(&self, log: &Log) -> AppResult<Option<{}>> {{",
+        event_enum
+    )?;
+    for (name, inputs) in &events {
+        let variant = pascal_case(name);
+        writeln!(
+            out,
+            "        if let Some(mut params) = self.instance.decode_log({:?}, log)? {{",
+            name
+        )?;
+        writeln!(
+            out,
+            "            return Ok(Some({}::{}({}Event {{",
+            event_enum, variant, variant
+        )?;
+        for input in inputs {
+            writeln!(
+                out,
+                "                {}: next_param(&mut params)?,",
+                input.name
+            )?;
+        }
+        writeln!(out, "            }})));")?;
+        writeln!(out, "        }}")?;
+    }
+    writeln!(out, "        Ok(None)")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+
+    writeln!(out)?;
+    writeln!(out, "#[derive(Clone, Debug)]")?;
+    writeln!(out, "pub enum {} {{", event_enum)?;
+    for (name, _) in &events {
+        let variant = pascal_case(name);
+        writeln!(out, "    {}({}Event),", variant, variant)?;
+    }
+    writeln!(out, "}}")?;
+    for (name, inputs) in &events {
+        writeln!(out)?;
+        writeln!(out, "#[derive(Clone, Debug)]")?;
+        writeln!(out, "pub struct {}Event {{", pascal_case(name))?;
+        for input in inputs {
+            writeln!(out, "    pub {}: {},", input.name, rust_type(&input.kind))?;
+        }
+        writeln!(out, "}}")?;
+    }
+
+    fs::write(out_dir.join(format!("{}.rs", file_name)), out)?;
+    Ok(())
+}
+
+fn rust_type(kind: &str) -> String {
+    if let Some(inner) = kind.strip_suffix("[]") {
+        return format!("Vec<{}>", rust_type(inner));
+    }
+    match kind {
+        "address" => "Address".to_string(),
+        "bool" => "bool".to_string(),
+        "string" => "String".to_string(),
+        "bytes" => "Vec<u8>".to_string(),
+        v if v.starts_with("uint") || v.starts_with("int") => "U256".to_string(),
+        v if v.starts_with("bytes") => format!("[u8; {}]", &v[5..]),
+        _ => panic!("unsupported abi type: {}", kind),
+    }
+}
+
+/// tokenURI -> token_uri, s3Key -> s3_key, _data -> data
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.trim_start_matches('_').chars().collect();
+    let mut out = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|v| v.is_ascii_lowercase());
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_lower)
+            {
+                out.push('_');
+            }
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    match out.as_str() {
+        "type" | "ref" | "self" | "move" | "match" | "loop" | "fn" | "impl" | "mod" => {
+            format!("{}_", out)
+        }
+        _ => out,
+    }
+}
+
+/// OwnershipTransferred -> OwnershipTransferred, URI -> Uri
+fn pascal_case(name: &str) -> String {
+    snake_case(name)
+        .split('_')
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            let mut chars = v.chars();
+            match chars.next() {
+                Some(c) => c.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => "".to_string(),
+            }
+        })
+        .collect()
+}
@@ -1,3 +1,5 @@
+pub mod contract;
 pub mod nft;
+pub mod sales;
 pub mod user;
 pub mod work;
@@ -0,0 +1,122 @@
+use crate::aws::s3::download_object;
+use crate::domain::contract::DeployedContract;
+use crate::domain::user::User;
+use crate::ethereum::deploy::Artifact;
+use crate::{ddb, ethereum, AppError, AppResult, CONTRACT_ARTIFACT_PATH_PREFIX};
+use std::{env, fs};
+
+pub struct Application {
+    #[allow(dead_code)]
+    me_id: String,
+    contract_dao: ddb::Dao<DeployedContract>,
+    user_dao: ddb::Dao<User>,
+    ethereum_cli: ethereum::Client,
+}
+
+impl Application {
+    pub async fn new(me_id: String) -> Self {
+        let contract_dao: ddb::Dao<DeployedContract> = ddb::Dao::new().await;
+        let user_dao: ddb::Dao<User> = ddb::Dao::new().await;
+        let ethereum_cli = ethereum::Client::new();
+
+        Self {
+            me_id,
+            contract_dao,
+            user_dao,
+            ethereum_cli,
+        }
+    }
+
+    /// ビルド成果物をsigner_idのユーザーのwalletでデプロイし、台帳に記録する
+    /// 同じコードが検証済みでデプロイされていれば、forceでない限り何もしない
+    pub async fn deploy(
+        &self,
+        name: String,
+        signer_id: String,
+        force: bool,
+    ) -> AppResult<DeployedContract> {
+        let artifact = self.load_artifact(&name).await?;
+        let chain_id = self.ethereum_cli.chain().chain_id;
+        let artifact_hash = format!("{:?}", artifact.deployed_code_hash());
+
+        if !force {
+            if let Ok(current) = self.contract_dao.get(chain_id, &name).await {
+                let address = self.ethereum_cli.parse_address(current.address.clone());
+                if let Some(address) = address {
+                    let onchain = self.ethereum_cli.deployed_code_hash(address).await;
+                    if let Ok(onchain) = onchain {
+                        if format!("{:?}", onchain) == artifact_hash {
+                            println!("already deployed: {} {}", name, current.address);
+                            return Ok(current);
+                        }
+                    }
+                }
+            }
+        }
+
+        let signer = self.user_dao.get(signer_id).await?;
+        let deployment = self
+            .ethereum_cli
+            .deploy_contract(&artifact, &signer.wallet_secret)
+            .await?;
+
+        let code_hash = self
+            .ethereum_cli
+            .deployed_code_hash(deployment.address)
+            .await?;
+        let code_hash = format!("{:?}", code_hash);
+
+        let contract = DeployedContract {
+            chain_id,
+            name: name.clone(),
+            address: format!("{:?}", deployment.address),
+            deployer: format!("{:?}", deployment.deployer),
+            tx_hash: format!("{:?}", deployment.tx_hash),
+            block_number: deployment.block_number,
+            code_hash: code_hash.clone(),
+            verified: code_hash == artifact_hash,
+        };
+        self.contract_dao.put(&contract).await?;
+
+        if !contract.verified {
+            return Err(AppError::Internal(format!(
+                "deployed code hash mismatch: artifact {}, onchain {}",
+                artifact_hash, code_hash
+            )));
+        }
+
+        println!(
+            "deployed {} on {}: {} (block {})",
+            name,
+            self.ethereum_cli.chain().name,
+            contract.address,
+            contract.block_number
+        );
+
+        Ok(contract)
+    }
+
+    /// CONTRACT_ARTIFACT_DIRがあればローカルのファイルを、なければS3の成果物を読む
+    async fn load_artifact(&self, name: &str) -> AppResult<Artifact> {
+        let file_name = format!("{}.json", name);
+        let data = match env::var("CONTRACT_ARTIFACT_DIR") {
+            Ok(dir) => fs::read(format!("{}/{}", dir, file_name))
+                .map_err(|e| AppError::BadRequest(format!("artifact read error: {}", e)))?,
+            Err(_) => download_object(
+                env::var("S3_USER_BUCKET").unwrap(),
+                format!("{}/{}", CONTRACT_ARTIFACT_PATH_PREFIX, file_name),
+            )
+            .await?
+            .to_vec(),
+        };
+
+        let artifact = Artifact::from_slice(&data)?;
+        if artifact.contract_name != name {
+            return Err(AppError::BadRequest(format!(
+                "artifact name mismatch: {}",
+                artifact.contract_name
+            )));
+        }
+        Ok(artifact)
+    }
+}
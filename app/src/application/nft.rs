@@ -1,95 +1,138 @@
-use crate::aws::s3::upload_object;
-use crate::aws::{lambda, sns};
-use crate::domain::asset::{Asset1155, Asset721};
+use crate::artwork::Artwork;
+use crate::aws::s3::{download_object, upload_object};
+use crate::aws::sns;
+use crate::domain::acquisition::Acquisition;
+use crate::domain::amount::Wei;
+use crate::domain::asset::{Asset1155, Asset721, MarketPrice, PriceSnapshot};
+use crate::domain::collection::Collection;
+use crate::domain::listing::Listing;
+use crate::domain::market::{self, TokenRef, TokenSchema};
+use crate::domain::metadata_check::{MetadataIssue, MetadataIssueKind};
+use crate::domain::offer::{Offer, OfferStatus};
+use crate::domain::ownership::Ownership;
+use crate::domain::pin::{Pin, PinKind, PinStatus};
+use crate::domain::reveal::{provenance_hash, sha256_hex, Placeholder, RevealItem, StagedAsset};
+use crate::domain::royalty::Royalty;
+use crate::domain::sale::{Sale, SaleSource};
+use crate::domain::template::{MetadataTemplate, RenderedMetadata, TemplateValues};
+use crate::domain::transaction::{TransactionKind, TransactionRecord};
 use crate::domain::user::User;
-use crate::domain::work::{Work, WorkStatus};
+use crate::domain::work::{Thumbnail, Work, WorkStatus};
+use crate::ethereum::address::{parse_checksummed, to_checksum};
+use crate::ethereum::tx::TokenTransfer;
+use crate::marketplace::Marketplace;
 use crate::open_sea::metadata::Metadata;
 use crate::{
-    ddb, ethereum, internal_api, open_sea, AppError, AppResult, ERC1155_ASSET_PATH_PREFIX,
-    ERC721_ASSET_PATH_PREFIX,
+    artwork, ddb, ethereum, internal_api, ipfs, marketplace, AppError, AppResult,
+    ERC1155_ASSET_PATH_PREFIX, ERC721_ASSET_PATH_PREFIX,
 };
 use bytes::Bytes;
-use std::{env, thread, time};
+use once_cell::sync::Lazy;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use web3::types::{TransactionReceipt, U64};
+
+// (チェーンID, 規格)ごとの発行済みの名前
+type TokenNames = HashMap<(u64, TokenSchema), Vec<String>>;
+
+// metadataの配信で毎回usedTokenNamesを引かないよう保持する
+static TOKEN_NAMES: Lazy<Mutex<TokenNames>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Seaportの約定（fulfillOrder）のgasの見積もり。実際はERC20の支払いや手数料の数で200k前後になる
+const FULFILL_GAS: u64 = 300_000;
 
 pub struct Application {
     #[allow(dead_code)]
     me_id: String,
     work_dao: ddb::Dao<Work>,
+    thumbnail_dao: ddb::Dao<Thumbnail>,
     asset721_dao: ddb::Dao<Asset721>,
     asset1155_dao: ddb::Dao<Asset1155>,
+    price_snapshot_dao: ddb::Dao<PriceSnapshot>,
+    listing_dao: ddb::Dao<Listing>,
+    offer_dao: ddb::Dao<Offer>,
+    sale_dao: ddb::Dao<Sale>,
+    transaction_dao: ddb::Dao<TransactionRecord>,
+    acquisition_dao: ddb::Dao<Acquisition>,
+    ownership_dao: ddb::Dao<Ownership>,
+    collection_dao: ddb::Dao<Collection>,
+    pin_dao: ddb::Dao<Pin>,
+    reveal_dao: ddb::Dao<RevealItem>,
     user_dao: ddb::Dao<User>,
-    open_sea_cli: open_sea::Client,
+    marketplace: Arc<dyn Marketplace>,
     internal_api: internal_api::Client,
+    ipfs: ipfs::Client,
     ethereum_cli: ethereum::Client,
 }
 
 impl Application {
-    pub async fn new(me_id: String) -> Self {
+    pub async fn new(me_id: String) -> AppResult<Self> {
         let work_dao: ddb::Dao<Work> = ddb::Dao::new().await;
+        let thumbnail_dao: ddb::Dao<Thumbnail> = ddb::Dao::new().await;
         let asset721_dao: ddb::Dao<Asset721> = ddb::Dao::new().await;
         let asset1155_dao: ddb::Dao<Asset1155> = ddb::Dao::new().await;
+        let price_snapshot_dao: ddb::Dao<PriceSnapshot> = ddb::Dao::new().await;
+        let listing_dao: ddb::Dao<Listing> = ddb::Dao::new().await;
+        let offer_dao: ddb::Dao<Offer> = ddb::Dao::new().await;
+        let sale_dao: ddb::Dao<Sale> = ddb::Dao::new().await;
+        let transaction_dao: ddb::Dao<TransactionRecord> = ddb::Dao::new().await;
+        let acquisition_dao: ddb::Dao<Acquisition> = ddb::Dao::new().await;
+        let ownership_dao: ddb::Dao<Ownership> = ddb::Dao::new().await;
+        let collection_dao: ddb::Dao<Collection> = ddb::Dao::new().await;
+        let pin_dao: ddb::Dao<Pin> = ddb::Dao::new().await;
+        let reveal_dao: ddb::Dao<RevealItem> = ddb::Dao::new().await;
         let user_dao: ddb::Dao<User> = ddb::Dao::new().await;
-        let open_sea_cli = open_sea::Client::new();
+        let ethereum_cli = ethereum::Client::from_registry().await?;
+        let marketplace = marketplace::from_env(ethereum_cli.chain());
         let internal_api = internal_api::Client::new();
-        let ethereum_cli = ethereum::Client::new();
+        let ipfs = ipfs::Client::new();
 
-        Self {
+        Ok(Self {
             me_id,
             work_dao,
+            thumbnail_dao,
             asset721_dao,
             asset1155_dao,
+            price_snapshot_dao,
+            listing_dao,
+            offer_dao,
+            sale_dao,
+            transaction_dao,
+            acquisition_dao,
+            ownership_dao,
+            collection_dao,
+            pin_dao,
+            reveal_dao,
             user_dao,
-            open_sea_cli,
+            marketplace,
             internal_api,
+            ipfs,
             ethereum_cli,
-        }
+        })
     }
 
     pub async fn prepare_erc721(
         &self,
         work_id: String,
-        gs_path: String,
+        gs_path: Option<String>,
         use_ipfs: bool,
     ) -> AppResult<()> {
-        let user = self.user_dao.get(self.me_id.clone()).await?;
         let work = self.work_dao.get(work_id.clone()).await?;
 
-        let urls = self
-            .internal_api
-            .get_signed_urls(vec![gs_path.clone()])
+        let (ipfs_hash, s3_key) = self
+            .create_token_metadata(&work, TokenSchema::Erc721, 1, gs_path, use_ipfs)
             .await?;
-        let url = urls.first().unwrap();
-        let bytes = reqwest::get(url).await?.bytes().await?;
-
-        let mut ipfs_hash: String = "".to_string();
-        let mut s3_key: String = "".to_string();
-
-        if use_ipfs {
-            let output =
-                lambda::invoke_open_sea_sdk(lambda::invoke_open_sea_sdk::Input::create_metadata(
-                    user,
-                    work_id.clone(),
-                    "nft from canvas-nft-server".to_string(),
-                    format!("https://canvas-329810.web.app/{}", work_id.clone()),
-                    base64::encode(bytes),
-                ))
-                .await?;
-
-            if output.ipfs_response.is_none() {
-                return Err(AppError::Internal("".to_string()));
-            }
-            ipfs_hash = output.ipfs_response.unwrap().hash;
-        } else {
-            s3_key = self
-                .upload_metadata_to_s3(work_id.clone(), ERC721_ASSET_PATH_PREFIX, bytes)
-                .await?;
-        }
 
-        let asset = Asset721::new(work.id.clone());
+        let asset = Asset721::new(work.id.clone(), self.ethereum_cli.chain().chain_id);
         self.asset721_dao.put(&asset).await?;
 
         let payload = sns::MintNft721Payload {
             executor_id: self.me_id.clone(),
+            chain_id: self.ethereum_cli.chain().chain_id,
             work_id: work.id.clone(),
             ipfs_hash,
             s3_key,
@@ -102,71 +145,66 @@ impl Application {
 
     pub async fn mint_erc721(
         &self,
+        chain_id: u64,
         work_id: String,
         ipfs_hash: String,
         s3_key: String,
     ) -> AppResult<()> {
+        self.validate_chain_id(chain_id)?;
+
         let user = self.user_dao.get(self.me_id.clone()).await?;
         let mut work = self.work_dao.get(work_id.clone()).await?;
+        let royalty = self.royalty_of(&work, TokenSchema::Erc721).await?;
 
-        self.ethereum_cli
-            .mint_erc721(&user, work_id.clone(), ipfs_hash, s3_key)
+        let (_receipt, transfers) = self
+            .ethereum_cli
+            .mint_erc721(&user, work_id.clone(), ipfs_hash, s3_key, royalty.as_ref())
             .await?;
-
-        self.save_asset721(work_id.clone()).await?;
-
+        // mintはチェーン上で完了しているので、以降の同期が失敗しても再mintされないよう先に反映する
         work.status = WorkStatus::PublishNFT;
         self.work_dao.put(&work).await?;
 
+        let contract_address = self.ethereum_cli.chain().erc721_contract_address.clone();
+        self.apply_transfers(&work_id, TokenSchema::Erc721, &contract_address, transfers)
+            .await?;
+
+        // mintは完了しているので、レートリミット時はsync-nft-assetでの反映に回す
+        if let Err(err) = self.save_asset721(work_id.clone()).await {
+            if let AppError::RateLimited(_) = err {
+                println!("deferred erc721 asset sync: {}", work_id);
+                return Ok(());
+            }
+            return Err(err);
+        }
+
         Ok(())
     }
 
     pub async fn prepare_erc1155(
         &self,
         work_id: String,
-        gs_path: String,
+        gs_path: Option<String>,
         amount: u32,
         use_ipfs: bool,
     ) -> AppResult<()> {
-        let user = self.user_dao.get(self.me_id.clone()).await?;
         let work = self.work_dao.get(work_id.clone()).await?;
 
-        let urls = self
-            .internal_api
-            .get_signed_urls(vec![gs_path.clone()])
+        let (ipfs_hash, s3_key) = self
+            .create_token_metadata(
+                &work,
+                TokenSchema::Erc1155,
+                amount as u64,
+                gs_path,
+                use_ipfs,
+            )
             .await?;
-        let url = urls.first().unwrap();
-        let bytes = reqwest::get(url).await?.bytes().await?;
-
-        let mut ipfs_hash: String = "".to_string();
-        let mut s3_key: String = "".to_string();
-
-        if use_ipfs {
-            let output =
-                lambda::invoke_open_sea_sdk(lambda::invoke_open_sea_sdk::Input::create_metadata(
-                    user,
-                    work_id.clone(),
-                    "nft from canvas-nft-server".to_string(),
-                    format!("https://canvas-329810.web.app/{}", work_id.clone()),
-                    base64::encode(bytes),
-                ))
-                .await?;
-
-            if output.ipfs_response.is_none() {
-                return Err(AppError::Internal("".to_string()));
-            }
-            ipfs_hash = output.ipfs_response.unwrap().hash;
-        } else {
-            s3_key = self
-                .upload_metadata_to_s3(work_id.clone(), ERC1155_ASSET_PATH_PREFIX, bytes)
-                .await?;
-        }
 
-        let asset = Asset1155::new(work.id.clone());
+        let asset = Asset1155::new(work.id.clone(), self.ethereum_cli.chain().chain_id);
         self.asset1155_dao.put(&asset).await?;
 
         let payload = sns::MintNft1155Payload {
             executor_id: self.me_id.clone(),
+            chain_id: self.ethereum_cli.chain().chain_id,
             work_id: work.id.clone(),
             amount: amount.to_owned(),
             ipfs_hash,
@@ -180,49 +218,155 @@ impl Application {
 
     pub async fn mint_erc1155(
         &self,
+        chain_id: u64,
         work_id: String,
         amount: u32,
         ipfs_hash: String,
         s3_key: String,
     ) -> AppResult<()> {
+        self.validate_chain_id(chain_id)?;
+
         let user = self.user_dao.get(self.me_id.clone()).await?;
         let mut work = self.work_dao.get(work_id.clone()).await?;
+        let royalty = self.royalty_of(&work, TokenSchema::Erc1155).await?;
 
-        self.ethereum_cli
-            .mint_erc1155(&user, work_id.clone(), amount, ipfs_hash, s3_key)
+        let (_receipt, transfers) = self
+            .ethereum_cli
+            .mint_erc1155(
+                &user,
+                work_id.clone(),
+                amount,
+                ipfs_hash,
+                s3_key,
+                royalty.as_ref(),
+            )
             .await?;
-
-        self.save_asset1155(work_id.clone()).await?;
-
+        // mintはチェーン上で完了しているので、以降の同期が失敗しても再mintされないよう先に反映する
         work.status = WorkStatus::PublishNFT;
         self.work_dao.put(&work).await?;
 
+        let contract_address = self.ethereum_cli.chain().erc1155_contract_address.clone();
+        self.apply_transfers(&work_id, TokenSchema::Erc1155, &contract_address, transfers)
+            .await?;
+        self.save_balance1155(work_id.clone(), amount as u64)
+            .await?;
+
+        // mintは完了しているので、レートリミット時はsync-nft-assetでの反映に回す
+        if let Err(err) = self.save_asset1155(work_id.clone()).await {
+            if let AppError::RateLimited(_) = err {
+                println!("deferred erc1155 asset sync: {}", work_id);
+                return Ok(());
+            }
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    fn validate_chain_id(&self, chain_id: u64) -> AppResult<()> {
+        if chain_id != self.ethereum_cli.chain().chain_id {
+            return Err(AppError::BadRequest(format!(
+                "チェーンが一致しません: {} != {}",
+                chain_id,
+                self.ethereum_cli.chain().chain_id
+            )));
+        }
         Ok(())
     }
 
-    async fn upload_metadata_to_s3(
+    /// 画像と動画をアップロードしてmetadataを作る。戻り値は(ipfs_hash, s3_key)のどちらか一方
+    async fn create_token_metadata(
         &self,
-        work_id: String,
-        path: &str,
-        bytes: Bytes,
-    ) -> AppResult<String> {
-        let s3_key = format!("{}/{}.png", path, work_id.clone());
-        let uploaded_url = upload_object(
+        work: &Work,
+        schema: TokenSchema,
+        supply: u64,
+        gs_path: Option<String>,
+        use_ipfs: bool,
+    ) -> AppResult<(String, String)> {
+        let collection = self.collection(schema).await?;
+        if collection.hidden {
+            return self.create_hidden_token_metadata(work, &collection).await;
+        }
+
+        let animation = self.download_animation(work).await?;
+        let artwork = self.artwork_of(gs_path, animation.as_ref()).await?;
+        let path = self.asset_path(schema);
+        let royalty = self.royalty_of(work, schema).await?;
+        let rendered = self.render_metadata(work, schema, supply).await?;
+        let image_name = format!("{}.{}", work.id, artwork.kind.extension());
+        self.upload_image_variants(&path, work, &artwork).await?;
+
+        if use_ipfs {
+            let image_cid = self
+                .pin_to_ipfs(work, PinKind::Image, image_name, artwork.data)
+                .await?;
+            let animation_url = match animation {
+                Some((extension, _, data)) => {
+                    let cid = self
+                        .pin_to_ipfs(
+                            work,
+                            PinKind::Animation,
+                            format!("{}.{}", work.id, extension),
+                            data,
+                        )
+                        .await?;
+                    Some(format!("ipfs://{}", cid))
+                }
+                None => None,
+            };
+
+            let metadata = Metadata::new(
+                work,
+                rendered,
+                format!("ipfs://{}", image_cid),
+                animation_url,
+                royalty.as_ref(),
+            );
+            let metadata = serde_json::to_string(&metadata)?;
+            let ipfs_hash = self
+                .pin_to_ipfs(
+                    work,
+                    PinKind::Metadata,
+                    format!("{}.metadata.json", work.id),
+                    Bytes::from(metadata.clone()),
+                )
+                .await?;
+            // APIから配信するための控え
+            upload_object(
+                env::var("S3_USER_BUCKET").unwrap(),
+                format!("{}/{}.metadata.json", path, work.id),
+                Bytes::from(metadata),
+                "application/json".to_string(),
+            )
+            .await?;
+            return Ok((ipfs_hash, "".to_string()));
+        }
+
+        let image_url = upload_object(
             env::var("S3_USER_BUCKET").unwrap(),
-            s3_key,
-            bytes,
-            "image/png".to_string(),
+            format!("{}/{}", path, image_name),
+            artwork.data,
+            artwork.kind.content_type().to_string(),
         )
         .await?;
 
-        let metadata = Metadata::new(
-            work_id.clone(),
-            "nft from canvas-nft-server".to_string(),
-            uploaded_url,
-        );
+        let animation_url = match animation {
+            Some((extension, content_type, data)) => Some(
+                upload_object(
+                    env::var("S3_USER_BUCKET").unwrap(),
+                    format!("{}/{}.{}", path, work.id, extension),
+                    data,
+                    content_type.to_string(),
+                )
+                .await?,
+            ),
+            None => None,
+        };
+
+        let metadata = Metadata::new(work, rendered, image_url, animation_url, royalty.as_ref());
         let metadata = serde_json::to_string(&metadata)?;
 
-        let s3_key = format!("{}/{}.metadata.json", path, work_id.clone());
+        let s3_key = format!("{}/{}.metadata.json", path, work.id);
         upload_object(
             env::var("S3_USER_BUCKET").unwrap(),
             s3_key.clone(),
@@ -231,248 +375,1937 @@ impl Application {
         )
         .await?;
 
-        Ok(s3_key.to_owned())
+        Ok(("".to_string(), s3_key))
     }
 
-    async fn save_asset721(&self, work_id: String) -> AppResult<()> {
-        let contract_address =
-            env::var("ERC721_CONTRACT_ADDRESS").expect("should set contract address");
-        let token_id = self
-            .ethereum_cli
-            .get_erc721_token_id_of(work_id.clone())
-            .await?;
+    /// gs_pathの指定がなければ動画モードとして、動画のポスターフレームを画像にする
+    /// ループするGIFのプレビューは派生画像のpreviewになる
+    async fn artwork_of(
+        &self,
+        gs_path: Option<String>,
+        animation: Option<&(String, &'static str, Bytes)>,
+    ) -> AppResult<Artwork> {
+        match (gs_path, animation) {
+            (Some(gs_path), _) if !gs_path.is_empty() => self.download_image(gs_path).await,
+            (_, Some((extension, _, data))) => artwork::from_video(extension, data).await,
+            _ => Err(AppError::BadRequest(
+                "動画のない作品では画像の指定が必要です".to_string(),
+            )),
+        }
+    }
 
-        // OpenSeaのレートリミット対応
-        thread::sleep(time::Duration::from_millis(500));
+    /// 作品の画像を取得し、形式・大きさの確認とメタデータの除去、プレビューの生成を行う
+    async fn download_image(&self, gs_path: String) -> AppResult<Artwork> {
+        let urls = self.internal_api.get_signed_urls(vec![gs_path]).await?;
+        let url = urls.first().unwrap();
+        let data = read_capped(reqwest::get(url).await?, artwork::MAX_FILE_SIZE).await?;
+        artwork::process(&data)
+    }
 
-        let asset = self
-            .open_sea_cli
-            .get_asset(open_sea::api::get_asset::Input {
-                address: contract_address.clone(),
-                token_id: token_id.clone().to_string(),
-            })
+    /// プレビューとWebPを {dir}/{作品ID}/ に置く
+    async fn upload_image_variants(
+        &self,
+        dir: &str,
+        work: &Work,
+        artwork: &Artwork,
+    ) -> AppResult<Vec<String>> {
+        let mut urls: Vec<String> = vec![];
+        for variant in artwork.variants.iter() {
+            let url = upload_object(
+                env::var("S3_USER_BUCKET").unwrap(),
+                format!("{}/{}/{}", dir, work.id, variant.file_name()),
+                variant.data.clone(),
+                variant.kind.content_type().to_string(),
+            )
             .await?;
-
-        let mut updated = self.asset721_dao.get(work_id.clone()).await?;
-        updated.published(
-            contract_address.clone(),
-            token_id.clone().to_string(),
-            asset.name,
-            asset.description,
-            asset.image_url,
-            asset.image_preview_url,
-            asset.permalink,
+            urls.push(url);
+        }
+        println!(
+            "uploaded {} image variants for {}: {}x{} {}",
+            urls.len(),
+            work.id,
+            artwork.width,
+            artwork.height,
+            artwork.kind.content_type()
         );
-
-        self.asset721_dao.put(&updated).await?;
-
-        Ok(())
+        Ok(urls)
     }
 
-    async fn save_asset1155(&self, work_id: String) -> AppResult<()> {
-        let contract_address =
-            env::var("ERC1155_CONTRACT_ADDRESS").expect("should set contract address");
-        let token_id = self
-            .ethereum_cli
-            .get_erc1155_token_id_of(work_id.clone())
-            .await?;
-
-        // OpenSeaのレートリミット対応
-        thread::sleep(time::Duration::from_millis(500));
-
-        let asset = self
-            .open_sea_cli
-            .get_asset(open_sea::api::get_asset::Input {
-                address: contract_address.clone(),
-                token_id: token_id.clone().to_string(),
-            })
-            .await?;
+    /// 公開前のコレクションでは準備済みの公開後のmetadataを使い、プレースホルダーでmintする
+    /// 公開時にS3のmetadataを差し替えるため、IPFSの指定があってもtokenURIはS3にする
+    async fn create_hidden_token_metadata(
+        &self,
+        work: &Work,
+        collection: &Collection,
+    ) -> AppResult<(String, String)> {
+        // ハッシュの確定前にmintすると、公開後のmetadataを後から選べてしまう
+        if collection.provenance_hash.is_none() {
+            return Err(AppError::BadRequest(
+                "公開後のmetadataのハッシュを確定してからmintしてください".to_string(),
+            ));
+        }
+        match self
+            .reveal_dao
+            .get(RevealItem::id_of(&collection.id(), &work.id))
+            .await
+        {
+            Ok(_) => {}
+            Err(AppError::NotFound) => {
+                return Err(AppError::BadRequest(format!(
+                    "公開後のmetadataが準備されていない作品です: {}",
+                    work.id
+                )));
+            }
+            Err(e) => return Err(e),
+        }
 
-        let mut updated = self.asset1155_dao.get(work_id.clone()).await?;
-        updated.published(
-            contract_address.clone(),
-            token_id.clone().to_string(),
-            asset.name,
-            asset.description,
-            asset.image_url,
-            asset.image_preview_url,
-            asset.permalink,
+        let metadata = Metadata::placeholder(&self.placeholder_of(collection));
+        let s3_key = format!(
+            "{}/{}.metadata.json",
+            self.asset_path(collection.schema),
+            work.id
         );
+        upload_object(
+            env::var("S3_USER_BUCKET").unwrap(),
+            s3_key.clone(),
+            Bytes::from(metadata.to_json()?),
+            "application/json".to_string(),
+        )
+        .await?;
 
-        self.asset1155_dao.put(&updated).await?;
-
-        Ok(())
+        Ok(("".to_string(), s3_key))
     }
 
-    pub async fn sync_asset(&self) -> AppResult<()> {
-        let used_erc721_ids = self.ethereum_cli.get_erc721_used_names().await?;
-        for work_id in &used_erc721_ids {
-            println!("sync work for erc721: {}", work_id);
+    /// 公開後のmetadataと画像・動画を、公開まで推測できないS3のキーに置いておく
+    /// IPFSを使う場合、URLは手元で計算したCIDにして公開時にピン留めする
+    async fn stage_reveal_item(
+        &self,
+        work: &Work,
+        collection: &Collection,
+        supply: u64,
+        gs_path: Option<String>,
+        use_ipfs: bool,
+    ) -> AppResult<RevealItem> {
+        if collection.provenance_hash.is_some() {
+            return Err(AppError::BadRequest(
+                "公開後のmetadataのハッシュを確定済みのため、作品を追加できません".to_string(),
+            ));
+        }
 
-            let work = self.work_dao.get(work_id.clone()).await;
-            if let Err(err) = work {
-                if err != AppError::NotFound {
-                    return Err(err);
-                }
-                continue;
+        let schema = collection.schema;
+        let bucket = env::var("S3_USER_BUCKET").unwrap();
+        let dir = format!(
+            "{}/staged/{}",
+            self.asset_path(schema),
+            uuid::Uuid::new_v4()
+        );
+        let mut assets: Vec<StagedAsset> = vec![];
+
+        let animation = self.download_animation(work).await?;
+        let artwork = self.artwork_of(gs_path, animation.as_ref()).await?;
+        self.upload_image_variants(&dir, work, &artwork).await?;
+        let content_type = artwork.kind.content_type().to_string();
+        let name = format!("{}.{}", work.id, artwork.kind.extension());
+        let s3_key = format!("{}/{}", dir, name);
+        let cid = ipfs::compute(&artwork.data).to_string();
+        let s3_url = upload_object(
+            bucket.clone(),
+            s3_key.clone(),
+            artwork.data,
+            content_type.clone(),
+        )
+        .await?;
+        let image_url = if use_ipfs {
+            format!("ipfs://{}", cid)
+        } else {
+            s3_url
+        };
+        assets.push(StagedAsset {
+            kind: PinKind::Image,
+            name,
+            s3_key,
+            content_type,
+        });
+
+        let animation_url = match animation {
+            Some((extension, content_type, data)) => {
+                let name = format!("{}.{}", work.id, extension);
+                let s3_key = format!("{}/{}", dir, name);
+                let cid = ipfs::compute(&data).to_string();
+                let s3_url = upload_object(
+                    bucket.clone(),
+                    s3_key.clone(),
+                    data,
+                    content_type.to_string(),
+                )
+                .await?;
+                assets.push(StagedAsset {
+                    kind: PinKind::Animation,
+                    name,
+                    s3_key,
+                    content_type: content_type.to_string(),
+                });
+                Some(if use_ipfs {
+                    format!("ipfs://{}", cid)
+                } else {
+                    s3_url
+                })
             }
-            let mut work = work.ok().unwrap();
+            None => None,
+        };
 
-            self.save_asset721(work.id.clone()).await?;
+        let royalty = self.royalty_of(work, schema).await?;
+        let rendered = self.render_metadata(work, schema, supply).await?;
+        let metadata = Metadata::new(work, rendered, image_url, animation_url, royalty.as_ref());
+
+        let item = RevealItem::new(
+            work.id.clone(),
+            collection.chain_id,
+            schema,
+            collection.contract_address.clone(),
+            serde_json::to_string(&metadata)?,
+            use_ipfs,
+            assets,
+            now(),
+        );
+        self.reveal_dao.put(&item).await?;
 
-            if work.status == WorkStatus::Prepare {
-                work.status = WorkStatus::PublishNFT;
-                self.work_dao.put(&work).await?;
-            }
+        Ok(item)
+    }
+
+    /// mintの前に公開後のmetadataを準備する。証明用のハッシュはすべて準備してから確定する
+    /// editionは準備した順に振るので、mintも同じ順で行う
+    pub async fn stage_reveal(
+        &self,
+        work_id: String,
+        schema: TokenSchema,
+        gs_path: Option<String>,
+        amount: u32,
+        use_ipfs: bool,
+    ) -> AppResult<RevealItem> {
+        let collection = self.collection(schema).await?;
+        if !collection.hidden {
+            return Err(AppError::BadRequest(
+                "公開前のコレクションではありません".to_string(),
+            ));
         }
+        let work = self.work_dao.get(work_id).await?;
+        let supply = match schema {
+            TokenSchema::Erc721 => 1,
+            TokenSchema::Erc1155 => amount as u64,
+        };
 
-        let used_erc1155_ids = self.ethereum_cli.get_erc1155_used_names().await?;
-        for work_id in &used_erc1155_ids {
-            println!("sync work for erc1155: {}", work_id);
+        self.stage_reveal_item(&work, &collection, supply, gs_path, use_ipfs)
+            .await
+    }
 
-            let work = self.work_dao.get(work_id.clone()).await;
-            if let Err(err) = work {
-                if err != AppError::NotFound {
-                    return Err(err);
-                }
-                continue;
-            }
-            let mut work = work.ok().unwrap();
+    /// 準備したすべての公開後のmetadataから証明用のハッシュを計算して確定する
+    pub async fn commit_provenance(&self, schema: TokenSchema) -> AppResult<String> {
+        let mut collection = self.collection(schema).await?;
+        if !collection.hidden {
+            return Err(AppError::BadRequest(
+                "公開前のコレクションではありません".to_string(),
+            ));
+        }
+        if collection.provenance_hash.is_some() {
+            return Err(AppError::BadRequest(
+                "証明用のハッシュは確定済みです".to_string(),
+            ));
+        }
 
-            self.save_asset1155(work.id.clone()).await?;
+        let items = self.reveal_dao.get_by_collection(collection.id()).await?;
+        if items.is_empty() {
+            return Err(AppError::BadRequest(
+                "公開後のmetadataが準備されていません".to_string(),
+            ));
+        }
+        let hash = provenance_hash(
+            &items
+                .iter()
+                .map(|v| (v.work_id.clone(), v.metadata_hash.clone()))
+                .collect::<Vec<_>>(),
+        );
 
-            if work.status == WorkStatus::Prepare {
-                work.status = WorkStatus::PublishNFT;
-                self.work_dao.put(&work).await?;
-            }
+        // 後から差し替えていないことを誰でも確かめられるよう、コントラクトにも書き込む
+        // 書き込み後に保存できなかった場合は、同じハッシュであればやり直せる
+        let published = self.ethereum_cli.provenance_hash(schema).await?;
+        if published.is_empty() {
+            let user = self.user_dao.get(self.me_id.clone()).await?;
+            self.ethereum_cli
+                .set_provenance_hash(&user, schema, hash.clone())
+                .await?;
+        } else if published != hash {
+            return Err(AppError::BadRequest(format!(
+                "コントラクトに別のハッシュが書き込まれています: {}",
+                published
+            )));
         }
 
-        println!("delete asset if need");
+        collection.provenance_hash = Some(hash.clone());
+        collection.updated_at = now();
+        self.collection_dao.put(&collection).await?;
 
-        let all_asset721 = self.asset721_dao.get_all().await?;
-        for asset in all_asset721.iter().filter(|asset| {
-            used_erc721_ids
-                .clone()
-                .into_iter()
-                .find(|id| id.to_string() == asset.work_id)
-                .is_none()
-        }) {
-            let id = asset.to_owned().work_id;
-            println!("delete erc721 asset: {}", id.clone());
-            self.asset721_dao.delete(id).await?;
-        }
+        Ok(hash)
+    }
 
-        let all_asset1155 = self.asset1155_dao.get_all().await?;
-        for asset in all_asset1155.iter().filter(|asset| {
-            used_erc1155_ids
-                .clone()
-                .into_iter()
-                .find(|id| id.to_string() == asset.work_id)
-                .is_none()
-        }) {
-            let id = asset.to_owned().work_id;
-            println!("delete erc1155 asset: {}", id.clone());
-            self.asset1155_dao.delete(id).await?;
+    /// 準備した公開後のmetadataをS3（とIPFS）に出し、metadataの配信を切り替える
+    pub async fn reveal(&self, schema: TokenSchema) -> AppResult<()> {
+        let mut collection = self.collection(schema).await?;
+        if !collection.hidden {
+            return Err(AppError::BadRequest(
+                "公開前のコレクションではありません".to_string(),
+            ));
         }
 
-        Ok(())
-    }
+        let bucket = env::var("S3_USER_BUCKET").unwrap();
+        let path = self.asset_path(schema);
+        for mut item in self.reveal_dao.get_by_collection(collection.id()).await? {
+            if item.revealed {
+                continue;
+            }
 
-    pub async fn sell_erc721(&self, work_id: String, ether: f64) -> AppResult<()> {
-        let user = self.user_dao.get(self.me_id.clone()).await?;
-        let mut work = self.work_dao.get(work_id.clone()).await?;
+            let work = self.work_dao.get(item.work_id.clone()).await?;
+            if item.use_ipfs {
+                for asset in &item.assets {
+                    let data = download_object(bucket.clone(), asset.s3_key.clone()).await?;
+                    self.pin_to_ipfs(&work, asset.kind, asset.name.clone(), data)
+                        .await?;
+                }
+                self.pin_to_ipfs(
+                    &work,
+                    PinKind::Metadata,
+                    format!("{}.metadata.json", work.id),
+                    Bytes::from(item.metadata.clone()),
+                )
+                .await?;
+            }
 
-        let contract_address =
-            env::var("ERC721_CONTRACT_ADDRESS").expect("should set contract address");
-        let token_id = self
-            .ethereum_cli
-            .get_erc721_token_id_of(work_id.clone())
+            upload_object(
+                bucket.clone(),
+                format!("{}/{}.metadata.json", path, item.work_id),
+                Bytes::from(item.metadata.clone()),
+                "application/json".to_string(),
+            )
             .await?;
+            println!("revealed: {}", item.work_id);
 
-        lambda::invoke_open_sea_sdk(lambda::invoke_open_sea_sdk::Input::sell_erc721(
-            user,
-            contract_address,
-            token_id.to_string(),
-            ether,
-        ))
-        .await?;
+            item.revealed(now());
+            self.reveal_dao.put(&item).await?;
+        }
 
-        work.status = WorkStatus::SellOrder;
-        self.work_dao.put(&work).await?;
+        collection.hidden = false;
+        collection.revealed_at = Some(now());
+        collection.updated_at = now();
+        self.collection_dao.put(&collection).await
+    }
 
-        Ok(())
+    /// 公開されているmetadataから計算したハッシュが、コントラクトに確定したものと一致するか。公開前ならNone
+    pub async fn verify_provenance(&self, schema: TokenSchema) -> AppResult<Option<bool>> {
+        let path = self.asset_path(schema);
+        self.verify_provenance_with(schema, |work_id| {
+            download_object(
+                env::var("S3_USER_BUCKET").unwrap(),
+                format!("{}/{}.metadata.json", path, work_id),
+            )
+        })
+        .await
     }
 
-    pub async fn sell_erc1155(&self, work_id: String, ether: f64) -> AppResult<()> {
-        let user = self.user_dao.get(self.me_id.clone()).await?;
-        let mut work = self.work_dao.get(work_id.clone()).await?;
+    /// 公開されているmetadataを作品IDからfetchで取得して確かめる
+    async fn verify_provenance_with<F, Fut>(
+        &self,
+        schema: TokenSchema,
+        fetch: F,
+    ) -> AppResult<Option<bool>>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = AppResult<Bytes>>,
+    {
+        let collection = self.collection(schema).await?;
+        if collection.provenance_hash.is_none() || collection.revealed_at.is_none() {
+            return Ok(None);
+        }
+        let committed = self.ethereum_cli.provenance_hash(schema).await?;
 
-        let contract_address =
-            env::var("ERC1155_CONTRACT_ADDRESS").expect("should set contract address");
-        let token_id = self
-            .ethereum_cli
-            .get_erc1155_token_id_of(work_id.clone())
-            .await?;
+        let mut hashes: Vec<(String, String)> = vec![];
+        for item in self.reveal_dao.get_by_collection(collection.id()).await? {
+            let data = fetch(item.work_id.clone()).await?;
+            hashes.push((item.work_id, sha256_hex(&data)));
+        }
 
-        lambda::invoke_open_sea_sdk(lambda::invoke_open_sea_sdk::Input::sell_erc1155(
-            user,
-            contract_address,
-            token_id.to_string(),
-            ether,
-        ))
-        .await?;
+        Ok(Some(provenance_hash(&hashes) == committed))
+    }
 
-        work.status = WorkStatus::SellOrder;
-        self.work_dao.put(&work).await?;
+    pub async fn update_reveal_placeholder(
+        &self,
+        schema: TokenSchema,
+        placeholder: Placeholder,
+    ) -> AppResult<()> {
+        let mut collection = self.collection(schema).await?;
+        collection.placeholder = Some(placeholder);
+        collection.updated_at = now();
+        self.collection_dao.put(&collection).await
+    }
 
-        Ok(())
+    fn placeholder_of(&self, collection: &Collection) -> Placeholder {
+        collection
+            .placeholder
+            .clone()
+            .unwrap_or_else(|| Placeholder {
+                name: "Unrevealed".to_string(),
+                description: "".to_string(),
+                image_url: env::var("METADATA_PLACEHOLDER_IMAGE_URL").unwrap_or_default(),
+                attributes: vec![],
+            })
     }
 
-    pub async fn transfer_erc721(&self, work_id: String, to_address: String) -> AppResult<()> {
-        let user = self.user_dao.get(self.me_id.clone()).await?;
+    /// 同じバケットを複数チェーンで共有するため、キーにチェーンIDを含める
+    fn asset_path(&self, schema: TokenSchema) -> String {
+        format!(
+            "{}/{}",
+            legacy_asset_path(schema),
+            self.ethereum_cli.chain().chain_id
+        )
+    }
+
+    /// APIから配信するmetadata。戻り値は(JSON, 公開前か)
+    /// token_idは10進数か、ERC1155の{id}の置き換えに使う16進数
+    pub async fn hosted_metadata(
+        &self,
+        contract_address: String,
+        token_id: String,
+    ) -> AppResult<(String, bool)> {
+        let chain = self.ethereum_cli.chain();
+        let schema = if self.ethereum_cli.equal_address(
+            contract_address.clone(),
+            chain.erc721_contract_address.clone(),
+        ) {
+            TokenSchema::Erc721
+        } else if self
+            .ethereum_cli
+            .equal_address(contract_address, chain.erc1155_contract_address.clone())
+        {
+            TokenSchema::Erc1155
+        } else {
+            return Err(AppError::NotFound);
+        };
+        self.hosted_metadata_of(schema, token_id).await
+    }
+
+    pub async fn hosted_metadata_of(
+        &self,
+        schema: TokenSchema,
+        token_id: String,
+    ) -> AppResult<(String, bool)> {
+        let token_id = parse_token_id(&token_id)?;
+        let work_id = self.work_id_of_token(schema, token_id).await?;
+
+        let collection = self.collection(schema).await?;
+        if collection.hidden {
+            let metadata = Metadata::placeholder(&self.placeholder_of(&collection));
+            return Ok((metadata.to_json()?, true));
+        }
+
+        let bucket = env::var("S3_USER_BUCKET").unwrap();
+        let data = match download_object(
+            bucket.clone(),
+            format!("{}/{}.metadata.json", self.asset_path(schema), work_id),
+        )
+        .await
+        {
+            // チェーンIDをキーに含める前に発行したもの
+            Err(AppError::NotFound) => {
+                download_object(
+                    bucket,
+                    format!("{}/{}.metadata.json", legacy_asset_path(schema), work_id),
+                )
+                .await?
+            }
+            v => v?,
+        };
+        let json = String::from_utf8(data.to_vec())
+            .map_err(|e| AppError::Internal(format!("invalid metadata: {}", e)))?;
+        Ok((json, false))
+    }
+
+    /// トークンIDから作品IDを引く
+    /// 発行済みの名前は追記されるだけなので、キャッシュにないIDのときだけコントラクトから取り直す
+    async fn work_id_of_token(&self, schema: TokenSchema, token_id: u64) -> AppResult<String> {
+        // トークンIDは1から順に振られる
+        let index = token_id.checked_sub(1).ok_or(AppError::NotFound)? as usize;
+        let key = (self.ethereum_cli.chain().chain_id, schema);
+        if let Some(work_id) = TOKEN_NAMES
+            .lock()
+            .unwrap()
+            .get(&key)
+            .and_then(|v| v.get(index))
+        {
+            return Ok(work_id.clone());
+        }
+
+        let names = match schema {
+            TokenSchema::Erc721 => self.ethereum_cli.get_erc721_used_names().await?,
+            TokenSchema::Erc1155 => self.ethereum_cli.get_erc1155_used_names().await?,
+        };
+        let work_id = names.get(index).cloned();
+        TOKEN_NAMES.lock().unwrap().insert(key, names);
+        work_id.ok_or(AppError::NotFound)
+    }
+
+    /// 公開前にするとmetadataの配信がプレースホルダーになる
+    pub async fn update_collection_visibility(
+        &self,
+        schema: TokenSchema,
+        hidden: bool,
+    ) -> AppResult<()> {
+        let mut collection = self.collection(schema).await?;
+        if !hidden {
+            let items = self.reveal_dao.get_by_collection(collection.id()).await?;
+            if items.iter().any(|v| !v.revealed) {
+                return Err(AppError::BadRequest(
+                    "公開前の作品があるため、revealで公開してください".to_string(),
+                ));
+            }
+        }
+        collection.hidden = hidden;
+        collection.updated_at = now();
+        self.collection_dao.put(&collection).await
+    }
+
+    /// コントラクトのtokenURIをAPIのmetadataの配信に向ける
+    pub async fn use_hosted_metadata(&self, schema: TokenSchema) -> AppResult<String> {
+        let user = self.user_dao.get(self.me_id.clone()).await?;
+        let base_url = env::var("METADATA_BASE_URL").expect("should set metadata base url");
+        let base_url = base_url.trim_end_matches('/');
+        let base_uri = match schema {
+            TokenSchema::Erc721 => format!(
+                "{}/metadata/{}/",
+                base_url,
+                self.ethereum_cli.chain().erc721_contract_address
+            ),
+            TokenSchema::Erc1155 => format!("{}/metadata/1155/{{id}}.json", base_url),
+        };
+
+        // IPFSだけに置いたものなど、配信元にないmetadataを切り替える前に写しておく
+        let names = match schema {
+            TokenSchema::Erc721 => self.ethereum_cli.get_erc721_used_names().await?,
+            TokenSchema::Erc1155 => self.ethereum_cli.get_erc1155_used_names().await?,
+        };
+        let bucket = env::var("S3_USER_BUCKET").unwrap();
+        for (i, work_id) in names.iter().enumerate() {
+            let key = format!("{}/{}.metadata.json", self.asset_path(schema), work_id);
+            match download_object(bucket.clone(), key.clone()).await {
+                Err(AppError::NotFound) => {}
+                v => {
+                    v?;
+                    continue;
+                }
+            }
+
+            let token_id = i as u128 + 1;
+            let uri = self.ethereum_cli.token_uri(schema, token_id).await?;
+            if uri.starts_with(base_url) {
+                continue;
+            }
+            let data = fetch_uri(&uri.replace("{id}", &format!("{:064x}", token_id))).await?;
+            upload_object(bucket.clone(), key, data, "application/json".to_string()).await?;
+            println!("copied metadata of {} from {}", work_id, uri);
+        }
+
+        self.ethereum_cli
+            .set_metadata_base_uri(&user, schema, base_uri.clone())
+            .await?;
+        Ok(base_uri)
+    }
+
+    /// コレクションのテンプレートで名前・説明などを展開する
+    async fn render_metadata(
+        &self,
+        work: &Work,
+        schema: TokenSchema,
+        supply: u64,
+    ) -> AppResult<RenderedMetadata> {
+        let thumbnails = self.thumbnail_dao.get_by_work(work.id.clone()).await?;
+        let names = match schema {
+            TokenSchema::Erc721 => self.ethereum_cli.get_erc721_used_names().await?,
+            TokenSchema::Erc1155 => self.ethereum_cli.get_erc1155_used_names().await?,
+        };
+        // mint前であれば次の番号になる。公開前のコレクションではmint待ちの準備済みの作品の後に続く
+        let collection = self.collection(schema).await?;
+        let staged = if collection.hidden {
+            self.reveal_dao
+                .get_by_collection(collection.id())
+                .await?
+                .iter()
+                .filter(|v| v.work_id != work.id && !names.contains(&v.work_id))
+                .count()
+        } else {
+            0
+        };
+        let edition = names
+            .iter()
+            .position(|v| v == &work.id)
+            .unwrap_or(names.len() + staged)
+            + 1;
+
+        let template = self.metadata_template(schema).await?;
+        template.render(&TemplateValues::new(
+            work,
+            &thumbnails,
+            edition as u64,
+            supply,
+        ))
+    }
+
+    /// mint前にmetadataを確認する。画像と動画はアップロード時に決まるため含まない
+    pub async fn preview_metadata(
+        &self,
+        work_id: String,
+        schema: TokenSchema,
+        supply: u64,
+    ) -> AppResult<Metadata> {
+        let work = self.work_dao.get(work_id).await?;
+        let royalty = self.royalty_of(&work, schema).await?;
+        let rendered = self.render_metadata(&work, schema, supply).await?;
+
+        Ok(Metadata::new(
+            &work,
+            rendered,
+            "".to_string(),
+            None,
+            royalty.as_ref(),
+        ))
+    }
+
+    /// 作品の動画を取得する。戻り値は(拡張子, Content-Type, データ)
+    async fn download_animation(
+        &self,
+        work: &Work,
+    ) -> AppResult<Option<(String, &'static str, Bytes)>> {
+        if work.video_path.is_empty() {
+            return Ok(None);
+        }
+        let extension = work
+            .video_path
+            .rsplit_once('.')
+            .map(|(_, v)| v.to_lowercase())
+            .unwrap_or_default();
+        let content_type = match extension.as_str() {
+            "mp4" | "m4v" => "video/mp4",
+            "webm" => "video/webm",
+            "mov" => "video/quicktime",
+            "ogv" => "video/ogg",
+            // animation_urlなしで画像だけのmetadataにする
+            _ => {
+                println!("skipped unsupported animation: {}", work.video_path);
+                return Ok(None);
+            }
+        };
+
+        let urls = self
+            .internal_api
+            .get_signed_urls(vec![work.video_path.clone()])
+            .await?;
+        let url = urls.first().unwrap();
+        let bytes = reqwest::get(url).await?.bytes().await?;
+
+        Ok(Some((extension, content_type, bytes)))
+    }
+
+    /// IPFSに追加して、ピン留めの状態を記録する
+    async fn pin_to_ipfs(
+        &self,
+        work: &Work,
+        kind: PinKind,
+        name: String,
+        data: Bytes,
+    ) -> AppResult<String> {
+        let size = data.len() as u64;
+        let cid = self.ipfs.add(&name, data).await?;
+        let status = self.ipfs.status(&cid).await?;
+
+        let pin = Pin::new(
+            cid.clone(),
+            work.id.clone(),
+            kind,
+            self.ipfs.provider_name().to_string(),
+            name,
+            size,
+            status,
+            now(),
+        );
+        self.pin_dao.put(&pin).await?;
+
+        Ok(cid)
+    }
+
+    pub async fn pins(&self, work_id: String) -> AppResult<Vec<Pin>> {
+        let mut pins = self.pin_dao.get_by_work(work_id).await?;
+        pins.sort_by_key(|v| v.created_at);
+        Ok(pins)
+    }
+
+    /// mint済みのトークンについて、tokenURIの指すmetadataと画像・動画を手元の内容と照らし合わせる
+    /// マーケットプレイスの表示が古ければ再取得を依頼する
+    pub async fn check_metadata(&self) -> AppResult<Vec<MetadataIssue>> {
+        let mut issues: Vec<MetadataIssue> = vec![];
+        let mut rate_limited = false;
+        let mut deferred: Vec<String> = vec![];
+        for schema in [TokenSchema::Erc721, TokenSchema::Erc1155] {
+            let names = match schema {
+                TokenSchema::Erc721 => self.ethereum_cli.get_erc721_used_names().await?,
+                TokenSchema::Erc1155 => self.ethereum_cli.get_erc1155_used_names().await?,
+            };
+            // トークンIDは1から順に振られる
+            for (i, work_id) in names.iter().enumerate() {
+                let token_id = (i + 1) as u128;
+                let issue = |kind: MetadataIssueKind, detail: String| MetadataIssue {
+                    work_id: work_id.clone(),
+                    schema,
+                    token_id: token_id.to_string(),
+                    kind,
+                    detail,
+                };
+
+                // 1件の失敗で残りのトークンの検査を止めない
+                let mut found = vec![];
+                match self.check_token_metadata(schema, token_id, work_id).await {
+                    Ok((v, Some(published))) => {
+                        found.extend(v);
+                        if rate_limited {
+                            deferred.push(work_id.clone());
+                        } else {
+                            match self
+                                .check_marketplace_metadata(schema, token_id, &published)
+                                .await
+                            {
+                                Ok(Some(detail)) => {
+                                    found.push(issue(MetadataIssueKind::MarketplaceStale, detail))
+                                }
+                                Ok(None) => {}
+                                Err(AppError::RateLimited(_)) => {
+                                    // 残りは次回のバッチに回す
+                                    rate_limited = true;
+                                    deferred.push(work_id.clone());
+                                }
+                                Err(e) => {
+                                    found.push(issue(MetadataIssueKind::CheckFailed, e.to_string()))
+                                }
+                            }
+                        }
+                    }
+                    Ok((v, None)) => found.extend(v),
+                    Err(e) => found.push(issue(MetadataIssueKind::CheckFailed, e.to_string())),
+                }
+                for issue in &found {
+                    println!(
+                        "metadata issue {} {} #{}: {} {}",
+                        schema, issue.work_id, issue.token_id, issue.kind, issue.detail
+                    );
+                }
+                issues.extend(found);
+            }
+        }
+
+        if !deferred.is_empty() {
+            println!("deferred by open sea rate limit: {:?}", deferred);
+        }
+        println!("metadata check finished: {} issues", issues.len());
+        Ok(issues)
+    }
+
+    /// 戻り値は(見つかった問題, 読めた場合は公開中のmetadata)
+    async fn check_token_metadata(
+        &self,
+        schema: TokenSchema,
+        token_id: u128,
+        work_id: &str,
+    ) -> AppResult<(Vec<MetadataIssue>, Option<Metadata>)> {
+        let issue = |kind: MetadataIssueKind, detail: String| MetadataIssue {
+            work_id: work_id.to_string(),
+            schema,
+            token_id: token_id.to_string(),
+            kind,
+            detail,
+        };
+        let mut issues: Vec<MetadataIssue> = vec![];
+
+        let uri = self
+            .ethereum_cli
+            .token_uri(schema, token_id)
+            .await?
+            .replace("{id}", &format!("{:064x}", token_id));
+        let data = match fetch_uri(&uri).await {
+            Ok(data) => data,
+            Err(e) => {
+                issues.push(issue(
+                    MetadataIssueKind::DeadMetadata,
+                    format!("{}: {}", uri, e),
+                ));
+                return Ok((issues, None));
+            }
+        };
+        if ipfs::matches_cid(&uri, &data) == Some(false) {
+            issues.push(issue(MetadataIssueKind::MetadataHashMismatch, uri.clone()));
+        }
+        let (published, published_json) = match (
+            serde_json::from_slice::<Metadata>(&data),
+            serde_json::from_slice::<serde_json::Value>(&data),
+        ) {
+            (Ok(published), Ok(json)) => (published, json),
+            (Err(e), _) | (_, Err(e)) => {
+                issues.push(issue(
+                    MetadataIssueKind::InvalidMetadata,
+                    format!("{}: {}", uri, e),
+                ));
+                return Ok((issues, None));
+            }
+        };
+
+        match download_object(
+            env::var("S3_USER_BUCKET").unwrap(),
+            format!("{}/{}.metadata.json", self.asset_path(schema), work_id),
+        )
+        .await
+        {
+            Ok(saved) => {
+                if serde_json::from_slice::<serde_json::Value>(&saved).ok()
+                    != Some(published_json.clone())
+                {
+                    issues.push(issue(MetadataIssueKind::MetadataMismatch, uri.clone()));
+                }
+            }
+            // IPFSだけに置いた以前のトークンには控えがない
+            Err(AppError::NotFound) => {}
+            Err(e) => return Err(e),
+        }
+
+        // 発行数は記録していないため、ERC1155でテンプレートが使っている場合は比べない
+        let collection = self.collection(schema).await?;
+        let template = collection.metadata_template.clone().unwrap_or_default();
+        if !collection.hidden && (schema == TokenSchema::Erc721 || !template.uses("supply")) {
+            let work = self.work_dao.get(work_id.to_string()).await?;
+            let royalty = self.royalty_of(&work, schema).await?;
+            let rendered = self.render_metadata(&work, schema, 1).await?;
+            let expected = Metadata::new(
+                &work,
+                rendered,
+                published.image.clone(),
+                published.animation_url.clone(),
+                royalty.as_ref(),
+            );
+            if serde_json::to_value(&expected)? != published_json {
+                issues.push(issue(MetadataIssueKind::OutdatedMetadata, uri.clone()));
+            }
+        }
+
+        for (url, dead, mismatch) in [
+            (
+                Some(published.image.clone()),
+                MetadataIssueKind::DeadImage,
+                MetadataIssueKind::ImageHashMismatch,
+            ),
+            (
+                published.animation_url.clone(),
+                MetadataIssueKind::DeadAnimation,
+                MetadataIssueKind::AnimationHashMismatch,
+            ),
+        ] {
+            let url = match url {
+                Some(url) if !url.is_empty() => url,
+                _ => continue,
+            };
+            match fetch_uri(&url).await {
+                Ok(data) => {
+                    if ipfs::matches_cid(&url, &data) == Some(false) {
+                        issues.push(issue(mismatch, url));
+                    }
+                }
+                Err(e) => issues.push(issue(dead, format!("{}: {}", url, e))),
+            }
+        }
+
+        Ok((issues, Some(published)))
+    }
+
+    /// マーケットプレイスの表示が古ければ再取得を依頼し、その内容を返す
+    async fn check_marketplace_metadata(
+        &self,
+        schema: TokenSchema,
+        token_id: u128,
+        published: &Metadata,
+    ) -> AppResult<Option<String>> {
+        let contract_address = match schema {
+            TokenSchema::Erc721 => self.ethereum_cli.chain().erc721_contract_address.clone(),
+            TokenSchema::Erc1155 => self.ethereum_cli.chain().erc1155_contract_address.clone(),
+        };
+        let token = TokenRef::new(schema, contract_address, token_id.to_string());
+        let asset = match self.marketplace.get_asset(&token).await {
+            Ok(asset) => asset,
+            // まだマーケットプレイスに登録されていない
+            Err(AppError::NotFound) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if asset.name == published.name && asset.description == published.description {
+            return Ok(None);
+        }
+
+        let requested = self.marketplace.refresh_metadata(&token).await?;
+        Ok(Some(format!(
+            "{}: {}",
+            self.marketplace.name(),
+            if requested {
+                "再取得を依頼しました"
+            } else {
+                "再取得に対応していません"
+            }
+        )))
+    }
+
+    /// ピン留めが完了していないものの状態を取り直す
+    pub async fn sync_pin_status(&self) -> AppResult<()> {
+        for mut pin in self.pin_dao.get_all().await? {
+            if pin.status == PinStatus::Pinned {
+                continue;
+            }
+            let status = self.ipfs.status(&pin.cid).await?;
+            if status != pin.status {
+                println!(
+                    "pin {} {}: {} -> {}",
+                    pin.work_id, pin.cid, pin.status, status
+                );
+                pin.changed(status, now());
+                self.pin_dao.put(&pin).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn save_asset721(&self, work_id: String) -> AppResult<()> {
+        let contract_address = self.ethereum_cli.chain().erc721_contract_address.clone();
+        let token_id = self
+            .ethereum_cli
+            .get_erc721_token_id_of(work_id.clone())
+            .await?;
+
+        let token = TokenRef::new(
+            TokenSchema::Erc721,
+            contract_address.clone(),
+            token_id.to_string(),
+        );
+        let asset = self.marketplace.get_asset(&token).await?;
+
+        let price = asset.price.clone();
+        let mut updated = self.asset721_dao.get(work_id.clone()).await?;
+        updated.published(
+            self.ethereum_cli.chain().chain_id,
+            contract_address.clone(),
+            token_id.clone().to_string(),
+            asset.name,
+            asset.description,
+            asset.image_url,
+            asset.image_preview_url,
+            asset.permalink,
+        );
+        updated.priced(&price);
+
+        self.asset721_dao.put(&updated).await?;
+        self.record_price(work_id.clone(), price).await?;
+        self.sync_offers(work_id.clone(), &token).await?;
+        self.sync_sales(work_id, &token).await?;
+
+        Ok(())
+    }
+
+    async fn save_asset1155(&self, work_id: String) -> AppResult<()> {
+        let contract_address = self.ethereum_cli.chain().erc1155_contract_address.clone();
+        let token_id = self
+            .ethereum_cli
+            .get_erc1155_token_id_of(work_id.clone())
+            .await?;
+
+        let token = TokenRef::new(
+            TokenSchema::Erc1155,
+            contract_address.clone(),
+            token_id.to_string(),
+        );
+        let asset = self.marketplace.get_asset(&token).await?;
+
+        let price = asset.price.clone();
+        let mut updated = self.asset1155_dao.get(work_id.clone()).await?;
+        updated.published(
+            self.ethereum_cli.chain().chain_id,
+            contract_address.clone(),
+            token_id.clone().to_string(),
+            asset.name,
+            asset.description,
+            asset.image_url,
+            asset.image_preview_url,
+            asset.permalink,
+        );
+        updated.priced(&price);
+
+        self.asset1155_dao.put(&updated).await?;
+        self.record_price(work_id.clone(), price).await?;
+        self.sync_offers(work_id.clone(), &token).await?;
+        self.sync_sales(work_id, &token).await?;
+
+        Ok(())
+    }
+
+    /// 直近の記録から価格が変わっていれば履歴に追加する
+    async fn record_price(&self, work_id: String, price: MarketPrice) -> AppResult<()> {
+        let latest = self
+            .price_snapshot_dao
+            .get_by_work(work_id.clone(), Some(1))
+            .await?;
+        if latest.first().map(|v| &v.price) == Some(&price) {
+            return Ok(());
+        }
+
+        self.price_snapshot_dao
+            .put(&PriceSnapshot::new(work_id, now(), price))
+            .await
+    }
+
+    /// マーケットプレイスの申し込みを保存し、なくなったものは終了にする
+    async fn sync_offers(&self, work_id: String, token: &TokenRef) -> AppResult<()> {
+        let fetched = self.marketplace.get_offers(token).await?;
+        let stored = self.offer_dao.get_by_work(work_id.clone()).await?;
+
+        for offer in &fetched {
+            if stored.iter().any(|v| v.id == offer.id) {
+                continue;
+            }
+            self.offer_dao
+                .put(&Offer::new(
+                    work_id.clone(),
+                    self.ethereum_cli.chain().chain_id,
+                    self.marketplace.name().to_string(),
+                    token,
+                    offer.clone(),
+                    now(),
+                ))
+                .await?;
+        }
+
+        for mut offer in stored {
+            if offer.status == OfferStatus::Open && !fetched.iter().any(|v| v.id == offer.id) {
+                offer.changed(OfferStatus::Closed, now());
+                self.offer_dao.put(&offer).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// マーケットプレイスの売買を台帳に記録する
+    /// こちらで承諾した申し込みでtx hashがまだないものは、同じ内容のイベントで埋める
+    async fn sync_sales(&self, work_id: String, token: &TokenRef) -> AppResult<()> {
+        let events = self.marketplace.get_sales(token).await?;
+        let mut stored = self.sale_dao.get_by_work(work_id.clone()).await?;
+        let work = self.work_dao.get(work_id.clone()).await?;
+        let royalty_basis_points = self
+            .royalty_of(&work, token.schema)
+            .await?
+            .map(|v| v.basis_points)
+            .unwrap_or_default();
+
+        for event in events {
+            if event.tx_hash.is_empty() || stored.iter().any(|v| v.tx_hash == event.tx_hash) {
+                continue;
+            }
+
+            let pending = stored.iter_mut().find(|v| {
+                v.tx_hash.is_empty()
+                    && v.source == SaleSource::Offer
+                    && v.buyer.eq_ignore_ascii_case(&event.buyer)
+                    && v.price == event.price
+            });
+            if let Some(sale) = pending {
+                sale.tx_hash = event.tx_hash.clone();
+                sale.sold_at = event.occurred_at;
+                self.sale_dao.put(sale).await?;
+                continue;
+            }
+
+            let publisher_id = match self
+                .user_dao
+                .get_by_wallet_address(event.seller.clone())
+                .await
+            {
+                Ok(user) => user.id,
+                Err(AppError::NotFound) => "".to_string(),
+                Err(e) => return Err(e),
+            };
+            let mut sale = Sale::new(
+                work_id.clone(),
+                self.ethereum_cli.chain().chain_id,
+                SaleSource::Marketplace,
+                self.marketplace.name().to_string(),
+                token,
+                publisher_id,
+                event.seller,
+                event.buyer,
+                event.currency,
+                event.price,
+                event.quantity,
+                event.tx_hash,
+                event.occurred_at,
+            );
+            sale.charged(self.marketplace.fee_basis_points(), royalty_basis_points);
+            self.sale_dao.put(&sale).await?;
+            stored.push(sale);
+        }
+
+        Ok(())
+    }
+
+    /// 新しい順
+    pub async fn offers(&self, work_id: String) -> AppResult<Vec<Offer>> {
+        let chain_id = self.ethereum_cli.chain().chain_id;
+        let mut offers: Vec<Offer> = self
+            .offer_dao
+            .get_by_work(work_id)
+            .await?
+            .into_iter()
+            .filter(|v| v.chain_id == chain_id)
+            .collect();
+        offers.sort_by_key(|v| Reverse(v.created_at));
+        Ok(offers)
+    }
+
+    async fn open_offer(&self, offer_id: String) -> AppResult<Offer> {
+        let offer = self.offer_dao.get(offer_id).await?;
+        if !offer.is_open(now()) {
+            return Err(AppError::BadRequest(
+                "受付中の申し込みではありません".to_string(),
+            ));
+        }
+        Ok(offer)
+    }
+
+    /// ERC721は出品を取り消してから承諾し、作品を売却済みにする
+    pub async fn accept_offer(&self, offer_id: String) -> AppResult<()> {
+        let user = self.user_dao.get(self.me_id.clone()).await?;
+        let mut offer = self.open_offer(offer_id).await?;
+        if offer.marketplace != self.marketplace.name() {
+            return Err(AppError::BadRequest(format!(
+                "{}の申し込みは承諾できません",
+                offer.marketplace
+            )));
+        }
+        let mut work = self.work_dao.get(offer.work_id.clone()).await?;
+        let token = offer.token();
+
+        let listings = self.active_listings(offer.work_id.clone()).await?;
+        match token.schema {
+            TokenSchema::Erc721 => {}
+            TokenSchema::Erc1155 => {
+                let token_id: u128 = token.token_id.parse().map_err(|_e| {
+                    AppError::Internal(format!("invalid token id: {}", token.token_id))
+                })?;
+                let balance = self
+                    .ethereum_cli
+                    .get_erc1155_balance_of(&user, token_id)
+                    .await?;
+                let listed: u64 = listings.iter().map(|v| v.quantity).sum();
+                if listed + offer.quantity > balance {
+                    return Err(AppError::BadRequest(format!(
+                        "保有数が足りません: 保有 {}, 出品中 {}",
+                        balance, listed
+                    )));
+                }
+            }
+        }
+
+        let tx_hash = self
+            .marketplace
+            .accept_offer(&user, &token, &offer.id)
+            .await?;
+        offer.changed(OfferStatus::Accepted, now());
+        self.offer_dao.put(&offer).await?;
+
+        let mut sale = Sale::new(
+            offer.work_id.clone(),
+            offer.chain_id,
+            SaleSource::Offer,
+            offer.marketplace.clone(),
+            &token,
+            user.id.clone(),
+            user.wallet_address.clone(),
+            offer.maker.clone(),
+            offer.currency.clone(),
+            offer.price.clone(),
+            offer.quantity,
+            tx_hash,
+            now(),
+        );
+        let royalty_basis_points = self
+            .royalty_of(&work, token.schema)
+            .await?
+            .map(|v| v.basis_points)
+            .unwrap_or_default();
+        sale.charged(self.marketplace.fee_basis_points(), royalty_basis_points);
+        self.sale_dao.put(&sale).await?;
+
+        let sold_out = match token.schema {
+            TokenSchema::Erc721 => {
+                // 約定が確定してから、もう約定できない出品を取り消す
+                // 約定は記録済みなので、取り消しに失敗しても承諾は成功として返す
+                if let Err(e) = self.cancel_listings(&user, listings).await {
+                    println!("failed to cancel listings after accepting offer: {:?}", e);
+                }
+                true
+            }
+            TokenSchema::Erc1155 => {
+                let token_id: u128 = token.token_id.parse().unwrap_or_default();
+                let balance = self
+                    .ethereum_cli
+                    .get_erc1155_balance_of(&user, token_id)
+                    .await?;
+                self.save_balance1155(offer.work_id.clone(), balance)
+                    .await?;
+                balance == 0
+            }
+        };
+        if sold_out {
+            for mut other in self.offer_dao.get_by_work(offer.work_id.clone()).await? {
+                if other.status == OfferStatus::Open {
+                    other.changed(OfferStatus::Closed, now());
+                    self.offer_dao.put(&other).await?;
+                }
+            }
+            work.status = WorkStatus::Sold;
+            self.work_dao.put(&work).await?;
+        }
+
+        Ok(())
+    }
+
+    /// マーケットプレイスには断る手段がないので、こちらで受け付けないようにするだけ
+    pub async fn reject_offer(&self, offer_id: String) -> AppResult<()> {
+        let mut offer = self.open_offer(offer_id).await?;
+        offer.changed(OfferStatus::Rejected, now());
+        self.offer_dao.put(&offer).await
+    }
+
+    /// 保存されていなければ未設定のものを返す
+    pub async fn collection(&self, schema: TokenSchema) -> AppResult<Collection> {
+        let chain = self.ethereum_cli.chain();
+        let contract_address = match schema {
+            TokenSchema::Erc721 => chain.erc721_contract_address.clone(),
+            TokenSchema::Erc1155 => chain.erc1155_contract_address.clone(),
+        };
+        match self
+            .collection_dao
+            .get(Collection::id_of(chain.chain_id, &contract_address))
+            .await
+        {
+            Ok(collection) => Ok(collection),
+            Err(AppError::NotFound) => Ok(Collection::new(
+                chain.chain_id,
+                schema,
+                contract_address,
+                now(),
+            )),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 作品の設定、なければコレクションの設定を使う
+    async fn royalty_of(&self, work: &Work, schema: TokenSchema) -> AppResult<Option<Royalty>> {
+        if work.royalty.is_some() {
+            return Ok(work.royalty.clone());
+        }
+        self.collection_royalty(schema).await
+    }
+
+    pub async fn collection_royalty(&self, schema: TokenSchema) -> AppResult<Option<Royalty>> {
+        Ok(self.collection(schema).await?.royalty)
+    }
+
+    /// mint済みであればコントラクトのトークンごとの設定も更新する
+    pub async fn update_work_royalty(
+        &self,
+        work_id: String,
+        receiver: String,
+        basis_points: u64,
+    ) -> AppResult<Royalty> {
+        let user = self.user_dao.get(self.me_id.clone()).await?;
+        let mut work = self.work_dao.get(work_id.clone()).await?;
+        let royalty = Royalty::new(&receiver, basis_points)?;
+
+        if self
+            .ethereum_cli
+            .get_erc721_token_id_of(work_id.clone())
+            .await?
+            > 0
+        {
+            self.ethereum_cli
+                .set_token_royalty(&user, TokenSchema::Erc721, work_id.clone(), &royalty)
+                .await?;
+        }
+        if self
+            .ethereum_cli
+            .get_erc1155_token_id_of(work_id.clone())
+            .await?
+            > 0
+        {
+            self.ethereum_cli
+                .set_token_royalty(&user, TokenSchema::Erc1155, work_id.clone(), &royalty)
+                .await?;
+        }
+
+        work.royalty = Some(royalty.clone());
+        self.work_dao.put(&work).await?;
+
+        Ok(royalty)
+    }
+
+    /// コントラクトの既定のロイヤリティを更新する
+    pub async fn update_collection_royalty(
+        &self,
+        schema: TokenSchema,
+        receiver: String,
+        basis_points: u64,
+    ) -> AppResult<Royalty> {
+        let user = self.user_dao.get(self.me_id.clone()).await?;
+        let royalty = Royalty::new(&receiver, basis_points)?;
+
+        self.ethereum_cli
+            .set_default_royalty(&user, schema, &royalty)
+            .await?;
+
+        let mut collection = self.collection(schema).await?;
+        collection.royalty = Some(royalty.clone());
+        collection.updated_at = now();
+        self.collection_dao.put(&collection).await?;
+
+        Ok(royalty)
+    }
+
+    pub async fn metadata_template(&self, schema: TokenSchema) -> AppResult<MetadataTemplate> {
+        Ok(self
+            .collection(schema)
+            .await?
+            .metadata_template
+            .unwrap_or_default())
+    }
+
+    pub async fn update_metadata_template(
+        &self,
+        schema: TokenSchema,
+        template: MetadataTemplate,
+    ) -> AppResult<()> {
+        template.validate()?;
+
+        let mut collection = self.collection(schema).await?;
+        collection.metadata_template = Some(template);
+        collection.updated_at = now();
+        self.collection_dao.put(&collection).await
+    }
+
+    pub async fn price_history(
+        &self,
+        work_id: String,
+        limit: Option<i32>,
+    ) -> AppResult<Vec<PriceSnapshot>> {
+        self.price_snapshot_dao.get_by_work(work_id, limit).await
+    }
+
+    pub async fn sync_asset(&self) -> AppResult<()> {
+        let mut rate_limited = false;
+        let mut deferred: Vec<String> = vec![];
+
+        let used_erc721_ids = self.ethereum_cli.get_erc721_used_names().await?;
+        for work_id in &used_erc721_ids {
+            println!("sync work for erc721: {}", work_id);
+
+            let work = self.work_dao.get(work_id.clone()).await;
+            if let Err(err) = work {
+                if err != AppError::NotFound {
+                    return Err(err);
+                }
+                continue;
+            }
+            let mut work = work.ok().unwrap();
+
+            if rate_limited {
+                deferred.push(work.id.clone());
+                continue;
+            }
+            if let Err(err) = self.save_asset721(work.id.clone()).await {
+                if let AppError::RateLimited(_) = err {
+                    // 残りは次回のバッチに回す
+                    rate_limited = true;
+                    deferred.push(work.id.clone());
+                    continue;
+                }
+                return Err(err);
+            }
+
+            if work.status == WorkStatus::Prepare {
+                work.status = WorkStatus::PublishNFT;
+                self.work_dao.put(&work).await?;
+            }
+        }
+
+        let used_erc1155_ids = self.ethereum_cli.get_erc1155_used_names().await?;
+        for work_id in &used_erc1155_ids {
+            println!("sync work for erc1155: {}", work_id);
+
+            let work = self.work_dao.get(work_id.clone()).await;
+            if let Err(err) = work {
+                if err != AppError::NotFound {
+                    return Err(err);
+                }
+                continue;
+            }
+            let mut work = work.ok().unwrap();
+
+            if rate_limited {
+                deferred.push(work.id.clone());
+                continue;
+            }
+            if let Err(err) = self.save_asset1155(work.id.clone()).await {
+                if let AppError::RateLimited(_) = err {
+                    // 残りは次回のバッチに回す
+                    rate_limited = true;
+                    deferred.push(work.id.clone());
+                    continue;
+                }
+                return Err(err);
+            }
+
+            if work.status == WorkStatus::Prepare {
+                work.status = WorkStatus::PublishNFT;
+                self.work_dao.put(&work).await?;
+            }
+        }
+
+        if !deferred.is_empty() {
+            println!("deferred by open sea rate limit: {:?}", deferred);
+        }
+
+        println!("delete asset if need");
+
+        let chain_id = self.ethereum_cli.chain().chain_id;
+
+        let all_asset721 = self.asset721_dao.get_all().await?;
+        for asset in all_asset721.iter().filter(|asset| {
+            (asset.chain_id == chain_id || asset.chain_id == 0)
+                && used_erc721_ids
+                    .clone()
+                    .into_iter()
+                    .find(|id| id.to_string() == asset.work_id)
+                    .is_none()
+        }) {
+            let id = asset.to_owned().work_id;
+            println!("delete erc721 asset: {}", id.clone());
+            self.asset721_dao.delete(id).await?;
+        }
+
+        let all_asset1155 = self.asset1155_dao.get_all().await?;
+        for asset in all_asset1155.iter().filter(|asset| {
+            (asset.chain_id == chain_id || asset.chain_id == 0)
+                && used_erc1155_ids
+                    .clone()
+                    .into_iter()
+                    .find(|id| id.to_string() == asset.work_id)
+                    .is_none()
+        }) {
+            let id = asset.to_owned().work_id;
+            println!("delete erc1155 asset: {}", id.clone());
+            self.asset1155_dao.delete(id).await?;
+        }
+
+        for metrics in self.ethereum_cli.rpc_metrics() {
+            println!("rpc metrics: {:?}", metrics);
+        }
+
+        Ok(())
+    }
+
+    pub async fn sell_erc721(&self, work_id: String, price: Wei) -> AppResult<()> {
+        if price.is_zero() {
+            return Err(AppError::BadRequest("価格を指定してください".to_string()));
+        }
+
+        let user = self.user_dao.get(self.me_id.clone()).await?;
+        let mut work = self.work_dao.get(work_id.clone()).await?;
+
+        let contract_address = self.ethereum_cli.chain().erc721_contract_address.clone();
+        let token_id = self
+            .ethereum_cli
+            .get_erc721_token_id_of(work_id.clone())
+            .await?;
+
+        self.list(
+            &user,
+            work_id,
+            TokenRef::new(TokenSchema::Erc721, contract_address, token_id.to_string()),
+            price,
+            1,
+            None,
+        )
+        .await?;
+
+        work.status = WorkStatus::SellOrder;
+        self.work_dao.put(&work).await?;
+
+        Ok(())
+    }
+
+    /// priceは1つあたりの価格。出品中の数量と合わせて保有数を超えないようにする
+    pub async fn sell_erc1155(&self, work_id: String, price: Wei, quantity: u64) -> AppResult<()> {
+        if price.is_zero() {
+            return Err(AppError::BadRequest("価格を指定してください".to_string()));
+        }
+        if quantity == 0 {
+            return Err(AppError::BadRequest("数量を指定してください".to_string()));
+        }
+
+        let user = self.user_dao.get(self.me_id.clone()).await?;
+        let mut work = self.work_dao.get(work_id.clone()).await?;
+
+        let contract_address = self.ethereum_cli.chain().erc1155_contract_address.clone();
+        let token_id = self
+            .ethereum_cli
+            .get_erc1155_token_id_of(work_id.clone())
+            .await?;
+
+        let balance = self
+            .ethereum_cli
+            .get_erc1155_balance_of(&user, token_id)
+            .await?;
+        let listed: u64 = self
+            .active_listings(work_id.clone())
+            .await?
+            .iter()
+            .map(|v| v.quantity)
+            .sum();
+        if listed + quantity > balance {
+            return Err(AppError::BadRequest(format!(
+                "保有数が足りません: 保有 {}, 出品中 {}",
+                balance, listed
+            )));
+        }
+
+        self.list(
+            &user,
+            work_id.clone(),
+            TokenRef::new(TokenSchema::Erc1155, contract_address, token_id.to_string()),
+            price,
+            quantity,
+            None,
+        )
+        .await?;
+        self.save_balance1155(work_id, balance).await?;
+
+        work.status = WorkStatus::SellOrder;
+        self.work_dao.put(&work).await?;
+
+        Ok(())
+    }
+
+    /// 出品して、取り消しや価格の変更のために注文を記録する
+    async fn list(
+        &self,
+        user: &User,
+        work_id: String,
+        token: TokenRef,
+        price: Wei,
+        quantity: u64,
+        expires_at: Option<i64>,
+    ) -> AppResult<Listing> {
+        let order = self
+            .marketplace
+            .list_for_sale(user, &token, price, quantity, expires_at)
+            .await?;
+
+        let listing = Listing::new(
+            work_id,
+            self.ethereum_cli.chain().chain_id,
+            self.marketplace.name().to_string(),
+            &token,
+            user.id.clone(),
+            price,
+            quantity,
+            order,
+            now(),
+        );
+        self.listing_dao.put(&listing).await?;
+
+        Ok(listing)
+    }
+
+    /// 自分が出品中の注文。期限切れのものは含めない
+    async fn active_listings(&self, work_id: String) -> AppResult<Vec<Listing>> {
+        let chain_id = self.ethereum_cli.chain().chain_id;
+        let listings: Vec<Listing> = self
+            .listing_dao
+            .get_by_work(work_id)
+            .await?
+            .into_iter()
+            .filter(|v| v.chain_id == chain_id && v.is_active(now()))
+            .collect();
+
+        if listings.iter().any(|v| v.seller_id != self.me_id) {
+            return Err(AppError::Forbidden);
+        }
+        Ok(listings)
+    }
+
+    async fn cancel_listings(&self, user: &User, listings: Vec<Listing>) -> AppResult<()> {
+        for mut listing in listings {
+            if listing.marketplace != self.marketplace.name() {
+                return Err(AppError::BadRequest(format!(
+                    "{}の出品は取り消せません",
+                    listing.marketplace
+                )));
+            }
+
+            self.marketplace
+                .cancel_listing(user, &listing.token(), &listing.listing_order())
+                .await?;
+            listing.cancelled();
+            self.listing_dao.put(&listing).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn cancel_sell_order(&self, work_id: String) -> AppResult<()> {
+        let user = self.user_dao.get(self.me_id.clone()).await?;
+        let mut work = self.work_dao.get(work_id.clone()).await?;
+
+        let listings = self.active_listings(work_id.clone()).await?;
+        if listings.is_empty() {
+            if work.status != WorkStatus::SellOrder {
+                return Err(AppError::NotFound);
+            }
+            // 出品を記録する前の注文はここから取り消せないので、ステータスだけ戻さない
+            // 記録があれば期限切れなどで注文はもう有効でない
+            let chain_id = self.ethereum_cli.chain().chain_id;
+            let recorded = self
+                .listing_dao
+                .get_by_work(work_id.clone())
+                .await?
+                .into_iter()
+                .any(|v| v.chain_id == chain_id);
+            if !recorded {
+                return Err(AppError::BadRequest(
+                    "出品の記録がないため取り消せません。マーケットプレイスで取り消してください"
+                        .to_string(),
+                ));
+            }
+        }
+        self.cancel_listings(&user, listings).await?;
+
+        work.status = WorkStatus::PublishNFT;
+        self.work_dao.put(&work).await?;
+
+        Ok(())
+    }
+
+    /// 既存の注文を取り消して、新しい価格と期限で出品し直す
+    pub async fn update_sell_order(
+        &self,
+        work_id: String,
+        price: Wei,
+        expires_at: Option<i64>,
+    ) -> AppResult<()> {
+        if price.is_zero() {
+            return Err(AppError::BadRequest("価格を指定してください".to_string()));
+        }
+        if let Some(expires_at) = expires_at {
+            if expires_at <= now() {
+                return Err(AppError::BadRequest(
+                    "期限には未来の日時を指定してください".to_string(),
+                ));
+            }
+        }
+
+        let user = self.user_dao.get(self.me_id.clone()).await?;
+        let mut work = self.work_dao.get(work_id.clone()).await?;
+
+        let listings = self.active_listings(work_id.clone()).await?;
+        let token = match listings.first() {
+            Some(listing) => listing.token(),
+            None => return Err(AppError::NotFound),
+        };
+        let quantity = listings.iter().map(|v| v.quantity).sum();
+        self.cancel_listings(&user, listings).await?;
+
+        self.list(&user, work_id, token, price, quantity, expires_at)
+            .await?;
+
+        work.status = WorkStatus::SellOrder;
+        self.work_dao.put(&work).await?;
+
+        Ok(())
+    }
+
+    /// 送り先はチェックサム付きのアドレスかENS名
+    pub async fn transfer_erc721(&self, work_id: String, to_address: String) -> AppResult<()> {
+        let user = self.user_dao.get(self.me_id.clone()).await?;
+        let to = self.ethereum_cli.resolve_address(&to_address).await?;
 
-        let contract_address =
-            env::var("ERC721_CONTRACT_ADDRESS").expect("should set contract address");
         let token_id = self
             .ethereum_cli
             .get_erc721_token_id_of(work_id.clone())
             .await?;
+        if token_id == 0 {
+            return Err(AppError::NotFound);
+        }
+        if !self.active_listings(work_id.clone()).await?.is_empty() {
+            return Err(AppError::BadRequest("出品中は転送できません".to_string()));
+        }
+
+        let (receipt, transfers) = self
+            .ethereum_cli
+            .transfer_erc721(&user, to, token_id)
+            .await?;
+        self.record_transaction(TransactionKind::TransferERC721, &work_id, &user, &receipt)
+            .await?;
 
-        lambda::invoke_open_sea_sdk(lambda::invoke_open_sea_sdk::Input::transfer_erc721(
-            user,
-            contract_address,
-            token_id.to_string(),
-            to_address,
-        ))
+        let contract_address = self.ethereum_cli.chain().erc721_contract_address.clone();
+        self.record_transfers(
+            &work_id,
+            TokenSchema::Erc721,
+            &contract_address,
+            &user,
+            &receipt,
+            &transfers,
+        )
         .await?;
+        self.apply_transfers(&work_id, TokenSchema::Erc721, &contract_address, transfers)
+            .await?;
 
         Ok(())
     }
 
-    pub async fn transfer_erc1155(&self, work_id: String, to_address: String) -> AppResult<()> {
+    /// 出品中の数量は転送できない
+    pub async fn transfer_erc1155(
+        &self,
+        work_id: String,
+        to_address: String,
+        quantity: u64,
+    ) -> AppResult<()> {
+        if quantity == 0 {
+            return Err(AppError::BadRequest("数量を指定してください".to_string()));
+        }
+
         let user = self.user_dao.get(self.me_id.clone()).await?;
+        let to = self.ethereum_cli.resolve_address(&to_address).await?;
 
-        let contract_address =
-            env::var("ERC1155_CONTRACT_ADDRESS").expect("should set contract address");
         let token_id = self
             .ethereum_cli
             .get_erc1155_token_id_of(work_id.clone())
             .await?;
+        if token_id == 0 {
+            return Err(AppError::NotFound);
+        }
+
+        let balance = self
+            .ethereum_cli
+            .get_erc1155_balance_of(&user, token_id)
+            .await?;
+        let listed: u64 = self
+            .active_listings(work_id.clone())
+            .await?
+            .iter()
+            .map(|v| v.quantity)
+            .sum();
+        if listed + quantity > balance {
+            return Err(AppError::BadRequest(format!(
+                "保有数が足りません: 保有 {}, 出品中 {}",
+                balance, listed
+            )));
+        }
 
-        lambda::invoke_open_sea_sdk(lambda::invoke_open_sea_sdk::Input::transfer_erc1155(
-            user,
-            contract_address,
-            token_id.to_string(),
-            to_address,
-        ))
+        let (receipt, transfers) = self
+            .ethereum_cli
+            .transfer_erc1155(&user, to, token_id, quantity)
+            .await?;
+        self.record_transaction(TransactionKind::TransferERC1155, &work_id, &user, &receipt)
+            .await?;
+
+        let contract_address = self.ethereum_cli.chain().erc1155_contract_address.clone();
+        self.record_transfers(
+            &work_id,
+            TokenSchema::Erc1155,
+            &contract_address,
+            &user,
+            &receipt,
+            &transfers,
+        )
         .await?;
+        self.apply_transfers(&work_id, TokenSchema::Erc1155, &contract_address, transfers)
+            .await?;
+
+        let balance = self
+            .ethereum_cli
+            .get_erc1155_balance_of(&user, token_id)
+            .await?;
+        self.save_balance1155(work_id, balance).await?;
+
+        Ok(())
+    }
+
+    async fn record_transaction(
+        &self,
+        kind: TransactionKind,
+        work_id: &str,
+        user: &User,
+        receipt: &TransactionReceipt,
+    ) -> AppResult<()> {
+        self.transaction_dao
+            .put(&TransactionRecord {
+                tx_hash: format!("{:?}", receipt.transaction_hash),
+                chain_id: self.ethereum_cli.chain().chain_id,
+                kind,
+                work_id: work_id.to_string(),
+                sender: user.wallet_address.clone(),
+                success: receipt.status == Some(U64::from(1)),
+                block_number: receipt.block_number.unwrap_or_default().as_u64(),
+                gas_used: receipt.gas_used.unwrap_or_default().low_u64(),
+                created_at: now(),
+            })
+            .await
+    }
+
+    /// 転送も代金0の記録として台帳に残す
+    async fn record_transfers(
+        &self,
+        work_id: &str,
+        schema: TokenSchema,
+        contract_address: &str,
+        user: &User,
+        receipt: &TransactionReceipt,
+        transfers: &[TokenTransfer],
+    ) -> AppResult<()> {
+        let tx_hash = format!("{:?}", receipt.transaction_hash);
+        for transfer in transfers {
+            self.sale_dao
+                .put(&Sale::new(
+                    work_id.to_string(),
+                    self.ethereum_cli.chain().chain_id,
+                    SaleSource::Transfer,
+                    "".to_string(),
+                    &TokenRef::new(
+                        schema,
+                        contract_address.to_string(),
+                        transfer.token_id.to_string(),
+                    ),
+                    user.id.clone(),
+                    to_checksum(&transfer.from),
+                    to_checksum(&transfer.to),
+                    "ETH".to_string(),
+                    "0".to_string(),
+                    transfer.amount.low_u64(),
+                    tx_hash.clone(),
+                    now(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// レシートのTransferを保有状況の索引に反映する（送り元を減らし、送り先を増やす）
+    /// 索引にない送り元は、mint時に記録する前のトークンなので転送後の残高をチェーンから読む
+    async fn apply_transfers(
+        &self,
+        work_id: &str,
+        schema: TokenSchema,
+        contract_address: &str,
+        transfers: Vec<TokenTransfer>,
+    ) -> AppResult<()> {
+        let chain_id = self.ethereum_cli.chain().chain_id;
+
+        for transfer in transfers {
+            let token_id = transfer.token_id.to_string();
+            let amount = transfer.amount.low_u64();
+
+            if !transfer.from.is_zero() {
+                let owner = to_checksum(&transfer.from);
+                let id = Ownership::id_of(chain_id, contract_address, &token_id, &owner);
+                let remaining = match self.ownership_dao.get(id.clone()).await {
+                    Ok(v) => v.amount.saturating_sub(amount),
+                    Err(AppError::NotFound) => match schema {
+                        TokenSchema::Erc721 => 0,
+                        TokenSchema::Erc1155 => {
+                            self.ethereum_cli
+                                .get_erc1155_balance_at(transfer.from, transfer.token_id)
+                                .await?
+                        }
+                    },
+                    Err(e) => return Err(e),
+                };
+                if remaining == 0 {
+                    self.ownership_dao.delete(id).await?;
+                } else {
+                    self.ownership_dao
+                        .put(&Ownership {
+                            chain_id,
+                            schema,
+                            contract_address: contract_address.to_string(),
+                            token_id: token_id.clone(),
+                            owner_address: owner,
+                            work_id: work_id.to_string(),
+                            amount: remaining,
+                            updated_at: now(),
+                        })
+                        .await?;
+                }
+            }
+
+            if !transfer.to.is_zero() {
+                let owner = to_checksum(&transfer.to);
+                let id = Ownership::id_of(chain_id, contract_address, &token_id, &owner);
+                let current = match self.ownership_dao.get(id).await {
+                    Ok(v) => v.amount,
+                    Err(AppError::NotFound) => 0,
+                    Err(e) => return Err(e),
+                };
+                self.ownership_dao
+                    .put(&Ownership {
+                        chain_id,
+                        schema,
+                        contract_address: contract_address.to_string(),
+                        token_id,
+                        owner_address: owner,
+                        work_id: work_id.to_string(),
+                        amount: current + amount,
+                        updated_at: now(),
+                    })
+                    .await?;
+            }
+        }
 
         Ok(())
     }
 
+    /// このチェーンでの作品のトークンの保有者
+    pub async fn owners_of(&self, work_id: String) -> AppResult<Vec<Ownership>> {
+        let chain_id = self.ethereum_cli.chain().chain_id;
+        Ok(self
+            .ownership_dao
+            .get_by_work(work_id)
+            .await?
+            .into_iter()
+            .filter(|v| v.chain_id == chain_id)
+            .collect())
+    }
+
+    async fn save_balance1155(&self, work_id: String, balance: u64) -> AppResult<()> {
+        let mut asset = self.asset1155_dao.get(work_id).await?;
+        asset.balance_changed(balance);
+        self.asset1155_dao.put(&asset).await
+    }
+
     pub async fn is_own_erc721(&self, work_id: String) -> AppResult<bool> {
-        let contract_address =
-            env::var("ERC721_CONTRACT_ADDRESS").expect("should set contract address");
+        let contract_address = self.ethereum_cli.chain().erc721_contract_address.clone();
         let token_id = self
             .ethereum_cli
             .get_erc721_token_id_of(work_id.clone())
@@ -482,12 +2315,16 @@ impl Application {
             return Ok(false);
         }
 
-        self.is_own(contract_address, token_id).await
+        self.is_own(TokenRef::new(
+            TokenSchema::Erc721,
+            contract_address,
+            token_id.to_string(),
+        ))
+        .await
     }
 
     pub async fn is_own_erc1155(&self, work_id: String) -> AppResult<bool> {
-        let contract_address =
-            env::var("ERC1155_CONTRACT_ADDRESS").expect("should set contract address");
+        let contract_address = self.ethereum_cli.chain().erc1155_contract_address.clone();
         let token_id = self
             .ethereum_cli
             .get_erc1155_token_id_of(work_id.clone())
@@ -497,24 +2334,143 @@ impl Application {
             return Ok(false);
         }
 
-        self.is_own(contract_address, token_id).await
+        self.is_own(TokenRef::new(
+            TokenSchema::Erc1155,
+            contract_address,
+            token_id.to_string(),
+        ))
+        .await
     }
 
-    async fn is_own(&self, contract_address: String, token_id: u128) -> AppResult<bool> {
+    /// 出品中の最安の注文を発行者のウォレットで購入する（上限価格と残高、ガス代を確認する）
+    pub async fn buy_nft(
+        &self,
+        contract_address: String,
+        token_id: String,
+        max_price: Wei,
+    ) -> AppResult<Acquisition> {
+        if max_price.is_zero() {
+            return Err(AppError::BadRequest(
+                "上限価格を指定してください".to_string(),
+            ));
+        }
+
         let user = self.user_dao.get(self.me_id.clone()).await?;
 
-        let asset = self
-            .open_sea_cli
-            .get_asset(open_sea::api::get_asset::Input {
-                address: contract_address.clone(),
-                token_id: token_id.clone().to_string(),
-            })
-            .await?;
+        let contract = parse_checksummed(contract_address.trim())?;
+        let schema = self.ethereum_cli.detect_token_schema(contract).await?;
+        let token = TokenRef::new(schema, to_checksum(&contract), token_id);
 
-        for item in asset.top_ownerships {
+        let (listing, price) =
+            cheapest_listing(self.marketplace.as_ref(), &token, &user.wallet_address).await?;
+
+        if price > max_price {
+            return Err(AppError::BadRequest(format!(
+                "価格が上限を超えています: {} ETH",
+                price.to_ether()
+            )));
+        }
+        self.ensure_payable(&user, &listing, price).await?;
+
+        let tx_hash = self.marketplace.buy(&user, &token, &listing).await?;
+
+        let acquisition = Acquisition::new(
+            self.ethereum_cli.chain().chain_id,
+            self.marketplace.name().to_string(),
+            &token,
+            user.id.clone(),
+            user.wallet_address.clone(),
+            listing.maker.clone(),
+            price,
+            listing.quantity,
+            tx_hash,
+            now(),
+        );
+        self.acquisition_dao.put(&acquisition).await?;
+        println!(
+            "bought: {:?} from {} for {} ETH",
+            token,
+            listing.maker,
+            price.to_ether()
+        );
+
+        Ok(acquisition)
+    }
+
+    /// ETHなら代金とガス代、ERC20なら代金の残高と承認額、ガス代のETHがあるか
+    async fn ensure_payable(
+        &self,
+        user: &User,
+        listing: &market::Listing,
+        price: Wei,
+    ) -> AppResult<()> {
+        let fee = self.ethereum_cli.estimate_fee(FULFILL_GAS).await?;
+        let balance = self.ethereum_cli.get_balance(user).await?;
+
+        let payment_token = match &listing.payment_token {
+            Some(v) => v,
+            None => {
+                let total = price
+                    .checked_add(fee)
+                    .ok_or_else(|| AppError::BadRequest("価格が大きすぎます".to_string()))?;
+                if balance < total {
+                    return Err(AppError::BadRequest(format!(
+                        "残高が足りません: 残高 {} ETH, 価格 {} ETH, ガス代 {} ETH",
+                        balance.to_ether(),
+                        price.to_ether(),
+                        fee.to_ether()
+                    )));
+                }
+                return Ok(());
+            }
+        };
+
+        if balance < fee {
+            return Err(AppError::BadRequest(format!(
+                "ガス代が足りません: 残高 {} ETH, ガス代 {} ETH",
+                balance.to_ether(),
+                fee.to_ether()
+            )));
+        }
+        let token = parse_checksummed(payment_token)?;
+        let owner = parse_checksummed(&user.wallet_address)?;
+        let token_balance = Wei::new(self.ethereum_cli.get_erc20_balance(token, owner).await?);
+        if token_balance < price {
+            return Err(AppError::BadRequest(format!(
+                "{}の残高が足りません: 残高 {}, 価格 {}",
+                listing.currency,
+                token_balance.to_ether(),
+                price.to_ether()
+            )));
+        }
+        if let Some(spender) = self.marketplace.payment_spender() {
+            let spender = parse_checksummed(&spender)?;
+            let allowance = Wei::new(
+                self.ethereum_cli
+                    .get_erc20_allowance(token, owner, spender)
+                    .await?,
+            );
+            if allowance < price {
+                return Err(AppError::BadRequest(format!(
+                    "{}の承認額が足りません: 承認 {}, 価格 {}",
+                    listing.currency,
+                    allowance.to_ether(),
+                    price.to_ether()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    async fn is_own(&self, token: TokenRef) -> AppResult<bool> {
+        let user = self.user_dao.get(self.me_id.clone()).await?;
+
+        let asset = self.marketplace.get_asset(&token).await?;
+
+        for owner in asset.owners {
             if self
                 .ethereum_cli
-                .equal_address(item.owner.address, user.wallet_address.clone())
+                .equal_address(owner, user.wallet_address.clone())
             {
                 return Ok(true);
             }
@@ -523,3 +2479,890 @@ impl Application {
         Ok(false)
     }
 }
+
+/// 自分以外のETHかWETHでの出品から最安のものを選ぶ。上限価格はETHなので、1:1のWETHだけを含める
+async fn cheapest_listing(
+    marketplace: &dyn Marketplace,
+    token: &TokenRef,
+    buyer_address: &str,
+) -> AppResult<(market::Listing, Wei)> {
+    marketplace
+        .get_listings(token)
+        .await?
+        .into_iter()
+        .filter(|v| match v.payment_token {
+            Some(_) => v.currency == "WETH",
+            None => v.currency == "ETH",
+        })
+        .filter(|v| !v.maker.eq_ignore_ascii_case(buyer_address))
+        .filter_map(|v| Wei::from_ether(&v.price).ok().map(|price| (v, price)))
+        .min_by_key(|(_, price)| *price)
+        .ok_or(AppError::NotFound)
+}
+
+/// チェーンIDを含める前のmetadataの置き場所
+fn legacy_asset_path(schema: TokenSchema) -> &'static str {
+    match schema {
+        TokenSchema::Erc721 => ERC721_ASSET_PATH_PREFIX,
+        TokenSchema::Erc1155 => ERC1155_ASSET_PATH_PREFIX,
+    }
+}
+
+/// 10進数、または0xの有無を問わない64桁の16進数
+fn parse_token_id(token_id: &str) -> AppResult<u64> {
+    let invalid = || AppError::BadRequest(format!("不正なトークンIDです: {}", token_id));
+    // from_str_radixは先頭の+も受け付けるので、桁だけでできていることを先に確かめる
+    let (digits, radix) = match token_id.strip_prefix("0x") {
+        Some(hex) => (hex, 16),
+        None if token_id.len() == 64 => (token_id, 16),
+        None => (token_id, 10),
+    };
+    if digits.is_empty() || !digits.chars().all(|v| v.is_digit(radix)) {
+        return Err(invalid());
+    }
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, radix).map_err(|_| invalid())
+}
+
+/// 大きすぎる応答を読み切る前に止める。Content-Lengthがなくても読んだ量で打ち切る
+async fn read_capped(mut res: reqwest::Response, limit: usize) -> AppResult<Vec<u8>> {
+    let too_large =
+        |size: u64| AppError::BadRequest(format!("画像が大きすぎます: {} > {} bytes", size, limit));
+    if let Some(size) = res.content_length().filter(|v| *v > limit as u64) {
+        return Err(too_large(size));
+    }
+    let mut data: Vec<u8> = vec![];
+    while let Some(chunk) = res.chunk().await? {
+        if data.len() + chunk.len() > limit {
+            return Err(too_large((data.len() + chunk.len()) as u64));
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+// 応答しないゲートウェイなどで止まらないよう、タイムアウトを設定して使い回す
+static FETCH_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    let seconds = env::var("METADATA_FETCH_TIMEOUT_SEC")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(seconds))
+        .build()
+        .unwrap()
+});
+
+/// ipfs://はゲートウェイ経由で取得する
+async fn fetch_uri(uri: &str) -> AppResult<Bytes> {
+    Ok(FETCH_CLIENT
+        .get(ipfs::gateway_url(uri))
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ddb::mock::MockDynamo;
+    use crate::domain::listing::ListingStatus;
+    use crate::domain::market::{ListingOrder, MarketAsset};
+    use crate::ethereum::chain::ChainProfile;
+    use crate::ethereum::mock::{
+        decode_call, encode, receipt, MockChain, ERC1155_ABI, ERC1155_CONTRACT,
+    };
+    use crate::marketplace::MemoryMarketplace;
+    use serde_json::{json, Value};
+    use web3::ethabi::Token;
+    use web3::types::U256;
+
+    const SELLER: &str = "0x1111111111111111111111111111111111111111";
+    const OTHER_SELLER: &str = "0x2222222222222222222222222222222222222222";
+    const BUYER: &str = "0x3333333333333333333333333333333333333333";
+
+    fn user(address: &str) -> User {
+        User::new(address.to_string(), address.to_string(), "".to_string())
+    }
+
+    fn token(token_id: &str) -> TokenRef {
+        TokenRef::new(
+            TokenSchema::Erc1155,
+            "0x4444444444444444444444444444444444444444".to_string(),
+            token_id.to_string(),
+        )
+    }
+
+    fn marketplace(token: &TokenRef, owners: &[&str]) -> MemoryMarketplace {
+        let marketplace = MemoryMarketplace::new();
+        marketplace.put_asset(
+            token.clone(),
+            MarketAsset {
+                name: "".to_string(),
+                description: "".to_string(),
+                image_url: "".to_string(),
+                image_preview_url: "".to_string(),
+                permalink: "".to_string(),
+                owners: owners.iter().map(|v| v.to_string()).collect(),
+                price: MarketPrice {
+                    currency: "ETH".to_string(),
+                    listing_price: None,
+                    last_sale_price: None,
+                    usd_price: "0".to_string(),
+                    eth_price: Wei::zero(),
+                },
+            },
+        );
+        marketplace
+    }
+
+    async fn list(marketplace: &MemoryMarketplace, seller: &str, token: &TokenRef, ether: &str) {
+        marketplace
+            .list_for_sale(
+                &user(seller),
+                token,
+                Wei::from_ether(ether).unwrap(),
+                1,
+                None,
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn buys_cheapest_listing_of_others() {
+        let token = token("1");
+        let marketplace = marketplace(&token, &[SELLER, OTHER_SELLER, BUYER]);
+        list(&marketplace, SELLER, &token, "0.5").await;
+        list(&marketplace, OTHER_SELLER, &token, "0.2").await;
+        // 自分の出品は安くても買わない
+        list(&marketplace, BUYER, &token, "0.1").await;
+
+        let (listing, price) = cheapest_listing(&marketplace, &token, &BUYER.to_uppercase())
+            .await
+            .unwrap();
+        assert_eq!(listing.maker, OTHER_SELLER);
+        assert_eq!(price, Wei::from_ether("0.2").unwrap());
+
+        let tx_hash = marketplace
+            .buy(&user(BUYER), &token, &listing)
+            .await
+            .unwrap();
+
+        let sales = marketplace.get_sales(&token).await.unwrap();
+        assert_eq!(sales.len(), 1);
+        assert_eq!(sales[0].seller, OTHER_SELLER);
+        assert_eq!(sales[0].buyer, BUYER);
+        assert_eq!(sales[0].tx_hash, tx_hash);
+        let remaining = marketplace.get_listings(&token).await.unwrap();
+        assert!(remaining.iter().all(|v| v.maker != OTHER_SELLER));
+    }
+
+    #[tokio::test]
+    async fn finds_nothing_to_buy_from_own_listings() {
+        let token = token("2");
+        let marketplace = marketplace(&token, &[BUYER]);
+        list(&marketplace, BUYER, &token, "0.1").await;
+
+        let res = cheapest_listing(&marketplace, &token, BUYER).await;
+        assert!(matches!(res, Err(AppError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn rejects_listing_and_cancel_by_non_owner() {
+        let token = token("3");
+        let marketplace = marketplace(&token, &[SELLER]);
+        let res = marketplace
+            .list_for_sale(&user(BUYER), &token, Wei::from_ether("1").unwrap(), 1, None)
+            .await;
+        assert!(matches!(res, Err(AppError::Forbidden)));
+
+        let order = marketplace
+            .list_for_sale(
+                &user(SELLER),
+                &token,
+                Wei::from_ether("1").unwrap(),
+                1,
+                None,
+            )
+            .await
+            .unwrap();
+        let res = marketplace
+            .cancel_listing(&user(BUYER), &token, &order)
+            .await;
+        assert!(matches!(res, Err(AppError::Forbidden)));
+        marketplace
+            .cancel_listing(&user(SELLER), &token, &order)
+            .await
+            .unwrap();
+        assert!(marketplace.get_listings(&token).await.unwrap().is_empty());
+    }
+
+    /// DynamoDBをモックにしたApplication。自分のIDはme
+    fn application(
+        dynamo: &MockDynamo,
+        ethereum_cli: ethereum::Client,
+        marketplace: Arc<dyn Marketplace>,
+    ) -> Application {
+        Application {
+            me_id: "me".to_string(),
+            work_dao: dynamo.dao(),
+            thumbnail_dao: dynamo.dao(),
+            asset721_dao: dynamo.dao(),
+            asset1155_dao: dynamo.dao(),
+            price_snapshot_dao: dynamo.dao(),
+            listing_dao: dynamo.dao(),
+            offer_dao: dynamo.dao(),
+            sale_dao: dynamo.dao(),
+            transaction_dao: dynamo.dao(),
+            acquisition_dao: dynamo.dao(),
+            ownership_dao: dynamo.dao(),
+            collection_dao: dynamo.dao(),
+            pin_dao: dynamo.dao(),
+            reveal_dao: dynamo.dao(),
+            user_dao: dynamo.dao(),
+            marketplace,
+            internal_api: internal_api::Client::for_test(),
+            ipfs: ipfs::Client::memory(),
+            ethereum_cli,
+        }
+    }
+
+    /// チェーンを呼ばないテスト用
+    fn offline_chain() -> ethereum::Client {
+        ethereum::Client::with_chain(ChainProfile::for_test("http://127.0.0.1:1", 1))
+    }
+
+    async fn put_work(dynamo: &MockDynamo, status: WorkStatus) {
+        let work = Work {
+            id: "w1".to_string(),
+            video_path: "gs://bucket/w1.mp4".to_string(),
+            status,
+            royalty: None,
+            title: "".to_string(),
+            description: "".to_string(),
+            attributes: vec![],
+            background_color: None,
+        };
+        dynamo.dao::<Work>().put(&work).await.unwrap();
+        let me = User::new("me".to_string(), SELLER.to_string(), "".to_string());
+        dynamo.dao::<User>().put(&me).await.unwrap();
+    }
+
+    async fn put_listing(dynamo: &MockDynamo, token: &TokenRef, order: ListingOrder) {
+        let listing = Listing::new(
+            "w1".to_string(),
+            1,
+            "memory".to_string(),
+            token,
+            "me".to_string(),
+            Wei::from_ether("1").unwrap(),
+            1,
+            order,
+            now(),
+        );
+        dynamo.dao::<Listing>().put(&listing).await.unwrap();
+    }
+
+    async fn work_status(app: &Application) -> WorkStatus {
+        app.work_dao.get("w1".to_string()).await.unwrap().status
+    }
+
+    #[tokio::test]
+    async fn refuses_to_cancel_unrecorded_listing() {
+        let dynamo = MockDynamo::start();
+        put_work(&dynamo, WorkStatus::SellOrder).await;
+        let app = application(&dynamo, offline_chain(), Arc::new(MemoryMarketplace::new()));
+
+        let res = app.cancel_sell_order("w1".to_string()).await;
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+        // マーケットプレイスに注文が残っているかもしれないので、出品中のままにする
+        assert_eq!(work_status(&app).await, WorkStatus::SellOrder);
+
+        put_work(&dynamo, WorkStatus::PublishNFT).await;
+        let res = app.cancel_sell_order("w1".to_string()).await;
+        assert!(matches!(res, Err(AppError::NotFound)), "{:?}", res);
+    }
+
+    #[tokio::test]
+    async fn resets_status_of_expired_listing() {
+        let dynamo = MockDynamo::start();
+        put_work(&dynamo, WorkStatus::SellOrder).await;
+        let order = ListingOrder {
+            id: "0x01".to_string(),
+            expires_at: now() - 1,
+            payload: "".to_string(),
+        };
+        put_listing(&dynamo, &token("4"), order).await;
+        let app = application(&dynamo, offline_chain(), Arc::new(MemoryMarketplace::new()));
+
+        app.cancel_sell_order("w1".to_string()).await.unwrap();
+        assert_eq!(work_status(&app).await, WorkStatus::PublishNFT);
+    }
+
+    #[tokio::test]
+    async fn cancels_recorded_listing() {
+        let dynamo = MockDynamo::start();
+        put_work(&dynamo, WorkStatus::SellOrder).await;
+        let token = token("5");
+        let marketplace = Arc::new(marketplace(&token, &[SELLER]));
+        let order = marketplace
+            .list_for_sale(
+                &user(SELLER),
+                &token,
+                Wei::from_ether("1").unwrap(),
+                1,
+                None,
+            )
+            .await
+            .unwrap();
+        put_listing(&dynamo, &token, order).await;
+        let app = application(&dynamo, offline_chain(), marketplace.clone());
+
+        app.cancel_sell_order("w1".to_string()).await.unwrap();
+        assert!(marketplace.get_listings(&token).await.unwrap().is_empty());
+        let listings = app.listing_dao.get_by_work("w1".to_string()).await.unwrap();
+        assert_eq!(listings[0].status, ListingStatus::Cancelled);
+        assert_eq!(work_status(&app).await, WorkStatus::PublishNFT);
+    }
+
+    /// tokenIdOf(w1)は7、残高はbalanceを返すチェーン
+    fn erc1155_chain(balance: u64) -> MockChain {
+        MockChain::start(move |method, params| match method {
+            "eth_call" => {
+                let (to, function, input) = decode_call(ERC1155_ABI, params);
+                assert_eq!(to, ERC1155_CONTRACT);
+                match function.name.as_str() {
+                    "tokenIdOf" => {
+                        assert_eq!(input[0], Token::String("w1".to_string()));
+                        Ok(encode(&[Token::Uint(U256::from(7))]))
+                    }
+                    "balanceOf" => {
+                        assert_eq!(input[1], Token::Uint(U256::from(7)));
+                        Ok(encode(&[Token::Uint(U256::from(balance))]))
+                    }
+                    v => panic!("unexpected function: {}", v),
+                }
+            }
+            v => panic!("unexpected method: {}", v),
+        })
+    }
+
+    #[tokio::test]
+    async fn sells_erc1155_within_balance() {
+        let dynamo = MockDynamo::start();
+        put_work(&dynamo, WorkStatus::PublishNFT).await;
+        dynamo
+            .dao::<Asset1155>()
+            .put(&Asset1155::new("w1".to_string(), 1))
+            .await
+            .unwrap();
+        let token = TokenRef::new(
+            TokenSchema::Erc1155,
+            ERC1155_CONTRACT.to_string(),
+            "7".to_string(),
+        );
+        let marketplace = Arc::new(marketplace(&token, &[SELLER]));
+        let app = application(&dynamo, erc1155_chain(3).client(), marketplace.clone());
+
+        let res = app
+            .sell_erc1155("w1".to_string(), Wei::from_ether("1").unwrap(), 4)
+            .await;
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+        assert!(marketplace.get_listings(&token).await.unwrap().is_empty());
+
+        app.sell_erc1155("w1".to_string(), Wei::from_ether("1").unwrap(), 2)
+            .await
+            .unwrap();
+        let listings = app.listing_dao.get_by_work("w1".to_string()).await.unwrap();
+        assert_eq!(listings.len(), 1);
+        assert_eq!(listings[0].quantity, 2);
+        let asset = app.asset1155_dao.get("w1".to_string()).await.unwrap();
+        assert_eq!(asset.balance, Some(3));
+        assert_eq!(work_status(&app).await, WorkStatus::SellOrder);
+
+        // 出品中の2つは、追加の出品にも送付にも使えない
+        let res = app
+            .sell_erc1155("w1".to_string(), Wei::from_ether("1").unwrap(), 2)
+            .await;
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+        let res = app
+            .transfer_erc1155("w1".to_string(), BUYER.to_string(), 2)
+            .await;
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+    }
+
+    #[tokio::test]
+    async fn rejects_zero_quantity() {
+        let dynamo = MockDynamo::start();
+        let app = application(&dynamo, offline_chain(), Arc::new(MemoryMarketplace::new()));
+
+        let res = app
+            .sell_erc1155("w1".to_string(), Wei::from_ether("1").unwrap(), 0)
+            .await;
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+        let res = app
+            .transfer_erc1155("w1".to_string(), BUYER.to_string(), 0)
+            .await;
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+    }
+
+    const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    const CONDUIT: &str = "0x1e0049783f008a0085193e00003d00cd54003c71";
+
+    /// ERC1155のコントラクトで、gas priceは10 gwei。残高はether単位で指定する
+    fn buyer_chain(ether: &str, weth: &str, allowance: &str) -> MockChain {
+        let ether = Wei::from_ether(ether).unwrap().value();
+        let weth = Wei::from_ether(weth).unwrap().value();
+        let allowance = Wei::from_ether(allowance).unwrap().value();
+        MockChain::start(move |method, params| match method {
+            "eth_gasPrice" => Ok(json!(U256::exp10(10))),
+            "eth_getBalance" => {
+                assert_eq!(params[0], SELLER);
+                Ok(json!(ether))
+            }
+            "eth_call" => {
+                let to = params[0]["to"].as_str().unwrap();
+                let data = params[0]["data"].as_str().unwrap();
+                let value = if to == ERC1155_CONTRACT {
+                    // supportsInterface(ERC1155)だけtrue
+                    U256::from(data.contains("d9b67a26") as u8)
+                } else if to == WETH && data.starts_with("0x70a08231") {
+                    weth
+                } else if to == WETH && data.starts_with("0xdd62ed3e") {
+                    assert!(data.ends_with(&CONDUIT[2..]), "{}", data);
+                    allowance
+                } else {
+                    panic!("unexpected call: {}", params);
+                };
+                Ok(encode(&[Token::Uint(value)]))
+            }
+            v => panic!("unexpected method: {}", v),
+        })
+    }
+
+    fn listing(id: &str, ether: &str, payment_token: Option<&str>) -> market::Listing {
+        market::Listing {
+            id: id.to_string(),
+            maker: OTHER_SELLER.to_string(),
+            currency: if payment_token.is_some() {
+                "WETH"
+            } else {
+                "ETH"
+            }
+            .to_string(),
+            price: ether.to_string(),
+            payment_token: payment_token.map(|v| v.to_string()),
+            quantity: 1,
+            expires_at: None,
+        }
+    }
+
+    async fn buy(app: &Application) -> AppResult<Acquisition> {
+        app.buy_nft(
+            ERC1155_CONTRACT.to_string(),
+            "7".to_string(),
+            Wei::from_ether("1").unwrap(),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn buys_with_price_and_gas() {
+        let dynamo = MockDynamo::start();
+        put_work(&dynamo, WorkStatus::PublishNFT).await;
+        let token = TokenRef::new(
+            TokenSchema::Erc1155,
+            to_checksum(&ERC1155_CONTRACT.parse().unwrap()),
+            "7".to_string(),
+        );
+        let marketplace = Arc::new(marketplace(&token, &[OTHER_SELLER]));
+        marketplace.put_listing(token.clone(), listing("0x01", "0.5", None));
+
+        // 代金ちょうどの残高では、ガス代(10 gwei * 1.1 * 300k = 0.0033 ETH)が払えない
+        let app = application(
+            &dynamo,
+            buyer_chain("0.5", "0", "0").client(),
+            marketplace.clone(),
+        );
+        let res = buy(&app).await;
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+        assert!(marketplace.get_sales(&token).await.unwrap().is_empty());
+
+        // 上限を超える出品は買わない
+        let app = application(
+            &dynamo,
+            buyer_chain("10", "0", "0").client(),
+            marketplace.clone(),
+        );
+        let res = app
+            .buy_nft(
+                ERC1155_CONTRACT.to_string(),
+                "7".to_string(),
+                Wei::from_ether("0.4").unwrap(),
+            )
+            .await;
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+
+        let acquisition = buy(&app).await.unwrap();
+        assert_eq!(acquisition.price, Wei::from_ether("0.5").unwrap());
+        assert_eq!(acquisition.seller_address, OTHER_SELLER);
+        let sales = marketplace.get_sales(&token).await.unwrap();
+        assert_eq!(sales.len(), 1);
+        assert_eq!(sales[0].tx_hash, acquisition.tx_hash);
+    }
+
+    #[tokio::test]
+    async fn buys_weth_listing_with_balance_and_allowance() {
+        let dynamo = MockDynamo::start();
+        put_work(&dynamo, WorkStatus::PublishNFT).await;
+        let token = TokenRef::new(
+            TokenSchema::Erc1155,
+            to_checksum(&ERC1155_CONTRACT.parse().unwrap()),
+            "7".to_string(),
+        );
+        let marketplace = Arc::new(marketplace(&token, &[OTHER_SELLER]));
+        marketplace.set_payment_spender(CONDUIT);
+        marketplace.put_listing(token.clone(), listing("0x02", "0.5", Some(WETH)));
+
+        let cases = [
+            // ガス代のETHがない
+            ("0", "1", "1"),
+            // WETHの残高が足りない
+            ("1", "0.4", "1"),
+            // conduitへの承認が足りない
+            ("1", "1", "0.4"),
+        ];
+        for (ether, weth, allowance) in cases {
+            let chain = buyer_chain(ether, weth, allowance);
+            let app = application(&dynamo, chain.client(), marketplace.clone());
+            let res = buy(&app).await;
+            assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+        }
+        assert!(marketplace.get_sales(&token).await.unwrap().is_empty());
+
+        // 代金はWETHで払うので、ETHはガス代だけあればよい
+        let chain = buyer_chain("0.01", "0.5", "0.5");
+        let app = application(&dynamo, chain.client(), marketplace.clone());
+        let acquisition = buy(&app).await.unwrap();
+        assert_eq!(acquisition.price, Wei::from_ether("0.5").unwrap());
+        assert_eq!(marketplace.get_sales(&token).await.unwrap().len(), 1);
+    }
+
+    fn offer(id: &str, token: &TokenRef) -> Offer {
+        Offer::new(
+            "w1".to_string(),
+            1,
+            "memory".to_string(),
+            token,
+            market::Offer {
+                id: id.to_string(),
+                maker: BUYER.to_string(),
+                currency: "WETH".to_string(),
+                price: "0.8".to_string(),
+                quantity: 1,
+                expires_at: None,
+            },
+            now(),
+        )
+    }
+
+    #[tokio::test]
+    async fn accepts_offer_and_closes_others() {
+        let dynamo = MockDynamo::start();
+        put_work(&dynamo, WorkStatus::SellOrder).await;
+        let token = TokenRef::new(
+            TokenSchema::Erc721,
+            "0x4444444444444444444444444444444444444444".to_string(),
+            "8".to_string(),
+        );
+        let marketplace = Arc::new(marketplace(&token, &[SELLER]));
+        for id in ["0x0a", "0x0b"] {
+            let offer = offer(id, &token);
+            marketplace.put_offer(
+                token.clone(),
+                market::Offer {
+                    id: offer.id.clone(),
+                    maker: offer.maker.clone(),
+                    currency: offer.currency.clone(),
+                    price: offer.price.clone(),
+                    quantity: offer.quantity,
+                    expires_at: None,
+                },
+            );
+            dynamo.dao::<Offer>().put(&offer).await.unwrap();
+        }
+        let app = application(&dynamo, offline_chain(), marketplace.clone());
+
+        app.accept_offer("0x0a".to_string()).await.unwrap();
+
+        let sales = marketplace.get_sales(&token).await.unwrap();
+        assert_eq!(sales.len(), 1);
+        assert_eq!(sales[0].seller, SELLER);
+        assert_eq!(sales[0].buyer, BUYER);
+        let recorded = app.sale_dao.get_by_work("w1".to_string()).await.unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].tx_hash, sales[0].tx_hash);
+        assert_eq!(recorded[0].price, "0.8");
+
+        let accepted = app.offer_dao.get("0x0a".to_string()).await.unwrap();
+        assert_eq!(accepted.status, OfferStatus::Accepted);
+        // ERC721は売れたので、残りの申し込みは受け付けない
+        let other = app.offer_dao.get("0x0b".to_string()).await.unwrap();
+        assert_eq!(other.status, OfferStatus::Closed);
+        assert_eq!(work_status(&app).await, WorkStatus::Sold);
+
+        let res = app.accept_offer("0x0b".to_string()).await;
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+    }
+
+    #[test]
+    fn parses_decimal_and_hex_token_ids() {
+        assert_eq!(parse_token_id("10").unwrap(), 10);
+        assert_eq!(parse_token_id("0x0a").unwrap(), 10);
+        assert_eq!(parse_token_id(&format!("{:064x}", 10)).unwrap(), 10);
+        assert_eq!(parse_token_id(&"0".repeat(64)).unwrap(), 0);
+        assert_eq!(parse_token_id("0").unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_invalid_token_ids() {
+        let too_large = format!("{:0>64}", "10000000000000000");
+        for token_id in [
+            "", "0x", "abc", "-1", "+1", "1.5", "0xzz", "0x+a", " 1", &too_large,
+        ] {
+            let res = parse_token_id(token_id);
+            assert!(
+                matches!(res, Err(AppError::BadRequest(_))),
+                "{:?}: {:?}",
+                token_id,
+                res
+            );
+        }
+    }
+
+    const OWNER_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const OWNER: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+    const TX_HASH: &str = "0x00000000000000000000000000000000000000000000000000000000000000aa";
+
+    /// 公開前のERC1155のコレクションに、w1とw2の公開後のmetadataを準備しておく
+    async fn put_reveal_items(dynamo: &MockDynamo, collection: Collection) -> Vec<RevealItem> {
+        dynamo.dao::<Collection>().put(&collection).await.unwrap();
+        let me = User::new("me".to_string(), OWNER.to_string(), OWNER_KEY.to_string());
+        dynamo.dao::<User>().put(&me).await.unwrap();
+
+        let mut items = vec![];
+        for work_id in ["w1", "w2"] {
+            let item = RevealItem::new(
+                work_id.to_string(),
+                1,
+                TokenSchema::Erc1155,
+                ERC1155_CONTRACT.to_string(),
+                format!(r#"{{"name":"{}"}}"#, work_id),
+                false,
+                vec![],
+                now(),
+            );
+            dynamo.dao::<RevealItem>().put(&item).await.unwrap();
+            items.push(item);
+        }
+        items
+    }
+
+    fn hidden_collection() -> Collection {
+        Collection {
+            hidden: true,
+            ..Collection::new(1, TokenSchema::Erc1155, ERC1155_CONTRACT.to_string(), now())
+        }
+    }
+
+    fn hash_of(items: &[RevealItem]) -> String {
+        provenance_hash(
+            &items
+                .iter()
+                .map(|v| (v.work_id.clone(), v.metadata_hash.clone()))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// provenanceHashに`published`を返し、書き込みのトランザクションの呼び出しを記録する
+    fn provenance_chain(published: String, sent: Arc<Mutex<Vec<Value>>>) -> MockChain {
+        MockChain::start(move |method, params| match method {
+            "eth_call" => {
+                let (to, function, _) = decode_call(ERC1155_ABI, params);
+                assert_eq!(to, ERC1155_CONTRACT);
+                assert_eq!(function.name, "provenanceHash");
+                Ok(encode(&[Token::String(published.clone())]))
+            }
+            "eth_estimateGas" => {
+                sent.lock().unwrap().push(params.clone());
+                Ok(json!("0x30000"))
+            }
+            "eth_getTransactionCount" => Ok(json!("0x0")),
+            "eth_gasPrice" => Ok(json!("0x3b9aca00")),
+            "eth_chainId" => Ok(json!("0x1")),
+            "eth_sendRawTransaction" => Ok(json!(TX_HASH)),
+            "eth_getTransactionReceipt" => Ok(receipt(TX_HASH, OWNER)),
+            v => panic!("unexpected method: {}", v),
+        })
+    }
+
+    #[tokio::test]
+    async fn commits_provenance_to_contract() {
+        let dynamo = MockDynamo::start();
+        let items = put_reveal_items(&dynamo, hidden_collection()).await;
+        let sent = Arc::new(Mutex::new(vec![]));
+        let chain = provenance_chain("".to_string(), sent.clone());
+        let app = application(&dynamo, chain.client(), Arc::new(MemoryMarketplace::new()));
+
+        let hash = app.commit_provenance(TokenSchema::Erc1155).await.unwrap();
+
+        assert_eq!(hash, hash_of(&items));
+        let sent = sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        let (to, function, input) = decode_call(ERC1155_ABI, &sent[0]);
+        assert_eq!(to, ERC1155_CONTRACT);
+        assert_eq!(function.name, "setProvenanceHash");
+        assert_eq!(input, vec![Token::String(hash.clone())]);
+        let collection = app.collection(TokenSchema::Erc1155).await.unwrap();
+        assert_eq!(collection.provenance_hash, Some(hash));
+
+        // 確定後はやり直せない
+        let res = app.commit_provenance(TokenSchema::Erc1155).await;
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+    }
+
+    #[tokio::test]
+    async fn resumes_commit_with_published_hash() {
+        let dynamo = MockDynamo::start();
+        let items = put_reveal_items(&dynamo, hidden_collection()).await;
+        let sent = Arc::new(Mutex::new(vec![]));
+        let chain = provenance_chain(hash_of(&items), sent.clone());
+        let app = application(&dynamo, chain.client(), Arc::new(MemoryMarketplace::new()));
+
+        let hash = app.commit_provenance(TokenSchema::Erc1155).await.unwrap();
+
+        assert_eq!(hash, hash_of(&items));
+        assert!(sent.lock().unwrap().is_empty());
+        assert!(!chain
+            .methods()
+            .contains(&"eth_sendRawTransaction".to_string()));
+        let collection = app.collection(TokenSchema::Erc1155).await.unwrap();
+        assert_eq!(collection.provenance_hash, Some(hash));
+    }
+
+    #[tokio::test]
+    async fn refuses_commit_over_other_published_hash() {
+        let dynamo = MockDynamo::start();
+        put_reveal_items(&dynamo, hidden_collection()).await;
+        let sent = Arc::new(Mutex::new(vec![]));
+        let chain = provenance_chain("other".to_string(), sent.clone());
+        let app = application(&dynamo, chain.client(), Arc::new(MemoryMarketplace::new()));
+
+        let res = app.commit_provenance(TokenSchema::Erc1155).await;
+
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+        assert!(sent.lock().unwrap().is_empty());
+        let collection = app.collection(TokenSchema::Erc1155).await.unwrap();
+        assert_eq!(collection.provenance_hash, None);
+    }
+
+    /// 公開済みのコレクションで、公開されているmetadataを`published`から返して確かめる
+    async fn verify(published: HashMap<String, String>) -> Option<bool> {
+        let dynamo = MockDynamo::start();
+        let mut collection = hidden_collection();
+        collection.hidden = false;
+        collection.revealed_at = Some(now());
+        collection.provenance_hash = Some("committed".to_string());
+        let items = put_reveal_items(&dynamo, collection).await;
+        let chain = provenance_chain(hash_of(&items), Arc::new(Mutex::new(vec![])));
+        let app = application(&dynamo, chain.client(), Arc::new(MemoryMarketplace::new()));
+
+        app.verify_provenance_with(TokenSchema::Erc1155, |work_id| {
+            let data = published.get(&work_id).cloned().ok_or(AppError::NotFound);
+            async move { data.map(Bytes::from) }
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn verifies_published_metadata() {
+        let published: HashMap<String, String> = ["w1", "w2"]
+            .iter()
+            .map(|v| (v.to_string(), format!(r#"{{"name":"{}"}}"#, v)))
+            .collect();
+        assert_eq!(verify(published.clone()).await, Some(true));
+
+        // 公開後に1件でも差し替えると一致しない
+        let mut tampered = published;
+        tampered.insert("w2".to_string(), r#"{"name":"other"}"#.to_string());
+        assert_eq!(verify(tampered).await, Some(false));
+    }
+
+    #[tokio::test]
+    async fn skips_verification_before_reveal() {
+        let dynamo = MockDynamo::start();
+        let mut collection = hidden_collection();
+        collection.provenance_hash = Some("committed".to_string());
+        put_reveal_items(&dynamo, collection).await;
+        let app = application(&dynamo, offline_chain(), Arc::new(MemoryMarketplace::new()));
+
+        let res = app
+            .verify_provenance_with(TokenSchema::Erc1155, |_| async {
+                panic!("should not fetch before reveal")
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(res, None);
+    }
+
+    /// /lengthはContent-Length付きで、/chunkedは4バイトずつ3回に分けて12バイトを返す
+    fn serve_body() -> String {
+        use hyper::service::{make_service_fn, service_fn};
+        use hyper::{Body, Request, Response, Server};
+        use std::convert::Infallible;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let make = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|req: Request<Body>| async move {
+                let body = match req.uri().path() {
+                    "/length" => Body::from(vec![0u8; 12]),
+                    _ => Body::wrap_stream(futures::stream::iter(
+                        (0..3).map(|_| Ok::<_, Infallible>(vec![0u8; 4])),
+                    )),
+                };
+                Ok::<_, Infallible>(Response::new(body))
+            }))
+        });
+        tokio::spawn(Server::from_tcp(listener).unwrap().serve(make));
+        url
+    }
+
+    #[tokio::test]
+    async fn caps_downloaded_size() {
+        let url = serve_body();
+        for path in ["length", "chunked"] {
+            let res = reqwest::get(format!("{}/{}", url, path)).await.unwrap();
+            let res = read_capped(res, 8).await;
+            assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+
+            let res = reqwest::get(format!("{}/{}", url, path)).await.unwrap();
+            assert_eq!(read_capped(res, 12).await.unwrap().len(), 12);
+        }
+    }
+}
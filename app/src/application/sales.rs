@@ -0,0 +1,133 @@
+use crate::aws::s3::{pre_sign_for_get, upload_object};
+use crate::datetime::format_datetime;
+use crate::domain::sale::{Revenue, RevenueGroup, Sale};
+use crate::{ddb, ethereum, AppError, AppResult, SALES_CSV_PATH_PREFIX};
+use bytes::Bytes;
+use std::env;
+
+pub struct Application {
+    me_id: String,
+    sale_dao: ddb::Dao<Sale>,
+    ethereum_cli: ethereum::Client,
+}
+
+impl Application {
+    pub async fn new(me_id: String) -> AppResult<Self> {
+        let sale_dao: ddb::Dao<Sale> = ddb::Dao::new().await;
+        let ethereum_cli = ethereum::Client::from_registry().await?;
+
+        Ok(Self {
+            me_id,
+            sale_dao,
+            ethereum_cli,
+        })
+    }
+
+    /// 接続中のチェーンでの自分の作品の台帳。monthは "2022-08" の形式で、指定するとその月だけにする（古い順）
+    pub async fn sales(&self, month: Option<String>) -> AppResult<Vec<Sale>> {
+        if let Some(month) = &month {
+            let valid = month.len() == 7
+                && month.chars().enumerate().all(|(i, c)| match i {
+                    4 => c == '-',
+                    _ => c.is_ascii_digit(),
+                });
+            if !valid {
+                return Err(AppError::BadRequest(format!(
+                    "月は2022-08の形式で指定してください: {}",
+                    month
+                )));
+            }
+        }
+
+        let chain_id = self.ethereum_cli.chain().chain_id;
+        let mut sales: Vec<Sale> = self
+            .sale_dao
+            .get_all()
+            .await?
+            .into_iter()
+            .filter(|v| v.chain_id == chain_id && v.publisher_id == self.me_id)
+            .filter(|v| month.is_none() || month.as_ref() == Some(&v.month()))
+            .collect();
+        sales.sort_by_key(|v| v.sold_at);
+        Ok(sales)
+    }
+
+    pub async fn revenue(
+        &self,
+        group: RevenueGroup,
+        month: Option<String>,
+    ) -> AppResult<Vec<Revenue>> {
+        let sales = self.sales(month).await?;
+        Ok(Revenue::aggregate(&sales, |v| group.key_of(v)))
+    }
+
+    /// 経理向けに台帳をCSVにしてS3に置き、ダウンロード用のURLを返す
+    pub async fn export_csv(&self, month: Option<String>) -> AppResult<String> {
+        let sales = self.sales(month.clone()).await?;
+
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.write_record([
+            "SoldAt",
+            "WorkID",
+            "Source",
+            "Marketplace",
+            "Schema",
+            "ContractAddress",
+            "TokenID",
+            "PublisherID",
+            "Seller",
+            "Buyer",
+            "Currency",
+            "Price",
+            "Fee",
+            "Royalty",
+            "Proceeds",
+            "Quantity",
+            "TxHash",
+        ])?;
+        for sale in &sales {
+            writer.write_record([
+                format_datetime(sale.sold_at),
+                sale.work_id.clone(),
+                sale.source.to_string(),
+                sale.marketplace.clone(),
+                sale.schema.to_string(),
+                sale.contract_address.clone(),
+                sale.token_id.clone(),
+                sale.publisher_id.clone(),
+                sale.seller.clone(),
+                sale.buyer.clone(),
+                sale.currency.clone(),
+                sale.price.clone(),
+                sale.fee.clone(),
+                sale.royalty.clone(),
+                sale.proceeds(),
+                sale.quantity.to_string(),
+                sale.tx_hash.clone(),
+            ])?;
+        }
+        let data = writer
+            .into_inner()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        let bucket = env::var("S3_USER_BUCKET").unwrap();
+        let s3_key = format!(
+            "{}/{}/{}/{}.csv",
+            SALES_CSV_PATH_PREFIX,
+            self.me_id,
+            self.ethereum_cli.chain().chain_id,
+            month.unwrap_or_else(|| "all".to_string())
+        );
+        upload_object(
+            bucket.clone(),
+            s3_key.clone(),
+            Bytes::from(data),
+            "text/csv".to_string(),
+        )
+        .await?;
+        println!("exported sales: {} ({} rows)", s3_key, sales.len());
+
+        let url = pre_sign_for_get(bucket, s3_key).await?;
+        Ok(url.to_string())
+    }
+}
@@ -10,15 +10,15 @@ pub struct Application {
 }
 
 impl Application {
-    pub async fn new(me_id: String) -> Self {
+    pub async fn new(me_id: String) -> AppResult<Self> {
         let user_dao: ddb::Dao<User> = ddb::Dao::new().await;
-        let ethereum_cli = ethereum::Client::new();
+        let ethereum_cli = ethereum::Client::from_registry().await?;
 
-        Self {
+        Ok(Self {
             me_id,
             user_dao,
             ethereum_cli,
-        }
+        })
     }
 
     pub async fn get_me(&self) -> AppResult<UserWithBalance> {
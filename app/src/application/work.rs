@@ -1,7 +1,7 @@
 use crate::aws::s3;
 use crate::aws::s3::upload_object;
 use crate::csv_loader::load_from_csv;
-use crate::domain::work::{Thumbnail, Work, WorkStatus};
+use crate::domain::work::{ImportedWork, Thumbnail, Work, WorkStatus};
 use crate::{ddb, internal_api, THUMBNAIL_CSV_PATH_PREFIX, WORK_CSV_PATH_PREFIX};
 use crate::{AppError, AppResult};
 use http::Uri;
@@ -91,11 +91,11 @@ impl Application {
             "text/csv".to_string(),
         )
         .await?;
-        let works = load_from_csv::<Work>(bytes, None)?;
+        let works = load_from_csv::<ImportedWork>(bytes, None)?;
         for work in works {
             let current = self.work_dao.get(work.id.clone()).await;
             if let Ok(mut w) = current {
-                w.video_path = work.video_path;
+                w.imported(work);
                 self.work_dao.put(&w).await?;
                 continue;
             }
@@ -104,7 +104,7 @@ impl Application {
                     return Err(err);
                 }
             }
-            self.work_dao.put(&work).await?;
+            self.work_dao.put(&work.into_work()).await?;
         }
 
         let url = urls[1].clone();
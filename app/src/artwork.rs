@@ -0,0 +1,288 @@
+#[cfg(test)]
+mod fixture;
+mod strip;
+mod video;
+
+use crate::{AppError, AppResult};
+use bytes::Bytes;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use std::io::Cursor;
+
+pub use strip::strip_metadata;
+pub use video::from_video;
+
+pub const MAX_FILE_SIZE: usize = 50 * 1024 * 1024;
+/// マーケットプレイスの推奨に合わせる
+pub const MIN_DIMENSION: u32 = 350;
+pub const MAX_DIMENSION: u32 = 8192;
+/// プレビューの幅。元の画像より小さいものだけ作る
+const PREVIEW_WIDTHS: [u32; 3] = [256, 512, 1024];
+const JPEG_QUALITY: u8 = 90;
+
+/// 受け付ける画像の形式。拡張子ではなく中身から判定する
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageKind {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+}
+
+impl ImageKind {
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::Png)
+        } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Self::Jpeg)
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some(Self::Gif)
+        } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+            Some(Self::Webp)
+        } else {
+            None
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Gif => "image/gif",
+            Self::Webp => "image/webp",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Gif => "gif",
+            Self::Webp => "webp",
+        }
+    }
+
+    fn format(&self) -> ImageFormat {
+        match self {
+            Self::Png => ImageFormat::Png,
+            Self::Jpeg => ImageFormat::Jpeg,
+            Self::Gif => ImageFormat::Gif,
+            Self::Webp => ImageFormat::WebP,
+        }
+    }
+}
+
+/// 配信用の派生画像
+#[derive(Clone, Debug)]
+pub struct Variant {
+    /// 幅ごとに w256 など。元の大きさは full
+    pub name: String,
+    pub kind: ImageKind,
+    pub width: u32,
+    pub height: u32,
+    pub data: Bytes,
+}
+
+impl Variant {
+    pub fn file_name(&self) -> String {
+        format!("{}.{}", self.name, self.kind.extension())
+    }
+}
+
+/// 確認済みの作品画像。dataはメタデータを取り除いたもので、metadataのimageに使う
+#[derive(Clone, Debug)]
+pub struct Artwork {
+    pub kind: ImageKind,
+    pub width: u32,
+    pub height: u32,
+    pub data: Bytes,
+    pub variants: Vec<Variant>,
+}
+
+/// 形式・大きさを確認し、メタデータの除去とプレビュー・WebPの生成を行う
+pub fn process(data: &[u8]) -> AppResult<Artwork> {
+    if data.len() > MAX_FILE_SIZE {
+        return Err(AppError::BadRequest(format!(
+            "画像が大きすぎます: {} > {} bytes",
+            data.len(),
+            MAX_FILE_SIZE
+        )));
+    }
+    let kind = ImageKind::sniff(data).ok_or_else(|| {
+        AppError::BadRequest(
+            "未対応の画像形式です。PNG, JPEG, GIF, WebPに対応しています".to_string(),
+        )
+    })?;
+
+    let mut decoder = ImageReader::with_format(Cursor::new(data), kind.format()).into_decoder()?;
+    let (width, height) = decoder.dimensions();
+    validate_dimensions(width, height)?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+
+    // 向きの情報もメタデータと一緒に消えるので、画素に反映して作り直す
+    let data = if orientation == Orientation::NoTransforms {
+        strip_metadata(kind, data)?
+    } else {
+        image.apply_orientation(orientation);
+        encode(&image, kind)?
+    };
+
+    Ok(Artwork {
+        kind,
+        width: image.width(),
+        height: image.height(),
+        data: Bytes::from(data),
+        variants: variants(&image, kind)?,
+    })
+}
+
+fn validate_dimensions(width: u32, height: u32) -> AppResult<()> {
+    if width < MIN_DIMENSION || height < MIN_DIMENSION {
+        return Err(AppError::BadRequest(format!(
+            "画像が小さすぎます: {}x{} (最小 {}x{})",
+            width, height, MIN_DIMENSION, MIN_DIMENSION
+        )));
+    }
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(AppError::BadRequest(format!(
+            "画像が大きすぎます: {}x{} (最大 {}x{})",
+            width, height, MAX_DIMENSION, MAX_DIMENSION
+        )));
+    }
+    Ok(())
+}
+
+/// 幅ごとのプレビューを元の系統の形式(JPEGはJPEG、それ以外はPNG)とWebPで作る
+/// アニメーションGIFのプレビューは最初のフレームになる
+fn variants(image: &DynamicImage, kind: ImageKind) -> AppResult<Vec<Variant>> {
+    let preview_kind = match kind {
+        ImageKind::Jpeg => ImageKind::Jpeg,
+        _ => ImageKind::Png,
+    };
+
+    let mut variants: Vec<Variant> = vec![];
+    for width in PREVIEW_WIDTHS {
+        if width >= image.width() {
+            continue;
+        }
+        let resized = image.resize(width, MAX_DIMENSION, FilterType::Lanczos3);
+        for kind in [preview_kind, ImageKind::Webp] {
+            variants.push(Variant {
+                name: format!("w{}", width),
+                kind,
+                width: resized.width(),
+                height: resized.height(),
+                data: Bytes::from(encode(&resized, kind)?),
+            });
+        }
+    }
+    variants.push(Variant {
+        name: "full".to_string(),
+        kind: ImageKind::Webp,
+        width: image.width(),
+        height: image.height(),
+        data: Bytes::from(encode(image, ImageKind::Webp)?),
+    });
+
+    Ok(variants)
+}
+
+fn encode(image: &DynamicImage, kind: ImageKind) -> AppResult<Vec<u8>> {
+    let mut out: Vec<u8> = vec![];
+    match kind {
+        ImageKind::Png => image.write_with_encoder(PngEncoder::new(&mut out))?,
+        ImageKind::Jpeg => image
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY))?,
+        // 対応しているのは可逆圧縮のみ
+        ImageKind::Webp => image
+            .to_rgba8()
+            .write_with_encoder(WebPEncoder::new_lossless(&mut out))?,
+        ImageKind::Gif => image.write_to(&mut Cursor::new(&mut out), ImageFormat::Gif)?,
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artwork::fixture::{exif_orientation, jpeg, jpeg_markers, png, png_chunks};
+
+    #[test]
+    fn sniffs_image_kind() {
+        assert_eq!(ImageKind::sniff(&png(1, 1, &[])), Some(ImageKind::Png));
+        assert_eq!(ImageKind::sniff(&jpeg(1, 1, &[])), Some(ImageKind::Jpeg));
+        assert_eq!(ImageKind::sniff(b"GIF87a"), Some(ImageKind::Gif));
+        assert_eq!(ImageKind::sniff(b"GIF89a"), Some(ImageKind::Gif));
+        assert_eq!(
+            ImageKind::sniff(b"RIFF\0\0\0\0WEBPVP8L"),
+            Some(ImageKind::Webp)
+        );
+        assert_eq!(ImageKind::sniff(b"RIFF\0\0\0\0WAVEfmt "), None);
+        assert_eq!(ImageKind::sniff(b"RIFF"), None);
+        assert_eq!(ImageKind::sniff(b"BM"), None);
+        assert_eq!(ImageKind::sniff(b""), None);
+    }
+
+    #[test]
+    fn validates_dimensions() {
+        assert!(validate_dimensions(MIN_DIMENSION, MAX_DIMENSION).is_ok());
+        assert!(validate_dimensions(MIN_DIMENSION - 1, MIN_DIMENSION).is_err());
+        assert!(validate_dimensions(MIN_DIMENSION, MAX_DIMENSION + 1).is_err());
+
+        let res = process(&png(MIN_DIMENSION - 1, MIN_DIMENSION, &[]));
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+    }
+
+    #[test]
+    fn rejects_unsupported_or_oversized_data() {
+        let res = process(b"BM\0\0\0\0");
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+
+        let mut data = png(1, 1, &[]);
+        data.resize(MAX_FILE_SIZE + 1, 0);
+        let res = process(&data);
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+    }
+
+    #[test]
+    fn strips_metadata_and_makes_variants() {
+        let artwork = process(&png(400, 360, &[(b"tEXt", b"Comment\0hello")])).unwrap();
+
+        assert_eq!(artwork.kind, ImageKind::Png);
+        assert_eq!((artwork.width, artwork.height), (400, 360));
+        assert_eq!(
+            png_chunks(&artwork.data),
+            vec![*b"IHDR", *b"IDAT", *b"IEND"]
+        );
+        let names: Vec<String> = artwork.variants.iter().map(|v| v.file_name()).collect();
+        assert_eq!(names, vec!["w256.png", "w256.webp", "full.webp"]);
+        for variant in &artwork.variants {
+            let image = image::load_from_memory(&variant.data).unwrap();
+            assert_eq!(
+                (image.width(), image.height()),
+                (variant.width, variant.height)
+            );
+        }
+        assert_eq!(
+            (artwork.variants[0].width, artwork.variants[0].height),
+            (256, 230)
+        );
+    }
+
+    #[test]
+    fn applies_orientation_before_dropping_exif() {
+        // 6は時計回りに90度回して表示する
+        let artwork = process(&jpeg(400, 360, &[(0xE1, &exif_orientation(6))])).unwrap();
+
+        assert_eq!((artwork.width, artwork.height), (360, 400));
+        assert!(!jpeg_markers(&artwork.data).contains(&0xE1));
+        let image = image::load_from_memory(&artwork.data).unwrap();
+        assert_eq!((image.width(), image.height()), (360, 400));
+    }
+}
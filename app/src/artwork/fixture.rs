@@ -0,0 +1,157 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, Rgb, RgbImage};
+
+const VP8X_FLAG_EXIF: u8 = 0x08;
+const VP8X_FLAG_XMP: u8 = 0x04;
+
+/// テスト用の画像。向きがわかるよう座標で色を変える
+pub fn image(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+        Rgb([x as u8, y as u8, 0])
+    }))
+}
+
+/// IHDRの直後にチャンクを入れたPNG
+pub fn png(width: u32, height: u32, chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![];
+    image(width, height)
+        .write_with_encoder(PngEncoder::new(&mut data))
+        .unwrap();
+    // シグネチャ(8) + IHDR(12 + 13)
+    let mut out = data[..33].to_vec();
+    for (kind, body) in chunks {
+        out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        out.extend_from_slice(*kind);
+        out.extend_from_slice(body);
+        let crc = crc32(&[kind.as_slice(), body].concat());
+        out.extend_from_slice(&crc.to_be_bytes());
+    }
+    out.extend_from_slice(&data[33..]);
+    out
+}
+
+/// SOIの直後にセグメントを入れたJPEG
+pub fn jpeg(width: u32, height: u32, segments: &[(u8, &[u8])]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![];
+    image(width, height)
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut data, 90))
+        .unwrap();
+    let mut out = data[..2].to_vec();
+    for (marker, body) in segments {
+        out.extend_from_slice(&[0xFF, *marker]);
+        out.extend_from_slice(&(body.len() as u16 + 2).to_be_bytes());
+        out.extend_from_slice(body);
+    }
+    out.extend_from_slice(&data[2..]);
+    out
+}
+
+/// 向き(Orientation)のタグだけを持つAPP1のEXIF
+pub fn exif_orientation(orientation: u16) -> Vec<u8> {
+    let mut out = b"Exif\0\0MM\0\x2a".to_vec();
+    out.extend_from_slice(&8u32.to_be_bytes());
+    out.extend_from_slice(&1u16.to_be_bytes());
+    // タグ、型(SHORT)、個数、値(4バイトに詰める)
+    out.extend_from_slice(&0x0112u16.to_be_bytes());
+    out.extend_from_slice(&3u16.to_be_bytes());
+    out.extend_from_slice(&1u32.to_be_bytes());
+    out.extend_from_slice(&orientation.to_be_bytes());
+    out.extend_from_slice(&[0, 0]);
+    out.extend_from_slice(&0u32.to_be_bytes());
+    out
+}
+
+/// VP8Xの拡張形式にして、画像のチャンクの後ろにチャンクを入れたWebP
+/// EXIFとXMPのフラグは入れたチャンクに合わせて立てる
+pub fn webp(width: u32, height: u32, chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![];
+    image(width, height)
+        .to_rgba8()
+        .write_with_encoder(WebPEncoder::new_lossless(&mut data))
+        .unwrap();
+
+    let mut flags = 0;
+    for (kind, _) in chunks {
+        match *kind {
+            b"EXIF" => flags |= VP8X_FLAG_EXIF,
+            b"XMP " => flags |= VP8X_FLAG_XMP,
+            _ => {}
+        }
+    }
+    let mut vp8x = vec![flags, 0, 0, 0];
+    vp8x.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+    vp8x.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+
+    let mut out = data[..12].to_vec();
+    push_riff_chunk(&mut out, b"VP8X", &vp8x);
+    out.extend_from_slice(&data[12..]);
+    for (kind, body) in chunks {
+        push_riff_chunk(&mut out, kind, body);
+    }
+    let size = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&size.to_le_bytes());
+    out
+}
+
+fn push_riff_chunk(out: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    out.extend_from_slice(kind);
+    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    out.extend_from_slice(body);
+    if body.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+/// PNGのチャンクのCRC
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// RIFFのチャンクを順に(種類, データ)で返す
+pub fn riff_chunks(data: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+    let mut chunks = vec![];
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let length = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let kind: [u8; 4] = data[pos..pos + 4].try_into().unwrap();
+        chunks.push((kind, data[pos + 8..pos + 8 + length].to_vec()));
+        pos += 8 + length + length % 2;
+    }
+    chunks
+}
+
+/// PNGのチャンクの種類を順に返す
+pub fn png_chunks(data: &[u8]) -> Vec<[u8; 4]> {
+    let mut chunks = vec![];
+    let mut pos = 8;
+    while pos + 8 <= data.len() {
+        let length = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        chunks.push(data[pos + 4..pos + 8].try_into().unwrap());
+        pos += 12 + length;
+    }
+    chunks
+}
+
+/// SOSまでのJPEGのマーカーを順に返す
+pub fn jpeg_markers(data: &[u8]) -> Vec<u8> {
+    let mut markers = vec![];
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos + 1] != 0xDA {
+        markers.push(data[pos + 1]);
+        pos += 2 + u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+    }
+    markers
+}
@@ -0,0 +1,214 @@
+use crate::artwork::ImageKind;
+use crate::{AppError, AppResult};
+
+/// 画素はそのままで、撮影情報などのメタデータだけを取り除く
+pub fn strip_metadata(kind: ImageKind, data: &[u8]) -> AppResult<Vec<u8>> {
+    match kind {
+        ImageKind::Png => strip_png(data),
+        ImageKind::Jpeg => strip_jpeg(data),
+        ImageKind::Webp => strip_webp(data),
+        // GIFにはEXIFを入れる場所がない
+        ImageKind::Gif => Ok(data.to_vec()),
+    }
+}
+
+fn broken() -> AppError {
+    AppError::BadRequest("画像が壊れています".to_string())
+}
+
+const PNG_SIGNATURE_SIZE: usize = 8;
+const PNG_METADATA_CHUNKS: [&[u8]; 5] = [b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// チャンクは 長さ(4) + 種類(4) + データ + CRC(4)
+fn strip_png(data: &[u8]) -> AppResult<Vec<u8>> {
+    let mut out = data[..PNG_SIGNATURE_SIZE].to_vec();
+    let mut pos = PNG_SIGNATURE_SIZE;
+    while pos < data.len() {
+        let header = data.get(pos..pos + 8).ok_or_else(broken)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let end = pos + 12 + length;
+        let chunk = data.get(pos..end).ok_or_else(broken)?;
+        if !PNG_METADATA_CHUNKS.contains(&&header[4..8]) {
+            out.extend_from_slice(chunk);
+        }
+        if &header[4..8] == b"IEND" {
+            break;
+        }
+        pos = end;
+    }
+    Ok(out)
+}
+
+const JPEG_APP1: u8 = 0xE1;
+const JPEG_APP13: u8 = 0xED;
+const JPEG_COM: u8 = 0xFE;
+const JPEG_SOS: u8 = 0xDA;
+
+/// APP1(EXIF/XMP)、APP13(IPTC)、コメントを取り除く。JFIFやICCプロファイルは残す
+/// SOS以降は画像データなのでそのまま
+fn strip_jpeg(data: &[u8]) -> AppResult<Vec<u8>> {
+    let mut out = data[..2].to_vec();
+    let mut pos = 2;
+    loop {
+        let header = data.get(pos..pos + 2).ok_or_else(broken)?;
+        if header[0] != 0xFF {
+            return Err(broken());
+        }
+        let marker = header[1];
+        // 区切りのための0xFFの連続
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+        // 長さを持たないマーカー
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            out.extend_from_slice(header);
+            pos += 2;
+            continue;
+        }
+        if marker == JPEG_SOS {
+            out.extend_from_slice(&data[pos..]);
+            return Ok(out);
+        }
+        let length = data.get(pos + 2..pos + 4).ok_or_else(broken)?;
+        let end = pos + 2 + u16::from_be_bytes([length[0], length[1]]) as usize;
+        let segment = data.get(pos..end).ok_or_else(broken)?;
+        if ![JPEG_APP1, JPEG_APP13, JPEG_COM].contains(&marker) {
+            out.extend_from_slice(segment);
+        }
+        pos = end;
+    }
+}
+
+const RIFF_HEADER_SIZE: usize = 12;
+const VP8X_FLAG_EXIF: u8 = 0x08;
+const VP8X_FLAG_XMP: u8 = 0x04;
+
+/// チャンクは 種類(4) + 長さ(4, LE) + データ + 偶数にするための詰め物
+/// EXIFとXMPを取り除き、VP8Xの対応するフラグを落としてRIFFの長さを直す
+fn strip_webp(data: &[u8]) -> AppResult<Vec<u8>> {
+    let mut out = data.get(..RIFF_HEADER_SIZE).ok_or_else(broken)?.to_vec();
+    let mut pos = RIFF_HEADER_SIZE;
+    while pos < data.len() {
+        let header = data.get(pos..pos + 8).ok_or_else(broken)?;
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let end = (pos + 8 + length + length % 2).min(data.len());
+        let chunk = data.get(pos..end).ok_or_else(broken)?;
+        match &header[..4] {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" => {
+                let start = out.len();
+                out.extend_from_slice(chunk);
+                if let Some(flags) = out.get_mut(start + 8) {
+                    *flags &= !(VP8X_FLAG_EXIF | VP8X_FLAG_XMP);
+                }
+            }
+            _ => out.extend_from_slice(chunk),
+        }
+        pos = end;
+    }
+    let size = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&size.to_le_bytes());
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artwork::fixture::{
+        exif_orientation, jpeg, jpeg_markers, png, png_chunks, riff_chunks, webp,
+    };
+
+    fn decoded_size(data: &[u8]) -> (u32, u32) {
+        let image = image::load_from_memory(data).unwrap();
+        (image.width(), image.height())
+    }
+
+    #[test]
+    fn removes_png_text_and_exif() {
+        let exif = exif_orientation(1);
+        let data = png(
+            8,
+            6,
+            &[
+                (b"tEXt", b"Comment\0hello"),
+                (b"eXIf", &exif[6..]),
+                (b"tIME", &[0x07, 0xE6, 1, 1, 0, 0, 0]),
+            ],
+        );
+
+        let out = strip_png(&data).unwrap();
+
+        assert_eq!(png_chunks(&out), vec![*b"IHDR", *b"IDAT", *b"IEND"]);
+        assert_eq!(out, png(8, 6, &[]));
+        assert_eq!(decoded_size(&out), (8, 6));
+    }
+
+    #[test]
+    fn removes_jpeg_app1_and_comment() {
+        let data = jpeg(
+            8,
+            6,
+            &[
+                (JPEG_APP1, &exif_orientation(1)),
+                (JPEG_APP13, b"Photoshop 3.0\0"),
+                (JPEG_COM, b"comment"),
+            ],
+        );
+
+        let out = strip_jpeg(&data).unwrap();
+
+        let markers = jpeg_markers(&out);
+        assert!(!markers.contains(&JPEG_APP1), "{:x?}", markers);
+        assert!(!markers.contains(&JPEG_APP13), "{:x?}", markers);
+        assert!(!markers.contains(&JPEG_COM), "{:x?}", markers);
+        assert_eq!(out, jpeg(8, 6, &[]));
+        assert_eq!(decoded_size(&out), (8, 6));
+    }
+
+    #[test]
+    fn clears_vp8x_flags_and_fixes_riff_size() {
+        // 奇数の長さで、詰め物ごと取り除けることも確かめる
+        let data = webp(
+            8,
+            6,
+            &[(b"EXIF", &exif_orientation(1)), (b"XMP ", b"<xmp/>x")],
+        );
+
+        let out = strip_webp(&data).unwrap();
+
+        let chunks = riff_chunks(&out);
+        let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+        assert_eq!(kinds, vec![b"VP8X", b"VP8L"]);
+        assert_eq!(chunks[0].1[0] & (VP8X_FLAG_EXIF | VP8X_FLAG_XMP), 0);
+        assert_eq!(
+            u32::from_le_bytes(out[4..8].try_into().unwrap()) as usize,
+            out.len() - 8
+        );
+        assert_eq!(decoded_size(&out), (8, 6));
+    }
+
+    #[test]
+    fn rejects_truncated_chunks() {
+        let data = png(8, 6, &[]);
+        assert!(matches!(
+            strip_png(&data[..40]),
+            Err(AppError::BadRequest(_))
+        ));
+
+        let data = jpeg(8, 6, &[]);
+        assert!(matches!(
+            strip_jpeg(&data[..4]),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            strip_jpeg(&[0xFF, 0xD8, 0x00, 0x00]),
+            Err(AppError::BadRequest(_))
+        ));
+
+        assert!(matches!(
+            strip_webp(b"RIFF\0\0\0\0WEBPVP8"),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
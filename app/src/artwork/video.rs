@@ -0,0 +1,181 @@
+use crate::artwork::{process, Artwork, ImageKind, Variant};
+use crate::{AppError, AppResult};
+use bytes::Bytes;
+use std::env;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Output;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// ポスターにするフレームの位置(秒)。これより短い動画は先頭のフレームにする
+const POSTER_SECONDS: &str = "1";
+/// ループするプレビューの長さ(秒)・フレームレート・幅
+const PREVIEW_SECONDS: &str = "3";
+const PREVIEW_FPS: u32 = 12;
+const PREVIEW_WIDTH: u32 = 480;
+/// ffmpegを打ち切るまでの時間(秒)。FFMPEG_TIMEOUT_SECで変えられる
+const DEFAULT_TIMEOUT_SECONDS: u64 = 120;
+
+/// 動画のポスターフレームを作品画像にし、ループするGIFのプレビューを preview として加える
+/// デコードには環境変数 FFMPEG_PATH のffmpeg(デフォルトはPATH上のもの)を使う
+pub async fn from_video(extension: &str, data: &[u8]) -> AppResult<Artwork> {
+    let input = env::temp_dir().join(format!("{}.{}", uuid::Uuid::new_v4(), extension));
+    tokio::fs::write(&input, data)
+        .await
+        .map_err(|e| AppError::Internal(format!("video write error: {:?}", e)))?;
+
+    let result = extract(&input).await;
+    if let Err(e) = tokio::fs::remove_file(&input).await {
+        println!("failed to remove {}: {:?}", input.display(), e);
+    }
+    result
+}
+
+async fn extract(input: &Path) -> AppResult<Artwork> {
+    let input = input.to_string_lossy();
+    let mut poster = poster_frame(&input, POSTER_SECONDS).await?;
+    if poster.is_empty() {
+        poster = poster_frame(&input, "0").await?;
+    }
+    if poster.is_empty() {
+        return Err(AppError::BadRequest(
+            "動画からフレームを取り出せません".to_string(),
+        ));
+    }
+    let mut artwork = process(&poster)?;
+
+    let filter = format!(
+        "fps={},scale={}:-1:flags=lanczos,split[a][b];[a]palettegen[p];[b][p]paletteuse",
+        PREVIEW_FPS, PREVIEW_WIDTH
+    );
+    let preview = ffmpeg(&[
+        "-t",
+        PREVIEW_SECONDS,
+        "-i",
+        &input,
+        "-vf",
+        &filter,
+        "-loop",
+        "0",
+        "-f",
+        "gif",
+        "pipe:1",
+    ])
+    .await?;
+    let (width, height) = gif_dimensions(&preview)
+        .ok_or_else(|| AppError::BadRequest("動画からプレビューを作れません".to_string()))?;
+    artwork.variants.push(Variant {
+        name: "preview".to_string(),
+        kind: ImageKind::Gif,
+        width,
+        height,
+        data: Bytes::from(preview),
+    });
+
+    Ok(artwork)
+}
+
+/// PNGで取り出す。指定の位置が動画より後ろなら空になる
+async fn poster_frame(input: &str, seconds: &str) -> AppResult<Vec<u8>> {
+    ffmpeg(&[
+        "-ss",
+        seconds,
+        "-i",
+        input,
+        "-frames:v",
+        "1",
+        "-f",
+        "image2pipe",
+        "-vcodec",
+        "png",
+        "pipe:1",
+    ])
+    .await
+}
+
+/// 標準出力の内容を返す
+async fn ffmpeg(args: &[&str]) -> AppResult<Vec<u8>> {
+    let path = env::var("FFMPEG_PATH").unwrap_or_else(|_| "ffmpeg".to_string());
+    let timeout = Duration::from_secs(
+        env::var("FFMPEG_TIMEOUT_SEC")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TIMEOUT_SECONDS),
+    );
+    let mut command = Command::new(&path);
+    command
+        .args(["-hide_banner", "-loglevel", "error", "-nostdin"])
+        .args(args);
+    let output = output_within(command, timeout)
+        .await?
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => AppError::Internal(format!(
+                "ffmpegが見つかりません。FFMPEG_PATHを設定してください: {}",
+                path
+            )),
+            _ => AppError::Internal(format!("ffmpeg error: {:?}", e)),
+        })?;
+    if !output.status.success() {
+        return Err(AppError::BadRequest(format!(
+            "動画を読み込めません: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// 壊れた動画などで終わらないときは、timeoutで打ち切ってプロセスを止める
+async fn output_within(
+    mut command: Command,
+    timeout: Duration,
+) -> AppResult<std::io::Result<Output>> {
+    command.kill_on_drop(true);
+    tokio::time::timeout(timeout, command.output())
+        .await
+        .map_err(|_| {
+            AppError::Internal(format!(
+                "ffmpegが{}秒以内に終わりませんでした",
+                timeout.as_secs()
+            ))
+        })
+}
+
+/// GIFのヘッダーにある論理画面の大きさ
+fn gif_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if ImageKind::sniff(data) != Some(ImageKind::Gif) || data.len() < 10 {
+        return None;
+    }
+    let width = u16::from_le_bytes([data[6], data[7]]) as u32;
+    let height = u16::from_le_bytes([data[8], data[9]]) as u32;
+    Some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn stops_process_after_timeout() {
+        let mut command = Command::new("sleep");
+        command.arg("10");
+
+        let started = Instant::now();
+        let result = output_within(command, Duration::from_millis(200)).await;
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn returns_output_within_timeout() {
+        let mut command = Command::new("echo");
+        command.arg("ok");
+
+        let output = output_within(command, Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(output.stdout, b"ok\n");
+    }
+}
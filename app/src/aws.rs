@@ -1,5 +1,4 @@
 pub mod cognito;
-pub mod lambda;
 pub mod s3;
 pub mod ses;
 pub mod sns;
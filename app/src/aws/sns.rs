@@ -1,3 +1,4 @@
+use crate::ethereum::chain::configured_chain_id;
 use crate::{AppError, AppResult};
 use aws_sdk_sns::Client;
 use serde::{Deserialize, Serialize};
@@ -68,6 +69,9 @@ pub struct CreateThumbnailPayload {
 #[derive(Serialize, Deserialize)]
 pub struct MintNft721Payload {
     pub executor_id: String,
+    /// デプロイ前に積まれたメッセージにはないので、設定中のチェーンとみなす
+    #[serde(default = "configured_chain_id")]
+    pub chain_id: u64,
     pub work_id: String,
     pub ipfs_hash: String,
     pub s3_key: String,
@@ -76,6 +80,9 @@ pub struct MintNft721Payload {
 #[derive(Serialize, Deserialize)]
 pub struct MintNft1155Payload {
     pub executor_id: String,
+    /// デプロイ前に積まれたメッセージにはないので、設定中のチェーンとみなす
+    #[serde(default = "configured_chain_id")]
+    pub chain_id: u64,
     pub work_id: String,
     pub amount: u32,
     pub ipfs_hash: String,
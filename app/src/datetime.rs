@@ -0,0 +1,169 @@
+// 日時はすべてUTCのunix時間（秒）で扱う
+
+/// "2022-08-01T12:34:56.123456" のようなUTCの日時をunix時間にする（タイムゾーンの表記は無視する）
+pub fn parse_iso8601(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if value.len() < 19 {
+        return None;
+    }
+    let (date, time) = (&value[..10], &value[11..19]);
+
+    let mut date = date.split('-').map(|v| v.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let mut time = time.split(':').map(|v| v.parse::<i64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    Some(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// "Sun, 06 Nov 1994 08:49:37 GMT" のようなHTTP-date（IMF-fixdate）をunix時間にする
+/// 廃止された書式（RFC 850やasctime）は送られてこないものとして扱わない
+pub fn parse_http_date(value: &str) -> Option<i64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let mut parts = value.split_whitespace();
+    let (weekday, day, month, year, time, zone) = (
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+    );
+    if !weekday.ends_with(',') || zone != "GMT" || parts.next().is_some() {
+        return None;
+    }
+
+    let day = day.parse::<i64>().ok()?;
+    let month = MONTHS.iter().position(|v| *v == month)? as i64 + 1;
+    let year = year.parse::<i64>().ok()?;
+    let mut time = time.split(':').map(|v| v.parse::<i64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    Some(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// "2022-08"
+pub fn format_month(timestamp: i64) -> String {
+    let (year, month, _) = civil_from_days(timestamp.div_euclid(86400));
+    format!("{:04}-{:02}", year, month)
+}
+
+/// "2022-08-01 12:34:56"
+pub fn format_datetime(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(86400));
+    let seconds = timestamp.rem_euclid(86400);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+// http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_leap_days() {
+        assert_eq!(parse_iso8601("2020-02-29T00:00:00"), Some(1582934400));
+        // 400で割り切れる年はうるう年
+        assert_eq!(parse_iso8601("2000-02-29T12:00:00"), Some(951825600));
+        // 100で割り切れる年はうるう年でない
+        assert_eq!(
+            format_datetime(parse_iso8601("1900-02-28T23:59:59").unwrap() + 1),
+            "1900-03-01 00:00:00"
+        );
+    }
+
+    #[test]
+    fn crosses_month_and_year_ends() {
+        let end_of_january = parse_iso8601("2022-01-31T23:59:59").unwrap();
+        assert_eq!(end_of_january, 1643673599);
+        assert_eq!(format_month(end_of_january), "2022-01");
+        assert_eq!(format_month(end_of_january + 1), "2022-02");
+        assert_eq!(format_datetime(end_of_january + 1), "2022-02-01 00:00:00");
+
+        let end_of_year = parse_iso8601("2021-12-31T23:59:59").unwrap();
+        assert_eq!(end_of_year, 1640995199);
+        assert_eq!(format_datetime(end_of_year + 1), "2022-01-01 00:00:00");
+    }
+
+    #[test]
+    fn handles_dates_before_epoch() {
+        assert_eq!(parse_iso8601("1969-12-31T23:59:59"), Some(-1));
+        assert_eq!(format_datetime(-1), "1969-12-31 23:59:59");
+        assert_eq!(format_month(-1), "1969-12");
+        assert_eq!(parse_iso8601("1960-01-01T00:00:00"), Some(-315619200));
+        assert_eq!(format_datetime(-315619200), "1960-01-01 00:00:00");
+    }
+
+    #[test]
+    fn ignores_fraction_and_timezone() {
+        assert_eq!(
+            parse_iso8601("2022-08-01T12:34:56.123456"),
+            Some(1659357296)
+        );
+        assert_eq!(parse_iso8601("2022-08-01T12:34:56Z"), Some(1659357296));
+    }
+
+    #[test]
+    fn parses_http_dates() {
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784111777)
+        );
+        assert_eq!(
+            parse_http_date("Thu, 29 Feb 2024 23:59:59 GMT"),
+            Some(1709251199)
+        );
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 JST"), None);
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), None);
+        assert_eq!(parse_http_date("Sun, 06 Foo 1994 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 24:00:00 GMT"), None);
+        assert_eq!(parse_http_date("120"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        assert_eq!(parse_iso8601("2022-13-01T00:00:00"), None);
+        assert_eq!(parse_iso8601("2022-08-32T00:00:00"), None);
+        assert_eq!(parse_iso8601("2022-08-01"), None);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::domain::amount::Wei;
 use crate::AppResult;
 use aws_sdk_dynamodb::model::AttributeValue;
 use aws_sdk_dynamodb::Client;
@@ -5,9 +6,22 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
+mod acquisition;
 mod asset1155;
 mod asset721;
+mod collection;
+mod contract;
+mod listing;
+#[cfg(test)]
+pub mod mock;
+mod offer;
+mod ownership;
+mod pin;
+mod price_snapshot;
+mod reveal;
+mod sale;
 mod thumbnail;
+mod transaction;
 mod user;
 mod work;
 
@@ -108,3 +122,36 @@ pub enum EncodableAttributeValue {
     S(String),
     N(String),
 }
+
+/// EthPriceはether単位の10進数で保存する
+/// f64で保存していた頃の値は18桁を超える小数を含むことがあるので、weiの桁で切り捨てて読む
+fn eth_price_of(value: &str) -> Wei {
+    let value = match value.split_once('.') {
+        Some((integer, fraction)) => {
+            format!("{}.{}", integer, &fraction[..fraction.len().min(18)])
+        }
+        None => value.to_string(),
+    };
+    Wei::from_ether(&value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_legacy_eth_prices() {
+        assert_eq!(eth_price_of("0.015"), Wei::from_ether("0.015").unwrap());
+        assert_eq!(eth_price_of("2"), Wei::from_ether("2").unwrap());
+        // f64のDisplayで保存された値
+        assert_eq!(
+            eth_price_of("0.30000000000000004"),
+            Wei::from_ether("0.30000000000000004").unwrap()
+        );
+        assert_eq!(
+            eth_price_of("0.0000000000000000012345"),
+            Wei::from_ether("0.000000000000000001").unwrap()
+        );
+        assert!(eth_price_of("NaN").is_zero());
+    }
+}
@@ -0,0 +1,68 @@
+use crate::ddb::Dao;
+use crate::domain::*;
+use crate::ethereum::unit::Unit;
+use crate::{AppError, AppResult};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client;
+
+const TABLE_NAME: &str = "canvas-nft-acquisition";
+const KEY_ID: &str = "ID";
+const KEY_CHAIN_ID: &str = "ChainID";
+const KEY_MARKETPLACE: &str = "Marketplace";
+const KEY_SCHEMA: &str = "Schema";
+const KEY_CONTRACT_ADDRESS: &str = "ContractAddress";
+const KEY_TOKEN_ID: &str = "TokenID";
+const KEY_BUYER_ID: &str = "BuyerID";
+const KEY_BUYER_ADDRESS: &str = "BuyerAddress";
+const KEY_SELLER_ADDRESS: &str = "SellerAddress";
+const KEY_PRICE: &str = "Price";
+const KEY_QUANTITY: &str = "Quantity";
+const KEY_TX_HASH: &str = "TxHash";
+const KEY_CREATED_AT: &str = "CreatedAt";
+
+impl acquisition::Acquisition {
+    async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
+        cli.put_item()
+            .table_name(table_name)
+            .item(KEY_ID, AttributeValue::S(self.id.to_owned()))
+            .item(KEY_CHAIN_ID, AttributeValue::N(self.chain_id.to_string()))
+            .item(
+                KEY_MARKETPLACE,
+                AttributeValue::S(self.marketplace.to_owned()),
+            )
+            .item(KEY_SCHEMA, AttributeValue::S(self.schema.to_string()))
+            .item(
+                KEY_CONTRACT_ADDRESS,
+                AttributeValue::S(self.contract_address.to_owned()),
+            )
+            .item(KEY_TOKEN_ID, AttributeValue::S(self.token_id.to_owned()))
+            .item(KEY_BUYER_ID, AttributeValue::S(self.buyer_id.to_owned()))
+            .item(
+                KEY_BUYER_ADDRESS,
+                AttributeValue::S(self.buyer_address.to_owned()),
+            )
+            .item(
+                KEY_SELLER_ADDRESS,
+                AttributeValue::S(self.seller_address.to_owned()),
+            )
+            .item(KEY_PRICE, AttributeValue::N(self.price.format(Unit::Wei)))
+            .item(KEY_QUANTITY, AttributeValue::N(self.quantity.to_string()))
+            .item(KEY_TX_HASH, AttributeValue::S(self.tx_hash.to_owned()))
+            .item(
+                KEY_CREATED_AT,
+                AttributeValue::N(self.created_at.to_string()),
+            )
+            .send()
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+}
+
+impl Dao<acquisition::Acquisition> {
+    pub async fn put(&self, item: &acquisition::Acquisition) -> AppResult<()> {
+        item.serialize_and_save(&self.cli, self.table_name_provider.with(TABLE_NAME))
+            .await?;
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::ddb::Dao;
+use crate::ddb::{eth_price_of, Dao};
 use crate::domain::*;
 use crate::{AppError, AppResult};
 use aws_sdk_dynamodb::model::AttributeValue;
@@ -7,6 +7,7 @@ use std::collections::HashMap;
 
 const TABLE_NAME: &str = "canvas-nft-asset721";
 const KEY_WORK_ID: &str = "WorkID";
+const KEY_CHAIN_ID: &str = "ChainID";
 const KEY_CONTRACT_ADDRESS: &str = "ContractAddress";
 const KEY_TOKEN_ID: &str = "TokenID";
 const KEY_NAME: &str = "Name";
@@ -16,6 +17,9 @@ const KEY_IMAGE_PREVIEW_URL: &str = "ImagePreviewURL";
 const KEY_PERMALINK: &str = "Permalink";
 const KEY_USD_PRICE: &str = "UsdPrice";
 const KEY_ETH_PRICE: &str = "EthPrice";
+const KEY_CURRENCY: &str = "Currency";
+const KEY_LISTING_PRICE: &str = "ListingPrice";
+const KEY_LAST_SALE_PRICE: &str = "LastSalePrice";
 
 impl asset::Asset721 {
     fn deserialize(data: HashMap<String, AttributeValue>) -> Option<Self> {
@@ -42,8 +46,15 @@ impl asset::Asset721 {
             data.get(KEY_USD_PRICE),
             data.get(KEY_ETH_PRICE),
         ) {
+            // マルチチェーン対応前のデータはChainIDを持たないため0として扱う
+            let chain_id = match data.get(KEY_CHAIN_ID) {
+                Some(AttributeValue::N(chain_id)) => chain_id.to_owned().parse().unwrap(),
+                _ => 0,
+            };
+
             let data = asset::Asset721 {
                 work_id: work_id.to_owned(),
+                chain_id,
                 contract_address: address.to_owned(),
                 token_id: token_id.to_owned(),
                 name: name.to_owned(),
@@ -51,8 +62,20 @@ impl asset::Asset721 {
                 image_url: image_url.to_owned(),
                 image_preview_url: image_preview_url.to_owned(),
                 permalink: permalink.to_owned(),
-                usd_price: usd_price.to_owned().parse().unwrap(),
-                eth_price: eth_price.to_owned().parse().unwrap(),
+                usd_price: usd_price.to_owned(),
+                eth_price: eth_price_of(eth_price),
+                currency: match data.get(KEY_CURRENCY) {
+                    Some(AttributeValue::S(currency)) => currency.to_owned(),
+                    _ => "".to_string(),
+                },
+                listing_price: match data.get(KEY_LISTING_PRICE) {
+                    Some(AttributeValue::S(price)) => Some(price.to_owned()),
+                    _ => None,
+                },
+                last_sale_price: match data.get(KEY_LAST_SALE_PRICE) {
+                    Some(AttributeValue::S(price)) => Some(price.to_owned()),
+                    _ => None,
+                },
             };
 
             return Some(data);
@@ -61,9 +84,19 @@ impl asset::Asset721 {
     }
 
     async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
-        cli.put_item()
-            .table_name(table_name)
-            .item(KEY_WORK_ID, AttributeValue::S(self.work_id.to_owned()))
+        let mut req = cli.put_item().table_name(table_name);
+        if let Some(price) = &self.listing_price {
+            req = req.item(KEY_LISTING_PRICE, AttributeValue::S(price.to_owned()));
+        }
+        if let Some(price) = &self.last_sale_price {
+            req = req.item(KEY_LAST_SALE_PRICE, AttributeValue::S(price.to_owned()));
+        }
+
+        req.item(KEY_WORK_ID, AttributeValue::S(self.work_id.to_owned()))
+            .item(
+                KEY_CHAIN_ID,
+                AttributeValue::N(self.chain_id.to_owned().to_string()),
+            )
             .item(
                 KEY_CONTRACT_ADDRESS,
                 AttributeValue::S(self.contract_address.to_owned()),
@@ -80,14 +113,9 @@ impl asset::Asset721 {
                 AttributeValue::S(self.image_preview_url.to_owned()),
             )
             .item(KEY_PERMALINK, AttributeValue::S(self.permalink.to_owned()))
-            .item(
-                KEY_USD_PRICE,
-                AttributeValue::N(self.usd_price.to_owned().to_string()),
-            )
-            .item(
-                KEY_ETH_PRICE,
-                AttributeValue::N(self.eth_price.to_owned().to_string()),
-            )
+            .item(KEY_USD_PRICE, AttributeValue::N(self.usd_price.to_owned()))
+            .item(KEY_ETH_PRICE, AttributeValue::N(self.eth_price.to_ether()))
+            .item(KEY_CURRENCY, AttributeValue::S(self.currency.to_owned()))
             .send()
             .await
             .map_err(AppError::from)?;
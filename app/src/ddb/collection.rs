@@ -0,0 +1,156 @@
+use crate::ddb::Dao;
+use crate::domain::*;
+use crate::{AppError, AppResult};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const TABLE_NAME: &str = "canvas-nft-collection";
+const KEY_ID: &str = "ID";
+const KEY_CHAIN_ID: &str = "ChainID";
+const KEY_SCHEMA: &str = "Schema";
+const KEY_CONTRACT_ADDRESS: &str = "ContractAddress";
+const KEY_ROYALTY_RECEIVER: &str = "RoyaltyReceiver";
+const KEY_ROYALTY_BASIS_POINTS: &str = "RoyaltyBasisPoints";
+const KEY_METADATA_TEMPLATE: &str = "MetadataTemplate";
+const KEY_HIDDEN: &str = "Hidden";
+const KEY_PLACEHOLDER: &str = "Placeholder";
+const KEY_PROVENANCE_HASH: &str = "ProvenanceHash";
+const KEY_REVEALED_AT: &str = "RevealedAt";
+const KEY_UPDATED_AT: &str = "UpdatedAt";
+
+impl collection::Collection {
+    fn deserialize(data: HashMap<String, AttributeValue>) -> Option<Self> {
+        if let (
+            Some(AttributeValue::N(chain_id)),
+            Some(AttributeValue::S(schema)),
+            Some(AttributeValue::S(contract_address)),
+            Some(AttributeValue::N(updated_at)),
+        ) = (
+            data.get(KEY_CHAIN_ID),
+            data.get(KEY_SCHEMA),
+            data.get(KEY_CONTRACT_ADDRESS),
+            data.get(KEY_UPDATED_AT),
+        ) {
+            let data = collection::Collection {
+                chain_id: chain_id.to_owned().parse().unwrap(),
+                schema: market::TokenSchema::from_str(schema).ok()?,
+                contract_address: contract_address.to_owned(),
+                royalty: match (
+                    data.get(KEY_ROYALTY_RECEIVER),
+                    data.get(KEY_ROYALTY_BASIS_POINTS),
+                ) {
+                    (Some(AttributeValue::S(receiver)), Some(AttributeValue::N(basis_points))) => {
+                        Some(royalty::Royalty {
+                            receiver: receiver.to_owned(),
+                            basis_points: basis_points.to_owned().parse().unwrap(),
+                        })
+                    }
+                    _ => None,
+                },
+                metadata_template: match data.get(KEY_METADATA_TEMPLATE) {
+                    Some(AttributeValue::S(template)) => serde_json::from_str(template).ok(),
+                    _ => None,
+                },
+                hidden: matches!(data.get(KEY_HIDDEN), Some(AttributeValue::Bool(true))),
+                placeholder: match data.get(KEY_PLACEHOLDER) {
+                    Some(AttributeValue::S(placeholder)) => serde_json::from_str(placeholder).ok(),
+                    _ => None,
+                },
+                provenance_hash: match data.get(KEY_PROVENANCE_HASH) {
+                    Some(AttributeValue::S(hash)) => Some(hash.to_owned()),
+                    _ => None,
+                },
+                revealed_at: match data.get(KEY_REVEALED_AT) {
+                    Some(AttributeValue::N(revealed_at)) => revealed_at.parse().ok(),
+                    _ => None,
+                },
+                updated_at: updated_at.to_owned().parse().unwrap(),
+            };
+
+            return Some(data);
+        }
+        None
+    }
+
+    async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
+        let mut req = cli.put_item().table_name(table_name);
+        if let Some(royalty) = &self.royalty {
+            req = req
+                .item(
+                    KEY_ROYALTY_RECEIVER,
+                    AttributeValue::S(royalty.receiver.to_owned()),
+                )
+                .item(
+                    KEY_ROYALTY_BASIS_POINTS,
+                    AttributeValue::N(royalty.basis_points.to_string()),
+                );
+        }
+        if let Some(template) = &self.metadata_template {
+            req = req.item(
+                KEY_METADATA_TEMPLATE,
+                AttributeValue::S(serde_json::to_string(template)?),
+            );
+        }
+        if let Some(placeholder) = &self.placeholder {
+            req = req.item(
+                KEY_PLACEHOLDER,
+                AttributeValue::S(serde_json::to_string(placeholder)?),
+            );
+        }
+        if let Some(hash) = &self.provenance_hash {
+            req = req.item(KEY_PROVENANCE_HASH, AttributeValue::S(hash.to_owned()));
+        }
+        if let Some(revealed_at) = self.revealed_at {
+            req = req.item(KEY_REVEALED_AT, AttributeValue::N(revealed_at.to_string()));
+        }
+
+        req.item(KEY_ID, AttributeValue::S(self.id()))
+            .item(KEY_CHAIN_ID, AttributeValue::N(self.chain_id.to_string()))
+            .item(KEY_SCHEMA, AttributeValue::S(self.schema.to_string()))
+            .item(
+                KEY_CONTRACT_ADDRESS,
+                AttributeValue::S(self.contract_address.to_owned()),
+            )
+            .item(KEY_HIDDEN, AttributeValue::Bool(self.hidden))
+            .item(
+                KEY_UPDATED_AT,
+                AttributeValue::N(self.updated_at.to_string()),
+            )
+            .send()
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    fn primary_key(id: String) -> AttributeValue {
+        AttributeValue::S(id)
+    }
+}
+
+impl Dao<collection::Collection> {
+    pub async fn get(&self, id: String) -> AppResult<collection::Collection> {
+        let res = self
+            .cli
+            .get_item()
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .key(KEY_ID, collection::Collection::primary_key(id))
+            .send()
+            .await?;
+
+        if res.item.is_none() {
+            return Err(AppError::NotFound);
+        }
+
+        let data = res.item.unwrap();
+
+        Ok(collection::Collection::deserialize(data).unwrap())
+    }
+
+    pub async fn put(&self, item: &collection::Collection) -> AppResult<()> {
+        item.serialize_and_save(&self.cli, self.table_name_provider.with(TABLE_NAME))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use crate::ddb::Dao;
+use crate::domain::*;
+use crate::{AppError, AppResult};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+
+const TABLE_NAME: &str = "canvas-nft-contract";
+const KEY_ID: &str = "ID";
+const KEY_CHAIN_ID: &str = "ChainID";
+const KEY_NAME: &str = "Name";
+const KEY_ADDRESS: &str = "Address";
+const KEY_DEPLOYER: &str = "Deployer";
+const KEY_TX_HASH: &str = "TxHash";
+const KEY_BLOCK_NUMBER: &str = "BlockNumber";
+const KEY_CODE_HASH: &str = "CodeHash";
+const KEY_VERIFIED: &str = "Verified";
+
+impl contract::DeployedContract {
+    fn deserialize(data: HashMap<String, AttributeValue>) -> Option<Self> {
+        if let (
+            Some(AttributeValue::N(chain_id)),
+            Some(AttributeValue::S(name)),
+            Some(AttributeValue::S(address)),
+            Some(AttributeValue::S(deployer)),
+            Some(AttributeValue::S(tx_hash)),
+            Some(AttributeValue::N(block_number)),
+            Some(AttributeValue::S(code_hash)),
+            Some(AttributeValue::Bool(verified)),
+        ) = (
+            data.get(KEY_CHAIN_ID),
+            data.get(KEY_NAME),
+            data.get(KEY_ADDRESS),
+            data.get(KEY_DEPLOYER),
+            data.get(KEY_TX_HASH),
+            data.get(KEY_BLOCK_NUMBER),
+            data.get(KEY_CODE_HASH),
+            data.get(KEY_VERIFIED),
+        ) {
+            let data = contract::DeployedContract {
+                chain_id: chain_id.to_owned().parse().unwrap(),
+                name: name.to_owned(),
+                address: address.to_owned(),
+                deployer: deployer.to_owned(),
+                tx_hash: tx_hash.to_owned(),
+                block_number: block_number.to_owned().parse().unwrap(),
+                code_hash: code_hash.to_owned(),
+                verified: verified.to_owned(),
+            };
+
+            return Some(data);
+        }
+        None
+    }
+
+    async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
+        cli.put_item()
+            .table_name(table_name)
+            .item(KEY_ID, AttributeValue::S(self.id()))
+            .item(KEY_CHAIN_ID, AttributeValue::N(self.chain_id.to_string()))
+            .item(KEY_NAME, AttributeValue::S(self.name.to_owned()))
+            .item(KEY_ADDRESS, AttributeValue::S(self.address.to_owned()))
+            .item(KEY_DEPLOYER, AttributeValue::S(self.deployer.to_owned()))
+            .item(KEY_TX_HASH, AttributeValue::S(self.tx_hash.to_owned()))
+            .item(
+                KEY_BLOCK_NUMBER,
+                AttributeValue::N(self.block_number.to_string()),
+            )
+            .item(KEY_CODE_HASH, AttributeValue::S(self.code_hash.to_owned()))
+            .item(KEY_VERIFIED, AttributeValue::Bool(self.verified))
+            .send()
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    fn primary_key(id: String) -> AttributeValue {
+        AttributeValue::S(id)
+    }
+}
+
+impl Dao<contract::DeployedContract> {
+    pub async fn get(&self, chain_id: u64, name: &str) -> AppResult<contract::DeployedContract> {
+        let res = self
+            .cli
+            .get_item()
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .key(
+                KEY_ID,
+                contract::DeployedContract::primary_key(contract::DeployedContract::id_of(
+                    chain_id, name,
+                )),
+            )
+            .send()
+            .await?;
+
+        if res.item.is_none() {
+            return Err(AppError::NotFound);
+        }
+
+        let data = res.item.unwrap();
+
+        Ok(contract::DeployedContract::deserialize(data).unwrap())
+    }
+
+    pub async fn put(&self, item: &contract::DeployedContract) -> AppResult<()> {
+        item.serialize_and_save(&self.cli, self.table_name_provider.with(TABLE_NAME))
+            .await?;
+        Ok(())
+    }
+}
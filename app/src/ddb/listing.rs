@@ -0,0 +1,148 @@
+use crate::ddb::Dao;
+use crate::domain::*;
+use crate::ethereum::unit::Unit;
+use crate::{AppError, AppResult};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const TABLE_NAME: &str = "canvas-nft-listing";
+const KEY_ID: &str = "ID";
+const KEY_WORK_ID: &str = "WorkID";
+const KEY_CHAIN_ID: &str = "ChainID";
+const KEY_MARKETPLACE: &str = "Marketplace";
+const KEY_SCHEMA: &str = "Schema";
+const KEY_CONTRACT_ADDRESS: &str = "ContractAddress";
+const KEY_TOKEN_ID: &str = "TokenID";
+const KEY_SELLER_ID: &str = "SellerID";
+const KEY_PRICE: &str = "Price";
+const KEY_QUANTITY: &str = "Quantity";
+const KEY_EXPIRES_AT: &str = "ExpiresAt";
+const KEY_ORDER: &str = "Order";
+const KEY_STATUS: &str = "Status";
+const KEY_CREATED_AT: &str = "CreatedAt";
+
+impl listing::Listing {
+    fn deserialize(data: HashMap<String, AttributeValue>) -> Option<Self> {
+        if let (
+            Some(AttributeValue::S(id)),
+            Some(AttributeValue::S(work_id)),
+            Some(AttributeValue::N(chain_id)),
+            Some(AttributeValue::S(marketplace)),
+            Some(AttributeValue::S(schema)),
+            Some(AttributeValue::S(contract_address)),
+            Some(AttributeValue::S(token_id)),
+            Some(AttributeValue::S(seller_id)),
+            Some(AttributeValue::N(price)),
+            Some(AttributeValue::N(expires_at)),
+            Some(AttributeValue::S(order)),
+            Some(AttributeValue::S(status)),
+            Some(AttributeValue::N(created_at)),
+        ) = (
+            data.get(KEY_ID),
+            data.get(KEY_WORK_ID),
+            data.get(KEY_CHAIN_ID),
+            data.get(KEY_MARKETPLACE),
+            data.get(KEY_SCHEMA),
+            data.get(KEY_CONTRACT_ADDRESS),
+            data.get(KEY_TOKEN_ID),
+            data.get(KEY_SELLER_ID),
+            data.get(KEY_PRICE),
+            data.get(KEY_EXPIRES_AT),
+            data.get(KEY_ORDER),
+            data.get(KEY_STATUS),
+            data.get(KEY_CREATED_AT),
+        ) {
+            let data = listing::Listing {
+                id: id.to_owned(),
+                work_id: work_id.to_owned(),
+                chain_id: chain_id.to_owned().parse().unwrap(),
+                marketplace: marketplace.to_owned(),
+                schema: market::TokenSchema::from_str(schema).ok()?,
+                contract_address: contract_address.to_owned(),
+                token_id: token_id.to_owned(),
+                seller_id: seller_id.to_owned(),
+                price: amount::Wei::parse(price, Unit::Wei).ok()?,
+                // 数量に対応する前の出品は1つ
+                quantity: match data.get(KEY_QUANTITY) {
+                    Some(AttributeValue::N(quantity)) => quantity.to_owned().parse().unwrap(),
+                    _ => 1,
+                },
+                expires_at: expires_at.to_owned().parse().unwrap(),
+                order: order.to_owned(),
+                status: listing::ListingStatus::from(status.to_owned()),
+                created_at: created_at.to_owned().parse().unwrap(),
+            };
+
+            return Some(data);
+        }
+        None
+    }
+
+    async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
+        cli.put_item()
+            .table_name(table_name)
+            .item(KEY_ID, AttributeValue::S(self.id.to_owned()))
+            .item(KEY_WORK_ID, AttributeValue::S(self.work_id.to_owned()))
+            .item(KEY_CHAIN_ID, AttributeValue::N(self.chain_id.to_string()))
+            .item(
+                KEY_MARKETPLACE,
+                AttributeValue::S(self.marketplace.to_owned()),
+            )
+            .item(KEY_SCHEMA, AttributeValue::S(self.schema.to_string()))
+            .item(
+                KEY_CONTRACT_ADDRESS,
+                AttributeValue::S(self.contract_address.to_owned()),
+            )
+            .item(KEY_TOKEN_ID, AttributeValue::S(self.token_id.to_owned()))
+            .item(KEY_SELLER_ID, AttributeValue::S(self.seller_id.to_owned()))
+            .item(KEY_PRICE, AttributeValue::N(self.price.format(Unit::Wei)))
+            .item(KEY_QUANTITY, AttributeValue::N(self.quantity.to_string()))
+            .item(
+                KEY_EXPIRES_AT,
+                AttributeValue::N(self.expires_at.to_string()),
+            )
+            .item(KEY_ORDER, AttributeValue::S(self.order.to_owned()))
+            .item(KEY_STATUS, AttributeValue::S(self.status.to_string()))
+            .item(
+                KEY_CREATED_AT,
+                AttributeValue::N(self.created_at.to_string()),
+            )
+            .send()
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+}
+
+impl Dao<listing::Listing> {
+    pub async fn get_by_work(&self, work_id: String) -> AppResult<Vec<listing::Listing>> {
+        let res = self
+            .cli
+            .query()
+            .index_name("WorkID-Index")
+            .key_condition_expression("#key = :value".to_string())
+            .expression_attribute_names("#key".to_string(), KEY_WORK_ID)
+            .expression_attribute_values(
+                ":value".to_string(),
+                AttributeValue::S(work_id.to_owned()),
+            )
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .send()
+            .await?;
+
+        let mut entities: Vec<listing::Listing> = vec![];
+        for item in res.items.unwrap_or_default() {
+            entities.push(listing::Listing::deserialize(item).unwrap())
+        }
+
+        Ok(entities)
+    }
+
+    pub async fn put(&self, item: &listing::Listing) -> AppResult<()> {
+        item.serialize_and_save(&self.cli, self.table_name_provider.with(TABLE_NAME))
+            .await?;
+        Ok(())
+    }
+}
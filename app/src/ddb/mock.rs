@@ -0,0 +1,153 @@
+use crate::ddb::{Dao, TableNameProvider};
+use aws_sdk_dynamodb::{Client, Config, Credentials, Endpoint, Region};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+type Item = Map<String, Value>;
+
+/// テーブルごとのキー。database.ymlと合わせる（指定がなければID）
+const KEYS: &[(&str, &[&str])] = &[
+    ("canvas-nft-asset721", &["WorkID"]),
+    ("canvas-nft-asset1155", &["WorkID"]),
+    ("canvas-nft-price-snapshot", &["WorkID", "Timestamp"]),
+    ("canvas-nft-transaction", &["TxHash"]),
+];
+
+/// テスト用のメモリ上のDynamoDB。Daoが使う操作だけを扱い、Queryは`#key = :value`の完全一致のみ
+#[derive(Clone)]
+pub struct MockDynamo {
+    url: String,
+    tables: Arc<Mutex<HashMap<String, Vec<Item>>>>,
+}
+
+impl MockDynamo {
+    pub fn start() -> Self {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mock = Self {
+            url: format!("http://{}", listener.local_addr().unwrap()),
+            tables: Arc::new(Mutex::new(HashMap::new())),
+        };
+        let state = mock.clone();
+        let make = make_service_fn(move |_| {
+            let state = state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| state.clone().handle(req))) }
+        });
+        tokio::spawn(Server::from_tcp(listener).unwrap().serve(make));
+        mock
+    }
+
+    pub fn dao<T>(&self) -> Dao<T> {
+        let config = Config::builder()
+            .region(Region::new("ap-northeast-1"))
+            .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+            .endpoint_resolver(Endpoint::immutable(self.url.parse().unwrap()))
+            .build();
+
+        Dao {
+            cli: Client::from_conf(config),
+            table_name_provider: TableNameProvider::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    async fn handle(self, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let target = req
+            .headers()
+            .get("x-amz-target")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split('.').next_back())
+            .unwrap_or_default()
+            .to_string();
+        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        let table = body["TableName"].as_str().unwrap_or_default().to_string();
+
+        let mut tables = self.tables.lock().unwrap();
+        let res = match target.as_str() {
+            "GetItem" => {
+                let item = tables
+                    .get(&table)
+                    .and_then(|items| items.iter().find(|v| matches(v, &body["Key"])));
+                match item {
+                    Some(item) => json!({ "Item": item }),
+                    None => json!({}),
+                }
+            }
+            "PutItem" => {
+                let item = body["Item"].as_object().unwrap().clone();
+                let key: Item = key_names(&table)
+                    .iter()
+                    .filter_map(|k| Some((k.to_string(), item.get(*k)?.clone())))
+                    .collect();
+                let items = tables.entry(table).or_default();
+                items.retain(|v| !matches(v, &Value::Object(key.clone())));
+                items.push(item);
+                json!({})
+            }
+            "DeleteItem" => {
+                if let Some(items) = tables.get_mut(&table) {
+                    items.retain(|v| !matches(v, &body["Key"]));
+                }
+                json!({})
+            }
+            "Query" => {
+                let name = body["ExpressionAttributeNames"]["#key"].as_str().unwrap();
+                let value = &body["ExpressionAttributeValues"][":value"];
+                let mut items: Vec<&Item> = tables
+                    .get(&table)
+                    .map(|items| {
+                        items
+                            .iter()
+                            .filter(|v| v.get(name) == Some(value))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if body["ScanIndexForward"] == json!(false) {
+                    items.reverse();
+                }
+                json!({ "Items": items, "Count": items.len() })
+            }
+            "Scan" => {
+                let items = tables.get(&table).cloned().unwrap_or_default();
+                json!({ "Items": items, "Count": items.len() })
+            }
+            "BatchGetItem" => {
+                let mut responses = Map::new();
+                for (table, request) in body["RequestItems"].as_object().unwrap() {
+                    let found: Vec<&Item> = request["Keys"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .filter_map(|key| tables.get(table)?.iter().find(|v| matches(v, key)))
+                        .collect();
+                    responses.insert(table.clone(), json!(found));
+                }
+                json!({ "Responses": responses, "UnprocessedKeys": {} })
+            }
+            other => panic!("unsupported dynamodb operation: {}", other),
+        };
+
+        Ok(Response::builder()
+            .header("Content-Type", "application/x-amz-json-1.0")
+            .body(Body::from(res.to_string()))
+            .unwrap())
+    }
+}
+
+fn key_names(table: &str) -> &'static [&'static str] {
+    KEYS.iter()
+        .find(|(name, _)| *name == table)
+        .map(|(_, keys)| *keys)
+        .unwrap_or(&["ID"])
+}
+
+fn matches(item: &Item, key: &Value) -> bool {
+    key.as_object()
+        .map(|key| key.iter().all(|(k, v)| item.get(k) == Some(v)))
+        .unwrap_or(false)
+}
@@ -0,0 +1,175 @@
+use crate::ddb::Dao;
+use crate::domain::*;
+use crate::{AppError, AppResult};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const TABLE_NAME: &str = "canvas-nft-offer";
+const KEY_ID: &str = "ID";
+const KEY_WORK_ID: &str = "WorkID";
+const KEY_CHAIN_ID: &str = "ChainID";
+const KEY_MARKETPLACE: &str = "Marketplace";
+const KEY_SCHEMA: &str = "Schema";
+const KEY_CONTRACT_ADDRESS: &str = "ContractAddress";
+const KEY_TOKEN_ID: &str = "TokenID";
+const KEY_MAKER: &str = "Maker";
+const KEY_CURRENCY: &str = "Currency";
+const KEY_PRICE: &str = "Price";
+const KEY_QUANTITY: &str = "Quantity";
+const KEY_EXPIRES_AT: &str = "ExpiresAt";
+const KEY_STATUS: &str = "Status";
+const KEY_CREATED_AT: &str = "CreatedAt";
+const KEY_UPDATED_AT: &str = "UpdatedAt";
+
+impl offer::Offer {
+    fn deserialize(data: HashMap<String, AttributeValue>) -> Option<Self> {
+        if let (
+            Some(AttributeValue::S(id)),
+            Some(AttributeValue::S(work_id)),
+            Some(AttributeValue::N(chain_id)),
+            Some(AttributeValue::S(marketplace)),
+            Some(AttributeValue::S(schema)),
+            Some(AttributeValue::S(contract_address)),
+            Some(AttributeValue::S(token_id)),
+            Some(AttributeValue::S(maker)),
+            Some(AttributeValue::S(currency)),
+            Some(AttributeValue::S(price)),
+            Some(AttributeValue::N(quantity)),
+            Some(AttributeValue::N(expires_at)),
+            Some(AttributeValue::S(status)),
+            Some(AttributeValue::N(created_at)),
+            Some(AttributeValue::N(updated_at)),
+        ) = (
+            data.get(KEY_ID),
+            data.get(KEY_WORK_ID),
+            data.get(KEY_CHAIN_ID),
+            data.get(KEY_MARKETPLACE),
+            data.get(KEY_SCHEMA),
+            data.get(KEY_CONTRACT_ADDRESS),
+            data.get(KEY_TOKEN_ID),
+            data.get(KEY_MAKER),
+            data.get(KEY_CURRENCY),
+            data.get(KEY_PRICE),
+            data.get(KEY_QUANTITY),
+            data.get(KEY_EXPIRES_AT),
+            data.get(KEY_STATUS),
+            data.get(KEY_CREATED_AT),
+            data.get(KEY_UPDATED_AT),
+        ) {
+            let data = offer::Offer {
+                id: id.to_owned(),
+                work_id: work_id.to_owned(),
+                chain_id: chain_id.to_owned().parse().unwrap(),
+                marketplace: marketplace.to_owned(),
+                schema: market::TokenSchema::from_str(schema).ok()?,
+                contract_address: contract_address.to_owned(),
+                token_id: token_id.to_owned(),
+                maker: maker.to_owned(),
+                currency: currency.to_owned(),
+                price: price.to_owned(),
+                quantity: quantity.to_owned().parse().unwrap(),
+                expires_at: expires_at.to_owned().parse().unwrap(),
+                status: offer::OfferStatus::from(status.to_owned()),
+                created_at: created_at.to_owned().parse().unwrap(),
+                updated_at: updated_at.to_owned().parse().unwrap(),
+            };
+
+            return Some(data);
+        }
+        None
+    }
+
+    async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
+        cli.put_item()
+            .table_name(table_name)
+            .item(KEY_ID, AttributeValue::S(self.id.to_owned()))
+            .item(KEY_WORK_ID, AttributeValue::S(self.work_id.to_owned()))
+            .item(KEY_CHAIN_ID, AttributeValue::N(self.chain_id.to_string()))
+            .item(
+                KEY_MARKETPLACE,
+                AttributeValue::S(self.marketplace.to_owned()),
+            )
+            .item(KEY_SCHEMA, AttributeValue::S(self.schema.to_string()))
+            .item(
+                KEY_CONTRACT_ADDRESS,
+                AttributeValue::S(self.contract_address.to_owned()),
+            )
+            .item(KEY_TOKEN_ID, AttributeValue::S(self.token_id.to_owned()))
+            .item(KEY_MAKER, AttributeValue::S(self.maker.to_owned()))
+            .item(KEY_CURRENCY, AttributeValue::S(self.currency.to_owned()))
+            .item(KEY_PRICE, AttributeValue::S(self.price.to_owned()))
+            .item(KEY_QUANTITY, AttributeValue::N(self.quantity.to_string()))
+            .item(
+                KEY_EXPIRES_AT,
+                AttributeValue::N(self.expires_at.to_string()),
+            )
+            .item(KEY_STATUS, AttributeValue::S(self.status.to_string()))
+            .item(
+                KEY_CREATED_AT,
+                AttributeValue::N(self.created_at.to_string()),
+            )
+            .item(
+                KEY_UPDATED_AT,
+                AttributeValue::N(self.updated_at.to_string()),
+            )
+            .send()
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    fn primary_key(id: String) -> AttributeValue {
+        AttributeValue::S(id)
+    }
+}
+
+impl Dao<offer::Offer> {
+    pub async fn get(&self, id: String) -> AppResult<offer::Offer> {
+        let res = self
+            .cli
+            .get_item()
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .key(KEY_ID, offer::Offer::primary_key(id))
+            .send()
+            .await?;
+
+        if res.item.is_none() {
+            return Err(AppError::NotFound);
+        }
+
+        let data = res.item.unwrap();
+
+        Ok(offer::Offer::deserialize(data).unwrap())
+    }
+
+    pub async fn get_by_work(&self, work_id: String) -> AppResult<Vec<offer::Offer>> {
+        let res = self
+            .cli
+            .query()
+            .index_name("WorkID-Index")
+            .key_condition_expression("#key = :value".to_string())
+            .expression_attribute_names("#key".to_string(), KEY_WORK_ID)
+            .expression_attribute_values(
+                ":value".to_string(),
+                AttributeValue::S(work_id.to_owned()),
+            )
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .send()
+            .await?;
+
+        let mut entities: Vec<offer::Offer> = vec![];
+        for item in res.items.unwrap_or_default() {
+            entities.push(offer::Offer::deserialize(item).unwrap())
+        }
+
+        Ok(entities)
+    }
+
+    pub async fn put(&self, item: &offer::Offer) -> AppResult<()> {
+        item.serialize_and_save(&self.cli, self.table_name_provider.with(TABLE_NAME))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use crate::ddb::Dao;
+use crate::domain::*;
+use crate::{AppError, AppResult};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const TABLE_NAME: &str = "canvas-nft-ownership";
+const KEY_ID: &str = "ID";
+const KEY_CHAIN_ID: &str = "ChainID";
+const KEY_SCHEMA: &str = "Schema";
+const KEY_CONTRACT_ADDRESS: &str = "ContractAddress";
+const KEY_TOKEN_ID: &str = "TokenID";
+const KEY_OWNER_ADDRESS: &str = "OwnerAddress";
+const KEY_WORK_ID: &str = "WorkID";
+const KEY_AMOUNT: &str = "Amount";
+const KEY_UPDATED_AT: &str = "UpdatedAt";
+
+impl ownership::Ownership {
+    fn deserialize(data: HashMap<String, AttributeValue>) -> Option<Self> {
+        if let (
+            Some(AttributeValue::N(chain_id)),
+            Some(AttributeValue::S(schema)),
+            Some(AttributeValue::S(contract_address)),
+            Some(AttributeValue::S(token_id)),
+            Some(AttributeValue::S(owner_address)),
+            Some(AttributeValue::S(work_id)),
+            Some(AttributeValue::N(amount)),
+            Some(AttributeValue::N(updated_at)),
+        ) = (
+            data.get(KEY_CHAIN_ID),
+            data.get(KEY_SCHEMA),
+            data.get(KEY_CONTRACT_ADDRESS),
+            data.get(KEY_TOKEN_ID),
+            data.get(KEY_OWNER_ADDRESS),
+            data.get(KEY_WORK_ID),
+            data.get(KEY_AMOUNT),
+            data.get(KEY_UPDATED_AT),
+        ) {
+            let data = ownership::Ownership {
+                chain_id: chain_id.to_owned().parse().unwrap(),
+                schema: market::TokenSchema::from_str(schema).ok()?,
+                contract_address: contract_address.to_owned(),
+                token_id: token_id.to_owned(),
+                owner_address: owner_address.to_owned(),
+                work_id: work_id.to_owned(),
+                amount: amount.to_owned().parse().unwrap(),
+                updated_at: updated_at.to_owned().parse().unwrap(),
+            };
+
+            return Some(data);
+        }
+        None
+    }
+
+    async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
+        cli.put_item()
+            .table_name(table_name)
+            .item(KEY_ID, AttributeValue::S(self.id()))
+            .item(KEY_CHAIN_ID, AttributeValue::N(self.chain_id.to_string()))
+            .item(KEY_SCHEMA, AttributeValue::S(self.schema.to_string()))
+            .item(
+                KEY_CONTRACT_ADDRESS,
+                AttributeValue::S(self.contract_address.to_owned()),
+            )
+            .item(KEY_TOKEN_ID, AttributeValue::S(self.token_id.to_owned()))
+            .item(
+                KEY_OWNER_ADDRESS,
+                AttributeValue::S(self.owner_address.to_owned()),
+            )
+            .item(KEY_WORK_ID, AttributeValue::S(self.work_id.to_owned()))
+            .item(KEY_AMOUNT, AttributeValue::N(self.amount.to_string()))
+            .item(
+                KEY_UPDATED_AT,
+                AttributeValue::N(self.updated_at.to_string()),
+            )
+            .send()
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    fn primary_key(id: String) -> AttributeValue {
+        AttributeValue::S(id)
+    }
+}
+
+impl Dao<ownership::Ownership> {
+    pub async fn get(&self, id: String) -> AppResult<ownership::Ownership> {
+        let res = self
+            .cli
+            .get_item()
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .key(KEY_ID, ownership::Ownership::primary_key(id))
+            .send()
+            .await?;
+
+        if res.item.is_none() {
+            return Err(AppError::NotFound);
+        }
+
+        let data = res.item.unwrap();
+
+        Ok(ownership::Ownership::deserialize(data).unwrap())
+    }
+
+    pub async fn get_by_work(&self, work_id: String) -> AppResult<Vec<ownership::Ownership>> {
+        let res = self
+            .cli
+            .query()
+            .index_name("WorkID-Index")
+            .key_condition_expression("#key = :value".to_string())
+            .expression_attribute_names("#key".to_string(), KEY_WORK_ID)
+            .expression_attribute_values(
+                ":value".to_string(),
+                AttributeValue::S(work_id.to_owned()),
+            )
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .send()
+            .await?;
+
+        let mut entities: Vec<ownership::Ownership> = vec![];
+        for item in res.items.unwrap_or_default() {
+            entities.push(ownership::Ownership::deserialize(item).unwrap())
+        }
+
+        Ok(entities)
+    }
+
+    pub async fn put(&self, item: &ownership::Ownership) -> AppResult<()> {
+        item.serialize_and_save(&self.cli, self.table_name_provider.with(TABLE_NAME))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: String) -> AppResult<()> {
+        self.cli
+            .delete_item()
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .key(KEY_ID, ownership::Ownership::primary_key(id))
+            .send()
+            .await?;
+        Ok(())
+    }
+}
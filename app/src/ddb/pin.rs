@@ -0,0 +1,140 @@
+use crate::ddb::Dao;
+use crate::domain::*;
+use crate::{AppError, AppResult};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+
+const TABLE_NAME: &str = "canvas-nft-pin";
+const KEY_ID: &str = "ID";
+const KEY_CID: &str = "CID";
+const KEY_WORK_ID: &str = "WorkID";
+const KEY_KIND: &str = "Kind";
+const KEY_PROVIDER: &str = "Provider";
+const KEY_NAME: &str = "Name";
+const KEY_SIZE: &str = "Size";
+const KEY_STATUS: &str = "Status";
+const KEY_CREATED_AT: &str = "CreatedAt";
+const KEY_UPDATED_AT: &str = "UpdatedAt";
+
+impl pin::Pin {
+    fn deserialize(data: HashMap<String, AttributeValue>) -> Option<Self> {
+        if let (
+            Some(AttributeValue::S(cid)),
+            Some(AttributeValue::S(work_id)),
+            Some(AttributeValue::S(kind)),
+            Some(AttributeValue::S(provider)),
+            Some(AttributeValue::S(name)),
+            Some(AttributeValue::N(size)),
+            Some(AttributeValue::S(status)),
+            Some(AttributeValue::N(created_at)),
+            Some(AttributeValue::N(updated_at)),
+        ) = (
+            data.get(KEY_CID),
+            data.get(KEY_WORK_ID),
+            data.get(KEY_KIND),
+            data.get(KEY_PROVIDER),
+            data.get(KEY_NAME),
+            data.get(KEY_SIZE),
+            data.get(KEY_STATUS),
+            data.get(KEY_CREATED_AT),
+            data.get(KEY_UPDATED_AT),
+        ) {
+            let data = pin::Pin {
+                cid: cid.to_owned(),
+                work_id: work_id.to_owned(),
+                kind: pin::PinKind::from(kind.to_owned()),
+                provider: provider.to_owned(),
+                name: name.to_owned(),
+                size: size.to_owned().parse().unwrap(),
+                status: pin::PinStatus::from(status.to_owned()),
+                created_at: created_at.to_owned().parse().unwrap(),
+                updated_at: updated_at.to_owned().parse().unwrap(),
+            };
+
+            return Some(data);
+        }
+        None
+    }
+
+    async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
+        cli.put_item()
+            .table_name(table_name)
+            .item(KEY_ID, AttributeValue::S(self.id()))
+            .item(KEY_CID, AttributeValue::S(self.cid.to_owned()))
+            .item(KEY_WORK_ID, AttributeValue::S(self.work_id.to_owned()))
+            .item(KEY_KIND, AttributeValue::S(self.kind.to_string()))
+            .item(KEY_PROVIDER, AttributeValue::S(self.provider.to_owned()))
+            .item(KEY_NAME, AttributeValue::S(self.name.to_owned()))
+            .item(KEY_SIZE, AttributeValue::N(self.size.to_string()))
+            .item(KEY_STATUS, AttributeValue::S(self.status.to_string()))
+            .item(
+                KEY_CREATED_AT,
+                AttributeValue::N(self.created_at.to_string()),
+            )
+            .item(
+                KEY_UPDATED_AT,
+                AttributeValue::N(self.updated_at.to_string()),
+            )
+            .send()
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+}
+
+impl Dao<pin::Pin> {
+    pub async fn get_by_work(&self, work_id: String) -> AppResult<Vec<pin::Pin>> {
+        let res = self
+            .cli
+            .query()
+            .index_name("WorkID-Index")
+            .key_condition_expression("#key = :value".to_string())
+            .expression_attribute_names("#key".to_string(), KEY_WORK_ID)
+            .expression_attribute_values(
+                ":value".to_string(),
+                AttributeValue::S(work_id.to_owned()),
+            )
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .send()
+            .await?;
+
+        let mut entities: Vec<pin::Pin> = vec![];
+        for item in res.items.unwrap_or_default() {
+            entities.push(pin::Pin::deserialize(item).unwrap())
+        }
+
+        Ok(entities)
+    }
+
+    pub async fn get_all(&self) -> AppResult<Vec<pin::Pin>> {
+        let mut entities: Vec<pin::Pin> = vec![];
+        let mut start_key: Option<HashMap<String, AttributeValue>> = None;
+        loop {
+            let res = self
+                .cli
+                .scan()
+                .set_exclusive_start_key(start_key)
+                .table_name(self.table_name_provider.with(TABLE_NAME))
+                .send()
+                .await?;
+
+            for item in res.items.unwrap_or_default() {
+                entities.push(pin::Pin::deserialize(item).unwrap())
+            }
+
+            start_key = res.last_evaluated_key;
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(entities)
+    }
+
+    pub async fn put(&self, item: &pin::Pin) -> AppResult<()> {
+        item.serialize_and_save(&self.cli, self.table_name_provider.with(TABLE_NAME))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use crate::ddb::{eth_price_of, Dao};
+use crate::domain::*;
+use crate::{AppError, AppResult};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+
+const TABLE_NAME: &str = "canvas-nft-price-snapshot";
+const KEY_WORK_ID: &str = "WorkID";
+const KEY_TIMESTAMP: &str = "Timestamp";
+const KEY_CURRENCY: &str = "Currency";
+const KEY_LISTING_PRICE: &str = "ListingPrice";
+const KEY_LAST_SALE_PRICE: &str = "LastSalePrice";
+const KEY_USD_PRICE: &str = "UsdPrice";
+const KEY_ETH_PRICE: &str = "EthPrice";
+
+impl asset::PriceSnapshot {
+    fn deserialize(data: HashMap<String, AttributeValue>) -> Option<Self> {
+        if let (
+            Some(AttributeValue::S(work_id)),
+            Some(AttributeValue::N(timestamp)),
+            Some(AttributeValue::S(currency)),
+            Some(AttributeValue::N(usd_price)),
+            Some(AttributeValue::N(eth_price)),
+        ) = (
+            data.get(KEY_WORK_ID),
+            data.get(KEY_TIMESTAMP),
+            data.get(KEY_CURRENCY),
+            data.get(KEY_USD_PRICE),
+            data.get(KEY_ETH_PRICE),
+        ) {
+            let data = asset::PriceSnapshot {
+                work_id: work_id.to_owned(),
+                timestamp: timestamp.to_owned().parse().unwrap(),
+                price: asset::MarketPrice {
+                    currency: currency.to_owned(),
+                    listing_price: match data.get(KEY_LISTING_PRICE) {
+                        Some(AttributeValue::S(price)) => Some(price.to_owned()),
+                        _ => None,
+                    },
+                    last_sale_price: match data.get(KEY_LAST_SALE_PRICE) {
+                        Some(AttributeValue::S(price)) => Some(price.to_owned()),
+                        _ => None,
+                    },
+                    usd_price: usd_price.to_owned(),
+                    eth_price: eth_price_of(eth_price),
+                },
+            };
+
+            return Some(data);
+        }
+        None
+    }
+
+    async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
+        let mut req = cli.put_item().table_name(table_name);
+        if let Some(price) = &self.price.listing_price {
+            req = req.item(KEY_LISTING_PRICE, AttributeValue::S(price.to_owned()));
+        }
+        if let Some(price) = &self.price.last_sale_price {
+            req = req.item(KEY_LAST_SALE_PRICE, AttributeValue::S(price.to_owned()));
+        }
+
+        req.item(KEY_WORK_ID, AttributeValue::S(self.work_id.to_owned()))
+            .item(KEY_TIMESTAMP, AttributeValue::N(self.timestamp.to_string()))
+            .item(
+                KEY_CURRENCY,
+                AttributeValue::S(self.price.currency.to_owned()),
+            )
+            .item(
+                KEY_USD_PRICE,
+                AttributeValue::N(self.price.usd_price.to_owned()),
+            )
+            .item(
+                KEY_ETH_PRICE,
+                AttributeValue::N(self.price.eth_price.to_ether()),
+            )
+            .send()
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+}
+
+impl Dao<asset::PriceSnapshot> {
+    /// 新しい順に取得する
+    pub async fn get_by_work(
+        &self,
+        work_id: String,
+        limit: Option<i32>,
+    ) -> AppResult<Vec<asset::PriceSnapshot>> {
+        let res = self
+            .cli
+            .query()
+            .key_condition_expression("#key = :value".to_string())
+            .expression_attribute_names("#key".to_string(), KEY_WORK_ID)
+            .expression_attribute_values(
+                ":value".to_string(),
+                AttributeValue::S(work_id.to_owned()),
+            )
+            .scan_index_forward(false)
+            .set_limit(limit)
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .send()
+            .await?;
+
+        let mut entities: Vec<asset::PriceSnapshot> = vec![];
+        for item in res.items.unwrap_or_default() {
+            entities.push(asset::PriceSnapshot::deserialize(item).unwrap())
+        }
+
+        Ok(entities)
+    }
+
+    pub async fn put(&self, item: &asset::PriceSnapshot) -> AppResult<()> {
+        item.serialize_and_save(&self.cli, self.table_name_provider.with(TABLE_NAME))
+            .await?;
+        Ok(())
+    }
+}
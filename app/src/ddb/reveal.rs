@@ -0,0 +1,171 @@
+use crate::ddb::Dao;
+use crate::domain::*;
+use crate::{AppError, AppResult};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const TABLE_NAME: &str = "canvas-nft-reveal";
+const KEY_ID: &str = "ID";
+const KEY_COLLECTION_ID: &str = "CollectionID";
+const KEY_WORK_ID: &str = "WorkID";
+const KEY_CHAIN_ID: &str = "ChainID";
+const KEY_SCHEMA: &str = "Schema";
+const KEY_CONTRACT_ADDRESS: &str = "ContractAddress";
+const KEY_METADATA: &str = "Metadata";
+const KEY_METADATA_HASH: &str = "MetadataHash";
+const KEY_USE_IPFS: &str = "UseIPFS";
+const KEY_ASSETS: &str = "Assets";
+const KEY_REVEALED: &str = "Revealed";
+const KEY_CREATED_AT: &str = "CreatedAt";
+const KEY_UPDATED_AT: &str = "UpdatedAt";
+
+impl reveal::RevealItem {
+    fn deserialize(data: HashMap<String, AttributeValue>) -> Option<Self> {
+        if let (
+            Some(AttributeValue::S(work_id)),
+            Some(AttributeValue::N(chain_id)),
+            Some(AttributeValue::S(schema)),
+            Some(AttributeValue::S(contract_address)),
+            Some(AttributeValue::S(metadata)),
+            Some(AttributeValue::S(metadata_hash)),
+            Some(AttributeValue::Bool(use_ipfs)),
+            Some(AttributeValue::S(assets)),
+            Some(AttributeValue::Bool(revealed)),
+            Some(AttributeValue::N(created_at)),
+            Some(AttributeValue::N(updated_at)),
+        ) = (
+            data.get(KEY_WORK_ID),
+            data.get(KEY_CHAIN_ID),
+            data.get(KEY_SCHEMA),
+            data.get(KEY_CONTRACT_ADDRESS),
+            data.get(KEY_METADATA),
+            data.get(KEY_METADATA_HASH),
+            data.get(KEY_USE_IPFS),
+            data.get(KEY_ASSETS),
+            data.get(KEY_REVEALED),
+            data.get(KEY_CREATED_AT),
+            data.get(KEY_UPDATED_AT),
+        ) {
+            let data = reveal::RevealItem {
+                work_id: work_id.to_owned(),
+                chain_id: chain_id.to_owned().parse().unwrap(),
+                schema: market::TokenSchema::from_str(schema).ok()?,
+                contract_address: contract_address.to_owned(),
+                metadata: metadata.to_owned(),
+                metadata_hash: metadata_hash.to_owned(),
+                use_ipfs: *use_ipfs,
+                assets: serde_json::from_str(assets).ok()?,
+                revealed: *revealed,
+                created_at: created_at.to_owned().parse().unwrap(),
+                updated_at: updated_at.to_owned().parse().unwrap(),
+            };
+
+            return Some(data);
+        }
+        None
+    }
+
+    async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
+        cli.put_item()
+            .table_name(table_name)
+            .item(KEY_ID, AttributeValue::S(self.id()))
+            .item(KEY_COLLECTION_ID, AttributeValue::S(self.collection_id()))
+            .item(KEY_WORK_ID, AttributeValue::S(self.work_id.to_owned()))
+            .item(KEY_CHAIN_ID, AttributeValue::N(self.chain_id.to_string()))
+            .item(KEY_SCHEMA, AttributeValue::S(self.schema.to_string()))
+            .item(
+                KEY_CONTRACT_ADDRESS,
+                AttributeValue::S(self.contract_address.to_owned()),
+            )
+            .item(KEY_METADATA, AttributeValue::S(self.metadata.to_owned()))
+            .item(
+                KEY_METADATA_HASH,
+                AttributeValue::S(self.metadata_hash.to_owned()),
+            )
+            .item(KEY_USE_IPFS, AttributeValue::Bool(self.use_ipfs))
+            .item(
+                KEY_ASSETS,
+                AttributeValue::S(serde_json::to_string(&self.assets)?),
+            )
+            .item(KEY_REVEALED, AttributeValue::Bool(self.revealed))
+            .item(
+                KEY_CREATED_AT,
+                AttributeValue::N(self.created_at.to_string()),
+            )
+            .item(
+                KEY_UPDATED_AT,
+                AttributeValue::N(self.updated_at.to_string()),
+            )
+            .send()
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    fn primary_key(id: String) -> AttributeValue {
+        AttributeValue::S(id)
+    }
+}
+
+impl Dao<reveal::RevealItem> {
+    pub async fn get(&self, id: String) -> AppResult<reveal::RevealItem> {
+        let res = self
+            .cli
+            .get_item()
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .key(KEY_ID, reveal::RevealItem::primary_key(id))
+            .send()
+            .await?;
+
+        if res.item.is_none() {
+            return Err(AppError::NotFound);
+        }
+
+        let data = res.item.unwrap();
+
+        Ok(reveal::RevealItem::deserialize(data).unwrap())
+    }
+
+    pub async fn get_by_collection(
+        &self,
+        collection_id: String,
+    ) -> AppResult<Vec<reveal::RevealItem>> {
+        let mut entities: Vec<reveal::RevealItem> = vec![];
+        let mut start_key: Option<HashMap<String, AttributeValue>> = None;
+        loop {
+            let res = self
+                .cli
+                .query()
+                .index_name("CollectionID-Index")
+                .key_condition_expression("#key = :value".to_string())
+                .expression_attribute_names("#key".to_string(), KEY_COLLECTION_ID)
+                .expression_attribute_values(
+                    ":value".to_string(),
+                    AttributeValue::S(collection_id.to_owned()),
+                )
+                .set_exclusive_start_key(start_key)
+                .table_name(self.table_name_provider.with(TABLE_NAME))
+                .send()
+                .await?;
+
+            for item in res.items.unwrap_or_default() {
+                entities.push(reveal::RevealItem::deserialize(item).unwrap())
+            }
+
+            start_key = res.last_evaluated_key;
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(entities)
+    }
+
+    pub async fn put(&self, item: &reveal::RevealItem) -> AppResult<()> {
+        item.serialize_and_save(&self.cli, self.table_name_provider.with(TABLE_NAME))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+use crate::ddb::Dao;
+use crate::domain::*;
+use crate::{AppError, AppResult};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const TABLE_NAME: &str = "canvas-nft-sale";
+const KEY_ID: &str = "ID";
+const KEY_WORK_ID: &str = "WorkID";
+const KEY_CHAIN_ID: &str = "ChainID";
+const KEY_SOURCE: &str = "Source";
+const KEY_MARKETPLACE: &str = "Marketplace";
+const KEY_SCHEMA: &str = "Schema";
+const KEY_CONTRACT_ADDRESS: &str = "ContractAddress";
+const KEY_TOKEN_ID: &str = "TokenID";
+const KEY_PUBLISHER_ID: &str = "PublisherID";
+const KEY_SELLER: &str = "Seller";
+const KEY_BUYER: &str = "Buyer";
+const KEY_CURRENCY: &str = "Currency";
+const KEY_PRICE: &str = "Price";
+const KEY_FEE: &str = "Fee";
+const KEY_ROYALTY: &str = "Royalty";
+const KEY_QUANTITY: &str = "Quantity";
+const KEY_TX_HASH: &str = "TxHash";
+const KEY_SOLD_AT: &str = "SoldAt";
+
+impl sale::Sale {
+    fn deserialize(data: HashMap<String, AttributeValue>) -> Option<Self> {
+        if let (
+            Some(AttributeValue::S(id)),
+            Some(AttributeValue::S(work_id)),
+            Some(AttributeValue::N(chain_id)),
+            Some(AttributeValue::S(marketplace)),
+            Some(AttributeValue::S(schema)),
+            Some(AttributeValue::S(contract_address)),
+            Some(AttributeValue::S(token_id)),
+            Some(AttributeValue::S(seller)),
+            Some(AttributeValue::S(buyer)),
+            Some(AttributeValue::S(currency)),
+            Some(AttributeValue::S(price)),
+            Some(AttributeValue::N(quantity)),
+            Some(AttributeValue::S(tx_hash)),
+            Some(AttributeValue::N(sold_at)),
+        ) = (
+            data.get(KEY_ID),
+            data.get(KEY_WORK_ID),
+            data.get(KEY_CHAIN_ID),
+            data.get(KEY_MARKETPLACE),
+            data.get(KEY_SCHEMA),
+            data.get(KEY_CONTRACT_ADDRESS),
+            data.get(KEY_TOKEN_ID),
+            data.get(KEY_SELLER),
+            data.get(KEY_BUYER),
+            data.get(KEY_CURRENCY),
+            data.get(KEY_PRICE),
+            data.get(KEY_QUANTITY),
+            data.get(KEY_TX_HASH),
+            data.get(KEY_SOLD_AT),
+        ) {
+            let string_of = |key: &str, default: &str| match data.get(key) {
+                Some(AttributeValue::S(v)) => v.to_owned(),
+                _ => default.to_string(),
+            };
+            let data = sale::Sale {
+                id: id.to_owned(),
+                work_id: work_id.to_owned(),
+                chain_id: chain_id.to_owned().parse().unwrap(),
+                // 台帳の導入前に承諾した申し込みの記録
+                source: sale::SaleSource::from(string_of(KEY_SOURCE, "Offer")),
+                marketplace: marketplace.to_owned(),
+                schema: market::TokenSchema::from_str(schema).ok()?,
+                contract_address: contract_address.to_owned(),
+                token_id: token_id.to_owned(),
+                publisher_id: string_of(KEY_PUBLISHER_ID, ""),
+                seller: seller.to_owned(),
+                buyer: buyer.to_owned(),
+                currency: currency.to_owned(),
+                price: price.to_owned(),
+                fee: string_of(KEY_FEE, "0"),
+                royalty: string_of(KEY_ROYALTY, "0"),
+                quantity: quantity.to_owned().parse().unwrap(),
+                tx_hash: tx_hash.to_owned(),
+                sold_at: sold_at.to_owned().parse().unwrap(),
+            };
+
+            return Some(data);
+        }
+        None
+    }
+
+    async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
+        cli.put_item()
+            .table_name(table_name)
+            .item(KEY_ID, AttributeValue::S(self.id.to_owned()))
+            .item(KEY_WORK_ID, AttributeValue::S(self.work_id.to_owned()))
+            .item(KEY_CHAIN_ID, AttributeValue::N(self.chain_id.to_string()))
+            .item(KEY_SOURCE, AttributeValue::S(self.source.to_string()))
+            .item(
+                KEY_MARKETPLACE,
+                AttributeValue::S(self.marketplace.to_owned()),
+            )
+            .item(KEY_SCHEMA, AttributeValue::S(self.schema.to_string()))
+            .item(
+                KEY_CONTRACT_ADDRESS,
+                AttributeValue::S(self.contract_address.to_owned()),
+            )
+            .item(KEY_TOKEN_ID, AttributeValue::S(self.token_id.to_owned()))
+            .item(
+                KEY_PUBLISHER_ID,
+                AttributeValue::S(self.publisher_id.to_owned()),
+            )
+            .item(KEY_SELLER, AttributeValue::S(self.seller.to_owned()))
+            .item(KEY_BUYER, AttributeValue::S(self.buyer.to_owned()))
+            .item(KEY_CURRENCY, AttributeValue::S(self.currency.to_owned()))
+            .item(KEY_PRICE, AttributeValue::S(self.price.to_owned()))
+            .item(KEY_FEE, AttributeValue::S(self.fee.to_owned()))
+            .item(KEY_ROYALTY, AttributeValue::S(self.royalty.to_owned()))
+            .item(KEY_QUANTITY, AttributeValue::N(self.quantity.to_string()))
+            .item(KEY_TX_HASH, AttributeValue::S(self.tx_hash.to_owned()))
+            .item(KEY_SOLD_AT, AttributeValue::N(self.sold_at.to_string()))
+            .send()
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+}
+
+impl Dao<sale::Sale> {
+    pub async fn get_by_work(&self, work_id: String) -> AppResult<Vec<sale::Sale>> {
+        let res = self
+            .cli
+            .query()
+            .index_name("WorkID-Index")
+            .key_condition_expression("#key = :value".to_string())
+            .expression_attribute_names("#key".to_string(), KEY_WORK_ID)
+            .expression_attribute_values(
+                ":value".to_string(),
+                AttributeValue::S(work_id.to_owned()),
+            )
+            .table_name(self.table_name_provider.with(TABLE_NAME))
+            .send()
+            .await?;
+
+        let mut entities: Vec<sale::Sale> = vec![];
+        for item in res.items.unwrap_or_default() {
+            entities.push(sale::Sale::deserialize(item).unwrap())
+        }
+
+        Ok(entities)
+    }
+
+    /// 集計用に全件を読む
+    pub async fn get_all(&self) -> AppResult<Vec<sale::Sale>> {
+        let mut entities: Vec<sale::Sale> = vec![];
+        let mut start_key: Option<HashMap<String, AttributeValue>> = None;
+        loop {
+            let res = self
+                .cli
+                .scan()
+                .set_exclusive_start_key(start_key)
+                .table_name(self.table_name_provider.with(TABLE_NAME))
+                .send()
+                .await?;
+
+            for item in res.items.unwrap_or_default() {
+                entities.push(sale::Sale::deserialize(item).unwrap())
+            }
+
+            start_key = res.last_evaluated_key;
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(entities)
+    }
+
+    pub async fn put(&self, item: &sale::Sale) -> AppResult<()> {
+        item.serialize_and_save(&self.cli, self.table_name_provider.with(TABLE_NAME))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use crate::ddb::Dao;
+use crate::domain::*;
+use crate::{AppError, AppResult};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::Client;
+
+const TABLE_NAME: &str = "canvas-nft-transaction";
+const KEY_TX_HASH: &str = "TxHash";
+const KEY_CHAIN_ID: &str = "ChainID";
+const KEY_KIND: &str = "Kind";
+const KEY_WORK_ID: &str = "WorkID";
+const KEY_SENDER: &str = "Sender";
+const KEY_SUCCESS: &str = "Success";
+const KEY_BLOCK_NUMBER: &str = "BlockNumber";
+const KEY_GAS_USED: &str = "GasUsed";
+const KEY_CREATED_AT: &str = "CreatedAt";
+
+impl transaction::TransactionRecord {
+    async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
+        cli.put_item()
+            .table_name(table_name)
+            .item(KEY_TX_HASH, AttributeValue::S(self.tx_hash.to_owned()))
+            .item(KEY_CHAIN_ID, AttributeValue::N(self.chain_id.to_string()))
+            .item(KEY_KIND, AttributeValue::S(self.kind.to_string()))
+            .item(KEY_WORK_ID, AttributeValue::S(self.work_id.to_owned()))
+            .item(KEY_SENDER, AttributeValue::S(self.sender.to_owned()))
+            .item(KEY_SUCCESS, AttributeValue::Bool(self.success))
+            .item(
+                KEY_BLOCK_NUMBER,
+                AttributeValue::N(self.block_number.to_string()),
+            )
+            .item(KEY_GAS_USED, AttributeValue::N(self.gas_used.to_string()))
+            .item(
+                KEY_CREATED_AT,
+                AttributeValue::N(self.created_at.to_string()),
+            )
+            .send()
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+}
+
+impl Dao<transaction::TransactionRecord> {
+    pub async fn put(&self, item: &transaction::TransactionRecord) -> AppResult<()> {
+        item.serialize_and_save(&self.cli, self.table_name_provider.with(TABLE_NAME))
+            .await?;
+        Ok(())
+    }
+}
@@ -11,6 +11,12 @@ const TABLE_NAME: &str = "canvas-nft-work";
 const KEY_ID: &str = "ID";
 const KEY_VIDEO_PATH: &str = "VideoPath";
 const KEY_STATUS: &str = "Status";
+const KEY_ROYALTY_RECEIVER: &str = "RoyaltyReceiver";
+const KEY_ROYALTY_BASIS_POINTS: &str = "RoyaltyBasisPoints";
+const KEY_TITLE: &str = "Title";
+const KEY_DESCRIPTION: &str = "Description";
+const KEY_ATTRIBUTES: &str = "Attributes";
+const KEY_BACKGROUND_COLOR: &str = "BackgroundColor";
 
 impl work::Work {
     fn deserialize(data: HashMap<String, AttributeValue>) -> Option<Self> {
@@ -27,6 +33,36 @@ impl work::Work {
                 id: id.to_owned(),
                 video_path: video_path.to_owned(),
                 status: WorkStatus::from(status.to_owned().to_string()),
+                royalty: match (
+                    data.get(KEY_ROYALTY_RECEIVER),
+                    data.get(KEY_ROYALTY_BASIS_POINTS),
+                ) {
+                    (Some(AttributeValue::S(receiver)), Some(AttributeValue::N(basis_points))) => {
+                        Some(royalty::Royalty {
+                            receiver: receiver.to_owned(),
+                            basis_points: basis_points.to_owned().parse().unwrap(),
+                        })
+                    }
+                    _ => None,
+                },
+                title: match data.get(KEY_TITLE) {
+                    Some(AttributeValue::S(title)) => title.to_owned(),
+                    _ => "".to_string(),
+                },
+                description: match data.get(KEY_DESCRIPTION) {
+                    Some(AttributeValue::S(description)) => description.to_owned(),
+                    _ => "".to_string(),
+                },
+                attributes: match data.get(KEY_ATTRIBUTES) {
+                    Some(AttributeValue::S(attributes)) => {
+                        work::WorkAttribute::parse_list(attributes).unwrap_or_default()
+                    }
+                    _ => vec![],
+                },
+                background_color: match data.get(KEY_BACKGROUND_COLOR) {
+                    Some(AttributeValue::S(color)) => Some(color.to_owned()),
+                    _ => None,
+                },
             };
 
             return Some(data);
@@ -35,9 +71,32 @@ impl work::Work {
     }
 
     async fn serialize_and_save(&self, cli: &Client, table_name: String) -> AppResult<()> {
-        cli.put_item()
-            .table_name(table_name)
-            .item(KEY_ID, AttributeValue::S(self.id.to_owned()))
+        let mut req = cli.put_item().table_name(table_name);
+        if let Some(royalty) = &self.royalty {
+            req = req
+                .item(
+                    KEY_ROYALTY_RECEIVER,
+                    AttributeValue::S(royalty.receiver.to_owned()),
+                )
+                .item(
+                    KEY_ROYALTY_BASIS_POINTS,
+                    AttributeValue::N(royalty.basis_points.to_string()),
+                );
+        }
+        if let Some(color) = &self.background_color {
+            req = req.item(KEY_BACKGROUND_COLOR, AttributeValue::S(color.to_owned()));
+        }
+
+        req.item(KEY_ID, AttributeValue::S(self.id.to_owned()))
+            .item(KEY_TITLE, AttributeValue::S(self.title.to_owned()))
+            .item(
+                KEY_DESCRIPTION,
+                AttributeValue::S(self.description.to_owned()),
+            )
+            .item(
+                KEY_ATTRIBUTES,
+                AttributeValue::S(work::WorkAttribute::format_list(&self.attributes)),
+            )
             .item(
                 KEY_VIDEO_PATH,
                 AttributeValue::S(self.video_path.to_owned()),
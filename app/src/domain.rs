@@ -1,3 +1,18 @@
+pub mod acquisition;
+pub mod amount;
 pub mod asset;
+pub mod collection;
+pub mod contract;
+pub mod listing;
+pub mod market;
+pub mod metadata_check;
+pub mod offer;
+pub mod ownership;
+pub mod pin;
+pub mod reveal;
+pub mod royalty;
+pub mod sale;
+pub mod template;
+pub mod transaction;
 pub mod user;
 pub mod work;
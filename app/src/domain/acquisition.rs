@@ -0,0 +1,53 @@
+use crate::domain::amount::Wei;
+use crate::domain::market::{TokenRef, TokenSchema};
+
+/// マーケットプレイスで購入したトークン
+#[derive(Clone, Debug)]
+pub struct Acquisition {
+    pub id: String,
+    pub chain_id: u64,
+    pub marketplace: String,
+    pub schema: TokenSchema,
+    pub contract_address: String,
+    pub token_id: String,
+    pub buyer_id: String,
+    pub buyer_address: String,
+    pub seller_address: String,
+    /// 注文全体の価格
+    pub price: Wei,
+    pub quantity: u64,
+    pub tx_hash: String,
+    pub created_at: i64,
+}
+
+impl Acquisition {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        chain_id: u64,
+        marketplace: String,
+        token: &TokenRef,
+        buyer_id: String,
+        buyer_address: String,
+        seller_address: String,
+        price: Wei,
+        quantity: u64,
+        tx_hash: String,
+        created_at: i64,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            chain_id,
+            marketplace,
+            schema: token.schema,
+            contract_address: token.contract_address.clone(),
+            token_id: token.token_id.clone(),
+            buyer_id,
+            buyer_address,
+            seller_address,
+            price,
+            quantity,
+            tx_hash,
+            created_at,
+        }
+    }
+}
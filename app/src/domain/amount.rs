@@ -0,0 +1,114 @@
+use crate::ethereum::unit::{format_units, parse_units, Unit};
+use crate::{AppError, AppResult};
+use std::fmt;
+use std::str::FromStr;
+use web3::types::U256;
+
+/// weiを単位とする金額。浮動小数を経由せずにU256のまま扱う
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Default, Hash)]
+pub struct Wei(U256);
+
+impl Wei {
+    pub fn new(value: U256) -> Self {
+        Self(value)
+    }
+
+    pub fn zero() -> Self {
+        Self(U256::zero())
+    }
+
+    pub fn parse(value: &str, unit: Unit) -> AppResult<Self> {
+        Ok(Self(parse_units(value, unit)?))
+    }
+
+    pub fn from_ether(value: &str) -> AppResult<Self> {
+        Self::parse(value, Unit::Ether)
+    }
+
+    pub fn value(&self) -> U256 {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn format(&self, unit: Unit) -> String {
+        format_units(self.0, unit)
+    }
+
+    pub fn to_ether(&self) -> String {
+        self.format(Unit::Ether)
+    }
+
+    pub fn checked_add(&self, other: Wei) -> Option<Wei> {
+        self.0.checked_add(other.0).map(Wei)
+    }
+
+    pub fn checked_sub(&self, other: Wei) -> Option<Wei> {
+        self.0.checked_sub(other.0).map(Wei)
+    }
+
+    pub fn checked_mul(&self, other: U256) -> Option<Wei> {
+        self.0.checked_mul(other).map(Wei)
+    }
+}
+
+impl From<U256> for Wei {
+    fn from(value: U256) -> Self {
+        Self(value)
+    }
+}
+
+/// 保存用に10進数のwei表記で出力する
+impl fmt::Display for Wei {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Wei {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> AppResult<Self> {
+        Self::parse(s, Unit::Wei)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_formats_ether() {
+        let wei = Wei::from_ether("0.015").unwrap();
+        assert_eq!(wei.value(), U256::from(15_000_000_000_000_000u64));
+        assert_eq!(wei.to_ether(), "0.015");
+        assert_eq!(wei.format(Unit::Gwei), "15000000");
+        // 保存用の表記はwei
+        assert_eq!(wei.to_string(), "15000000000000000");
+        assert_eq!("15000000000000000".parse::<Wei>().unwrap(), wei);
+
+        assert!(Wei::from_ether("").is_err());
+        assert!(Wei::from_ether("-0.1").is_err());
+        assert!(Wei::from_ether("0.0000000000000000001").is_err());
+        assert!("1.5".parse::<Wei>().is_err());
+        assert!(Wei::default().is_zero());
+    }
+
+    #[test]
+    fn checks_overflow() {
+        let max = Wei::new(U256::MAX);
+        let one = Wei::new(U256::one());
+
+        assert_eq!(max.checked_add(one), None);
+        assert_eq!(Wei::zero().checked_sub(one), None);
+        assert_eq!(max.checked_mul(U256::from(2)), None);
+        assert_eq!(one.checked_add(one), Some(Wei::new(U256::from(2))));
+        assert_eq!(
+            Wei::from_ether("0.1").unwrap().checked_mul(U256::from(3)),
+            Some(Wei::from_ether("0.3").unwrap())
+        );
+        assert!(Wei::from_ether("1").unwrap() > Wei::from_ether("0.999999999999999999").unwrap());
+    }
+}
@@ -1,6 +1,9 @@
+use crate::domain::amount::Wei;
+
 #[derive(Clone, Debug)]
 pub struct Asset721 {
     pub work_id: String,
+    pub chain_id: u64,
     pub contract_address: String,
     pub token_id: String,
     pub name: String,
@@ -8,14 +11,18 @@ pub struct Asset721 {
     pub image_url: String,
     pub image_preview_url: String,
     pub permalink: String,
-    pub usd_price: f64,
-    pub eth_price: f64,
+    pub usd_price: String,
+    pub eth_price: Wei,
+    pub currency: String,
+    pub listing_price: Option<String>,
+    pub last_sale_price: Option<String>,
 }
 
 impl Asset721 {
-    pub fn new(work_id: String) -> Self {
+    pub fn new(work_id: String, chain_id: u64) -> Self {
         Self {
             work_id,
+            chain_id,
             contract_address: "".to_string(),
             token_id: "".to_string(),
             name: "".to_string(),
@@ -23,13 +30,17 @@ impl Asset721 {
             image_url: "".to_string(),
             image_preview_url: "".to_string(),
             permalink: "".to_string(),
-            usd_price: 0.0,
-            eth_price: 0.0,
+            usd_price: "0".to_string(),
+            eth_price: Wei::zero(),
+            currency: "".to_string(),
+            listing_price: None,
+            last_sale_price: None,
         }
     }
 
     pub fn published(
         &mut self,
+        chain_id: u64,
         contract_address: String,
         token_id: String,
         name: String,
@@ -38,6 +49,7 @@ impl Asset721 {
         image_preview_url: String,
         permalink: String,
     ) {
+        self.chain_id = chain_id;
         self.contract_address = contract_address;
         self.token_id = token_id;
         self.name = name;
@@ -46,11 +58,30 @@ impl Asset721 {
         self.image_preview_url = image_preview_url;
         self.permalink = permalink;
     }
+
+    pub fn priced(&mut self, price: &MarketPrice) {
+        self.currency = price.currency.to_owned();
+        self.listing_price = price.listing_price.to_owned();
+        self.last_sale_price = price.last_sale_price.to_owned();
+        self.usd_price = price.usd_price.to_owned();
+        self.eth_price = price.eth_price;
+    }
+
+    pub fn market_price(&self) -> MarketPrice {
+        MarketPrice {
+            currency: self.currency.to_owned(),
+            listing_price: self.listing_price.to_owned(),
+            last_sale_price: self.last_sale_price.to_owned(),
+            usd_price: self.usd_price.to_owned(),
+            eth_price: self.eth_price,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Asset1155 {
     pub work_id: String,
+    pub chain_id: u64,
     pub contract_address: String,
     pub token_id: String,
     pub name: String,
@@ -58,14 +89,20 @@ pub struct Asset1155 {
     pub image_url: String,
     pub image_preview_url: String,
     pub permalink: String,
-    pub usd_price: f64,
-    pub eth_price: f64,
+    pub usd_price: String,
+    pub eth_price: Wei,
+    pub currency: String,
+    pub listing_price: Option<String>,
+    pub last_sale_price: Option<String>,
+    /// 発行者が保有している残りの数量。未取得ならNone
+    pub balance: Option<u64>,
 }
 
 impl Asset1155 {
-    pub fn new(work_id: String) -> Self {
+    pub fn new(work_id: String, chain_id: u64) -> Self {
         Self {
             work_id,
+            chain_id,
             contract_address: "".to_string(),
             token_id: "".to_string(),
             name: "".to_string(),
@@ -73,13 +110,18 @@ impl Asset1155 {
             image_url: "".to_string(),
             image_preview_url: "".to_string(),
             permalink: "".to_string(),
-            usd_price: 0.0,
-            eth_price: 0.0,
+            usd_price: "0".to_string(),
+            eth_price: Wei::zero(),
+            currency: "".to_string(),
+            listing_price: None,
+            last_sale_price: None,
+            balance: None,
         }
     }
 
     pub fn published(
         &mut self,
+        chain_id: u64,
         contract_address: String,
         token_id: String,
         name: String,
@@ -88,6 +130,7 @@ impl Asset1155 {
         image_preview_url: String,
         permalink: String,
     ) {
+        self.chain_id = chain_id;
         self.contract_address = contract_address;
         self.token_id = token_id;
         self.name = name;
@@ -96,4 +139,56 @@ impl Asset1155 {
         self.image_preview_url = image_preview_url;
         self.permalink = permalink;
     }
+
+    pub fn priced(&mut self, price: &MarketPrice) {
+        self.currency = price.currency.to_owned();
+        self.listing_price = price.listing_price.to_owned();
+        self.last_sale_price = price.last_sale_price.to_owned();
+        self.usd_price = price.usd_price.to_owned();
+        self.eth_price = price.eth_price;
+    }
+
+    pub fn balance_changed(&mut self, balance: u64) {
+        self.balance = Some(balance);
+    }
+
+    pub fn market_price(&self) -> MarketPrice {
+        MarketPrice {
+            currency: self.currency.to_owned(),
+            listing_price: self.listing_price.to_owned(),
+            last_sale_price: self.last_sale_price.to_owned(),
+            usd_price: self.usd_price.to_owned(),
+            eth_price: self.eth_price,
+        }
+    }
+}
+
+/// マーケットプレイスでの価格。金額は支払い通貨の単位の10進数の文字列
+/// usd_price / eth_price は出品価格（なければ直近の売買価格）を換算したもの
+/// usd_priceはドル単位の10進数の文字列
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketPrice {
+    pub currency: String,
+    pub listing_price: Option<String>,
+    pub last_sale_price: Option<String>,
+    pub usd_price: String,
+    pub eth_price: Wei,
+}
+
+/// 価格推移の表示用に、同期のたびに価格が変わっていれば記録する
+#[derive(Clone, Debug)]
+pub struct PriceSnapshot {
+    pub work_id: String,
+    pub timestamp: i64,
+    pub price: MarketPrice,
+}
+
+impl PriceSnapshot {
+    pub fn new(work_id: String, timestamp: i64, price: MarketPrice) -> Self {
+        Self {
+            work_id,
+            timestamp,
+            price,
+        }
+    }
 }
@@ -0,0 +1,49 @@
+use crate::domain::market::TokenSchema;
+use crate::domain::reveal::Placeholder;
+use crate::domain::royalty::Royalty;
+use crate::domain::template::MetadataTemplate;
+
+/// コントラクト単位の設定（チェーン・コントラクトごとに1件）
+#[derive(Clone, Debug)]
+pub struct Collection {
+    pub chain_id: u64,
+    pub schema: TokenSchema,
+    pub contract_address: String,
+    /// 作品ごとの設定がない場合に使う
+    pub royalty: Option<Royalty>,
+    /// 未設定なら既定のテンプレートを使う
+    pub metadata_template: Option<MetadataTemplate>,
+    /// 公開前はmetadataの配信でプレースホルダーを返す
+    pub hidden: bool,
+    /// 未設定なら環境変数の画像だけのものを使う
+    pub placeholder: Option<Placeholder>,
+    /// 公開前に確定した、公開後のmetadata全体のハッシュ
+    pub provenance_hash: Option<String>,
+    pub revealed_at: Option<i64>,
+    pub updated_at: i64,
+}
+
+impl Collection {
+    pub fn new(chain_id: u64, schema: TokenSchema, contract_address: String, now: i64) -> Self {
+        Self {
+            chain_id,
+            schema,
+            contract_address,
+            royalty: None,
+            metadata_template: None,
+            hidden: false,
+            placeholder: None,
+            provenance_hash: None,
+            revealed_at: None,
+            updated_at: now,
+        }
+    }
+
+    pub fn id_of(chain_id: u64, contract_address: &str) -> String {
+        format!("{}:{}", chain_id, contract_address.to_lowercase())
+    }
+
+    pub fn id(&self) -> String {
+        Self::id_of(self.chain_id, &self.contract_address)
+    }
+}
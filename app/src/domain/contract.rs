@@ -0,0 +1,22 @@
+/// デプロイしたコントラクトの台帳（チェーンとコントラクト名ごとに1件）
+#[derive(Clone, Debug)]
+pub struct DeployedContract {
+    pub chain_id: u64,
+    pub name: String,
+    pub address: String,
+    pub deployer: String,
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub code_hash: String,
+    pub verified: bool,
+}
+
+impl DeployedContract {
+    pub fn id_of(chain_id: u64, name: &str) -> String {
+        format!("{}:{}", chain_id, name)
+    }
+
+    pub fn id(&self) -> String {
+        Self::id_of(self.chain_id, &self.name)
+    }
+}
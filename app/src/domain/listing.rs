@@ -0,0 +1,93 @@
+use crate::domain::amount::Wei;
+use crate::domain::market::{ListingOrder, TokenRef, TokenSchema};
+use std::str::FromStr;
+use strum_macros::Display as StrumDisplay;
+use strum_macros::EnumString;
+
+#[derive(PartialEq, Clone, Debug, StrumDisplay, EnumString)]
+pub enum ListingStatus {
+    Active,
+    Cancelled,
+}
+
+impl ListingStatus {
+    pub fn from(str: String) -> Self {
+        ListingStatus::from_str(&str).unwrap()
+    }
+}
+
+/// 作品の出品。IDはマーケットプレイスの注文ID
+#[derive(Clone, Debug)]
+pub struct Listing {
+    pub id: String,
+    pub work_id: String,
+    pub chain_id: u64,
+    pub marketplace: String,
+    pub schema: TokenSchema,
+    pub contract_address: String,
+    pub token_id: String,
+    pub seller_id: String,
+    /// 1つあたりの価格
+    pub price: Wei,
+    pub quantity: u64,
+    pub expires_at: i64,
+    pub order: String,
+    pub status: ListingStatus,
+    pub created_at: i64,
+}
+
+impl Listing {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        work_id: String,
+        chain_id: u64,
+        marketplace: String,
+        token: &TokenRef,
+        seller_id: String,
+        price: Wei,
+        quantity: u64,
+        order: ListingOrder,
+        created_at: i64,
+    ) -> Self {
+        Self {
+            id: order.id,
+            work_id,
+            chain_id,
+            marketplace,
+            schema: token.schema,
+            contract_address: token.contract_address.clone(),
+            token_id: token.token_id.clone(),
+            seller_id,
+            price,
+            quantity,
+            expires_at: order.expires_at,
+            order: order.payload,
+            status: ListingStatus::Active,
+            created_at,
+        }
+    }
+
+    pub fn token(&self) -> TokenRef {
+        TokenRef::new(
+            self.schema,
+            self.contract_address.clone(),
+            self.token_id.clone(),
+        )
+    }
+
+    pub fn listing_order(&self) -> ListingOrder {
+        ListingOrder {
+            id: self.id.clone(),
+            expires_at: self.expires_at,
+            payload: self.order.clone(),
+        }
+    }
+
+    pub fn is_active(&self, now: i64) -> bool {
+        self.status == ListingStatus::Active && self.expires_at > now
+    }
+
+    pub fn cancelled(&mut self) {
+        self.status = ListingStatus::Cancelled;
+    }
+}
@@ -0,0 +1,96 @@
+use crate::domain::asset::MarketPrice;
+use strum_macros::Display as StrumDisplay;
+use strum_macros::EnumString;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, StrumDisplay, EnumString)]
+pub enum TokenSchema {
+    #[strum(serialize = "ERC721")]
+    Erc721,
+    #[strum(serialize = "ERC1155")]
+    Erc1155,
+}
+
+impl TokenSchema {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenSchema::Erc721 => "ERC721",
+            TokenSchema::Erc1155 => "ERC1155",
+        }
+    }
+}
+
+/// マーケットプレイス上のトークンの指定
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TokenRef {
+    pub schema: TokenSchema,
+    pub contract_address: String,
+    pub token_id: String,
+}
+
+impl TokenRef {
+    pub fn new(schema: TokenSchema, contract_address: String, token_id: String) -> Self {
+        Self {
+            schema,
+            contract_address,
+            token_id,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MarketAsset {
+    pub name: String,
+    pub description: String,
+    pub image_url: String,
+    pub image_preview_url: String,
+    pub permalink: String,
+    pub owners: Vec<String>,
+    pub price: MarketPrice,
+}
+
+/// 出品中の注文。金額は支払い通貨の単位の10進数の文字列で、注文全体（数量分）の価格
+#[derive(Clone, Debug, PartialEq)]
+pub struct Listing {
+    /// マーケットプレイスの注文ID（Seaportの注文ハッシュ）
+    pub id: String,
+    pub maker: String,
+    pub currency: String,
+    pub price: String,
+    /// 支払いに使うERC20のアドレス。ネイティブ通貨ならNone
+    pub payment_token: Option<String>,
+    pub quantity: u64,
+    pub expires_at: Option<String>,
+}
+
+/// 受け取った購入の申し込み。金額は支払い通貨の単位の10進数の文字列で、注文全体の価格
+#[derive(Clone, Debug, PartialEq)]
+pub struct Offer {
+    pub id: String,
+    pub maker: String,
+    pub currency: String,
+    pub price: String,
+    pub quantity: u64,
+    pub expires_at: Option<i64>,
+}
+
+/// 成立した売買
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaleEvent {
+    pub seller: String,
+    pub buyer: String,
+    pub currency: String,
+    pub price: String,
+    pub quantity: u64,
+    pub tx_hash: String,
+    /// unix時間（秒）
+    pub occurred_at: i64,
+}
+
+/// マーケットプレイスに登録した出品の注文
+/// payloadは取り消しに必要な、マーケットプレイスごとの注文の内容
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListingOrder {
+    pub id: String,
+    pub expires_at: i64,
+    pub payload: String,
+}
@@ -0,0 +1,34 @@
+use crate::domain::market::TokenSchema;
+use strum_macros::Display as StrumDisplay;
+
+#[derive(PartialEq, Clone, Copy, Debug, StrumDisplay)]
+pub enum MetadataIssueKind {
+    /// tokenURIの先が取得できない
+    DeadMetadata,
+    /// JSONとして読めない
+    InvalidMetadata,
+    /// ipfs://のCIDと内容が一致しない
+    MetadataHashMismatch,
+    /// 保存しているmetadataと異なる
+    MetadataMismatch,
+    /// 作品やテンプレートの今の内容から作ったmetadataと異なる
+    OutdatedMetadata,
+    DeadImage,
+    ImageHashMismatch,
+    DeadAnimation,
+    AnimationHashMismatch,
+    /// マーケットプレイスの表示がmetadataと異なる
+    MarketplaceStale,
+    /// RPCやS3の失敗などで検査できなかった
+    CheckFailed,
+}
+
+/// metadataの検査で見つかった問題（トークン・種類ごとに1件）
+#[derive(Clone, Debug)]
+pub struct MetadataIssue {
+    pub work_id: String,
+    pub schema: TokenSchema,
+    pub token_id: String,
+    pub kind: MetadataIssueKind,
+    pub detail: String,
+}
@@ -0,0 +1,87 @@
+use crate::domain::market::{self, TokenRef, TokenSchema};
+use std::str::FromStr;
+use strum_macros::Display as StrumDisplay;
+use strum_macros::EnumString;
+
+#[derive(PartialEq, Clone, Debug, StrumDisplay, EnumString)]
+pub enum OfferStatus {
+    Open,
+    Accepted,
+    Rejected,
+    /// マーケットプレイスから消えた（取り消し・期限切れ・他で成立）
+    Closed,
+}
+
+impl OfferStatus {
+    pub fn from(str: String) -> Self {
+        OfferStatus::from_str(&str).unwrap()
+    }
+}
+
+/// 作品に届いた購入の申し込み。IDはマーケットプレイスの注文ID
+#[derive(Clone, Debug)]
+pub struct Offer {
+    pub id: String,
+    pub work_id: String,
+    pub chain_id: u64,
+    pub marketplace: String,
+    pub schema: TokenSchema,
+    pub contract_address: String,
+    pub token_id: String,
+    pub maker: String,
+    pub currency: String,
+    /// 注文全体の価格（支払い通貨の単位の10進数）
+    pub price: String,
+    pub quantity: u64,
+    /// 期限がない場合は0
+    pub expires_at: i64,
+    pub status: OfferStatus,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Offer {
+    pub fn new(
+        work_id: String,
+        chain_id: u64,
+        marketplace: String,
+        token: &TokenRef,
+        offer: market::Offer,
+        now: i64,
+    ) -> Self {
+        Self {
+            id: offer.id,
+            work_id,
+            chain_id,
+            marketplace,
+            schema: token.schema,
+            contract_address: token.contract_address.clone(),
+            token_id: token.token_id.clone(),
+            maker: offer.maker,
+            currency: offer.currency,
+            price: offer.price,
+            quantity: offer.quantity,
+            expires_at: offer.expires_at.unwrap_or_default(),
+            status: OfferStatus::Open,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn token(&self) -> TokenRef {
+        TokenRef::new(
+            self.schema,
+            self.contract_address.clone(),
+            self.token_id.clone(),
+        )
+    }
+
+    pub fn is_open(&self, now: i64) -> bool {
+        self.status == OfferStatus::Open && (self.expires_at == 0 || self.expires_at > now)
+    }
+
+    pub fn changed(&mut self, status: OfferStatus, now: i64) {
+        self.status = status;
+        self.updated_at = now;
+    }
+}
@@ -0,0 +1,35 @@
+use crate::domain::market::TokenSchema;
+
+/// トークンの保有状況の索引（チェーン・コントラクト・トークン・保有者ごとに1件）
+#[derive(Clone, Debug)]
+pub struct Ownership {
+    pub chain_id: u64,
+    pub schema: TokenSchema,
+    pub contract_address: String,
+    pub token_id: String,
+    pub owner_address: String,
+    pub work_id: String,
+    pub amount: u64,
+    pub updated_at: i64,
+}
+
+impl Ownership {
+    pub fn id_of(chain_id: u64, contract_address: &str, token_id: &str, owner: &str) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            chain_id,
+            contract_address.to_lowercase(),
+            token_id,
+            owner.to_lowercase()
+        )
+    }
+
+    pub fn id(&self) -> String {
+        Self::id_of(
+            self.chain_id,
+            &self.contract_address,
+            &self.token_id,
+            &self.owner_address,
+        )
+    }
+}
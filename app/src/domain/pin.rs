@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use strum_macros::Display as StrumDisplay;
+use strum_macros::EnumString;
+
+#[derive(PartialEq, Clone, Copy, Debug, StrumDisplay, EnumString)]
+pub enum PinStatus {
+    Queued,
+    Pinning,
+    Pinned,
+    Failed,
+}
+
+impl PinStatus {
+    pub fn from(str: String) -> Self {
+        PinStatus::from_str(&str).unwrap()
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, StrumDisplay, EnumString, Serialize, Deserialize)]
+pub enum PinKind {
+    Image,
+    Animation,
+    Metadata,
+}
+
+impl PinKind {
+    pub fn from(str: String) -> Self {
+        PinKind::from_str(&str).unwrap()
+    }
+}
+
+/// IPFSにピン留めした内容の記録（作品・CIDごとに1件）
+#[derive(Clone, Debug)]
+pub struct Pin {
+    pub cid: String,
+    pub work_id: String,
+    pub kind: PinKind,
+    pub provider: String,
+    pub name: String,
+    pub size: u64,
+    pub status: PinStatus,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Pin {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        cid: String,
+        work_id: String,
+        kind: PinKind,
+        provider: String,
+        name: String,
+        size: u64,
+        status: PinStatus,
+        now: i64,
+    ) -> Self {
+        Self {
+            cid,
+            work_id,
+            kind,
+            provider,
+            name,
+            size,
+            status,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn id_of(work_id: &str, cid: &str) -> String {
+        format!("{}:{}", work_id, cid)
+    }
+
+    pub fn id(&self) -> String {
+        Self::id_of(&self.work_id, &self.cid)
+    }
+
+    pub fn changed(&mut self, status: PinStatus, now: i64) {
+        self.status = status;
+        self.updated_at = now;
+    }
+}
@@ -0,0 +1,104 @@
+use crate::domain::market::TokenSchema;
+use crate::domain::pin::PinKind;
+use crate::domain::work::WorkAttribute;
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+
+/// 公開前のトークンのmetadataに使う内容
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Placeholder {
+    pub name: String,
+    pub description: String,
+    pub image_url: String,
+    #[serde(default)]
+    pub attributes: Vec<WorkAttribute>,
+}
+
+/// 公開時に出すファイル。公開までは推測できないS3のキーに置いておく
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StagedAsset {
+    pub kind: PinKind,
+    pub name: String,
+    pub s3_key: String,
+    pub content_type: String,
+}
+
+/// 公開前のコレクションで準備した作品の、公開後のmetadata（作品ごとに1件）
+#[derive(Clone, Debug)]
+pub struct RevealItem {
+    pub work_id: String,
+    pub chain_id: u64,
+    pub schema: TokenSchema,
+    pub contract_address: String,
+    /// 公開するmetadataのJSON。このバイト列をそのまま公開する
+    pub metadata: String,
+    pub metadata_hash: String,
+    pub use_ipfs: bool,
+    pub assets: Vec<StagedAsset>,
+    pub revealed: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl RevealItem {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        work_id: String,
+        chain_id: u64,
+        schema: TokenSchema,
+        contract_address: String,
+        metadata: String,
+        use_ipfs: bool,
+        assets: Vec<StagedAsset>,
+        now: i64,
+    ) -> Self {
+        Self {
+            work_id,
+            chain_id,
+            schema,
+            contract_address,
+            metadata_hash: sha256_hex(metadata.as_bytes()),
+            metadata,
+            use_ipfs,
+            assets,
+            revealed: false,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn id_of(collection_id: &str, work_id: &str) -> String {
+        format!("{}:{}", collection_id, work_id)
+    }
+
+    /// Collection::idと同じ形式
+    pub fn collection_id(&self) -> String {
+        format!("{}:{}", self.chain_id, self.contract_address.to_lowercase())
+    }
+
+    pub fn id(&self) -> String {
+        Self::id_of(&self.collection_id(), &self.work_id)
+    }
+
+    pub fn revealed(&mut self, now: i64) {
+        self.revealed = true;
+        self.updated_at = now;
+    }
+}
+
+/// 作品IDの順に各metadataのハッシュをつなげたもののハッシュ
+/// 公開後のmetadataから誰でも同じ値を計算できる
+pub fn provenance_hash(hashes: &[(String, String)]) -> String {
+    let mut hashes = hashes.to_vec();
+    hashes.sort_by(|a, b| a.0.cmp(&b.0));
+    let joined: String = hashes.into_iter().map(|(_, hash)| hash).collect();
+    sha256_hex(joined.as_bytes())
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    digest(&SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|v| format!("{:02x}", v))
+        .collect()
+}
@@ -0,0 +1,30 @@
+use crate::ethereum::address::{parse_checksummed, to_checksum};
+use crate::{AppError, AppResult};
+
+/// EIP-2981の率の上限（100%）
+pub const MAX_BASIS_POINTS: u64 = 10000;
+
+/// EIP-2981のロイヤリティ（受取人と率）
+#[derive(Clone, Debug, PartialEq)]
+pub struct Royalty {
+    /// チェックサム付きの表記
+    pub receiver: String,
+    pub basis_points: u64,
+}
+
+impl Royalty {
+    pub fn new(receiver: &str, basis_points: u64) -> AppResult<Self> {
+        if basis_points > MAX_BASIS_POINTS {
+            return Err(AppError::BadRequest(format!(
+                "ロイヤリティの率が大きすぎます: {}",
+                basis_points
+            )));
+        }
+        let address = parse_checksummed(receiver)?;
+
+        Ok(Self {
+            receiver: to_checksum(&address),
+            basis_points,
+        })
+    }
+}
@@ -0,0 +1,215 @@
+use crate::datetime::format_month;
+use crate::domain::market::{TokenRef, TokenSchema};
+use crate::ethereum::unit::{format_units, parse_units, Unit};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use strum_macros::Display as StrumDisplay;
+use strum_macros::EnumString;
+use web3::types::U256;
+
+#[derive(PartialEq, Clone, Debug, StrumDisplay, EnumString)]
+pub enum SaleSource {
+    /// マーケットプレイスの売買のイベント
+    Marketplace,
+    /// こちらで承諾した申し込み
+    Offer,
+    /// コントラクトを直接呼んだ転送（代金なし）
+    Transfer,
+}
+
+impl SaleSource {
+    pub fn from(str: String) -> Self {
+        SaleSource::from_str(&str).unwrap()
+    }
+}
+
+/// 売上の台帳。成立した売買や転送ごとに1件
+/// 金額は支払い通貨の単位の10進数の文字列
+#[derive(Clone, Debug)]
+pub struct Sale {
+    pub id: String,
+    pub work_id: String,
+    pub chain_id: u64,
+    pub source: SaleSource,
+    pub marketplace: String,
+    pub schema: TokenSchema,
+    pub contract_address: String,
+    pub token_id: String,
+    /// 売り手が発行者のウォレットならそのユーザーID（二次流通は空）
+    pub publisher_id: String,
+    pub seller: String,
+    pub buyer: String,
+    pub currency: String,
+    /// 注文全体の価格
+    pub price: String,
+    /// マーケットプレイスの手数料
+    pub fee: String,
+    /// クリエイターへのロイヤリティ
+    pub royalty: String,
+    pub quantity: u64,
+    pub tx_hash: String,
+    pub sold_at: i64,
+}
+
+impl Sale {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        work_id: String,
+        chain_id: u64,
+        source: SaleSource,
+        marketplace: String,
+        token: &TokenRef,
+        publisher_id: String,
+        seller: String,
+        buyer: String,
+        currency: String,
+        price: String,
+        quantity: u64,
+        tx_hash: String,
+        sold_at: i64,
+    ) -> Self {
+        Self {
+            id: Self::id_of(&tx_hash, token),
+            work_id,
+            chain_id,
+            source,
+            marketplace,
+            schema: token.schema,
+            contract_address: token.contract_address.clone(),
+            token_id: token.token_id.clone(),
+            publisher_id,
+            seller,
+            buyer,
+            currency,
+            price,
+            fee: "0".to_string(),
+            royalty: "0".to_string(),
+            quantity,
+            tx_hash,
+            sold_at,
+        }
+    }
+
+    /// tx hashがわかるものは同じ売買を重複して記録しないようにtx hashとトークンから作る
+    pub fn id_of(tx_hash: &str, token: &TokenRef) -> String {
+        if tx_hash.is_empty() {
+            return uuid::Uuid::new_v4().to_string();
+        }
+        format!(
+            "{}:{}:{}",
+            tx_hash.to_lowercase(),
+            token.contract_address.to_lowercase(),
+            token.token_id
+        )
+    }
+
+    /// 価格に対する手数料とロイヤリティの率を設定する
+    pub fn charged(&mut self, fee_basis_points: u64, royalty_basis_points: u64) {
+        self.fee = basis_points_of(&self.price, fee_basis_points);
+        self.royalty = basis_points_of(&self.price, royalty_basis_points);
+    }
+
+    /// 売り手の受け取り額（価格から手数料とロイヤリティを除いた額）
+    pub fn proceeds(&self) -> String {
+        let price = amount_of(&self.price);
+        let charged = amount_of(&self.fee) + amount_of(&self.royalty);
+        format_units(price.saturating_sub(charged), Unit::Ether)
+    }
+
+    pub fn month(&self) -> String {
+        format_month(self.sold_at)
+    }
+
+    pub fn is_publisher_sale(&self) -> bool {
+        !self.publisher_id.is_empty()
+    }
+}
+
+/// 集計の単位
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum RevenueGroup {
+    Work,
+    Publisher,
+    Month,
+}
+
+impl RevenueGroup {
+    pub fn key_of(&self, sale: &Sale) -> String {
+        match self {
+            RevenueGroup::Work => sale.work_id.clone(),
+            RevenueGroup::Publisher => sale.publisher_id.clone(),
+            RevenueGroup::Month => sale.month(),
+        }
+    }
+}
+
+/// 台帳の集計。通貨ごとに分ける
+#[derive(Clone, Debug, PartialEq)]
+pub struct Revenue {
+    pub key: String,
+    pub currency: String,
+    pub sales_count: u64,
+    /// 売買の総額
+    pub volume: String,
+    pub fees: String,
+    /// 受け取ったロイヤリティ（二次流通を含む）
+    pub royalties: String,
+    /// 発行者が売り手の売買の受け取り額
+    pub proceeds: String,
+}
+
+impl Revenue {
+    /// key_ofで分類して集計する。転送は件数に含めない
+    pub fn aggregate<F: Fn(&Sale) -> String>(sales: &[Sale], key_of: F) -> Vec<Self> {
+        let mut totals: BTreeMap<(String, String), Total> = BTreeMap::new();
+        for sale in sales {
+            if sale.source == SaleSource::Transfer {
+                continue;
+            }
+            let total = totals
+                .entry((key_of(sale), sale.currency.clone()))
+                .or_default();
+            total.sales_count += 1;
+            total.volume += amount_of(&sale.price);
+            total.fees += amount_of(&sale.fee);
+            total.royalties += amount_of(&sale.royalty);
+            if sale.is_publisher_sale() {
+                total.proceeds += amount_of(&sale.proceeds());
+            }
+        }
+
+        totals
+            .into_iter()
+            .map(|((key, currency), total)| Self {
+                key,
+                currency,
+                sales_count: total.sales_count,
+                volume: format_units(total.volume, Unit::Ether),
+                fees: format_units(total.fees, Unit::Ether),
+                royalties: format_units(total.royalties, Unit::Ether),
+                proceeds: format_units(total.proceeds, Unit::Ether),
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct Total {
+    sales_count: u64,
+    volume: U256,
+    fees: U256,
+    royalties: U256,
+    proceeds: U256,
+}
+
+// 通貨のdecimalsは18以下なので、18桁の固定小数として足し引きする
+fn amount_of(value: &str) -> U256 {
+    parse_units(value, Unit::Ether).unwrap_or_default()
+}
+
+fn basis_points_of(value: &str, basis_points: u64) -> String {
+    format_units(
+        amount_of(value) * U256::from(basis_points) / U256::from(10000),
+        Unit::Ether,
+    )
+}
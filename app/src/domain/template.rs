@@ -0,0 +1,344 @@
+use crate::domain::work::{Thumbnail, Work, WorkAttribute};
+use crate::{AppError, AppResult};
+use handlebars::{Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// コレクション単位のmetadataのテンプレート
+///
+/// Handlebarsの書式で作品の項目を埋め込める。`{{default work.description "既定値"}}` で空のときの値を指定できる
+/// - work.id, work.title, work.description, work.background_color
+/// - edition: コレクション内での通し番号, supply: 発行数
+/// - `{{thumbnail N}}`: N番目(0始まり)のサムネイルの公開URL, thumbnail_count
+/// - `{{attribute "X"}}`: 作品のattributesのうちtrait_typeがXの値
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MetadataTemplate {
+    pub name: String,
+    pub description: String,
+    pub external_url: String,
+    /// 作品のattributesの後ろに追加する
+    #[serde(default)]
+    pub attributes: Vec<WorkAttribute>,
+}
+
+impl Default for MetadataTemplate {
+    fn default() -> Self {
+        Self {
+            name: "{{work.title}}".to_string(),
+            description: "{{default work.description \"nft from canvas-nft-server\"}}".to_string(),
+            external_url: "https://canvas-329810.web.app/{{work.id}}".to_string(),
+            attributes: vec![],
+        }
+    }
+}
+
+/// テンプレートを展開した結果
+#[derive(Clone, Debug)]
+pub struct RenderedMetadata {
+    pub name: String,
+    pub description: String,
+    pub external_url: String,
+    pub attributes: Vec<WorkAttribute>,
+}
+
+/// テンプレートに渡す値
+pub struct TemplateValues {
+    data: Value,
+}
+
+impl TemplateValues {
+    pub fn new(work: &Work, thumbnails: &[Thumbnail], edition: u64, supply: u64) -> Self {
+        let mut thumbnails = thumbnails.to_vec();
+        thumbnails.sort_by_key(|v| v.order);
+
+        let attributes: serde_json::Map<String, Value> = work
+            .attributes
+            .iter()
+            .map(|v| (v.trait_type.clone(), Value::String(v.value.clone())))
+            .collect();
+
+        Self {
+            data: json!({
+                "work": {
+                    "id": work.id,
+                    "title": work.display_name(),
+                    "description": work.description,
+                    "background_color": work.background_color.clone().unwrap_or_default(),
+                },
+                "edition": edition,
+                "supply": supply,
+                "thumbnail_count": thumbnails.len(),
+                "thumbnails": thumbnails.iter().map(|v| v.public_url()).collect::<Vec<String>>(),
+                "attributes": attributes,
+            }),
+        }
+    }
+
+    /// テンプレートの書式の確認用に、すべての項目を空にしたもの
+    pub fn empty() -> Self {
+        Self {
+            data: json!({
+                "work": {
+                    "id": "",
+                    "title": "",
+                    "description": "",
+                    "background_color": "",
+                },
+                "edition": "",
+                "supply": "",
+                "thumbnail_count": "",
+                "thumbnails": [],
+                "attributes": {},
+            }),
+        }
+    }
+}
+
+impl MetadataTemplate {
+    /// 未知の項目や閉じていないタグがないか確かめる
+    pub fn validate(&self) -> AppResult<()> {
+        self.render(&TemplateValues::empty()).map(|_| ())
+    }
+
+    /// いずれかの項目がkeyの値によって変わるか
+    pub fn uses(&self, key: &str) -> bool {
+        let mut probe = TemplateValues::empty();
+        if let Some(value) = probe
+            .data
+            .pointer_mut(&format!("/{}", key.replace('.', "/")))
+        {
+            *value = Value::String("\u{1}".to_string());
+        }
+
+        let hb = registry();
+        self.fields().any(|v| {
+            let before = hb.render_template(v, &TemplateValues::empty().data).ok();
+            let after = hb.render_template(v, &probe.data).ok();
+            before != after
+        })
+    }
+
+    pub fn render(&self, values: &TemplateValues) -> AppResult<RenderedMetadata> {
+        let hb = registry();
+        let render = |template: &str| -> AppResult<String> {
+            hb.render_template(template, &values.data).map_err(|e| {
+                AppError::BadRequest(format!("テンプレートを展開できません: {}: {}", template, e))
+            })
+        };
+
+        let mut attributes: Vec<WorkAttribute> = vec![];
+        for attribute in &self.attributes {
+            attributes.push(WorkAttribute {
+                trait_type: render(&attribute.trait_type)?,
+                value: render(&attribute.value)?,
+                display_type: attribute.display_type.clone(),
+            });
+        }
+
+        Ok(RenderedMetadata {
+            name: render(&self.name)?,
+            description: render(&self.description)?,
+            external_url: render(&self.external_url)?,
+            attributes,
+        })
+    }
+
+    fn fields(&self) -> impl Iterator<Item = &String> {
+        [&self.name, &self.description, &self.external_url]
+            .into_iter()
+            .chain(
+                self.attributes
+                    .iter()
+                    .flat_map(|v| [&v.trait_type, &v.value]),
+            )
+    }
+}
+
+fn registry() -> Handlebars<'static> {
+    let mut hb = Handlebars::new();
+    // 未知の項目はエラーにする
+    hb.set_strict_mode(true);
+    // HTMLではないのでエスケープしない
+    hb.register_escape_fn(handlebars::no_escape);
+    hb.register_helper("default", Box::new(default_helper));
+    hb.register_helper("attribute", Box::new(attribute_helper));
+    hb.register_helper("thumbnail", Box::new(thumbnail_helper));
+    hb
+}
+
+fn param<'a>(h: &'a Helper, index: usize, name: &str) -> Result<&'a Value, RenderError> {
+    h.param(index)
+        .map(|v| v.value())
+        .ok_or_else(|| RenderError::new(format!("{}の引数が足りません", name)))
+}
+
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::Null => "".to_string(),
+        Value::String(v) => v.clone(),
+        v => v.to_string(),
+    }
+}
+
+/// `{{default value "既定値"}}`: valueが空なら既定値
+fn default_helper(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let value = as_text(param(h, 0, "default")?);
+    if value.is_empty() {
+        out.write(&as_text(param(h, 1, "default")?))?;
+    } else {
+        out.write(&value)?;
+    }
+    Ok(())
+}
+
+/// `{{attribute "X"}}`: 作品ごとに有無が変わるので、ないときは空にする
+fn attribute_helper(
+    h: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let name = as_text(param(h, 0, "attribute")?);
+    if let Some(value) = ctx.data()["attributes"].get(&name) {
+        out.write(&as_text(value))?;
+    }
+    Ok(())
+}
+
+/// `{{thumbnail N}}`: ないときは空にする
+fn thumbnail_helper(
+    h: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let index = param(h, 0, "thumbnail")?
+        .as_u64()
+        .ok_or_else(|| RenderError::new("thumbnailの引数は0以上の整数で指定してください"))?;
+    if let Some(value) = ctx.data()["thumbnails"].get(index as usize) {
+        out.write(&as_text(value))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::work::WorkStatus;
+
+    fn work() -> Work {
+        Work {
+            id: "w1".to_string(),
+            video_path: "gs://bucket/w1.mp4".to_string(),
+            status: WorkStatus::PublishNFT,
+            royalty: None,
+            title: "作品".to_string(),
+            description: "".to_string(),
+            attributes: vec![WorkAttribute {
+                trait_type: "Hair Color".to_string(),
+                value: "red".to_string(),
+                display_type: None,
+            }],
+            background_color: None,
+        }
+    }
+
+    fn thumbnails() -> Vec<Thumbnail> {
+        vec![
+            Thumbnail {
+                id: "t2".to_string(),
+                work_id: "w1".to_string(),
+                image_path: "gs://bucket/w1/2.png".to_string(),
+                order: 2,
+            },
+            Thumbnail {
+                id: "t1".to_string(),
+                work_id: "w1".to_string(),
+                image_path: "https://example.com/w1/1.png".to_string(),
+                order: 1,
+            },
+        ]
+    }
+
+    #[test]
+    fn renders_work_fields() {
+        let template = MetadataTemplate {
+            name: "{{work.title}} #{{edition}}/{{supply}}".to_string(),
+            description: "{{default work.description \"a | b\"}}".to_string(),
+            external_url: "{{thumbnail 1}}".to_string(),
+            attributes: vec![WorkAttribute {
+                trait_type: "Hair".to_string(),
+                value: "{{attribute \"Hair Color\"}}{{attribute \"Eyes\"}} & {{thumbnail_count}}"
+                    .to_string(),
+                display_type: None,
+            }],
+        };
+        let rendered = template
+            .render(&TemplateValues::new(&work(), &thumbnails(), 3, 10))
+            .unwrap();
+
+        assert_eq!(rendered.name, "作品 #3/10");
+        // 既定値に|を含めても分割しない
+        assert_eq!(rendered.description, "a | b");
+        assert_eq!(
+            rendered.external_url,
+            "https://storage.googleapis.com/bucket/w1/2.png"
+        );
+        // 値をHTMLエスケープしない
+        assert_eq!(rendered.attributes[0].value, "red & 2");
+    }
+
+    #[test]
+    fn renders_default_template() {
+        let rendered = MetadataTemplate::default()
+            .render(&TemplateValues::new(&work(), &[], 1, 1))
+            .unwrap();
+        assert_eq!(rendered.name, "作品");
+        assert_eq!(rendered.description, "nft from canvas-nft-server");
+        assert_eq!(rendered.external_url, "https://canvas-329810.web.app/w1");
+    }
+
+    #[test]
+    fn rejects_invalid_template() {
+        let with_name = |name: &str| MetadataTemplate {
+            name: name.to_string(),
+            ..Default::default()
+        };
+
+        assert!(with_name("{{work.title}} {{thumbnail 0}}")
+            .validate()
+            .is_ok());
+        assert!(with_name("{{work.unknown}}").validate().is_err());
+        assert!(with_name("{{owner}}").validate().is_err());
+        assert!(with_name("{{work.title").validate().is_err());
+        assert!(with_name("{{#if supply}}x").validate().is_err());
+    }
+
+    #[test]
+    fn detects_used_keys() {
+        let template = MetadataTemplate {
+            name: "{{work.title}} #{{edition}}".to_string(),
+            ..Default::default()
+        };
+        assert!(template.uses("edition"));
+        assert!(!template.uses("supply"));
+
+        let template = MetadataTemplate {
+            attributes: vec![WorkAttribute {
+                trait_type: "Supply".to_string(),
+                value: "{{#if supply}}limited{{/if}}".to_string(),
+                display_type: None,
+            }],
+            ..Default::default()
+        };
+        assert!(template.uses("supply"));
+    }
+}
@@ -0,0 +1,29 @@
+use std::str::FromStr;
+use strum_macros::Display as StrumDisplay;
+use strum_macros::EnumString;
+
+#[derive(PartialEq, Clone, Debug, StrumDisplay, EnumString)]
+pub enum TransactionKind {
+    TransferERC721,
+    TransferERC1155,
+}
+
+impl TransactionKind {
+    pub fn from(str: String) -> Self {
+        TransactionKind::from_str(&str).unwrap()
+    }
+}
+
+/// 送信したトランザクションのレシート（tx hashごとに1件）
+#[derive(Clone, Debug)]
+pub struct TransactionRecord {
+    pub tx_hash: String,
+    pub chain_id: u64,
+    pub kind: TransactionKind,
+    pub work_id: String,
+    pub sender: String,
+    pub success: bool,
+    pub block_number: u64,
+    pub gas_used: u64,
+    pub created_at: i64,
+}
@@ -1,4 +1,5 @@
-use crate::ethereum;
+use crate::domain::amount::Wei;
+use web3::types::U256;
 
 #[derive(Clone, PartialEq)]
 pub enum AuthUser {
@@ -51,19 +52,14 @@ impl User {
 
     pub fn with_balance(
         &self,
-        balance: u128,
-        nft_721_num: u128,
-        nft_1155_num: Vec<(String, u128)>,
+        balance: Wei,
+        nft_721_num: U256,
+        nft_1155_num: Vec<(String, U256)>,
     ) -> UserWithBalance {
-        let amt_unit = "wei";
-        let to_unit = "ether";
-        let map = ethereum::unit::convert(format!("{}", balance).as_str(), &amt_unit);
-        let val = map.get(to_unit).unwrap();
-
         UserWithBalance {
             id: self.id.to_owned(),
             wallet_address: self.wallet_address.to_owned(),
-            balance: val.parse().unwrap_or(0.0),
+            balance,
             nft_721_num,
             nft_1155_num,
         }
@@ -74,7 +70,7 @@ impl User {
 pub struct UserWithBalance {
     pub id: String,
     pub wallet_address: String,
-    pub balance: f64,
-    pub nft_721_num: u128,
-    pub nft_1155_num: Vec<(String, u128)>,
+    pub balance: Wei,
+    pub nft_721_num: U256,
+    pub nft_1155_num: Vec<(String, U256)>,
 }
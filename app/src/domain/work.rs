@@ -1,7 +1,10 @@
 use crate::csv_loader::{parse, CSVParser};
-use crate::AppResult;
+use crate::domain::royalty::Royalty;
+use crate::{AppError, AppResult};
 use csv::StringRecord;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::env;
 use std::str::FromStr;
 use strum_macros::Display as StrumDisplay;
 use strum_macros::EnumString;
@@ -11,6 +14,7 @@ pub enum WorkStatus {
     Prepare,
     PublishNFT,
     SellOrder,
+    Sold,
 }
 
 impl WorkStatus {
@@ -24,6 +28,14 @@ pub struct Work {
     pub id: String,
     pub video_path: String,
     pub status: WorkStatus,
+    /// 未設定ならコレクションの設定に従う
+    pub royalty: Option<Royalty>,
+    /// 空ならIDを表示名にする
+    pub title: String,
+    pub description: String,
+    pub attributes: Vec<WorkAttribute>,
+    /// "#"なしの6桁の16進数
+    pub background_color: Option<String>,
 }
 
 impl Work {
@@ -31,6 +43,92 @@ impl Work {
         self.status = status;
         Ok(())
     }
+
+    pub fn display_name(&self) -> String {
+        if self.title.is_empty() {
+            return self.id.clone();
+        }
+        self.title.clone()
+    }
+
+    /// CSVの取り込みで変わりうる項目のうち、CSVに列があるものだけを上書きする
+    pub fn imported(&mut self, other: ImportedWork) {
+        self.video_path = other.video_path;
+        if let Some(title) = other.title {
+            self.title = title;
+        }
+        if let Some(description) = other.description {
+            self.description = description;
+        }
+        if let Some(attributes) = other.attributes {
+            self.attributes = attributes;
+        }
+        if let Some(background_color) = other.background_color {
+            self.background_color = background_color;
+        }
+    }
+}
+
+/// CSVの1行。任意の列は、列がなければNone（既存の値を残す）、空なら空の値になる
+#[derive(Clone, Debug)]
+pub struct ImportedWork {
+    pub id: String,
+    pub video_path: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub attributes: Option<Vec<WorkAttribute>>,
+    pub background_color: Option<Option<String>>,
+}
+
+impl ImportedWork {
+    pub fn into_work(self) -> Work {
+        Work {
+            id: self.id,
+            video_path: self.video_path,
+            status: WorkStatus::Prepare,
+            royalty: None,
+            title: self.title.unwrap_or_default(),
+            description: self.description.unwrap_or_default(),
+            attributes: self.attributes.unwrap_or_default(),
+            background_color: self.background_color.flatten(),
+        }
+    }
+}
+
+/// OpenSeaのmetadataのattributesの1項目
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkAttribute {
+    pub trait_type: String,
+    pub value: String,
+    /// number, boost_number, boost_percentage, date など
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_type: Option<String>,
+}
+
+impl WorkAttribute {
+    /// CSVやDynamoDBにはJSONの配列で持つ
+    pub fn parse_list(value: &str) -> AppResult<Vec<Self>> {
+        if value.is_empty() {
+            return Ok(vec![]);
+        }
+        serde_json::from_str(value)
+            .map_err(|e| AppError::BadRequest(format!("attributesのパースに失敗しました: {}", e)))
+    }
+
+    pub fn format_list(attributes: &[Self]) -> String {
+        serde_json::to_string(attributes).unwrap_or_default()
+    }
+}
+
+pub fn validate_background_color(value: &str) -> AppResult<String> {
+    let value = value.trim_start_matches('#');
+    if value.len() != 6 || !value.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::BadRequest(format!(
+            "background_colorは6桁の16進数で指定してください: {}",
+            value
+        )));
+    }
+    Ok(value.to_lowercase())
 }
 
 #[derive(Clone, Debug)]
@@ -41,15 +139,53 @@ pub struct Thumbnail {
     pub order: i32,
 }
 
+impl Thumbnail {
+    /// metadataに載せる公開URL。gs://はTHUMBNAIL_PUBLIC_BASE_URLの下のURLにする
+    pub fn public_url(&self) -> String {
+        match self.image_path.strip_prefix("gs://") {
+            Some(path) => format!(
+                "{}/{}",
+                env::var("THUMBNAIL_PUBLIC_BASE_URL")
+                    .unwrap_or_else(|_| "https://storage.googleapis.com".to_string())
+                    .trim_end_matches('/'),
+                path
+            ),
+            None => self.image_path.clone(),
+        }
+    }
+}
+
 impl CSVParser for Work {
+    fn from(header: HashMap<&str, usize>, record: StringRecord) -> AppResult<Box<Self>> {
+        let data = <ImportedWork as CSVParser>::from(header, record)?;
+        Ok(Box::new(data.into_work()))
+    }
+}
+
+impl CSVParser for ImportedWork {
     fn from(header: HashMap<&str, usize>, record: StringRecord) -> AppResult<Box<Self>> {
         let id = parse(&header, &record, "ID")?;
         let video_path = parse(&header, &record, "VideoPath")?;
+        // 以下は任意の列
+        let title = parse(&header, &record, "Title").ok();
+        let description = parse(&header, &record, "Description").ok();
+        let attributes = match parse(&header, &record, "Attributes") {
+            Ok(v) => Some(WorkAttribute::parse_list(&v)?),
+            Err(_) => None,
+        };
+        let background_color = match parse(&header, &record, "BackgroundColor") {
+            Ok(v) if !v.is_empty() => Some(Some(validate_background_color(&v)?)),
+            Ok(_) => Some(None),
+            Err(_) => None,
+        };
 
         let data = Self {
             id,
             video_path,
-            status: WorkStatus::Prepare,
+            title,
+            description,
+            attributes,
+            background_color,
         };
         return Ok(Box::new(data));
     }
@@ -71,3 +207,60 @@ impl CSVParser for Thumbnail {
         return Ok(Box::new(data));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv_loader::load_from_csv;
+    use bytes::Bytes;
+
+    fn existing() -> Work {
+        Work {
+            id: "w1".to_string(),
+            video_path: "gs://bucket/old.mp4".to_string(),
+            status: WorkStatus::PublishNFT,
+            royalty: None,
+            title: "タイトル".to_string(),
+            description: "説明".to_string(),
+            attributes: vec![WorkAttribute {
+                trait_type: "Color".to_string(),
+                value: "Red".to_string(),
+                display_type: None,
+            }],
+            background_color: Some("ffffff".to_string()),
+        }
+    }
+
+    fn imported(csv: &str) -> ImportedWork {
+        load_from_csv::<ImportedWork>(Bytes::from(csv.to_string()), None)
+            .unwrap()
+            .remove(0)
+    }
+
+    #[test]
+    fn keeps_fields_without_columns() {
+        let mut work = existing();
+        work.imported(imported("ID,VideoPath\nw1,gs://bucket/new.mp4\n"));
+
+        assert_eq!(work.video_path, "gs://bucket/new.mp4");
+        assert_eq!(work.title, "タイトル");
+        assert_eq!(work.description, "説明");
+        assert_eq!(work.attributes.len(), 1);
+        assert_eq!(work.background_color.as_deref(), Some("ffffff"));
+        assert_eq!(work.status, WorkStatus::PublishNFT);
+    }
+
+    #[test]
+    fn overwrites_fields_with_columns() {
+        let mut work = existing();
+        work.imported(imported(
+            "ID,VideoPath,Title,Attributes,BackgroundColor\nw1,gs://bucket/old.mp4,新しいタイトル,,#00FF00\n",
+        ));
+
+        assert_eq!(work.title, "新しいタイトル");
+        // 列が空なら消す
+        assert!(work.attributes.is_empty());
+        assert_eq!(work.background_color.as_deref(), Some("00ff00"));
+        assert_eq!(work.description, "説明");
+    }
+}
@@ -1,26 +1,105 @@
+pub mod address;
+pub mod bindings;
+pub mod chain;
+pub mod deploy;
+pub mod ens;
 mod erc1155;
+mod erc165;
+mod erc20;
+mod erc2981;
 pub mod erc721;
+#[cfg(test)]
+pub mod mock;
+pub mod multicall;
+pub mod provenance;
+pub mod seaport;
+pub mod token_uri;
+pub mod transport;
+pub mod tx;
 pub mod unit;
 mod wallet;
 
-use std::env;
-use web3::types::Address;
+use crate::domain::contract::DeployedContract;
+use crate::ethereum::chain::{ChainProfile, ERC1155_CONTRACT_NAME, ERC721_CONTRACT_NAME};
+use crate::ethereum::transport::{EndpointMetrics, FailoverTransport, RetryConfig};
+use crate::{ddb, AppError, AppResult};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+use web3::types::{Address, U256};
 use web3::*;
 
 #[derive(Clone, Debug)]
 pub struct Client {
-    cli: web3::Web3<transports::Http>,
+    cli: web3::Web3<FailoverTransport>,
+    transport: FailoverTransport,
+    chain: ChainProfile,
+    multicall_available: Arc<OnceCell<bool>>,
 }
 
 impl Client {
     pub fn new() -> Self {
-        let base_url = env::var("ETHEREUM_URL").expect("should set ethereum url");
-        let transport = transports::Http::new(&base_url)
-            .ok()
+        Self::with_chain(ChainProfile::from_env())
+    }
+
+    /// 環境変数で指定のないコントラクトのアドレスを、deploy-contractの台帳から解決する
+    /// どちらも見つからなければ、使う側で処理を失敗させられるようにエラーを返す
+    pub async fn from_registry() -> AppResult<Self> {
+        let mut chain = ChainProfile::from_env();
+        if !chain.has_contracts() {
+            let dao: ddb::Dao<DeployedContract> = ddb::Dao::new().await;
+            let mut contracts: Vec<DeployedContract> = vec![];
+            for name in [ERC721_CONTRACT_NAME, ERC1155_CONTRACT_NAME] {
+                match dao.get(chain.chain_id, name).await {
+                    Ok(v) => contracts.push(v),
+                    Err(AppError::NotFound) => {}
+                    Err(e) => {
+                        return Err(AppError::Internal(format!(
+                            "failed to load contract registry: {:?}",
+                            e
+                        )))
+                    }
+                }
+            }
+            chain = chain.with_registry(&contracts);
+        }
+        if !chain.has_contracts() {
+            return Err(AppError::Internal(
+                "should set contract address or deploy contracts".to_string(),
+            ));
+        }
+        Ok(Self::with_chain(chain))
+    }
+
+    pub fn with_chain(chain: ChainProfile) -> Self {
+        let transport = FailoverTransport::new(&chain.rpc_urls, RetryConfig::from_env())
             .expect("should set ethereum url");
-        let cli = Web3::new(transport);
+        let cli = Web3::new(transport.clone());
+
+        Client {
+            cli,
+            transport,
+            chain,
+            multicall_available: Arc::new(OnceCell::new()),
+        }
+    }
+
+    pub fn chain(&self) -> &ChainProfile {
+        &self.chain
+    }
+
+    pub fn rpc_metrics(&self) -> Vec<EndpointMetrics> {
+        self.transport.metrics()
+    }
 
-        Client { cli }
+    pub async fn validate_chain_id(&self) -> AppResult<()> {
+        let chain_id = self.cli.eth().chain_id().await?;
+        if chain_id != U256::from(self.chain.chain_id) {
+            return Err(AppError::Internal(format!(
+                "chain id mismatch: expected {}, rpc returned {}",
+                self.chain.chain_id, chain_id
+            )));
+        }
+        Ok(())
     }
 
     pub fn parse_address(&self, address: String) -> Option<Address> {
@@ -41,3 +120,15 @@ impl Client {
         return a1.unwrap() == b1.unwrap();
     }
 }
+
+/// 起動時に設定したチェーンとRPCの `eth_chainId` が一致するかを検証する
+pub async fn validate_chain() -> AppResult<()> {
+    let cli = Client::new();
+    cli.validate_chain_id().await?;
+    println!(
+        "ethereum chain: {} ({})",
+        cli.chain().name,
+        cli.chain().chain_id
+    );
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::{AppError, AppResult};
+use web3::signing::keccak256;
+use web3::types::Address;
+
+/// EIP-55のチェックサム付きの表記
+pub fn to_checksum(address: &Address) -> String {
+    let hex = format!("{:x}", address);
+    let hash = keccak256(hex.as_bytes());
+
+    let mut out = String::from("0x");
+    for (i, c) in hex.chars().enumerate() {
+        let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+        if c.is_ascii_alphabetic() && nibble >= 8 {
+            out.push(c.to_ascii_uppercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// 大文字・小文字が混在している場合はEIP-55のチェックサムとして検証する
+pub fn parse_checksummed(value: &str) -> AppResult<Address> {
+    let hex = value
+        .strip_prefix("0x")
+        .ok_or_else(|| AppError::BadRequest(format!("invalid address: {}", value)))?;
+    if hex.len() != 40 {
+        return Err(AppError::BadRequest(format!("invalid address: {}", value)));
+    }
+    let address: Address = hex
+        .parse()
+        .map_err(|_e| AppError::BadRequest(format!("invalid address: {}", value)))?;
+
+    let mixed =
+        hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    if mixed && to_checksum(&address) != value {
+        return Err(AppError::BadRequest(format!(
+            "address checksum mismatch: {}",
+            value
+        )));
+    }
+    Ok(address)
+}
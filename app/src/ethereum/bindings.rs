@@ -0,0 +1,172 @@
+use crate::{AppError, AppResult};
+use std::time::Duration;
+use std::vec::IntoIter;
+use web3::contract::tokens::{Detokenize, Tokenizable};
+use web3::contract::Options;
+use web3::ethabi::{Contract as Abi, Function, RawLog, Token};
+use web3::signing::Key;
+use web3::types::{Address, Bytes, CallRequest, Log, TransactionParameters, TransactionReceipt};
+use web3::{Transport, Web3};
+
+// build.rsがABIから生成する型付きのコントラクト
+pub mod canvas1155 {
+    include!(concat!(env!("OUT_DIR"), "/canvas_erc1155.rs"));
+}
+pub mod canvas721 {
+    include!(concat!(env!("OUT_DIR"), "/canvas_erc721.rs"));
+}
+
+/// 生成したバインディングから使う、ABIのエンコード・送信の共通部分
+/// オーバーロードされた関数は名前と宣言順のindexで区別する
+#[derive(Clone, Debug)]
+pub struct ContractInstance<T: Transport> {
+    web3: Web3<T>,
+    address: Address,
+    abi: Abi,
+    confirmations: usize,
+}
+
+impl<T: Transport> ContractInstance<T> {
+    pub fn new(
+        web3: Web3<T>,
+        address: Address,
+        abi_json: &[u8],
+        confirmations: usize,
+    ) -> AppResult<Self> {
+        Ok(Self {
+            web3,
+            address,
+            abi: Abi::load(abi_json)?,
+            confirmations,
+        })
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    fn function(&self, name: &str, index: usize) -> AppResult<&Function> {
+        self.abi
+            .functions_by_name(name)?
+            .get(index)
+            .ok_or_else(|| AppError::Internal(format!("function not found: {}#{}", name, index)))
+    }
+
+    pub fn encode(&self, name: &str, index: usize, params: Vec<Token>) -> AppResult<Vec<u8>> {
+        Ok(self.function(name, index)?.encode_input(&params)?)
+    }
+
+    pub fn decode<R: Detokenize>(&self, name: &str, index: usize, data: &[u8]) -> AppResult<R> {
+        let tokens = self.function(name, index)?.decode_output(data)?;
+        Ok(R::from_tokens(tokens)?)
+    }
+
+    pub async fn query<R: Detokenize>(
+        &self,
+        name: &str,
+        index: usize,
+        params: Vec<Token>,
+    ) -> AppResult<R> {
+        let data = self.encode(name, index, params)?;
+        let result = self
+            .web3
+            .eth()
+            .call(
+                CallRequest {
+                    to: Some(self.address),
+                    data: Some(Bytes(data)),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+        self.decode(name, index, &result.0)
+    }
+
+    /// 署名して送信し、確認数を待つ。gasの指定がなければ見積もる
+    pub async fn send<K: Key>(
+        &self,
+        name: &str,
+        index: usize,
+        params: Vec<Token>,
+        options: Options,
+        key: K,
+    ) -> AppResult<TransactionReceipt> {
+        let data = Bytes(self.encode(name, index, params)?);
+        let gas = match options.gas {
+            Some(gas) => gas,
+            None => {
+                self.web3
+                    .eth()
+                    .estimate_gas(
+                        CallRequest {
+                            from: Some(key.address()),
+                            to: Some(self.address),
+                            value: options.value,
+                            data: Some(data.clone()),
+                            ..Default::default()
+                        },
+                        None,
+                    )
+                    .await?
+            }
+        };
+
+        let signed = self
+            .web3
+            .accounts()
+            .sign_transaction(
+                TransactionParameters {
+                    nonce: options.nonce,
+                    to: Some(self.address),
+                    gas,
+                    gas_price: options.gas_price,
+                    value: options.value.unwrap_or_default(),
+                    data,
+                    transaction_type: options.transaction_type,
+                    access_list: options.access_list,
+                    ..Default::default()
+                },
+                key,
+            )
+            .await?;
+
+        let receipt = self
+            .web3
+            .send_raw_transaction_with_confirmation(
+                signed.raw_transaction,
+                Duration::from_secs(1),
+                self.confirmations,
+            )
+            .await?;
+        Ok(receipt)
+    }
+
+    /// このコントラクトの指定したイベントのログであれば、パラメーターを宣言順で返す
+    pub fn decode_log(&self, name: &str, log: &Log) -> AppResult<Option<IntoIter<Token>>> {
+        let event = self.abi.event(name)?;
+        if log.address != self.address || log.topics.first() != Some(&event.signature()) {
+            return Ok(None);
+        }
+
+        let parsed = event.parse_log(RawLog {
+            topics: log.topics.clone(),
+            data: log.data.0.clone(),
+        })?;
+        Ok(Some(
+            parsed
+                .params
+                .into_iter()
+                .map(|v| v.value)
+                .collect::<Vec<Token>>()
+                .into_iter(),
+        ))
+    }
+}
+
+pub fn next_param<R: Tokenizable>(params: &mut IntoIter<Token>) -> AppResult<R> {
+    let token = params
+        .next()
+        .ok_or_else(|| AppError::Internal("missing event param".to_string()))?;
+    Ok(R::from_token(token)?)
+}
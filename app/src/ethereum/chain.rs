@@ -0,0 +1,288 @@
+use crate::domain::contract::DeployedContract;
+use std::env;
+
+// 既存環境（rinkeby）との互換のため、CHAIN_ID未設定時のデフォルト
+const DEFAULT_CHAIN_ID: u64 = 4;
+// Multicall3は主要チェーンで同じアドレスにデプロイされている
+const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+// ENSのレジストリはENSがあるチェーンで同じアドレス
+const ENS_REGISTRY_ADDRESS: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+// Seaport 1.5も同様に主要チェーンで同じアドレス
+const SEAPORT_ADDRESS: &str = "0x00000000000000ADc04C56Bf30aC9d3c0aAF14dC";
+// deploy-contractで台帳に記録するコントラクト名
+pub const ERC721_CONTRACT_NAME: &str = "Canvas721";
+pub const ERC1155_CONTRACT_NAME: &str = "Canvas1155";
+
+#[derive(Clone, Debug)]
+pub struct ChainProfile {
+    pub chain_id: u64,
+    pub name: String,
+    pub rpc_urls: Vec<String>,
+    pub explorer_url: String,
+    pub native_currency: String,
+    pub confirmations: usize,
+    pub open_sea_chain: String,
+    pub open_sea_base_url: String,
+    pub erc721_contract_address: String,
+    pub erc1155_contract_address: String,
+    pub multicall_address: String,
+    pub seaport_address: String,
+    pub ens_registry_address: Option<String>,
+}
+
+struct Preset {
+    chain_id: u64,
+    name: &'static str,
+    explorer_url: &'static str,
+    native_currency: &'static str,
+    confirmations: usize,
+    open_sea_chain: &'static str,
+    open_sea_base_url: &'static str,
+    ens: bool,
+}
+
+const PRESETS: &[Preset] = &[
+    Preset {
+        chain_id: 1,
+        name: "mainnet",
+        explorer_url: "https://etherscan.io",
+        native_currency: "ETH",
+        confirmations: 3,
+        open_sea_chain: "ethereum",
+        open_sea_base_url: "https://api.opensea.io",
+        ens: true,
+    },
+    Preset {
+        chain_id: 4,
+        name: "rinkeby",
+        explorer_url: "https://rinkeby.etherscan.io",
+        native_currency: "ETH",
+        confirmations: 1,
+        open_sea_chain: "rinkeby",
+        open_sea_base_url: "https://testnets-api.opensea.io",
+        ens: true,
+    },
+    Preset {
+        chain_id: 5,
+        name: "goerli",
+        explorer_url: "https://goerli.etherscan.io",
+        native_currency: "ETH",
+        confirmations: 1,
+        open_sea_chain: "goerli",
+        open_sea_base_url: "https://testnets-api.opensea.io",
+        ens: true,
+    },
+    Preset {
+        chain_id: 11155111,
+        name: "sepolia",
+        explorer_url: "https://sepolia.etherscan.io",
+        native_currency: "ETH",
+        confirmations: 1,
+        open_sea_chain: "sepolia",
+        open_sea_base_url: "https://testnets-api.opensea.io",
+        ens: true,
+    },
+    Preset {
+        chain_id: 137,
+        name: "polygon",
+        explorer_url: "https://polygonscan.com",
+        native_currency: "MATIC",
+        confirmations: 10,
+        open_sea_chain: "matic",
+        open_sea_base_url: "https://api.opensea.io",
+        ens: false,
+    },
+    Preset {
+        chain_id: 80001,
+        name: "mumbai",
+        explorer_url: "https://mumbai.polygonscan.com",
+        native_currency: "MATIC",
+        confirmations: 3,
+        open_sea_chain: "mumbai",
+        open_sea_base_url: "https://testnets-api.opensea.io",
+        ens: false,
+    },
+    // ローカルでのデプロイ確認用（ganache / anvil）
+    Preset {
+        chain_id: 1337,
+        name: "ganache",
+        explorer_url: "",
+        native_currency: "ETH",
+        confirmations: 0,
+        open_sea_chain: "ganache",
+        open_sea_base_url: "https://testnets-api.opensea.io",
+        ens: false,
+    },
+    Preset {
+        chain_id: 31337,
+        name: "anvil",
+        explorer_url: "",
+        native_currency: "ETH",
+        confirmations: 0,
+        open_sea_chain: "anvil",
+        open_sea_base_url: "https://testnets-api.opensea.io",
+        ens: false,
+    },
+];
+
+/// 環境変数 CHAIN_ID で選択したチェーン
+pub fn configured_chain_id() -> u64 {
+    env::var("CHAIN_ID")
+        .map(|v| v.parse().expect("should set valid chain id"))
+        .unwrap_or(DEFAULT_CHAIN_ID)
+}
+
+impl ChainProfile {
+    /// CHAIN_IDで選択したプリセットに、環境変数の設定を重ねて読み込む
+    /// チェーンごとの値は `{KEY}_{CHAIN_ID}` を優先し、なければ `{KEY}` を参照する
+    pub fn from_env() -> Self {
+        let chain_id = configured_chain_id();
+        let preset = PRESETS.iter().find(|v| v.chain_id == chain_id);
+
+        let rpc_urls: Vec<String> = env_for_chain("ETHEREUM_URL", chain_id)
+            .expect("should set ethereum url")
+            .split(',')
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect();
+        if rpc_urls.is_empty() {
+            panic!("should set ethereum url");
+        }
+
+        let confirmations = env_for_chain("ETHEREUM_CONFIRMATIONS", chain_id)
+            .map(|v| v.parse().expect("should set valid confirmations"))
+            .or_else(|| preset.map(|v| v.confirmations))
+            .unwrap_or(1);
+
+        Self {
+            chain_id,
+            name: preset
+                .map(|v| v.name.to_string())
+                .unwrap_or_else(|| format!("chain-{}", chain_id)),
+            rpc_urls,
+            explorer_url: env_for_chain("EXPLORER_URL", chain_id)
+                .or_else(|| preset.map(|v| v.explorer_url.to_string()))
+                .unwrap_or_default(),
+            native_currency: env_for_chain("NATIVE_CURRENCY", chain_id)
+                .or_else(|| preset.map(|v| v.native_currency.to_string()))
+                .unwrap_or_else(|| "ETH".to_string()),
+            confirmations,
+            open_sea_chain: env_for_chain("OPEN_SEA_CHAIN", chain_id)
+                .or_else(|| preset.map(|v| v.open_sea_chain.to_string()))
+                .unwrap_or_else(|| format!("chain-{}", chain_id)),
+            open_sea_base_url: env_for_chain("OPEN_SEA_BASE_URL", chain_id)
+                .or_else(|| preset.map(|v| v.open_sea_base_url.to_string()))
+                .expect("should set open sea base url"),
+            // 未設定ならデプロイの台帳から補う（with_registry）
+            erc721_contract_address: env_for_chain("ERC721_CONTRACT_ADDRESS", chain_id)
+                .unwrap_or_default(),
+            erc1155_contract_address: env_for_chain("ERC1155_CONTRACT_ADDRESS", chain_id)
+                .unwrap_or_default(),
+            multicall_address: env_for_chain("MULTICALL_ADDRESS", chain_id)
+                .unwrap_or_else(|| MULTICALL3_ADDRESS.to_string()),
+            seaport_address: env_for_chain("SEAPORT_ADDRESS", chain_id)
+                .unwrap_or_else(|| SEAPORT_ADDRESS.to_string()),
+            ens_registry_address: env_for_chain("ENS_REGISTRY_ADDRESS", chain_id).or_else(|| {
+                preset
+                    .filter(|v| v.ens)
+                    .map(|_v| ENS_REGISTRY_ADDRESS.to_string())
+            }),
+        }
+    }
+
+    pub fn tx_url(&self, tx_hash: &str) -> String {
+        format!("{}/tx/{}", self.explorer_url, tx_hash)
+    }
+
+    /// 環境変数で指定されていないコントラクトのアドレスを、台帳の検証済みのデプロイから埋める
+    pub fn with_registry(mut self, contracts: &[DeployedContract]) -> Self {
+        let lookup = |name: &str| {
+            contracts
+                .iter()
+                .find(|v| v.chain_id == self.chain_id && v.name == name && v.verified)
+                .map(|v| v.address.clone())
+        };
+        if self.erc721_contract_address.is_empty() {
+            self.erc721_contract_address = lookup(ERC721_CONTRACT_NAME).unwrap_or_default();
+        }
+        if self.erc1155_contract_address.is_empty() {
+            self.erc1155_contract_address = lookup(ERC1155_CONTRACT_NAME).unwrap_or_default();
+        }
+        self
+    }
+
+    pub fn has_contracts(&self) -> bool {
+        !self.erc721_contract_address.is_empty() && !self.erc1155_contract_address.is_empty()
+    }
+}
+
+fn env_for_chain(key: &str, chain_id: u64) -> Option<String> {
+    env::var(format!("{}_{}", key, chain_id))
+        .or_else(|_| env::var(key))
+        .ok()
+}
+
+#[cfg(test)]
+impl ChainProfile {
+    /// テスト用のローカルチェーン。コントラクトのアドレスは空
+    pub fn for_test(rpc_url: &str, chain_id: u64) -> Self {
+        Self {
+            chain_id,
+            name: "test".to_string(),
+            rpc_urls: vec![rpc_url.to_string()],
+            explorer_url: "".to_string(),
+            native_currency: "ETH".to_string(),
+            confirmations: 0,
+            open_sea_chain: "test".to_string(),
+            open_sea_base_url: "http://127.0.0.1".to_string(),
+            erc721_contract_address: "".to_string(),
+            erc1155_contract_address: "".to_string(),
+            multicall_address: MULTICALL3_ADDRESS.to_string(),
+            seaport_address: SEAPORT_ADDRESS.to_string(),
+            ens_registry_address: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployed(chain_id: u64, name: &str, address: &str, verified: bool) -> DeployedContract {
+        DeployedContract {
+            chain_id,
+            name: name.to_string(),
+            address: address.to_string(),
+            deployer: "".to_string(),
+            tx_hash: "".to_string(),
+            block_number: 1,
+            code_hash: "".to_string(),
+            verified,
+        }
+    }
+
+    #[test]
+    fn fills_missing_addresses_from_registry() {
+        let chain = ChainProfile::for_test("http://127.0.0.1:8545", 31337).with_registry(&[
+            deployed(31337, ERC721_CONTRACT_NAME, "0x721", true),
+            deployed(31337, ERC1155_CONTRACT_NAME, "0x1155", true),
+        ]);
+        assert_eq!(chain.erc721_contract_address, "0x721");
+        assert_eq!(chain.erc1155_contract_address, "0x1155");
+        assert!(chain.has_contracts());
+    }
+
+    #[test]
+    fn prefers_configured_addresses_and_skips_unverified() {
+        let mut chain = ChainProfile::for_test("http://127.0.0.1:8545", 31337);
+        chain.erc721_contract_address = "0xenv".to_string();
+        let chain = chain.with_registry(&[
+            deployed(31337, ERC721_CONTRACT_NAME, "0x721", true),
+            deployed(31337, ERC1155_CONTRACT_NAME, "0x1155", false),
+            deployed(1, ERC1155_CONTRACT_NAME, "0xother", true),
+        ]);
+        assert_eq!(chain.erc721_contract_address, "0xenv");
+        assert_eq!(chain.erc1155_contract_address, "");
+        assert!(!chain.has_contracts());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::ethereum::Client;
+use crate::{AppError, AppResult};
+use secp256k1::SecretKey;
+use serde::Deserialize;
+use std::str::FromStr;
+use std::time::Duration;
+use web3::signing::{keccak256, Key, SecretKeyRef};
+use web3::types::{Address, BlockNumber, Bytes, CallRequest, TransactionParameters, H256, U256};
+
+/// truffle compileで生成されるビルド成果物（build/contracts/*.json）
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    pub contract_name: String,
+    pub bytecode: Bytes,
+    pub deployed_bytecode: Bytes,
+}
+
+impl Artifact {
+    pub fn from_slice(data: &[u8]) -> AppResult<Self> {
+        let artifact: Self = serde_json::from_slice(data)?;
+        if artifact.bytecode.0.is_empty() || artifact.deployed_bytecode.0.is_empty() {
+            return Err(AppError::BadRequest(format!(
+                "artifact has no bytecode: {}",
+                artifact.contract_name
+            )));
+        }
+        Ok(artifact)
+    }
+
+    pub fn deployed_code_hash(&self) -> H256 {
+        H256::from(keccak256(&self.deployed_bytecode.0))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Deployment {
+    pub address: Address,
+    pub deployer: Address,
+    pub nonce: U256,
+    pub tx_hash: H256,
+    pub block_number: u64,
+}
+
+impl Client {
+    /// CREATEで作られるコントラクトのアドレス: keccak256(rlp([sender, nonce]))の下位20byte
+    pub fn contract_address_of(&self, sender: Address, nonce: U256) -> Address {
+        let mut nonce_bytes = [0u8; 32];
+        nonce.to_big_endian(&mut nonce_bytes);
+        let nonce_bytes: Vec<u8> = nonce_bytes
+            .iter()
+            .skip_while(|v| **v == 0)
+            .copied()
+            .collect();
+
+        let mut payload = vec![0x80 + 20];
+        payload.extend_from_slice(sender.as_bytes());
+        match nonce_bytes.as_slice() {
+            [] => payload.push(0x80),
+            [v] if *v < 0x80 => payload.push(*v),
+            v => {
+                payload.push(0x80 + v.len() as u8);
+                payload.extend_from_slice(v);
+            }
+        }
+
+        // payloadは最大でも56byte未満なので短いリストの形式でよい
+        let mut encoded = vec![0xc0 + payload.len() as u8];
+        encoded.extend_from_slice(&payload);
+
+        Address::from_slice(&keccak256(&encoded)[12..])
+    }
+
+    /// 署名者の次のnonceからアドレスを事前に確定させてデプロイし、確認数を待つ
+    pub async fn deploy_contract(
+        &self,
+        artifact: &Artifact,
+        secret: &str,
+    ) -> AppResult<Deployment> {
+        let key = SecretKey::from_str(secret)
+            .map_err(|e| AppError::BadRequest(format!("invalid signer key: {}", e)))?;
+        let key = SecretKeyRef::from(&key);
+        let deployer = key.address();
+
+        let nonce = self
+            .cli
+            .eth()
+            .transaction_count(deployer, Some(BlockNumber::Pending))
+            .await?;
+        let expected = self.contract_address_of(deployer, nonce);
+        println!(
+            "deploy {} from {:?} (nonce {}), expected address: {:?}",
+            artifact.contract_name, deployer, nonce, expected
+        );
+
+        let gas = self
+            .cli
+            .eth()
+            .estimate_gas(
+                CallRequest {
+                    from: Some(deployer),
+                    data: Some(artifact.bytecode.clone()),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+        let gas_price = self.cli.eth().gas_price().await?;
+
+        let signed = self
+            .cli
+            .accounts()
+            .sign_transaction(
+                TransactionParameters {
+                    nonce: Some(nonce),
+                    to: None,
+                    // 見積もりぴったりだと失敗することがあるので2割上乗せする
+                    gas: gas * 12 / 10,
+                    gas_price: Some(gas_price),
+                    data: artifact.bytecode.clone(),
+                    chain_id: Some(self.chain.chain_id),
+                    ..Default::default()
+                },
+                key,
+            )
+            .await?;
+
+        let receipt = self
+            .cli
+            .send_raw_transaction_with_confirmation(
+                signed.raw_transaction,
+                Duration::from_secs(1),
+                self.chain.confirmations,
+            )
+            .await?;
+        println!(
+            "tx: {}",
+            self.chain
+                .tx_url(&format!("{:?}", receipt.transaction_hash))
+        );
+
+        if receipt.status != Some(1.into()) {
+            return Err(AppError::Internal(format!(
+                "deploy transaction failed: {:?}",
+                receipt.transaction_hash
+            )));
+        }
+        let address = receipt
+            .contract_address
+            .ok_or_else(|| AppError::Internal("receipt has no contract address".to_string()))?;
+        if address != expected {
+            return Err(AppError::Internal(format!(
+                "deployed address mismatch: expected {:?}, got {:?}",
+                expected, address
+            )));
+        }
+
+        Ok(Deployment {
+            address,
+            deployer,
+            nonce,
+            tx_hash: receipt.transaction_hash,
+            block_number: receipt.block_number.unwrap_or_default().as_u64(),
+        })
+    }
+
+    /// チェーン上のコードのハッシュを返す。コードがなければNotFound
+    pub async fn deployed_code_hash(&self, address: Address) -> AppResult<H256> {
+        let code = self.cli.eth().code(address, None).await?;
+        if code.0.is_empty() {
+            return Err(AppError::NotFound);
+        }
+        Ok(H256::from(keccak256(&code.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::chain::ChainProfile;
+    use std::env;
+
+    // anvilの最初のアカウント（公開されているテスト用の鍵）
+    const ANVIL_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    /// 実行時コード 0x00 を返すだけの初期化コード
+    fn artifact() -> Artifact {
+        Artifact {
+            contract_name: "Stop".to_string(),
+            bytecode: Bytes(hex_literal::hex!("6001600c60003960016000f300").to_vec()),
+            deployed_bytecode: Bytes(vec![0x00]),
+        }
+    }
+
+    #[test]
+    fn predicts_create_address() {
+        let cli = Client::with_chain(ChainProfile::for_test("http://127.0.0.1:8545", 31337));
+        let sender: Address = "6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0".parse().unwrap();
+        let cases = [
+            (0u64, "cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"),
+            (1, "343c43a37d37dff08ae8c4a11544c718abb4fcf8"),
+            (2, "f778b86fa74e846c4f0a1fbd1335fe81c00a0c91"),
+            (3, "fffd933a0bc612844eaf0c6fe3e5b8e9b6c1d19c"),
+        ];
+        for (nonce, expected) in cases {
+            let expected: Address = expected.parse().unwrap();
+            assert_eq!(cli.contract_address_of(sender, U256::from(nonce)), expected);
+        }
+    }
+
+    #[test]
+    fn rejects_artifact_without_bytecode() {
+        let data = br#"{"contractName":"Empty","bytecode":"0x","deployedBytecode":"0x"}"#;
+        assert!(matches!(
+            Artifact::from_slice(data),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    /// ローカルのanvil / ganacheにデプロイする
+    /// ETHEREUM_TEST_URL=http://127.0.0.1:8545 cargo test -p app -- --ignored deploys_to_local_node
+    #[tokio::test]
+    #[ignore]
+    async fn deploys_to_local_node() {
+        let url =
+            env::var("ETHEREUM_TEST_URL").unwrap_or_else(|_| "http://127.0.0.1:8545".to_string());
+        let chain_id: u64 = env::var("ETHEREUM_TEST_CHAIN_ID")
+            .map(|v| v.parse().unwrap())
+            .unwrap_or(31337);
+        let key = env::var("ETHEREUM_TEST_KEY").unwrap_or_else(|_| ANVIL_KEY.to_string());
+        let cli = Client::with_chain(ChainProfile::for_test(&url, chain_id));
+        cli.validate_chain_id().await.unwrap();
+
+        let artifact = artifact();
+        let deployment = cli.deploy_contract(&artifact, &key).await.unwrap();
+
+        assert_eq!(
+            deployment.address,
+            cli.contract_address_of(deployment.deployer, deployment.nonce)
+        );
+        assert_eq!(
+            cli.deployed_code_hash(deployment.address).await.unwrap(),
+            artifact.deployed_code_hash()
+        );
+    }
+}
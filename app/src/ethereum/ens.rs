@@ -0,0 +1,85 @@
+use crate::ethereum::address::parse_checksummed;
+use crate::ethereum::Client;
+use crate::{AppError, AppResult};
+use web3::ethabi::{encode, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, CallRequest, H256};
+
+/// ENSのnamehash
+pub fn namehash(name: &str) -> H256 {
+    let mut node = [0u8; 32];
+    for label in name.rsplit('.').filter(|v| !v.is_empty()) {
+        let mut data = node.to_vec();
+        data.extend_from_slice(&keccak256(label.to_lowercase().as_bytes()));
+        node = keccak256(&data);
+    }
+    H256::from(node)
+}
+
+impl Client {
+    /// アドレスかENS名（xxx.eth）を受け取り、送り先のアドレスにする
+    pub async fn resolve_address(&self, value: &str) -> AppResult<Address> {
+        let value = value.trim();
+        if value.starts_with("0x") {
+            return parse_checksummed(value);
+        }
+        if !value.contains('.') {
+            return Err(AppError::BadRequest(format!("invalid address: {}", value)));
+        }
+        self.resolve_ens(value).await
+    }
+
+    pub async fn resolve_ens(&self, name: &str) -> AppResult<Address> {
+        let registry = self
+            .chain
+            .ens_registry_address
+            .clone()
+            .and_then(|v| self.parse_address(v))
+            .ok_or_else(|| {
+                AppError::BadRequest(format!("{}ではENSを利用できません", self.chain.name))
+            })?;
+        let node = namehash(name);
+
+        let resolver = self
+            .call_address(registry, "resolver(bytes32)", node)
+            .await?;
+        if resolver.is_zero() {
+            return Err(AppError::BadRequest(format!(
+                "ENS名が見つかりません: {}",
+                name
+            )));
+        }
+
+        let address = self.call_address(resolver, "addr(bytes32)", node).await?;
+        if address.is_zero() {
+            return Err(AppError::BadRequest(format!(
+                "ENS名にアドレスが設定されていません: {}",
+                name
+            )));
+        }
+        println!("resolved ens: {} -> {:?}", name, address);
+        Ok(address)
+    }
+
+    async fn call_address(&self, to: Address, signature: &str, node: H256) -> AppResult<Address> {
+        let mut data = keccak256(signature.as_bytes())[..4].to_vec();
+        data.extend(encode(&[Token::FixedBytes(node.as_bytes().to_vec())]));
+
+        let result = self
+            .cli
+            .eth()
+            .call(
+                CallRequest {
+                    to: Some(to),
+                    data: Some(Bytes(data)),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+        if result.0.len() < 32 {
+            return Ok(Address::zero());
+        }
+        Ok(Address::from_slice(&result.0[12..32]))
+    }
+}
@@ -1,70 +1,110 @@
+use crate::domain::royalty::Royalty;
 use crate::domain::user::User;
+use crate::ethereum::bindings::canvas1155::{Canvas1155, Canvas1155Event};
+use crate::ethereum::erc2981::royalty_args;
+use crate::ethereum::multicall::Call;
+use crate::ethereum::transport::FailoverTransport;
+use crate::ethereum::tx::TokenTransfer;
 use crate::ethereum::Client;
-use crate::AppResult;
+use crate::{AppError, AppResult};
 use secp256k1::SecretKey;
-use std::env;
 use std::str::FromStr;
-use web3::contract::{Contract, Options};
-use web3::signing::SecretKeyRef;
-use web3::transports::Http;
-use web3::types::U256;
+use web3::contract::Options;
+use web3::signing::{Key, SecretKeyRef};
+use web3::types::{Address, TransactionReceipt, U256};
 
 impl Client {
-    fn erc1155(&self) -> AppResult<Contract<Http>> {
-        let contract_address =
-            env::var("ERC1155_CONTRACT_ADDRESS").expect("should set contract address");
-        let contract = Contract::from_json(
-            self.cli.eth(),
-            self.parse_address(contract_address.clone()).unwrap(),
-            include_bytes!("canvas_erc1155.abi.json"),
-        )?;
-        Ok(contract)
-    }
-
-    pub async fn get_erc1155_nft_balance(&self, user: &User) -> AppResult<Vec<(String, u128)>> {
+    pub fn erc1155(&self) -> AppResult<Canvas1155<FailoverTransport>> {
+        Canvas1155::new(
+            self.cli.clone(),
+            self.parse_address(self.chain.erc1155_contract_address.clone())
+                .unwrap(),
+            self.chain.confirmations,
+        )
+    }
+
+    pub async fn get_erc1155_nft_balance(&self, user: &User) -> AppResult<Vec<(String, U256)>> {
         let contract = self.erc1155()?;
+        let owner = self.parse_address(user.wallet_address.to_owned()).unwrap();
 
-        let result = contract.query("usedTokenNames", (), None, Options::default(), None);
-        let names: Vec<String> = result.await?;
+        let names = contract.used_token_names().await?;
+        if names.is_empty() {
+            return Ok(vec![]);
+        }
 
-        let mut balances: Vec<(String, u128)> = vec![];
-        for name in names {
-            let result = contract.query("tokenIdOf", name.clone(), None, Options::default(), None);
-            let token_id: u128 = result.await?;
+        let token_ids = self.erc1155_token_ids_of(&contract, &names).await?;
 
-            let result = contract.query(
-                "balanceOf",
-                (
-                    self.parse_address(user.wallet_address.to_owned()).unwrap(),
-                    token_id,
-                ),
-                None,
-                Options::default(),
-                None,
-            );
-            let balance_of: U256 = result.await?;
-            balances.push((name.to_owned(), balance_of.to_owned().as_u128()))
+        // 発行数に関わらず1回のRPCで残高を取得する
+        let balances = contract
+            .balance_of_batch(vec![owner; token_ids.len()], token_ids)
+            .await?;
+
+        Ok(names.into_iter().zip(balances).collect())
+    }
+
+    pub async fn get_erc1155_balance_of(&self, user: &User, token_id: u128) -> AppResult<u64> {
+        self.get_erc1155_balance_at(
+            self.parse_address(user.wallet_address.to_owned()).unwrap(),
+            U256::from(token_id),
+        )
+        .await
+    }
+
+    pub async fn get_erc1155_balance_at(&self, owner: Address, token_id: U256) -> AppResult<u64> {
+        let contract = self.erc1155()?;
+        let balance = contract.balance_of(owner, token_id).await?;
+
+        u64::try_from(balance)
+            .map_err(|_e| AppError::Internal(format!("balance overflow: {}", balance)))
+    }
+
+    async fn erc1155_token_ids_of(
+        &self,
+        contract: &Canvas1155<FailoverTransport>,
+        names: &[String],
+    ) -> AppResult<Vec<U256>> {
+        if !self.multicall_available().await? {
+            let mut token_ids: Vec<U256> = vec![];
+            for name in names {
+                token_ids.push(contract.token_id_of(name.clone()).await?);
+            }
+            return Ok(token_ids);
         }
 
-        Ok(balances)
+        let calls = names
+            .iter()
+            .map(|name| {
+                Ok(Call {
+                    target: contract.address(),
+                    data: contract.encode_token_id_of(name.to_owned())?,
+                })
+            })
+            .collect::<AppResult<Vec<Call>>>()?;
+
+        let mut token_ids: Vec<U256> = vec![];
+        for (name, data) in names.iter().zip(self.aggregate(calls).await?) {
+            let data = data.ok_or_else(|| {
+                AppError::Internal(format!("tokenIdOf failed in multicall: {}", name))
+            })?;
+            token_ids.push(contract.decode_token_id_of(&data)?);
+        }
+
+        Ok(token_ids)
     }
 
     pub async fn get_erc1155_used_names(&self) -> AppResult<Vec<String>> {
         let contract = self.erc1155()?;
-        let result = contract.query("usedTokenNames", (), None, Options::default(), None);
-        let names: Vec<String> = result.await?;
-
-        Ok(names)
+        contract.used_token_names().await
     }
 
     pub async fn get_erc1155_token_id_of(&self, work_id: String) -> AppResult<u128> {
         let contract = self.erc1155()?;
-        let result = contract.query("tokenIdOf", work_id, None, Options::default(), None);
-        let id: u128 = result.await?;
+        let id = contract.token_id_of(work_id).await?;
 
-        Ok(id)
+        u128::try_from(id).map_err(|_e| AppError::Internal(format!("token id overflow: {}", id)))
     }
 
+    /// mintし、レシートのTransferSingle/TransferBatchを返す
     pub async fn mint_erc1155(
         &self,
         user: &User,
@@ -72,35 +112,313 @@ impl Client {
         amount: u32,
         ipfs_hash: String,
         s3_key: String,
-    ) -> AppResult<()> {
+        royalty: Option<&Royalty>,
+    ) -> AppResult<(TransactionReceipt, Vec<TokenTransfer>)> {
         let contract = self.erc1155()?;
         let prev_key = SecretKey::from_str(&user.wallet_secret).unwrap();
         let gas_limit: i64 = 5500000;
         let gas_price: i64 = 35000000000;
+        let (royalty_receiver, royalty_basis_points) = royalty_args(royalty)?;
 
         let result = contract
-            .signed_call_with_confirmations(
-                "mint",
-                (
-                    self.parse_address(user.wallet_address.to_owned()).unwrap(),
-                    work_id,
-                    amount,
-                    ipfs_hash,
-                    s3_key,
-                ),
+            .mint(
+                SecretKeyRef::from(&prev_key),
                 Options::with(|opt| {
                     opt.gas = Some(U256::from(gas_limit));
                     opt.gas_price = Some(U256::from(gas_price));
                 }),
-                1,
-                SecretKeyRef::from(&prev_key),
+                self.parse_address(user.wallet_address.to_owned()).unwrap(),
+                work_id,
+                U256::from(amount),
+                ipfs_hash,
+                s3_key,
+                royalty_receiver,
+                royalty_basis_points,
+            )
+            .await?;
+
+        // revertしたmintを成功扱いにしない
+        self.ensure_success(&result)?;
+
+        let transfers = erc1155_transfers(&contract, &result)?;
+        Ok((result, transfers))
+    }
+
+    /// 発行者のウォレットからsafeTransferFromで転送し、レシートのTransferSingle/TransferBatchを返す
+    pub async fn transfer_erc1155(
+        &self,
+        user: &User,
+        to: Address,
+        token_id: u128,
+        amount: u64,
+    ) -> AppResult<(TransactionReceipt, Vec<TokenTransfer>)> {
+        let contract = self.erc1155()?;
+        let key = SecretKey::from_str(&user.wallet_secret)
+            .map_err(|e| AppError::Internal(format!("invalid wallet secret: {}", e)))?;
+        let from = SecretKeyRef::from(&key).address();
+
+        let receipt = contract
+            .safe_transfer_from(
+                SecretKeyRef::from(&key),
+                self.tx_options(from).await?,
+                from,
+                to,
+                U256::from(token_id),
+                U256::from(amount),
+                vec![],
             )
             .await?;
+        self.ensure_success(&receipt)?;
+
+        let transfers = erc1155_transfers(&contract, &receipt)?;
+        Ok((receipt, transfers))
+    }
+}
+
+fn erc1155_transfers(
+    contract: &Canvas1155<FailoverTransport>,
+    receipt: &TransactionReceipt,
+) -> AppResult<Vec<TokenTransfer>> {
+    let mut transfers: Vec<TokenTransfer> = vec![];
+    for log in &receipt.logs {
+        match contract.decode_event(log)? {
+            Some(Canvas1155Event::TransferSingle(event)) => transfers.push(TokenTransfer {
+                from: event.from,
+                to: event.to,
+                token_id: event.id,
+                amount: event.value,
+            }),
+            Some(Canvas1155Event::TransferBatch(event)) => {
+                for (id, value) in event.ids.into_iter().zip(event.values) {
+                    transfers.push(TokenTransfer {
+                        from: event.from,
+                        to: event.to,
+                        token_id: id,
+                        amount: value,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(transfers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::chain::ChainProfile;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use serde_json::{json, Value};
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+    use web3::ethabi::{Contract as Abi, Function, Token};
+    use web3::signing::keccak256;
+    use web3::types::Bytes;
+
+    const CONTRACT: &str = "0x00000000000000000000000000000000000001a1";
+    const OWNER: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+
+    /// 発行済みの名前を返し、tokenIdOfはa=1,b=2以外をrevertし、残高はtoken id * 10を返すサーバー
+    #[derive(Clone)]
+    struct Mock {
+        deployed: bool,
+        names: Vec<&'static str>,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Mock {
+        fn start(deployed: bool, names: Vec<&'static str>) -> (Self, String) {
+            let mock = Self {
+                deployed,
+                names,
+                calls: Arc::new(Mutex::new(vec![])),
+            };
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let state = mock.clone();
+            let make = make_service_fn(move |_| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(service_fn(move |req| state.clone().handle(req))) }
+            });
+            tokio::spawn(Server::from_tcp(listener).unwrap().serve(make));
+            (mock, url)
+        }
+
+        async fn handle(self, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            let body: Value = serde_json::from_slice(&body).unwrap();
+
+            let res = match body["method"].as_str().unwrap() {
+                "eth_getCode" => {
+                    json!({"result": if self.deployed { "0x6080" } else { "0x" }})
+                }
+                "eth_call" => {
+                    let to = body["params"][0]["to"].as_str().unwrap().to_string();
+                    let data: Bytes =
+                        serde_json::from_value(body["params"][0]["data"].clone()).unwrap();
+                    if to != CONTRACT {
+                        self.calls.lock().unwrap().push("aggregate3".to_string());
+                        json!({"result": Bytes(self.aggregate3(&data.0))})
+                    } else {
+                        self.calls.lock().unwrap().push(function(&data.0).name);
+                        match self.call(&data.0) {
+                            Some(v) => json!({"result": Bytes(v)}),
+                            None => json!({"error": {"code": 3, "message": "execution reverted"}}),
+                        }
+                    }
+                }
+                v => panic!("unexpected method: {}", v),
+            };
+
+            let mut res = res;
+            res["jsonrpc"] = json!("2.0");
+            res["id"] = body["id"].clone();
+            Ok(Response::new(Body::from(res.to_string())))
+        }
+
+        fn call(&self, data: &[u8]) -> Option<Vec<u8>> {
+            let function = function(data);
+            let input = function.decode_input(&data[4..]).unwrap();
+
+            let output = match function.name.as_str() {
+                "usedTokenNames" => Token::Array(
+                    self.names
+                        .iter()
+                        .map(|v| Token::String(v.to_string()))
+                        .collect(),
+                ),
+                "tokenIdOf" => match input[0].clone().into_string().unwrap().as_str() {
+                    "a" => Token::Uint(U256::from(1)),
+                    "b" => Token::Uint(U256::from(2)),
+                    _ => return None,
+                },
+                "balanceOfBatch" => {
+                    let owners = input[0].clone().into_array().unwrap();
+                    let ids = input[1].clone().into_array().unwrap();
+                    assert_eq!(owners.len(), ids.len());
+                    Token::Array(
+                        ids.into_iter()
+                            .map(|v| Token::Uint(v.into_uint().unwrap() * 10))
+                            .collect(),
+                    )
+                }
+                v => panic!("unexpected function: {}", v),
+            };
+            Some(web3::ethabi::encode(&[output]))
+        }
+
+        /// 各callをそのまま実行した結果をaggregate3の戻り値にする
+        fn aggregate3(&self, data: &[u8]) -> Vec<u8> {
+            let abi = Abi::load(&include_bytes!("multicall3.abi.json")[..]).unwrap();
+            let function = abi.function("aggregate3").unwrap();
+            let calls = function.decode_input(&data[4..]).unwrap().remove(0);
+
+            let results = calls
+                .into_array()
+                .unwrap()
+                .into_iter()
+                .map(|v| {
+                    let data = match v {
+                        Token::Tuple(v) => {
+                            assert_eq!(v[1], Token::Bool(true));
+                            v[2].clone().into_bytes().unwrap()
+                        }
+                        v => panic!("unexpected call: {:?}", v),
+                    };
+                    match self.call(&data) {
+                        Some(v) => Token::Tuple(vec![Token::Bool(true), Token::Bytes(v)]),
+                        None => Token::Tuple(vec![Token::Bool(false), Token::Bytes(vec![])]),
+                    }
+                })
+                .collect();
+            web3::ethabi::encode(&[Token::Array(results)])
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    fn function(data: &[u8]) -> Function {
+        let abi = Abi::load(&include_bytes!("canvas_erc1155.abi.json")[..]).unwrap();
+        abi.functions()
+            .find(|v| {
+                let types: Vec<String> = v.inputs.iter().map(|v| v.kind.to_string()).collect();
+                keccak256(format!("{}({})", v.name, types.join(",")).as_bytes())[..4] == data[..4]
+            })
+            .unwrap()
+            .clone()
+    }
+
+    fn client(url: &str) -> Client {
+        Client::with_chain(ChainProfile {
+            erc1155_contract_address: CONTRACT.to_string(),
+            ..ChainProfile::for_test(url, 1)
+        })
+    }
+
+    fn user() -> User {
+        User::new("owner".to_string(), OWNER.to_string(), "".to_string())
+    }
+
+    #[tokio::test]
+    async fn gets_balances_with_multicall() {
+        let (mock, url) = Mock::start(true, vec!["a", "b"]);
+
+        let balances = client(&url).get_erc1155_nft_balance(&user()).await.unwrap();
+        assert_eq!(
+            balances,
+            vec![
+                ("a".to_string(), U256::from(10)),
+                ("b".to_string(), U256::from(20)),
+            ]
+        );
+        // tokenIdOfはまとめて1回で呼ぶ
+        assert_eq!(
+            mock.calls(),
+            vec!["usedTokenNames", "aggregate3", "balanceOfBatch"]
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_without_multicall() {
+        let (mock, url) = Mock::start(false, vec!["a", "b"]);
+
+        let balances = client(&url).get_erc1155_nft_balance(&user()).await.unwrap();
+        assert_eq!(
+            balances,
+            vec![
+                ("a".to_string(), U256::from(10)),
+                ("b".to_string(), U256::from(20)),
+            ]
+        );
+        assert_eq!(
+            mock.calls(),
+            vec!["usedTokenNames", "tokenIdOf", "tokenIdOf", "balanceOfBatch"]
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_on_failed_sub_call() {
+        let (mock, url) = Mock::start(true, vec!["a", "broken"]);
+
+        let err = client(&url).get_erc1155_nft_balance(&user()).await;
+        assert!(
+            matches!(&err, Err(AppError::Internal(v)) if v.contains("broken")),
+            "{:?}",
+            err
+        );
+        assert_eq!(mock.calls(), vec!["usedTokenNames", "aggregate3"]);
+    }
 
-        println!("tx id: {:?}", result.transaction_type);
-        println!("gas used: {:?}", result.gas_used);
-        println!("status: {:?}", result.status);
+    #[tokio::test]
+    async fn empty_without_tokens() {
+        let (mock, url) = Mock::start(true, vec![]);
 
-        Ok(())
+        let balances = client(&url).get_erc1155_nft_balance(&user()).await.unwrap();
+        assert!(balances.is_empty());
+        assert_eq!(mock.calls(), vec!["usedTokenNames"]);
     }
 }
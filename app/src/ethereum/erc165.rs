@@ -0,0 +1,124 @@
+use crate::domain::market::TokenSchema;
+use crate::ethereum::Client;
+use crate::{AppError, AppResult};
+use jsonrpc_core::ErrorCode;
+use web3::ethabi::{encode, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, CallRequest};
+
+const INTERFACE_ERC721: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+const INTERFACE_ERC1155: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+impl Client {
+    /// ERC165のsupportsInterfaceでトークンの規格を判定する
+    pub async fn detect_token_schema(&self, contract: Address) -> AppResult<TokenSchema> {
+        if self.supports_interface(contract, INTERFACE_ERC721).await? {
+            return Ok(TokenSchema::Erc721);
+        }
+        if self.supports_interface(contract, INTERFACE_ERC1155).await? {
+            return Ok(TokenSchema::Erc1155);
+        }
+        Err(AppError::BadRequest(format!(
+            "ERC721/ERC1155のコントラクトではありません: {:?}",
+            contract
+        )))
+    }
+
+    async fn supports_interface(
+        &self,
+        contract: Address,
+        interface_id: [u8; 4],
+    ) -> AppResult<bool> {
+        let mut data = keccak256("supportsInterface(bytes4)".as_bytes())[..4].to_vec();
+        data.extend(encode(&[Token::FixedBytes(interface_id.to_vec())]));
+
+        // 未対応のコントラクトはrevertするので、実行の失敗だけを非対応として扱う
+        let result = match self
+            .cli
+            .eth()
+            .call(
+                CallRequest {
+                    to: Some(contract),
+                    data: Some(Bytes(data)),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+        {
+            Ok(v) => v,
+            Err(e) if is_execution_error(&e) => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(result.0.len() >= 32 && result.0[31] == 1)
+    }
+}
+
+/// ノードがeth_callを実行して失敗したか。接続の失敗やノード自体のエラーは含めない
+fn is_execution_error(err: &web3::Error) -> bool {
+    match err {
+        web3::Error::Rpc(e) => {
+            e.code == ErrorCode::ServerError(3)
+                || e.message.contains("revert")
+                || e.message.contains("invalid opcode")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::mock::{revert, MockChain};
+    use serde_json::json;
+
+    const CONTRACT: &str = "0x00000000000000000000000000000000000000c1";
+
+    fn supports(interface_id: &'static str) -> MockChain {
+        MockChain::start(move |method, params| {
+            assert_eq!(method, "eth_call");
+            let data = params[0]["data"].as_str().unwrap();
+            // supportsInterface(bytes4)のselectorは0x01ffc9a7
+            assert!(data.starts_with("0x01ffc9a7"), "{}", data);
+            if data[10..18] == *interface_id {
+                Ok(json!(format!("0x{:064x}", 1)))
+            } else {
+                Ok(json!(format!("0x{:064x}", 0)))
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn detects_token_schema() {
+        let contract: Address = CONTRACT.parse().unwrap();
+
+        let chain = supports("80ac58cd");
+        let schema = chain.client().detect_token_schema(contract).await.unwrap();
+        assert_eq!(schema, TokenSchema::Erc721);
+
+        let chain = supports("d9b67a26");
+        let schema = chain.client().detect_token_schema(contract).await.unwrap();
+        assert_eq!(schema, TokenSchema::Erc1155);
+    }
+
+    #[tokio::test]
+    async fn treats_revert_as_unsupported() {
+        let chain = MockChain::start(|_, _| Err(revert("")));
+        let res = chain
+            .client()
+            .detect_token_schema(CONTRACT.parse().unwrap())
+            .await;
+        assert!(matches!(res, Err(AppError::BadRequest(_))), "{:?}", res);
+    }
+
+    #[tokio::test]
+    async fn propagates_node_error() {
+        let chain =
+            MockChain::start(|_, _| Err(json!({"code": -32000, "message": "header not found"})));
+        let res = chain
+            .client()
+            .detect_token_schema(CONTRACT.parse().unwrap())
+            .await;
+        assert!(matches!(res, Err(AppError::Internal(_))), "{:?}", res);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::ethereum::Client;
+use crate::{AppError, AppResult};
+use secp256k1::SecretKey;
+use std::str::FromStr;
+use web3::ethabi::{decode, encode, ParamType, Token};
+use web3::signing::{keccak256, Key, SecretKeyRef};
+use web3::types::{Address, Bytes, CallRequest, U256};
+
+impl Client {
+    /// 支払いに使われるERC20の symbol と decimals
+    pub async fn get_erc20_metadata(&self, token: Address) -> AppResult<(String, usize)> {
+        let symbol = self.call_erc20(token, "symbol()").await?;
+        let symbol = match decode(&[ParamType::String], &symbol).ok().as_deref() {
+            Some([Token::String(v)]) => v.clone(),
+            _ => {
+                return Err(AppError::Internal(format!(
+                    "invalid erc20 symbol: {:?}",
+                    token
+                )))
+            }
+        };
+
+        let decimals = self.call_erc20(token, "decimals()").await?;
+        if decimals.len() < 32 {
+            return Err(AppError::Internal(format!(
+                "invalid erc20 decimals: {:?}",
+                token
+            )));
+        }
+        Ok((
+            symbol,
+            U256::from_big_endian(&decimals[..32]).low_u64() as usize,
+        ))
+    }
+
+    pub async fn get_erc20_balance(&self, token: Address, owner: Address) -> AppResult<U256> {
+        let result = self
+            .call_erc20_with(token, "balanceOf(address)", &[Token::Address(owner)])
+            .await?;
+        parse_uint(token, &result)
+    }
+
+    /// ownerがspenderに引き出しを認めている額
+    pub async fn get_erc20_allowance(
+        &self,
+        token: Address,
+        owner: Address,
+        spender: Address,
+    ) -> AppResult<U256> {
+        let result = self
+            .call_erc20_with(
+                token,
+                "allowance(address,address)",
+                &[Token::Address(owner), Token::Address(spender)],
+            )
+            .await?;
+        parse_uint(token, &result)
+    }
+
+    /// spenderが引き出せる額がamountに満たなければ、amountだけapproveを送る
+    pub async fn ensure_erc20_allowance(
+        &self,
+        token: Address,
+        secret: &str,
+        spender: Address,
+        amount: U256,
+    ) -> AppResult<()> {
+        let key = SecretKey::from_str(secret)
+            .map_err(|e| AppError::BadRequest(format!("invalid signer key: {}", e)))?;
+        let key = SecretKeyRef::from(&key);
+        if self
+            .get_erc20_allowance(token, key.address(), spender)
+            .await?
+            >= amount
+        {
+            return Ok(());
+        }
+
+        let mut data = keccak256(b"approve(address,uint256)")[..4].to_vec();
+        data.extend(encode(&[Token::Address(spender), Token::Uint(amount)]));
+        self.send_call(key, token, Bytes(data), U256::zero())
+            .await?;
+        println!("approved: {:?} -> {:?}, {}", token, spender, amount);
+        Ok(())
+    }
+
+    async fn call_erc20(&self, token: Address, signature: &str) -> AppResult<Vec<u8>> {
+        self.call_erc20_with(token, signature, &[]).await
+    }
+
+    async fn call_erc20_with(
+        &self,
+        token: Address,
+        signature: &str,
+        args: &[Token],
+    ) -> AppResult<Vec<u8>> {
+        let mut data = keccak256(signature.as_bytes())[..4].to_vec();
+        data.extend(encode(args));
+        let result = self
+            .cli
+            .eth()
+            .call(
+                CallRequest {
+                    to: Some(token),
+                    data: Some(Bytes(data)),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+        Ok(result.0)
+    }
+}
+
+fn parse_uint(token: Address, result: &[u8]) -> AppResult<U256> {
+    if result.len() < 32 {
+        return Err(AppError::Internal(format!(
+            "invalid erc20 response: {:?}",
+            token
+        )));
+    }
+    Ok(U256::from_big_endian(&result[..32]))
+}
@@ -0,0 +1,186 @@
+use crate::domain::market::TokenSchema;
+use crate::domain::royalty::Royalty;
+use crate::domain::user::User;
+use crate::ethereum::address::parse_checksummed;
+use crate::ethereum::Client;
+use crate::{AppError, AppResult};
+use secp256k1::SecretKey;
+use std::str::FromStr;
+use web3::signing::{Key, SecretKeyRef};
+use web3::types::{Address, U256};
+
+/// mintに渡すロイヤリティの引数。未設定なら率0で、コントラクト側で設定を省く
+pub fn royalty_args(royalty: Option<&Royalty>) -> AppResult<(Address, U256)> {
+    match royalty {
+        Some(royalty) => Ok((
+            parse_checksummed(&royalty.receiver)?,
+            U256::from(royalty.basis_points),
+        )),
+        None => Ok((Address::zero(), U256::zero())),
+    }
+}
+
+impl Client {
+    /// コントラクト全体のロイヤリティ（トークンごとの設定がない場合に使われる）
+    pub async fn set_default_royalty(
+        &self,
+        user: &User,
+        schema: TokenSchema,
+        royalty: &Royalty,
+    ) -> AppResult<()> {
+        let key = SecretKey::from_str(&user.wallet_secret)
+            .map_err(|e| AppError::Internal(format!("invalid wallet secret: {}", e)))?;
+        let from = SecretKeyRef::from(&key).address();
+        let (receiver, basis_points) = royalty_args(Some(royalty))?;
+
+        let receipt = match schema {
+            TokenSchema::Erc721 => {
+                self.erc721()?
+                    .set_default_royalty(
+                        SecretKeyRef::from(&key),
+                        self.tx_options(from).await?,
+                        receiver,
+                        basis_points,
+                    )
+                    .await?
+            }
+            TokenSchema::Erc1155 => {
+                self.erc1155()?
+                    .set_default_royalty(
+                        SecretKeyRef::from(&key),
+                        self.tx_options(from).await?,
+                        receiver,
+                        basis_points,
+                    )
+                    .await?
+            }
+        };
+        self.ensure_success(&receipt)
+    }
+
+    /// mint済みの作品のロイヤリティを変更する
+    pub async fn set_token_royalty(
+        &self,
+        user: &User,
+        schema: TokenSchema,
+        work_id: String,
+        royalty: &Royalty,
+    ) -> AppResult<()> {
+        let key = SecretKey::from_str(&user.wallet_secret)
+            .map_err(|e| AppError::Internal(format!("invalid wallet secret: {}", e)))?;
+        let from = SecretKeyRef::from(&key).address();
+        let (receiver, basis_points) = royalty_args(Some(royalty))?;
+
+        let receipt = match schema {
+            TokenSchema::Erc721 => {
+                self.erc721()?
+                    .set_token_royalty(
+                        SecretKeyRef::from(&key),
+                        self.tx_options(from).await?,
+                        work_id,
+                        receiver,
+                        basis_points,
+                    )
+                    .await?
+            }
+            TokenSchema::Erc1155 => {
+                self.erc1155()?
+                    .set_token_royalty(
+                        SecretKeyRef::from(&key),
+                        self.tx_options(from).await?,
+                        work_id,
+                        receiver,
+                        basis_points,
+                    )
+                    .await?
+            }
+        };
+        self.ensure_success(&receipt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::mock::{decode_call, receipt, MockChain, ERC721_ABI, ERC721_CONTRACT};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use web3::ethabi::Token;
+
+    // anvilの1番目のアカウント
+    const OWNER_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const OWNER: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+    const RECEIVER: &str = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+    const TX_HASH: &str = "0xabababababababababababababababababababababababababababababababab";
+
+    #[test]
+    fn passes_receiver_and_basis_points() {
+        let royalty = Royalty::new(RECEIVER, 500).unwrap();
+        let (receiver, basis_points) = royalty_args(Some(&royalty)).unwrap();
+        assert_eq!(receiver, RECEIVER.parse().unwrap());
+        assert_eq!(basis_points, U256::from(500));
+
+        // 未設定ならコントラクト側で設定を省く
+        let (receiver, basis_points) = royalty_args(None).unwrap();
+        assert!(receiver.is_zero());
+        assert!(basis_points.is_zero());
+    }
+
+    #[test]
+    fn rejects_invalid_royalty() {
+        assert!(matches!(
+            Royalty::new(RECEIVER, 10001),
+            Err(AppError::BadRequest(_))
+        ));
+        // チェックサムが合わない
+        assert!(matches!(
+            Royalty::new("0x70997970c51812dc3a010c7d01b50e0d17dc79C8", 500),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    /// コントラクトに作品IDと受取人、率を渡す
+    #[tokio::test]
+    async fn sends_token_royalty() {
+        let calls = Arc::new(Mutex::new(vec![]));
+        let recorded = calls.clone();
+        let chain = MockChain::start(move |method, params| match method {
+            "eth_estimateGas" => {
+                recorded.lock().unwrap().push(params.clone());
+                Ok(json!("0x30000"))
+            }
+            "eth_getTransactionCount" => Ok(json!("0x0")),
+            "eth_gasPrice" => Ok(json!("0x3b9aca00")),
+            "eth_chainId" => Ok(json!("0x1")),
+            "eth_sendRawTransaction" => Ok(json!(TX_HASH)),
+            "eth_getTransactionReceipt" => Ok(receipt(TX_HASH, OWNER)),
+            v => panic!("unexpected method: {}", v),
+        });
+        let user = User::new(
+            "owner".to_string(),
+            OWNER.to_string(),
+            OWNER_KEY.to_string(),
+        );
+        let royalty = Royalty::new(RECEIVER, 750).unwrap();
+
+        chain
+            .client()
+            .set_token_royalty(&user, TokenSchema::Erc721, "w1".to_string(), &royalty)
+            .await
+            .unwrap();
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        let (to, function, input) = decode_call(ERC721_ABI, &calls[0]);
+        assert_eq!(to, ERC721_CONTRACT);
+        assert_eq!(function.name, "setTokenRoyalty");
+        assert_eq!(
+            input,
+            vec![
+                Token::String("w1".to_string()),
+                Token::Address(RECEIVER.parse().unwrap()),
+                Token::Uint(U256::from(750)),
+            ]
+        );
+    }
+}
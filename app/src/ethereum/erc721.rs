@@ -1,90 +1,126 @@
+use crate::domain::royalty::Royalty;
 use crate::domain::user::User;
+use crate::ethereum::bindings::canvas721::{Canvas721, Canvas721Event};
+use crate::ethereum::erc2981::royalty_args;
+use crate::ethereum::transport::FailoverTransport;
+use crate::ethereum::tx::TokenTransfer;
 use crate::ethereum::Client;
-use crate::AppResult;
+use crate::{AppError, AppResult};
 use secp256k1::SecretKey;
-use std::env;
 use std::str::FromStr;
-use web3::contract::{Contract, Options};
-use web3::signing::SecretKeyRef;
-use web3::transports::Http;
-use web3::types::U256;
+use web3::contract::Options;
+use web3::signing::{Key, SecretKeyRef};
+use web3::types::{Address, TransactionReceipt, U256};
 
 impl Client {
-    fn erc721(&self) -> AppResult<Contract<Http>> {
-        let contract_address =
-            env::var("ERC721_CONTRACT_ADDRESS").expect("should set contract address");
-        let contract = Contract::from_json(
-            self.cli.eth(),
-            self.parse_address(contract_address.clone()).unwrap(),
-            include_bytes!("canvas_erc721.abi.json"),
-        )?;
-        Ok(contract)
+    pub fn erc721(&self) -> AppResult<Canvas721<FailoverTransport>> {
+        Canvas721::new(
+            self.cli.clone(),
+            self.parse_address(self.chain.erc721_contract_address.clone())
+                .unwrap(),
+            self.chain.confirmations,
+        )
     }
 
-    pub async fn get_erc721_nft_balance(&self, user: &User) -> AppResult<u128> {
+    pub async fn get_erc721_nft_balance(&self, user: &User) -> AppResult<U256> {
         let contract = self.erc721()?;
-        let result = contract.query(
-            "balanceOf",
-            self.parse_address(user.wallet_address.to_owned()).unwrap(),
-            None,
-            Options::default(),
-            None,
-        );
-        let balance_of: U256 = result.await?;
-
-        Ok(balance_of.as_u128())
+        contract
+            .balance_of(self.parse_address(user.wallet_address.to_owned()).unwrap())
+            .await
     }
 
     pub async fn get_erc721_used_names(&self) -> AppResult<Vec<String>> {
         let contract = self.erc721()?;
-        let result = contract.query("usedTokenNames", (), None, Options::default(), None);
-        let names: Vec<String> = result.await?;
-
-        Ok(names)
+        contract.used_token_names().await
     }
 
     pub async fn get_erc721_token_id_of(&self, work_id: String) -> AppResult<u128> {
         let contract = self.erc721()?;
-        let result = contract.query("tokenIdOf", work_id, None, Options::default(), None);
-        let id: u128 = result.await?;
+        let id = contract.token_id_of(work_id).await?;
 
-        Ok(id)
+        u128::try_from(id).map_err(|_e| AppError::Internal(format!("token id overflow: {}", id)))
     }
 
+    /// mintし、レシートのTransferを返す
     pub async fn mint_erc721(
         &self,
         user: &User,
         work_id: String,
         ipfs_hash: String,
         s3_key: String,
-    ) -> AppResult<()> {
+        royalty: Option<&Royalty>,
+    ) -> AppResult<(TransactionReceipt, Vec<TokenTransfer>)> {
         let contract = self.erc721()?;
         let prev_key = SecretKey::from_str(&user.wallet_secret).unwrap();
         let gas_limit: i64 = 5500000;
         let gas_price: i64 = 35000000000;
+        let (royalty_receiver, royalty_basis_points) = royalty_args(royalty)?;
 
         let result = contract
-            .signed_call_with_confirmations(
-                "mint",
-                (
-                    self.parse_address(user.wallet_address.to_owned()).unwrap(),
-                    work_id,
-                    ipfs_hash,
-                    s3_key,
-                ),
+            .mint(
+                SecretKeyRef::from(&prev_key),
                 Options::with(|opt| {
                     opt.gas = Some(U256::from(gas_limit));
                     opt.gas_price = Some(U256::from(gas_price));
                 }),
-                1,
-                SecretKeyRef::from(&prev_key),
+                self.parse_address(user.wallet_address.to_owned()).unwrap(),
+                work_id,
+                ipfs_hash,
+                s3_key,
+                royalty_receiver,
+                royalty_basis_points,
+            )
+            .await?;
+
+        // revertしたmintを成功扱いにしない
+        self.ensure_success(&result)?;
+
+        let transfers = erc721_transfers(&contract, &result)?;
+        Ok((result, transfers))
+    }
+
+    /// 発行者のウォレットからsafeTransferFromで転送し、レシートのTransferを返す
+    pub async fn transfer_erc721(
+        &self,
+        user: &User,
+        to: Address,
+        token_id: u128,
+    ) -> AppResult<(TransactionReceipt, Vec<TokenTransfer>)> {
+        let contract = self.erc721()?;
+        let key = SecretKey::from_str(&user.wallet_secret)
+            .map_err(|e| AppError::Internal(format!("invalid wallet secret: {}", e)))?;
+        let from = SecretKeyRef::from(&key).address();
+
+        let receipt = contract
+            .safe_transfer_from(
+                SecretKeyRef::from(&key),
+                self.tx_options(from).await?,
+                from,
+                to,
+                U256::from(token_id),
             )
             .await?;
+        self.ensure_success(&receipt)?;
 
-        println!("tx id: {:?}", result.transaction_type);
-        println!("gas used: {:?}", result.gas_used);
-        println!("status: {:?}", result.status);
+        let transfers = erc721_transfers(&contract, &receipt)?;
+        Ok((receipt, transfers))
+    }
+}
 
-        Ok(())
+fn erc721_transfers(
+    contract: &Canvas721<FailoverTransport>,
+    receipt: &TransactionReceipt,
+) -> AppResult<Vec<TokenTransfer>> {
+    let mut transfers: Vec<TokenTransfer> = vec![];
+    for log in &receipt.logs {
+        if let Some(Canvas721Event::Transfer(event)) = contract.decode_event(log)? {
+            transfers.push(TokenTransfer {
+                from: event.from,
+                to: event.to,
+                token_id: event.token_id,
+                amount: U256::one(),
+            });
+        }
     }
+    Ok(transfers)
 }
@@ -0,0 +1,117 @@
+use crate::ethereum::chain::ChainProfile;
+use crate::ethereum::Client;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use web3::ethabi::{Contract as Abi, Function, Token};
+use web3::signing::keccak256;
+use web3::types::Bytes;
+
+pub const ERC721_CONTRACT: &str = "0x00000000000000000000000000000000000007a1";
+pub const ERC1155_CONTRACT: &str = "0x00000000000000000000000000000000000001a1";
+pub const ERC721_ABI: &[u8] = include_bytes!("canvas_erc721.abi.json");
+pub const ERC1155_ABI: &[u8] = include_bytes!("canvas_erc1155.abi.json");
+
+type Handler = dyn Fn(&str, &Value) -> Result<Value, Value> + Send + Sync;
+
+/// テスト用のJSON-RPCサーバー。メソッドとparamsを渡し、結果かJSON-RPCのエラーを返してもらう
+#[derive(Clone)]
+pub struct MockChain {
+    url: String,
+    handler: Arc<Handler>,
+    methods: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockChain {
+    pub fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&str, &Value) -> Result<Value, Value> + Send + Sync + 'static,
+    {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mock = Self {
+            url: format!("http://{}", listener.local_addr().unwrap()),
+            handler: Arc::new(handler),
+            methods: Arc::new(Mutex::new(vec![])),
+        };
+        let state = mock.clone();
+        let make = make_service_fn(move |_| {
+            let state = state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| state.clone().handle(req))) }
+        });
+        tokio::spawn(Server::from_tcp(listener).unwrap().serve(make));
+        mock
+    }
+
+    /// chain id 1で、ERC721/ERC1155のアドレスを固定したクライアント
+    pub fn client(&self) -> Client {
+        Client::with_chain(ChainProfile {
+            erc721_contract_address: ERC721_CONTRACT.to_string(),
+            erc1155_contract_address: ERC1155_CONTRACT.to_string(),
+            ..ChainProfile::for_test(&self.url, 1)
+        })
+    }
+
+    /// 呼ばれた順のJSON-RPCのメソッド名
+    pub fn methods(&self) -> Vec<String> {
+        self.methods.lock().unwrap().clone()
+    }
+
+    async fn handle(self, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        let method = body["method"].as_str().unwrap().to_string();
+        self.methods.lock().unwrap().push(method.clone());
+
+        let mut res = match (self.handler)(&method, &body["params"]) {
+            Ok(v) => json!({ "result": v }),
+            Err(e) => json!({ "error": e }),
+        };
+        res["jsonrpc"] = json!("2.0");
+        res["id"] = body["id"].clone();
+        Ok(Response::new(Body::from(res.to_string())))
+    }
+}
+
+/// eth_callのparamsから、宛先と呼ばれた関数、その引数を取り出す
+pub fn decode_call(abi_json: &[u8], params: &Value) -> (String, Function, Vec<Token>) {
+    let to = params[0]["to"].as_str().unwrap().to_string();
+    let data: Bytes = serde_json::from_value(params[0]["data"].clone()).unwrap();
+    let abi = Abi::load(abi_json).unwrap();
+    let function = abi
+        .functions()
+        .find(|v| {
+            let types: Vec<String> = v.inputs.iter().map(|v| v.kind.to_string()).collect();
+            keccak256(format!("{}({})", v.name, types.join(",")).as_bytes())[..4] == data.0[..4]
+        })
+        .unwrap_or_else(|| panic!("unknown selector: {:?}", &data.0[..4]))
+        .clone();
+    let input = function.decode_input(&data.0[4..]).unwrap();
+    (to, function, input)
+}
+
+/// 成功したトランザクションのレシート
+pub fn receipt(tx_hash: &str, from: &str) -> Value {
+    json!({
+        "transactionHash": tx_hash,
+        "transactionIndex": "0x0",
+        "blockNumber": "0x1",
+        "from": from,
+        "cumulativeGasUsed": "0x30000",
+        "gasUsed": "0x30000",
+        "logs": [],
+        "logsBloom": format!("0x{}", "0".repeat(512)),
+        "status": "0x1",
+    })
+}
+
+/// eth_callがrevertしたときのエラー
+pub fn revert(reason: &str) -> Value {
+    json!({"code": 3, "message": format!("execution reverted: {}", reason)})
+}
+
+/// eth_callの戻り値
+pub fn encode(tokens: &[Token]) -> Value {
+    json!(Bytes(web3::ethabi::encode(tokens)))
+}
@@ -0,0 +1,195 @@
+use crate::ethereum::Client;
+use crate::{AppError, AppResult};
+use web3::ethabi::{Contract as Abi, Function, Token};
+use web3::types::{Address, Bytes, CallRequest};
+
+pub struct Call {
+    pub target: Address,
+    pub data: Vec<u8>,
+}
+
+impl Call {
+    pub fn new(target: Address, function: &Function, params: &[Token]) -> AppResult<Self> {
+        Ok(Self {
+            target,
+            data: function.encode_input(params)?,
+        })
+    }
+}
+
+impl Client {
+    /// Multicall3がこのチェーンにデプロイされているか（結果はClient単位でキャッシュ）
+    pub async fn multicall_available(&self) -> AppResult<bool> {
+        let address = match self.parse_address(self.chain.multicall_address.clone()) {
+            Some(v) => v,
+            None => return Ok(false),
+        };
+
+        let available = self
+            .multicall_available
+            .get_or_try_init(|| async {
+                let code = self.cli.eth().code(address, None).await?;
+                Ok::<bool, AppError>(!code.0.is_empty())
+            })
+            .await?;
+
+        Ok(*available)
+    }
+
+    /// 複数のeth_callを1回のRPCにまとめる。失敗したcallはNoneで返す
+    pub async fn aggregate(&self, calls: Vec<Call>) -> AppResult<Vec<Option<Vec<u8>>>> {
+        if calls.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let address = self
+            .parse_address(self.chain.multicall_address.clone())
+            .ok_or_else(|| AppError::Internal("multicall is not configured".to_string()))?;
+        let abi = Abi::load(&include_bytes!("multicall3.abi.json")[..])?;
+        let function = abi.function("aggregate3")?;
+
+        let params = Token::Array(
+            calls
+                .into_iter()
+                .map(|v| {
+                    Token::Tuple(vec![
+                        Token::Address(v.target),
+                        Token::Bool(true),
+                        Token::Bytes(v.data),
+                    ])
+                })
+                .collect(),
+        );
+
+        let result = self
+            .cli
+            .eth()
+            .call(
+                CallRequest {
+                    to: Some(address),
+                    data: Some(Bytes(function.encode_input(&[params])?)),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+
+        let output = function.decode_output(&result.0)?;
+        let results = match output.into_iter().next() {
+            Some(Token::Array(results)) => results,
+            _ => {
+                return Err(AppError::Internal(
+                    "unexpected multicall response".to_string(),
+                ))
+            }
+        };
+
+        Ok(results
+            .into_iter()
+            .map(|v| match v {
+                Token::Tuple(values) => match (values.first(), values.get(1)) {
+                    (Some(Token::Bool(true)), Some(Token::Bytes(data))) => Some(data.to_owned()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::chain::ChainProfile;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use serde_json::{json, Value};
+    use std::convert::Infallible;
+
+    /// Multicall3のコードの有無を切り替えられ、aggregate3には1件目だけ成功した結果を返すサーバー
+    fn start(deployed: bool) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let make = make_service_fn(move |_| async move {
+            Ok::<_, Infallible>(service_fn(move |req| handle(req, deployed)))
+        });
+        tokio::spawn(Server::from_tcp(listener).unwrap().serve(make));
+        url
+    }
+
+    async fn handle(req: Request<Body>, deployed: bool) -> Result<Response<Body>, Infallible> {
+        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+
+        let result = match body["method"].as_str().unwrap() {
+            "eth_getCode" => json!(if deployed { "0x6080" } else { "0x" }),
+            "eth_call" => {
+                let data: Bytes =
+                    serde_json::from_value(body["params"][0]["data"].clone()).unwrap();
+                let abi = Abi::load(&include_bytes!("multicall3.abi.json")[..]).unwrap();
+                let function = abi.function("aggregate3").unwrap();
+
+                // 失敗を許容して、渡したcallをそのまま送っている
+                let calls = match function.decode_input(&data.0[4..]).unwrap().remove(0) {
+                    Token::Array(v) => v,
+                    v => panic!("unexpected input: {:?}", v),
+                };
+                assert_eq!(calls.len(), 2);
+                assert_eq!(
+                    calls[1],
+                    Token::Tuple(vec![
+                        Token::Address(Address::from_low_u64_be(2)),
+                        Token::Bool(true),
+                        Token::Bytes(vec![0xbe, 0xef]),
+                    ])
+                );
+
+                let output = web3::ethabi::encode(&[Token::Array(vec![
+                    Token::Tuple(vec![Token::Bool(true), Token::Bytes(vec![1, 2, 3])]),
+                    // revertの理由が入っていても失敗として扱う
+                    Token::Tuple(vec![Token::Bool(false), Token::Bytes(vec![0x08, 0xc3])]),
+                ])]);
+                json!(Bytes(output))
+            }
+            v => panic!("unexpected method: {}", v),
+        };
+        let res = json!({"jsonrpc": "2.0", "id": body["id"], "result": result});
+        Ok(Response::new(Body::from(res.to_string())))
+    }
+
+    fn calls() -> Vec<Call> {
+        vec![
+            Call {
+                target: Address::from_low_u64_be(1),
+                data: vec![0xca, 0xfe],
+            },
+            Call {
+                target: Address::from_low_u64_be(2),
+                data: vec![0xbe, 0xef],
+            },
+        ]
+    }
+
+    #[tokio::test]
+    async fn decodes_aggregate3_results() {
+        let client = Client::with_chain(ChainProfile::for_test(&start(true), 1));
+
+        assert!(client.multicall_available().await.unwrap());
+        let results = client.aggregate(calls()).await.unwrap();
+        assert_eq!(results, vec![Some(vec![1, 2, 3]), None]);
+        assert!(client.aggregate(vec![]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unavailable_without_code_or_address() {
+        let client = Client::with_chain(ChainProfile::for_test(&start(false), 1));
+        assert!(!client.multicall_available().await.unwrap());
+
+        let client = Client::with_chain(ChainProfile {
+            multicall_address: "".to_string(),
+            ..ChainProfile::for_test(&start(true), 1)
+        });
+        assert!(!client.multicall_available().await.unwrap());
+        assert!(client.aggregate(calls()).await.is_err());
+    }
+}
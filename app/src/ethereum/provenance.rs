@@ -0,0 +1,51 @@
+use crate::domain::market::TokenSchema;
+use crate::domain::user::User;
+use crate::ethereum::Client;
+use crate::{AppError, AppResult};
+use secp256k1::SecretKey;
+use std::str::FromStr;
+use web3::signing::{Key, SecretKeyRef};
+
+impl Client {
+    /// コントラクトに確定した公開後のmetadataのハッシュ。未確定なら空
+    pub async fn provenance_hash(&self, schema: TokenSchema) -> AppResult<String> {
+        match schema {
+            TokenSchema::Erc721 => self.erc721()?.provenance_hash().await,
+            TokenSchema::Erc1155 => self.erc1155()?.provenance_hash().await,
+        }
+    }
+
+    /// 一度だけ書き込める。ProvenanceHashSetイベントが出る
+    pub async fn set_provenance_hash(
+        &self,
+        user: &User,
+        schema: TokenSchema,
+        hash: String,
+    ) -> AppResult<()> {
+        let key = SecretKey::from_str(&user.wallet_secret)
+            .map_err(|e| AppError::Internal(format!("invalid wallet secret: {}", e)))?;
+        let from = SecretKeyRef::from(&key).address();
+
+        let receipt = match schema {
+            TokenSchema::Erc721 => {
+                self.erc721()?
+                    .set_provenance_hash(
+                        SecretKeyRef::from(&key),
+                        self.tx_options(from).await?,
+                        hash,
+                    )
+                    .await?
+            }
+            TokenSchema::Erc1155 => {
+                self.erc1155()?
+                    .set_provenance_hash(
+                        SecretKeyRef::from(&key),
+                        self.tx_options(from).await?,
+                        hash,
+                    )
+                    .await?
+            }
+        };
+        self.ensure_success(&receipt)
+    }
+}
@@ -0,0 +1,584 @@
+use crate::domain::market::TokenSchema;
+use crate::ethereum::Client;
+use crate::{AppError, AppResult};
+use secp256k1::SecretKey;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+use web3::contract::Options;
+use web3::ethabi::{encode, Token};
+use web3::signing::{keccak256, Key, SecretKeyRef};
+use web3::types::{Address, Bytes, CallRequest, H256, U256};
+
+const SEAPORT_NAME: &str = "Seaport";
+const SEAPORT_VERSION: &str = "1.5";
+
+const EIP712_DOMAIN_TYPE: &str =
+    "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const OFFER_ITEM_TYPE: &str =
+    "OfferItem(uint8 itemType,address token,uint256 identifierOrCriteria,uint256 startAmount,uint256 endAmount)";
+const CONSIDERATION_ITEM_TYPE: &str =
+    "ConsiderationItem(uint8 itemType,address token,uint256 identifierOrCriteria,uint256 startAmount,uint256 endAmount,address recipient)";
+const CANCEL_SIGNATURE: &str =
+    "cancel((address,address,(uint8,address,uint256,uint256,uint256)[],(uint8,address,uint256,uint256,uint256,address)[],uint8,uint256,uint256,bytes32,uint256,bytes32,uint256)[])";
+const FULFILL_ORDER_SIGNATURE: &str =
+    "fulfillOrder(((address,address,(uint8,address,uint256,uint256,uint256)[],(uint8,address,uint256,uint256,uint256,address)[],uint8,uint256,uint256,bytes32,uint256,bytes32,uint256),bytes),bytes32)";
+const ORDER_COMPONENTS_TYPE: &str =
+    "OrderComponents(address offerer,address zone,OfferItem[] offer,ConsiderationItem[] consideration,uint8 orderType,uint256 startTime,uint256 endTime,bytes32 zoneHash,uint256 salt,bytes32 conduitKey,uint256 counter)";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemType {
+    Native = 0,
+    Erc20 = 1,
+    Erc721 = 2,
+    Erc1155 = 3,
+}
+
+impl From<TokenSchema> for ItemType {
+    fn from(schema: TokenSchema) -> Self {
+        match schema {
+            TokenSchema::Erc721 => ItemType::Erc721,
+            TokenSchema::Erc1155 => ItemType::Erc1155,
+        }
+    }
+}
+
+impl Serialize for ItemType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match u8::deserialize(deserializer)? {
+            0 => Ok(ItemType::Native),
+            1 => Ok(ItemType::Erc20),
+            2 => Ok(ItemType::Erc721),
+            3 => Ok(ItemType::Erc1155),
+            v => Err(serde::de::Error::custom(format!(
+                "unsupported item type: {}",
+                v
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfferItem {
+    pub item_type: ItemType,
+    pub token: Address,
+    #[serde(serialize_with = "decimal", deserialize_with = "from_decimal")]
+    pub identifier_or_criteria: U256,
+    #[serde(serialize_with = "decimal", deserialize_with = "from_decimal")]
+    pub start_amount: U256,
+    #[serde(serialize_with = "decimal", deserialize_with = "from_decimal")]
+    pub end_amount: U256,
+}
+
+impl OfferItem {
+    fn token(&self) -> Token {
+        Token::Tuple(vec![
+            Token::Uint(U256::from(self.item_type as u8)),
+            Token::Address(self.token),
+            Token::Uint(self.identifier_or_criteria),
+            Token::Uint(self.start_amount),
+            Token::Uint(self.end_amount),
+        ])
+    }
+
+    fn hash(&self) -> [u8; 32] {
+        keccak256(&encode(&[
+            Token::FixedBytes(keccak256(OFFER_ITEM_TYPE.as_bytes()).to_vec()),
+            Token::Uint(U256::from(self.item_type as u8)),
+            Token::Address(self.token),
+            Token::Uint(self.identifier_or_criteria),
+            Token::Uint(self.start_amount),
+            Token::Uint(self.end_amount),
+        ]))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsiderationItem {
+    pub item_type: ItemType,
+    pub token: Address,
+    #[serde(serialize_with = "decimal", deserialize_with = "from_decimal")]
+    pub identifier_or_criteria: U256,
+    #[serde(serialize_with = "decimal", deserialize_with = "from_decimal")]
+    pub start_amount: U256,
+    #[serde(serialize_with = "decimal", deserialize_with = "from_decimal")]
+    pub end_amount: U256,
+    pub recipient: Address,
+}
+
+impl ConsiderationItem {
+    /// ネイティブ通貨での支払い
+    pub fn native(amount: U256, recipient: Address) -> Self {
+        Self {
+            item_type: ItemType::Native,
+            token: Address::zero(),
+            identifier_or_criteria: U256::zero(),
+            start_amount: amount,
+            end_amount: amount,
+            recipient,
+        }
+    }
+
+    fn token(&self) -> Token {
+        Token::Tuple(vec![
+            Token::Uint(U256::from(self.item_type as u8)),
+            Token::Address(self.token),
+            Token::Uint(self.identifier_or_criteria),
+            Token::Uint(self.start_amount),
+            Token::Uint(self.end_amount),
+            Token::Address(self.recipient),
+        ])
+    }
+
+    fn hash(&self) -> [u8; 32] {
+        keccak256(&encode(&[
+            Token::FixedBytes(keccak256(CONSIDERATION_ITEM_TYPE.as_bytes()).to_vec()),
+            Token::Uint(U256::from(self.item_type as u8)),
+            Token::Address(self.token),
+            Token::Uint(self.identifier_or_criteria),
+            Token::Uint(self.start_amount),
+            Token::Uint(self.end_amount),
+            Token::Address(self.recipient),
+        ]))
+    }
+}
+
+/// 署名対象のSeaportの注文。APIに送るときはtotalOriginalConsiderationItemsを加える
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderComponents {
+    pub offerer: Address,
+    pub zone: Address,
+    pub offer: Vec<OfferItem>,
+    pub consideration: Vec<ConsiderationItem>,
+    pub order_type: u8,
+    #[serde(serialize_with = "decimal", deserialize_with = "from_decimal")]
+    pub start_time: U256,
+    #[serde(serialize_with = "decimal", deserialize_with = "from_decimal")]
+    pub end_time: U256,
+    pub zone_hash: H256,
+    #[serde(serialize_with = "decimal", deserialize_with = "from_decimal")]
+    pub salt: U256,
+    pub conduit_key: H256,
+    #[serde(serialize_with = "decimal", deserialize_with = "from_decimal")]
+    pub counter: U256,
+    pub total_original_consideration_items: usize,
+}
+
+impl OrderComponents {
+    /// 1つのNFTを、代金と手数料をネイティブ通貨で受け取って売る注文
+    /// ERC1155は一部だけの購入も許可する
+    #[allow(clippy::too_many_arguments)]
+    pub fn listing(
+        offerer: Address,
+        schema: TokenSchema,
+        token: Address,
+        token_id: U256,
+        quantity: U256,
+        consideration: Vec<ConsiderationItem>,
+        start_time: u64,
+        end_time: u64,
+        salt: U256,
+        conduit_key: H256,
+        counter: U256,
+    ) -> Self {
+        let total_original_consideration_items = consideration.len();
+        Self {
+            offerer,
+            zone: Address::zero(),
+            offer: vec![OfferItem {
+                item_type: schema.into(),
+                token,
+                identifier_or_criteria: token_id,
+                start_amount: quantity,
+                end_amount: quantity,
+            }],
+            consideration,
+            order_type: match schema {
+                TokenSchema::Erc721 => 0,
+                TokenSchema::Erc1155 => 1,
+            },
+            start_time: U256::from(start_time),
+            end_time: U256::from(end_time),
+            zone_hash: H256::zero(),
+            salt,
+            conduit_key,
+            counter,
+            total_original_consideration_items,
+        }
+    }
+
+    pub fn struct_hash(&self) -> [u8; 32] {
+        let offer: Vec<u8> = self.offer.iter().flat_map(|v| v.hash()).collect();
+        let consideration: Vec<u8> = self.consideration.iter().flat_map(|v| v.hash()).collect();
+        let type_hash = keccak256(
+            format!(
+                "{}{}{}",
+                ORDER_COMPONENTS_TYPE, CONSIDERATION_ITEM_TYPE, OFFER_ITEM_TYPE
+            )
+            .as_bytes(),
+        );
+
+        keccak256(&encode(&[
+            Token::FixedBytes(type_hash.to_vec()),
+            Token::Address(self.offerer),
+            Token::Address(self.zone),
+            Token::FixedBytes(keccak256(&offer).to_vec()),
+            Token::FixedBytes(keccak256(&consideration).to_vec()),
+            Token::Uint(U256::from(self.order_type)),
+            Token::Uint(self.start_time),
+            Token::Uint(self.end_time),
+            Token::FixedBytes(self.zone_hash.as_bytes().to_vec()),
+            Token::Uint(self.salt),
+            Token::FixedBytes(self.conduit_key.as_bytes().to_vec()),
+            Token::Uint(self.counter),
+        ]))
+    }
+
+    /// Seaportの注文ハッシュ（getOrderHashと同じ値）
+    pub fn order_hash(&self) -> String {
+        format!("{:?}", H256::from(self.struct_hash()))
+    }
+
+    fn token(&self) -> Token {
+        Token::Tuple(vec![
+            Token::Address(self.offerer),
+            Token::Address(self.zone),
+            Token::Array(self.offer.iter().map(|v| v.token()).collect()),
+            Token::Array(self.consideration.iter().map(|v| v.token()).collect()),
+            Token::Uint(U256::from(self.order_type)),
+            Token::Uint(self.start_time),
+            Token::Uint(self.end_time),
+            Token::FixedBytes(self.zone_hash.as_bytes().to_vec()),
+            Token::Uint(self.salt),
+            Token::FixedBytes(self.conduit_key.as_bytes().to_vec()),
+            Token::Uint(self.counter),
+        ])
+    }
+
+    /// fulfillOrderに渡すOrderParameters。counterの代わりにtotalOriginalConsiderationItemsを入れる
+    fn parameters_token(&self) -> Token {
+        Token::Tuple(vec![
+            Token::Address(self.offerer),
+            Token::Address(self.zone),
+            Token::Array(self.offer.iter().map(|v| v.token()).collect()),
+            Token::Array(self.consideration.iter().map(|v| v.token()).collect()),
+            Token::Uint(U256::from(self.order_type)),
+            Token::Uint(self.start_time),
+            Token::Uint(self.end_time),
+            Token::FixedBytes(self.zone_hash.as_bytes().to_vec()),
+            Token::Uint(self.salt),
+            Token::FixedBytes(self.conduit_key.as_bytes().to_vec()),
+            Token::Uint(U256::from(self.total_original_consideration_items)),
+        ])
+    }
+
+    /// zoneの承認が要る注文（FULL_RESTRICTED / PARTIAL_RESTRICTED）か
+    pub fn is_restricted(&self) -> bool {
+        self.order_type >= 2
+    }
+
+    /// 受け取り側が支払う額。item_typeとtokenが一致するconsiderationの合計
+    pub fn consideration_amount(&self, item_type: ItemType, token: Address) -> U256 {
+        self.consideration
+            .iter()
+            .filter(|v| v.item_type == item_type && v.token == token)
+            .fold(U256::zero(), |acc, v| {
+                acc + v.start_amount.max(v.end_amount)
+            })
+    }
+
+    /// EIP-712の署名対象のダイジェスト
+    pub fn digest(&self, chain_id: u64, seaport: Address) -> [u8; 32] {
+        let domain_separator = keccak256(&encode(&[
+            Token::FixedBytes(keccak256(EIP712_DOMAIN_TYPE.as_bytes()).to_vec()),
+            Token::FixedBytes(keccak256(SEAPORT_NAME.as_bytes()).to_vec()),
+            Token::FixedBytes(keccak256(SEAPORT_VERSION.as_bytes()).to_vec()),
+            Token::Uint(U256::from(chain_id)),
+            Token::Address(seaport),
+        ]));
+
+        let mut message = vec![0x19, 0x01];
+        message.extend_from_slice(&domain_separator);
+        message.extend_from_slice(&self.struct_hash());
+        keccak256(&message)
+    }
+}
+
+fn decimal<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+fn from_decimal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    U256::from_dec_str(&value).map_err(|e| serde::de::Error::custom(format!("{:?}", e)))
+}
+
+impl Client {
+    pub fn seaport_address(&self) -> AppResult<Address> {
+        self.parse_address(self.chain.seaport_address.clone())
+            .ok_or_else(|| AppError::Internal("invalid seaport address".to_string()))
+    }
+
+    /// 注文の無効化ごとに増える、offererごとのカウンター
+    pub async fn get_seaport_counter(&self, offerer: Address) -> AppResult<U256> {
+        let mut data = keccak256(b"getCounter(address)")[..4].to_vec();
+        data.extend(encode(&[Token::Address(offerer)]));
+
+        let result = self
+            .cli
+            .eth()
+            .call(
+                CallRequest {
+                    to: Some(self.seaport_address()?),
+                    data: Some(Bytes(data)),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+        if result.0.len() < 32 {
+            return Err(AppError::Internal("invalid seaport counter".to_string()));
+        }
+        Ok(U256::from_big_endian(&result.0[..32]))
+    }
+
+    /// 注文に署名し、r || s || v の形式で返す。秘密鍵はこのプロセスから出さない
+    pub fn sign_seaport_order(&self, order: &OrderComponents, secret: &str) -> AppResult<String> {
+        let key = SecretKey::from_str(secret)
+            .map_err(|e| AppError::BadRequest(format!("invalid signer key: {}", e)))?;
+        let key = SecretKeyRef::from(&key);
+        if key.address() != order.offerer {
+            return Err(AppError::Forbidden);
+        }
+
+        let signature = key
+            .sign(
+                &order.digest(self.chain.chain_id, self.seaport_address()?),
+                None,
+            )
+            .map_err(|e| AppError::Internal(format!("failed to sign order: {:?}", e)))?;
+
+        let mut bytes = signature.r.as_bytes().to_vec();
+        bytes.extend_from_slice(signature.s.as_bytes());
+        bytes.push(signature.v as u8);
+        Ok(format!(
+            "0x{}",
+            bytes
+                .iter()
+                .map(|v| format!("{:02x}", v))
+                .collect::<String>()
+        ))
+    }
+
+    /// conduitがNFTを移動できるように、未承認であればsetApprovalForAllを送る
+    pub async fn ensure_approval_for_all(
+        &self,
+        schema: TokenSchema,
+        secret: &str,
+        operator: Address,
+    ) -> AppResult<()> {
+        let key = SecretKey::from_str(secret)
+            .map_err(|e| AppError::BadRequest(format!("invalid signer key: {}", e)))?;
+        let key = SecretKeyRef::from(&key);
+        let owner = key.address();
+
+        match schema {
+            TokenSchema::Erc721 => {
+                let contract = self.erc721()?;
+                if contract.is_approved_for_all(owner, operator).await? {
+                    return Ok(());
+                }
+                contract
+                    .set_approval_for_all(key, Options::default(), operator, true)
+                    .await?;
+            }
+            TokenSchema::Erc1155 => {
+                let contract = self.erc1155()?;
+                if contract.is_approved_for_all(owner, operator).await? {
+                    return Ok(());
+                }
+                contract
+                    .set_approval_for_all(key, Options::default(), operator, true)
+                    .await?;
+            }
+        }
+        println!("approved for all: {:?} -> {:?}", owner, operator);
+        Ok(())
+    }
+
+    /// offererとして注文をオンチェーンで取り消す
+    pub async fn cancel_seaport_orders(
+        &self,
+        orders: &[OrderComponents],
+        secret: &str,
+    ) -> AppResult<H256> {
+        let key = SecretKey::from_str(secret)
+            .map_err(|e| AppError::BadRequest(format!("invalid signer key: {}", e)))?;
+        let key = SecretKeyRef::from(&key);
+        if orders.iter().any(|v| v.offerer != key.address()) {
+            return Err(AppError::Forbidden);
+        }
+
+        let mut data = keccak256(CANCEL_SIGNATURE.as_bytes())[..4].to_vec();
+        data.extend(encode(&[Token::Array(
+            orders.iter().map(|v| v.token()).collect(),
+        )]));
+        self.send_call(key, self.seaport_address()?, Bytes(data), U256::zero())
+            .await
+    }
+
+    /// 署名済みの注文を自分のウォレットで約定する。秘密鍵はこのプロセスから出さない
+    /// ネイティブ通貨の代金はvalueとして送り、ERC20とNFTはconduit_keyのconduitが移動する
+    pub async fn fulfill_seaport_order(
+        &self,
+        order: &OrderComponents,
+        signature: &str,
+        secret: &str,
+        conduit_key: H256,
+    ) -> AppResult<H256> {
+        let key = SecretKey::from_str(secret)
+            .map_err(|e| AppError::BadRequest(format!("invalid signer key: {}", e)))?;
+        let key = SecretKeyRef::from(&key);
+        if order.offerer == key.address() {
+            return Err(AppError::BadRequest(
+                "自分の注文は約定できません".to_string(),
+            ));
+        }
+        if order.is_restricted() {
+            return Err(AppError::BadRequest(
+                "zoneの承認が必要な注文は約定できません".to_string(),
+            ));
+        }
+        let signature: Bytes =
+            serde_json::from_value(serde_json::Value::String(signature.to_string()))
+                .map_err(|e| AppError::Internal(format!("invalid order signature: {}", e)))?;
+
+        let mut data = keccak256(FULFILL_ORDER_SIGNATURE.as_bytes())[..4].to_vec();
+        data.extend(encode(&[
+            Token::Tuple(vec![order.parameters_token(), Token::Bytes(signature.0)]),
+            Token::FixedBytes(conduit_key.as_bytes().to_vec()),
+        ]));
+        let value = order.consideration_amount(ItemType::Native, Address::zero());
+        self.send_call(key, self.seaport_address()?, Bytes(data), value)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::chain::ChainProfile;
+
+    // Seaport 1.5 の ConsiderationConstants と同じ値
+    const EIP712_DOMAIN_TYPEHASH: &str =
+        "0x8b73c3c69bb8fe3d512ecc4cf759cc79239f7b179b0ffacaa9a75d522b39400f";
+    const OFFER_ITEM_TYPEHASH: &str =
+        "0xa66999307ad1bb4fde44d13a5d710bd7718e0c87c1eef68a571629fbf5b93d02";
+    const CONSIDERATION_ITEM_TYPEHASH: &str =
+        "0x42d81c6929ffdc4eb27a0808e40e82516ad42296c166065de7f812492304ff6e";
+    const ORDER_TYPEHASH: &str =
+        "0xfa445660b7e21515a59617fcd68910b487aa5808b8abda3d78bc85df364b2c2f";
+
+    // anvilの1番目のアカウント
+    const OFFERER_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const OFFERER: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+    const FEE_RECIPIENT: &str = "0x0000a26b00c1f0df003000390027140000faa719";
+    const CONDUIT_KEY: &str = "0x0000007b02230091a7ed01230072f7006a004d60a8d4e71d599b8104250f0000";
+    const SEAPORT: &str = "0x00000000000000adc04c56bf30ac9d3c0aaf14dc";
+
+    fn hash(value: &str) -> String {
+        format!("{:?}", H256::from(keccak256(value.as_bytes())))
+    }
+
+    /// 1 ETH で出品し、2.5% をOpenSeaの手数料にする注文
+    fn order() -> OrderComponents {
+        let offerer: Address = OFFERER.parse().unwrap();
+        let ether = U256::exp10(15);
+        OrderComponents::listing(
+            offerer,
+            TokenSchema::Erc721,
+            "0x1111111111111111111111111111111111111111"
+                .parse()
+                .unwrap(),
+            U256::from(7),
+            U256::one(),
+            vec![
+                ConsiderationItem::native(ether * 975, offerer),
+                ConsiderationItem::native(ether * 25, FEE_RECIPIENT.parse().unwrap()),
+            ],
+            1700000000,
+            1702592000,
+            U256::from(12345),
+            CONDUIT_KEY.parse().unwrap(),
+            U256::zero(),
+        )
+    }
+
+    #[test]
+    fn type_hashes_match_seaport() {
+        assert_eq!(hash(EIP712_DOMAIN_TYPE), EIP712_DOMAIN_TYPEHASH);
+        assert_eq!(hash(OFFER_ITEM_TYPE), OFFER_ITEM_TYPEHASH);
+        assert_eq!(hash(CONSIDERATION_ITEM_TYPE), CONSIDERATION_ITEM_TYPEHASH);
+        assert_eq!(
+            hash(&format!(
+                "{}{}{}",
+                ORDER_COMPONENTS_TYPE, CONSIDERATION_ITEM_TYPE, OFFER_ITEM_TYPE
+            )),
+            ORDER_TYPEHASH
+        );
+    }
+
+    // 期待値はEIP-712の定義どおりに別の実装で計算したもの
+    #[test]
+    fn hashes_order_components() {
+        let order = order();
+        assert_eq!(
+            order.order_hash(),
+            "0xfffaf70efb79b6201a3fcf1be0878fd7697af2cd0b26a7eb759432f9af72ce71"
+        );
+        assert_eq!(
+            format!(
+                "{:?}",
+                H256::from(order.digest(1, SEAPORT.parse().unwrap()))
+            ),
+            "0xabae2964a369767030a845899b837fe4edfb2be1b52c7a8fe2ffbfae7f538706"
+        );
+    }
+
+    #[test]
+    fn signs_order_as_offerer() {
+        let mut chain = ChainProfile::for_test("http://127.0.0.1:8545", 1);
+        chain.seaport_address = SEAPORT.to_string();
+        let cli = Client::with_chain(chain);
+        let order = order();
+
+        let signature = cli.sign_seaport_order(&order, OFFERER_KEY).unwrap();
+        let bytes: Vec<u8> = (2..signature.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&signature[i..i + 2], 16).unwrap())
+            .collect();
+        assert_eq!(bytes.len(), 65);
+        let recovered = web3::signing::recover(
+            &order.digest(1, SEAPORT.parse().unwrap()),
+            &bytes[..64],
+            bytes[64] as i32 - 27,
+        )
+        .unwrap();
+        assert_eq!(recovered, order.offerer);
+
+        // offerer以外の鍵では署名しない
+        let other = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+        assert!(matches!(
+            cli.sign_seaport_order(&order, other),
+            Err(AppError::Forbidden)
+        ));
+    }
+}
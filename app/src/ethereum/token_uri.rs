@@ -0,0 +1,52 @@
+use crate::domain::market::TokenSchema;
+use crate::domain::user::User;
+use crate::ethereum::Client;
+use crate::{AppError, AppResult};
+use secp256k1::SecretKey;
+use std::str::FromStr;
+use web3::signing::{Key, SecretKeyRef};
+use web3::types::U256;
+
+impl Client {
+    /// ERC1155では{id}が置き換えられていないまま返る
+    pub async fn token_uri(&self, schema: TokenSchema, token_id: u128) -> AppResult<String> {
+        match schema {
+            TokenSchema::Erc721 => self.erc721()?.token_uri(U256::from(token_id)).await,
+            TokenSchema::Erc1155 => self.erc1155()?.uri(U256::from(token_id)).await,
+        }
+    }
+
+    /// tokenURI(uri)の返す先を固定のURLにする。空にするとIPFS/S3の個別のURLに戻る
+    pub async fn set_metadata_base_uri(
+        &self,
+        user: &User,
+        schema: TokenSchema,
+        base_uri: String,
+    ) -> AppResult<()> {
+        let key = SecretKey::from_str(&user.wallet_secret)
+            .map_err(|e| AppError::Internal(format!("invalid wallet secret: {}", e)))?;
+        let from = SecretKeyRef::from(&key).address();
+
+        let receipt = match schema {
+            TokenSchema::Erc721 => {
+                self.erc721()?
+                    .set_metadata_base_uri(
+                        SecretKeyRef::from(&key),
+                        self.tx_options(from).await?,
+                        base_uri,
+                    )
+                    .await?
+            }
+            TokenSchema::Erc1155 => {
+                self.erc1155()?
+                    .set_metadata_base_uri(
+                        SecretKeyRef::from(&key),
+                        self.tx_options(from).await?,
+                        base_uri,
+                    )
+                    .await?
+            }
+        };
+        self.ensure_success(&receipt)
+    }
+}
@@ -0,0 +1,517 @@
+use futures::future::BoxFuture;
+use jsonrpc_core as rpc;
+use reqwest::Url;
+use std::collections::HashMap;
+use std::env;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use web3::helpers;
+use web3::transports::Http;
+use web3::{error, RequestId, Transport};
+
+// 何度リトライしても結果が変わらない読み取り系のメソッド
+const IDEMPOTENT_METHODS: &[&str] = &[
+    "eth_blockNumber",
+    "eth_call",
+    "eth_chainId",
+    "eth_estimateGas",
+    "eth_feeHistory",
+    "eth_gasPrice",
+    "eth_getBalance",
+    "eth_getBlockByHash",
+    "eth_getBlockByNumber",
+    "eth_getCode",
+    "eth_getLogs",
+    "eth_getTransactionByHash",
+    "eth_getTransactionCount",
+    "eth_getTransactionReceipt",
+    "eth_maxPriorityFeePerGas",
+    "net_version",
+];
+
+// フィルターはノードごとの状態なので、作成したエンドポイントに固定する
+const FILTER_CREATE_METHODS: &[&str] = &[
+    "eth_newBlockFilter",
+    "eth_newFilter",
+    "eth_newPendingTransactionFilter",
+];
+const FILTER_METHODS: &[&str] = &[
+    "eth_getFilterChanges",
+    "eth_getFilterLogs",
+    "eth_uninstallFilter",
+];
+
+#[derive(Clone, Debug)]
+pub struct RetryConfig {
+    pub max_attempts: usize,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub timeout: Duration,
+    pub failure_threshold: u64,
+    pub cooldown: Duration,
+}
+
+impl RetryConfig {
+    pub fn from_env() -> Self {
+        let get = |key: &str, default: u64| -> u64 {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            max_attempts: get("ETHEREUM_RPC_MAX_ATTEMPTS", 4) as usize,
+            base_delay: Duration::from_millis(get("ETHEREUM_RPC_BASE_DELAY_MS", 200)),
+            max_delay: Duration::from_millis(get("ETHEREUM_RPC_MAX_DELAY_MS", 3000)),
+            timeout: Duration::from_millis(get("ETHEREUM_RPC_TIMEOUT_MS", 10000)),
+            failure_threshold: get("ETHEREUM_RPC_FAILURE_THRESHOLD", 3),
+            cooldown: Duration::from_millis(get("ETHEREUM_RPC_COOLDOWN_MS", 30000)),
+        }
+    }
+
+    fn backoff(&self, attempt: usize) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt as u32))
+            .min(self.max_delay);
+        // full jitter: 0 ~ exp の範囲でランダムに待つ
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|v| v.subsec_nanos() as u64)
+            .unwrap_or_default();
+        let max = exp.as_millis().max(1) as u64;
+        Duration::from_millis(nanos % max)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EndpointMetrics {
+    pub host: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub timeouts: u64,
+    pub healthy: bool,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    host: String,
+    http: Http,
+    requests: AtomicU64,
+    errors: AtomicU64,
+    timeouts: AtomicU64,
+    consecutive_failures: AtomicU64,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn is_healthy(&self, now: Instant) -> bool {
+        match *self.unhealthy_until.lock().unwrap() {
+            Some(until) => until <= now,
+            None => true,
+        }
+    }
+
+    fn record_success(&self) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        *self.unhealthy_until.lock().unwrap() = None;
+    }
+
+    fn record_failure(&self, config: &RetryConfig, timeout: bool) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        if timeout {
+            self.timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= config.failure_threshold {
+            println!("rpc endpoint marked unhealthy: {}", self.host);
+            *self.unhealthy_until.lock().unwrap() = Some(Instant::now() + config.cooldown);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    endpoints: Vec<Endpoint>,
+    config: RetryConfig,
+    id: AtomicUsize,
+    filters: Mutex<HashMap<String, usize>>,
+}
+
+/// 複数のRPCエンドポイントを優先順に使い、障害時に切り替えるTransport
+#[derive(Clone, Debug)]
+pub struct FailoverTransport {
+    inner: Arc<Inner>,
+}
+
+impl FailoverTransport {
+    pub fn new(urls: &[String], config: RetryConfig) -> error::Result<Self> {
+        let mut endpoints: Vec<Endpoint> = vec![];
+        for url in urls {
+            // URLにAPIキーが含まれることがあるので、ログにはホストのみ出す
+            let host = url
+                .parse::<Url>()
+                .ok()
+                .and_then(|v| v.host_str().map(String::from))
+                .unwrap_or_default();
+            endpoints.push(Endpoint {
+                host,
+                http: Http::new(url)?,
+                requests: AtomicU64::new(0),
+                errors: AtomicU64::new(0),
+                timeouts: AtomicU64::new(0),
+                consecutive_failures: AtomicU64::new(0),
+                unhealthy_until: Mutex::new(None),
+            });
+        }
+        if endpoints.is_empty() {
+            return Err(error::Error::Transport("no rpc endpoint".to_string()));
+        }
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                endpoints,
+                config,
+                id: AtomicUsize::new(0),
+                filters: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+
+    pub fn metrics(&self) -> Vec<EndpointMetrics> {
+        let now = Instant::now();
+        self.inner
+            .endpoints
+            .iter()
+            .map(|v| EndpointMetrics {
+                host: v.host.clone(),
+                requests: v.requests.load(Ordering::Relaxed),
+                errors: v.errors.load(Ordering::Relaxed),
+                timeouts: v.timeouts.load(Ordering::Relaxed),
+                healthy: v.is_healthy(now),
+            })
+            .collect()
+    }
+}
+
+impl Inner {
+    /// 健全なエンドポイントを優先順に選ぶ。全て不健全なら復帰が最も早いものを使う
+    fn select(&self, exclude: &[usize]) -> usize {
+        let now = Instant::now();
+        let candidates: Vec<usize> = (0..self.endpoints.len())
+            .filter(|i| !exclude.contains(i))
+            .collect();
+        let candidates = if candidates.is_empty() {
+            (0..self.endpoints.len()).collect()
+        } else {
+            candidates
+        };
+
+        if let Some(i) = candidates
+            .iter()
+            .find(|i| self.endpoints[**i].is_healthy(now))
+        {
+            return *i;
+        }
+
+        candidates
+            .into_iter()
+            .min_by_key(|i| *self.endpoints[*i].unhealthy_until.lock().unwrap())
+            .unwrap()
+    }
+
+    fn pinned_endpoint(&self, method: &str, call: &rpc::Call) -> Option<usize> {
+        if !FILTER_METHODS.contains(&method) {
+            return None;
+        }
+        let filter_id = self.filter_id_of(call)?;
+        self.filters.lock().unwrap().get(&filter_id).copied()
+    }
+
+    async fn send(&self, id: RequestId, call: rpc::Call) -> error::Result<rpc::Value> {
+        let method = match &call {
+            rpc::Call::MethodCall(v) => v.method.clone(),
+            rpc::Call::Notification(v) => v.method.clone(),
+            _ => "".to_string(),
+        };
+        let pinned = self.pinned_endpoint(&method, &call);
+        let max_attempts = if pinned.is_none() && IDEMPOTENT_METHODS.contains(&method.as_str()) {
+            self.config.max_attempts.max(1)
+        } else {
+            1
+        };
+
+        let mut tried: Vec<usize> = vec![];
+        let mut last_err = error::Error::Unreachable;
+        for attempt in 0..max_attempts {
+            if attempt > 0 {
+                tokio::time::sleep(self.config.backoff(attempt - 1)).await;
+            }
+
+            let index = pinned.unwrap_or_else(|| self.select(&tried));
+            tried.push(index);
+            let endpoint = &self.endpoints[index];
+            endpoint.requests.fetch_add(1, Ordering::Relaxed);
+
+            match tokio::time::timeout(self.config.timeout, endpoint.http.send(id, call.clone()))
+                .await
+            {
+                Ok(Ok(value)) => {
+                    endpoint.record_success();
+                    if FILTER_CREATE_METHODS.contains(&method.as_str()) {
+                        if let Some(filter_id) = value.as_str() {
+                            self.filters
+                                .lock()
+                                .unwrap()
+                                .insert(filter_id.to_string(), index);
+                        }
+                    }
+                    if method == "eth_uninstallFilter" {
+                        if let Some(filter_id) = self.filter_id_of(&call) {
+                            self.filters.lock().unwrap().remove(&filter_id);
+                        }
+                    }
+                    return Ok(value);
+                }
+                // revertなどノードが正常に返したエラーはエンドポイントの障害ではない
+                Ok(Err(err @ error::Error::Rpc(_))) => {
+                    return Err(err);
+                }
+                Ok(Err(err)) => {
+                    println!(
+                        "rpc error: {}, method: {}, attempt: {}, error: {:?}",
+                        endpoint.host,
+                        method,
+                        attempt + 1,
+                        err
+                    );
+                    endpoint.record_failure(&self.config, false);
+                    last_err = err;
+                }
+                Err(_) => {
+                    println!(
+                        "rpc timeout: {}, method: {}, attempt: {}",
+                        endpoint.host,
+                        method,
+                        attempt + 1
+                    );
+                    endpoint.record_failure(&self.config, true);
+                    last_err = error::Error::Transport(format!(
+                        "request timed out after {:?}",
+                        self.config.timeout
+                    ));
+                }
+            }
+        }
+
+        Err(last_err)
+    }
+
+    fn filter_id_of(&self, call: &rpc::Call) -> Option<String> {
+        match call {
+            rpc::Call::MethodCall(v) => match &v.params {
+                rpc::Params::Array(params) => Some(params.first()?.as_str()?.to_string()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Transport for FailoverTransport {
+    type Out = BoxFuture<'static, error::Result<rpc::Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        let id = self.inner.id.fetch_add(1, Ordering::AcqRel);
+        (id, helpers::build_request(id, method, params))
+    }
+
+    fn send(&self, id: RequestId, call: rpc::Call) -> Self::Out {
+        let inner = self.inner.clone();
+        Box::pin(async move { inner.send(id, call).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server, StatusCode};
+    use serde_json::json;
+    use std::convert::Infallible;
+    use std::sync::atomic::AtomicBool;
+
+    /// 受けたメソッドを記録し、指定に応じて失敗・無応答になるJSON-RPCサーバー
+    #[derive(Clone)]
+    struct Mock {
+        name: &'static str,
+        methods: Arc<Mutex<Vec<String>>>,
+        failing: Arc<AtomicBool>,
+        hanging: Arc<AtomicBool>,
+    }
+
+    impl Mock {
+        fn start(name: &'static str) -> (Self, String) {
+            let mock = Self {
+                name,
+                methods: Arc::new(Mutex::new(vec![])),
+                failing: Arc::new(AtomicBool::new(false)),
+                hanging: Arc::new(AtomicBool::new(false)),
+            };
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let state = mock.clone();
+            let make = make_service_fn(move |_| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(service_fn(move |req| state.clone().handle(req))) }
+            });
+            tokio::spawn(Server::from_tcp(listener).unwrap().serve(make));
+            (mock, url)
+        }
+
+        async fn handle(self, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            let call: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            let method = call["method"].as_str().unwrap().to_string();
+            self.methods.lock().unwrap().push(method.clone());
+
+            if self.hanging.load(Ordering::SeqCst) {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+            if self.failing.load(Ordering::SeqCst) {
+                return Ok(Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::from("unavailable"))
+                    .unwrap());
+            }
+            let result = match method.as_str() {
+                "eth_newFilter" => json!(format!("0x{}", self.name)),
+                _ => json!(self.name),
+            };
+            let res = json!({"jsonrpc": "2.0", "id": call["id"], "result": result});
+            Ok(Response::new(Body::from(res.to_string())))
+        }
+
+        fn count(&self, method: &str) -> usize {
+            self.methods
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|v| *v == method)
+                .count()
+        }
+    }
+
+    fn config() -> RetryConfig {
+        RetryConfig {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            timeout: Duration::from_millis(300),
+            failure_threshold: 1,
+            cooldown: Duration::from_secs(60),
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_next_endpoint() {
+        let (a, url_a) = Mock::start("a");
+        let (b, url_b) = Mock::start("b");
+        a.failing.store(true, Ordering::SeqCst);
+        let transport = FailoverTransport::new(&[url_a, url_b], config()).unwrap();
+
+        let value = transport.execute("eth_blockNumber", vec![]).await.unwrap();
+
+        assert_eq!(value, json!("b"));
+        assert_eq!(a.count("eth_blockNumber"), 1);
+        assert_eq!(b.count("eth_blockNumber"), 1);
+        let metrics = transport.metrics();
+        assert_eq!(metrics[0].errors, 1);
+        assert!(!metrics[0].healthy);
+        assert!(metrics[1].healthy);
+
+        // 不健全なエンドポイントは復帰まで使わない
+        transport.execute("eth_blockNumber", vec![]).await.unwrap();
+        assert_eq!(a.count("eth_blockNumber"), 1);
+        assert_eq!(b.count("eth_blockNumber"), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_non_idempotent_methods() {
+        let (a, url_a) = Mock::start("a");
+        let (b, url_b) = Mock::start("b");
+        a.failing.store(true, Ordering::SeqCst);
+        let transport = FailoverTransport::new(&[url_a, url_b], config()).unwrap();
+
+        let res = transport
+            .execute("eth_sendRawTransaction", vec![json!("0x00")])
+            .await;
+
+        assert!(res.is_err());
+        assert_eq!(a.count("eth_sendRawTransaction"), 1);
+        assert_eq!(b.count("eth_sendRawTransaction"), 0);
+    }
+
+    #[tokio::test]
+    async fn retries_after_timeout() {
+        let (a, url_a) = Mock::start("a");
+        let (_b, url_b) = Mock::start("b");
+        a.hanging.store(true, Ordering::SeqCst);
+        let transport = FailoverTransport::new(&[url_a, url_b], config()).unwrap();
+
+        let value = transport.execute("eth_chainId", vec![]).await.unwrap();
+
+        assert_eq!(value, json!("b"));
+        let metrics = transport.metrics();
+        assert_eq!(metrics[0].timeouts, 1);
+        assert_eq!(metrics[1].timeouts, 0);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let (a, url_a) = Mock::start("a");
+        let (b, url_b) = Mock::start("b");
+        a.failing.store(true, Ordering::SeqCst);
+        b.failing.store(true, Ordering::SeqCst);
+        let transport = FailoverTransport::new(&[url_a, url_b], config()).unwrap();
+
+        let res = transport.execute("eth_blockNumber", vec![]).await;
+
+        assert!(res.is_err());
+        assert_eq!(a.count("eth_blockNumber") + b.count("eth_blockNumber"), 3);
+    }
+
+    #[tokio::test]
+    async fn pins_filter_calls_to_creating_endpoint() {
+        let (a, url_a) = Mock::start("a");
+        let (b, url_b) = Mock::start("b");
+        let transport = FailoverTransport::new(&[url_a, url_b], config()).unwrap();
+
+        let filter_id = transport
+            .execute("eth_newFilter", vec![json!({})])
+            .await
+            .unwrap();
+        assert_eq!(filter_id, json!("0xa"));
+
+        // aを不健全にして、通常のリクエストはbに回るようにする
+        a.failing.store(true, Ordering::SeqCst);
+        transport.execute("eth_blockNumber", vec![]).await.unwrap();
+        assert!(!transport.metrics()[0].healthy);
+        a.failing.store(false, Ordering::SeqCst);
+
+        transport
+            .execute("eth_getFilterChanges", vec![filter_id.clone()])
+            .await
+            .unwrap();
+        assert_eq!(a.count("eth_getFilterChanges"), 1);
+        assert_eq!(b.count("eth_getFilterChanges"), 0);
+
+        transport
+            .execute("eth_uninstallFilter", vec![filter_id])
+            .await
+            .unwrap();
+        assert_eq!(a.count("eth_uninstallFilter"), 1);
+    }
+}
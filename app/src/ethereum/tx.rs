@@ -0,0 +1,149 @@
+use crate::domain::amount::Wei;
+use crate::ethereum::Client;
+use crate::{AppError, AppResult};
+use std::env;
+use std::time::Duration;
+use web3::contract::Options;
+use web3::signing::{Key, SecretKeyRef};
+use web3::types::{
+    Address, BlockNumber, Bytes, CallRequest, TransactionParameters, TransactionReceipt, H256,
+    U256, U64,
+};
+
+// 現在のgas priceにこの割合を掛けて送る
+const GAS_PRICE_PERCENT: u64 = 110;
+
+/// レシートのログから読み取ったトークンの移動
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenTransfer {
+    pub from: Address,
+    pub to: Address,
+    pub token_id: U256,
+    pub amount: U256,
+}
+
+impl Client {
+    /// 送信者のpendingのnonceと、現在のgas priceに上乗せした手数料を設定する
+    /// MAX_GAS_PRICE_GWEIを超える場合は送信しない
+    pub async fn tx_options(&self, from: Address) -> AppResult<Options> {
+        let nonce = self
+            .cli
+            .eth()
+            .transaction_count(from, Some(BlockNumber::Pending))
+            .await?;
+
+        let gas_price = self.cli.eth().gas_price().await? * U256::from(self.gas_price_percent())
+            / U256::from(100);
+
+        if let Some(max) = env::var("MAX_GAS_PRICE_GWEI")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+        {
+            let max = U256::from(max) * U256::exp10(9);
+            if gas_price > max {
+                return Err(AppError::Internal(format!(
+                    "gas price is too high: {} > {}",
+                    gas_price, max
+                )));
+            }
+        }
+
+        Ok(Options::with(|opt| {
+            opt.nonce = Some(nonce);
+            opt.gas_price = Some(gas_price);
+        }))
+    }
+
+    /// gasを使うトランザクションの手数料の見積もり。tx_optionsと同じgas priceで計算する
+    pub async fn estimate_fee(&self, gas: u64) -> AppResult<Wei> {
+        let gas_price = self.cli.eth().gas_price().await? * U256::from(self.gas_price_percent())
+            / U256::from(100);
+        Ok(Wei::new(gas_price * U256::from(gas)))
+    }
+
+    fn gas_price_percent(&self) -> u64 {
+        env::var("GAS_PRICE_PERCENT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(GAS_PRICE_PERCENT)
+    }
+
+    /// 鍵で署名して送り、確定を待ってトランザクションハッシュを返す。失敗したトランザクションはエラーにする
+    pub async fn send_call(
+        &self,
+        key: SecretKeyRef<'_>,
+        to: Address,
+        data: Bytes,
+        value: U256,
+    ) -> AppResult<H256> {
+        let nonce = self
+            .cli
+            .eth()
+            .transaction_count(key.address(), Some(BlockNumber::Pending))
+            .await?;
+        let gas = self
+            .cli
+            .eth()
+            .estimate_gas(
+                CallRequest {
+                    from: Some(key.address()),
+                    to: Some(to),
+                    value: Some(value),
+                    data: Some(data.clone()),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+
+        let signed = self
+            .cli
+            .accounts()
+            .sign_transaction(
+                TransactionParameters {
+                    nonce: Some(nonce),
+                    to: Some(to),
+                    gas: gas * 12 / 10,
+                    value,
+                    data,
+                    ..Default::default()
+                },
+                key,
+            )
+            .await?;
+        let receipt = self
+            .cli
+            .send_raw_transaction_with_confirmation(
+                signed.raw_transaction,
+                Duration::from_secs(1),
+                self.chain.confirmations,
+            )
+            .await?;
+        if receipt.status != Some(U64::from(1)) {
+            return Err(AppError::Internal(format!(
+                "transaction failed: {:?}",
+                receipt.transaction_hash
+            )));
+        }
+
+        Ok(receipt.transaction_hash)
+    }
+
+    pub fn ensure_success(&self, receipt: &TransactionReceipt) -> AppResult<()> {
+        println!(
+            "tx: {}",
+            self.chain
+                .tx_url(&format!("{:?}", receipt.transaction_hash))
+        );
+        println!("gas used: {:?}", receipt.gas_used);
+        println!("status: {:?}", receipt.status);
+
+        if receipt.status != Some(U64::from(1)) {
+            return Err(AppError::Internal(format!(
+                "transaction failed: {:?}",
+                receipt.transaction_hash
+            )));
+        }
+        Ok(())
+    }
+}
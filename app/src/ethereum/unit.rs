@@ -1,160 +1,212 @@
-extern crate bigdecimal;
-extern crate regex;
-
-use bigdecimal::BigDecimal;
-use regex::Regex;
-use std::collections::HashMap;
-use std::ops::Mul;
-use std::process;
+use crate::{AppError, AppResult};
 use std::str::FromStr;
-
-pub fn convert<'a>(value: &str, unit: &'a str) -> HashMap<&'a str, String> {
-    let v = to_ether(value, unit);
-    let mut map: HashMap<&'a str, String> = HashMap::new();
-
-    map.insert(unit, BigDecimal::from_str(&value).unwrap().to_string());
-
-    if unit != "wei" {
-        map.insert("wei", s(&v, "1000000000000000000"));
-    }
-    if unit != "kwei" {
-        map.insert("kwei", s(&v, "1000000000000000"));
-    }
-    if unit != "mwei" {
-        map.insert("mwei", s(&v, "1000000000000"));
-    }
-    if unit != "gwei" {
-        map.insert("gwei", s(&v, "1000000000"));
-    }
-    if unit != "szabo" {
-        map.insert("szabo", s(&v, "1000000"));
-    }
-    if unit != "finney" {
-        map.insert("finney", s(&v, "1000"));
-    }
-    if unit != "ether" {
-        map.insert("ether", s(&v, "1"));
-    }
-    if unit != "kether" {
-        map.insert("kether", s(&v, "0.001"));
-    }
-    if unit != "mether" {
-        map.insert("mether", s(&v, "0.000001"));
-    }
-    if unit != "gether" {
-        map.insert("gether", s(&v, "0.000000001"));
-    }
-    if unit != "tether" {
-        map.insert("tether", s(&v, "0.000000000001"));
-    }
-
-    return map;
+use strum_macros::Display as StrumDisplay;
+use web3::types::U256;
+
+#[derive(PartialEq, Clone, Copy, Debug, StrumDisplay)]
+#[strum(serialize_all = "lowercase")]
+pub enum Unit {
+    Wei,
+    Kwei,
+    Mwei,
+    Gwei,
+    Szabo,
+    Finney,
+    Ether,
+    Kether,
+    Mether,
+    Gether,
+    Tether,
 }
 
-pub fn to_ether(value: &str, unit: &str) -> BigDecimal {
-    let v = BigDecimal::from_str(&value).unwrap();
-
-    if unit == "wei" {
-        return m(&v, "0.000000000000000001");
-    }
-    if unit == "kwei" {
-        return m(&v, "0.000000000000001");
-    }
-    if unit == "mwei" {
-        return m(&v, "0.000000000001");
-    }
-    if unit == "gwei" {
-        return m(&v, "0.000000001");
-    }
-    if unit == "szabo" {
-        return m(&v, "0.000001");
-    }
-    if unit == "finney" {
-        return m(&v, "0.001");
+impl Unit {
+    /// 1単位あたりのweiの桁数
+    pub fn decimals(&self) -> usize {
+        match self {
+            Unit::Wei => 0,
+            Unit::Kwei => 3,
+            Unit::Mwei => 6,
+            Unit::Gwei => 9,
+            Unit::Szabo => 12,
+            Unit::Finney => 15,
+            Unit::Ether => 18,
+            Unit::Kether => 21,
+            Unit::Mether => 24,
+            Unit::Gether => 27,
+            Unit::Tether => 30,
+        }
     }
-    if unit == "ether" {
-        return m(&v, "1");
-    }
-    if unit == "kether" {
-        return m(&v, "1000");
-    }
-    if unit == "mether" {
-        return m(&v, "1000000");
-    }
-    if unit == "gether" {
-        return m(&v, "1000000000");
-    }
-    if unit == "tether" {
-        return m(&v, "1000000000000");
-    }
-
-    println!("unit not supported");
-    process::exit(1);
-}
-
-#[allow(dead_code)]
-pub fn to_wei(value: &str, unit: &str) -> String {
-    return convert(&value, &unit).get("wei").unwrap().to_string();
-}
-
-#[allow(dead_code)]
-pub fn to_kwei(value: &str, unit: &str) -> String {
-    return convert(&value, &unit).get("kwei").unwrap().to_string();
 }
 
-#[allow(dead_code)]
-pub fn to_mwei(value: &str, unit: &str) -> String {
-    return convert(&value, &unit).get("mwei").unwrap().to_string();
-}
-
-#[allow(dead_code)]
-pub fn to_gwei(value: &str, unit: &str) -> String {
-    return convert(&value, &unit).get("gwei").unwrap().to_string();
-}
-
-#[allow(dead_code)]
-pub fn to_szabo(value: &str, unit: &str) -> String {
-    return convert(&value, &unit).get("szabo").unwrap().to_string();
+impl FromStr for Unit {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> AppResult<Self> {
+        match s {
+            "wei" => Ok(Unit::Wei),
+            "kwei" => Ok(Unit::Kwei),
+            "mwei" => Ok(Unit::Mwei),
+            "gwei" => Ok(Unit::Gwei),
+            "szabo" => Ok(Unit::Szabo),
+            "finney" => Ok(Unit::Finney),
+            "ether" => Ok(Unit::Ether),
+            "kether" => Ok(Unit::Kether),
+            "mether" => Ok(Unit::Mether),
+            "gether" => Ok(Unit::Gether),
+            "tether" => Ok(Unit::Tether),
+            _ => Err(AppError::BadRequest(format!("unit not supported: {}", s))),
+        }
+    }
 }
 
-#[allow(dead_code)]
-pub fn to_finney(value: &str, unit: &str) -> String {
-    return convert(&value, &unit).get("finney").unwrap().to_string();
+/// "1.5" のような10進数の文字列をweiに変換する
+/// 単位の桁数を超える小数や、U256に収まらない値はエラーにする
+pub fn parse_units(value: &str, unit: Unit) -> AppResult<U256> {
+    parse_decimals(value, unit.decimals())
 }
 
-#[allow(dead_code)]
-pub fn to_kether(value: &str, unit: &str) -> String {
-    return convert(&value, &unit).get("kether").unwrap().to_string();
+/// 10進数の文字列を、decimals桁の最小単位の値に変換する
+pub fn parse_decimals(value: &str, decimals: usize) -> AppResult<U256> {
+    let invalid = || AppError::BadRequest(format!("金額が不正です: {}", value));
+
+    let value = value.trim();
+    let (integer, fraction) = match value.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction.trim_end_matches('0')),
+        None => (value, ""),
+    };
+    if (integer.is_empty() && fraction.is_empty())
+        || !integer.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    if fraction.len() > decimals {
+        return Err(AppError::BadRequest(format!(
+            "小数は{}桁までです: {}",
+            decimals, value
+        )));
+    }
+
+    let digits = format!(
+        "{}{}{}",
+        integer,
+        fraction,
+        "0".repeat(decimals - fraction.len())
+    );
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(U256::zero());
+    }
+
+    U256::from_dec_str(digits).map_err(|_e| invalid())
 }
 
-#[allow(dead_code)]
-pub fn to_mether(value: &str, unit: &str) -> String {
-    return convert(&value, &unit).get("mether").unwrap().to_string();
+/// weiを指定した単位の10進数の文字列にする（末尾の0は省く）
+pub fn format_units(value: U256, unit: Unit) -> String {
+    format_decimals(value, unit.decimals())
 }
 
-#[allow(dead_code)]
-pub fn to_gether(value: &str, unit: &str) -> String {
-    return convert(&value, &unit).get("gether").unwrap().to_string();
-}
+/// ERC20などdecimalsが任意のトークンの最小単位の値を10進数の文字列にする
+pub fn format_decimals(value: U256, decimals: usize) -> String {
+    let digits = value.to_string();
+    if decimals == 0 {
+        return digits;
+    }
 
-#[allow(dead_code)]
-pub fn to_tether(value: &str, unit: &str) -> String {
-    return convert(&value, &unit).get("tether").unwrap().to_string();
-}
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        return integer.to_string();
+    }
 
-fn m(v: &BigDecimal, u: &str) -> BigDecimal {
-    return v.mul(&BigDecimal::from_str(u).unwrap());
+    format!("{}.{}", integer, fraction)
 }
 
-fn s(v: &BigDecimal, u: &str) -> String {
-    return t(v.mul(&BigDecimal::from_str(u).unwrap()).to_string());
+pub fn convert(value: &str, from: Unit, to: Unit) -> AppResult<String> {
+    Ok(format_units(parse_units(value, from)?, to))
 }
 
-// normalize decimal places
-// TODO: better way
-fn t(v: String) -> String {
-    let re = Regex::new(r"(.*)\.0+$").unwrap();
-    let v = re.replace_all(&v, "$1").to_string();
-    let re = Regex::new(r"(.*\.\d+[1-9]+)(0+)$").unwrap();
-    return re.replace_all(&v, "$1").to_string();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_decimal_strings() {
+        assert_eq!(
+            parse_units("1.5", Unit::Ether).unwrap(),
+            U256::from(1_500_000_000_000_000_000u128)
+        );
+        assert_eq!(
+            parse_units(".5", Unit::Gwei).unwrap(),
+            U256::from(500_000_000)
+        );
+        assert_eq!(parse_units("2.", Unit::Kwei).unwrap(), U256::from(2000));
+        assert_eq!(parse_units(" 007 ", Unit::Wei).unwrap(), U256::from(7));
+        assert_eq!(parse_units("0.000", Unit::Ether).unwrap(), U256::zero());
+        // 末尾の0は桁数に数えない
+        assert_eq!(
+            parse_units("1.0000000000000000010000", Unit::Ether).unwrap(),
+            U256::from(1_000_000_000_000_000_001u128)
+        );
+        assert_eq!(
+            parse_decimals("12.345678", 6).unwrap(),
+            U256::from(12_345_678)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_amounts() {
+        for value in [
+            "", " ", ".", "-1", "+1", "1.2.3", "1e18", "0x10", "1,000", "１",
+        ] {
+            assert!(parse_units(value, Unit::Ether).is_err(), "{}", value);
+        }
+
+        // 丸めずにエラーにする
+        assert!(parse_units("0.0000000000000000001", Unit::Ether).is_err());
+        assert!(parse_units("1.5", Unit::Wei).is_err());
+        assert!(parse_decimals("0.1234567", 6).is_err());
+
+        let max = U256::MAX.to_string();
+        assert_eq!(parse_units(&max, Unit::Wei).unwrap(), U256::MAX);
+        assert!(parse_units(&format!("{}0", max), Unit::Wei).is_err());
+        assert!(parse_units(&max, Unit::Kwei).is_err());
+    }
+
+    #[test]
+    fn formats_decimals() {
+        assert_eq!(format_decimals(U256::zero(), 18), "0");
+        assert_eq!(format_decimals(U256::from(1), 18), "0.000000000000000001");
+        assert_eq!(format_decimals(U256::from(1_500_000), 6), "1.5");
+        assert_eq!(format_decimals(U256::from(2_000_000), 6), "2");
+        assert_eq!(format_decimals(U256::from(123), 0), "123");
+        assert_eq!(format_units(U256::MAX, Unit::Wei), U256::MAX.to_string());
+        assert_eq!(
+            format_units(U256::from(1_230_000_000u64), Unit::Gwei),
+            "1.23"
+        );
+    }
+
+    #[test]
+    fn round_trips_between_units() {
+        assert_eq!(
+            convert("1.5", Unit::Ether, Unit::Gwei).unwrap(),
+            "1500000000"
+        );
+        assert_eq!(
+            convert("1", Unit::Wei, Unit::Ether).unwrap(),
+            "0.000000000000000001"
+        );
+        assert!(convert("abc", Unit::Ether, Unit::Wei).is_err());
+
+        let value =
+            "115792089237316195423570985008687907853269984665640564039457.584007913129639935";
+        assert_eq!(
+            format_units(parse_units(value, Unit::Ether).unwrap(), Unit::Ether),
+            value
+        );
+        assert_eq!("gwei".parse::<Unit>().unwrap(), Unit::Gwei);
+        assert!("GWEI".parse::<Unit>().is_err());
+    }
 }
@@ -1,9 +1,10 @@
+use crate::domain::amount::Wei;
 use crate::domain::user::User;
 use crate::ethereum::Client;
 use crate::AppResult;
 
 impl Client {
-    pub async fn get_balance(&self, user: &User) -> AppResult<u128> {
+    pub async fn get_balance(&self, user: &User) -> AppResult<Wei> {
         let balance = self
             .cli
             .eth()
@@ -12,6 +13,6 @@ impl Client {
                 None,
             )
             .await?;
-        Ok(balance.as_u128())
+        Ok(Wei::new(balance))
     }
 }
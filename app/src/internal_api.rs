@@ -27,6 +27,15 @@ impl Client {
         }
     }
 
+    /// 呼び出さないテストで、環境変数なしで作る
+    #[cfg(test)]
+    pub fn for_test() -> Self {
+        Client {
+            base_url: "http://127.0.0.1".parse().unwrap(),
+            token: "".to_string(),
+        }
+    }
+
     pub async fn call(&self, req: Request<Body>) -> AppResult<Body> {
         let client = hyper::Client::builder().build::<_, hyper::Body>(HttpsConnector::new());
         let response = client.request(req).await.unwrap();
@@ -0,0 +1,164 @@
+mod cid;
+mod kubo;
+#[cfg(any(test, feature = "memory-marketplace"))]
+mod memory;
+mod pinata;
+
+use crate::domain::pin::PinStatus;
+use crate::{AppError, AppResult};
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::env;
+use std::sync::Arc;
+
+pub use cid::{compute, Cid};
+pub use kubo::KuboProvider;
+#[cfg(any(test, feature = "memory-marketplace"))]
+pub use memory::MemoryProvider;
+pub use pinata::PinataProvider;
+
+/// IPFSにデータを置いてピン留めするサービス
+#[async_trait]
+pub trait PinningProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// データを追加してピン留めし、サービスが返したCIDを返す
+    async fn add(&self, name: &str, data: Bytes) -> AppResult<String>;
+
+    async fn status(&self, cid: &str) -> AppResult<PinStatus>;
+}
+
+const DEFAULT_GATEWAY_URL: &str = "https://ipfs.io/ipfs";
+
+/// ipfs://のURLを環境変数 IPFS_GATEWAY_URL のゲートウェイのURLにする。それ以外はそのまま
+pub fn gateway_url(uri: &str) -> String {
+    match uri.strip_prefix("ipfs://") {
+        Some(path) => format!(
+            "{}/{}",
+            env::var("IPFS_GATEWAY_URL")
+                .unwrap_or_else(|_| DEFAULT_GATEWAY_URL.to_string())
+                .trim_end_matches('/'),
+            path.trim_start_matches("ipfs/")
+        ),
+        None => uri.to_string(),
+    }
+}
+
+/// ipfs://のURLのCIDが、データから計算したものと一致するか
+/// 手元で計算できるのはこのモジュールで追加したCIDv1だけなので、それ以外はNone
+pub fn matches_cid(uri: &str, data: &[u8]) -> Option<bool> {
+    let cid = uri.strip_prefix("ipfs://")?.trim_start_matches("ipfs/");
+    if (!cid.starts_with("bafk") && !cid.starts_with("bafy")) || cid.contains('/') {
+        return None;
+    }
+    Some(compute(data).to_string() == cid)
+}
+
+/// 環境変数 IPFS_PROVIDER で使うサービスを選ぶ。未指定や不明な値ならpanicする
+/// memoryはmemory-marketplaceのfeatureを有効にしたビルドでのみ使える
+pub fn from_env() -> Arc<dyn PinningProvider> {
+    let provider = env::var("IPFS_PROVIDER").expect("should set ipfs provider");
+    match provider.as_str() {
+        #[cfg(any(test, feature = "memory-marketplace"))]
+        "memory" => memory::shared(),
+        "kubo" => Arc::new(KuboProvider::new()),
+        "pinata" => Arc::new(PinataProvider::new()),
+        other => panic!("unsupported ipfs provider in this build: {}", other),
+    }
+}
+
+#[derive(Clone)]
+pub struct Client {
+    provider: Arc<dyn PinningProvider>,
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Self {
+            provider: from_env(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn memory() -> Self {
+        Self {
+            provider: memory::shared(),
+        }
+    }
+
+    pub fn provider_name(&self) -> &'static str {
+        self.provider.name()
+    }
+
+    /// 手元で計算したCIDとサービスが返したCIDが一致することを確かめてから返す
+    pub async fn add(&self, name: &str, data: Bytes) -> AppResult<String> {
+        let expected = compute(&data).to_string();
+        let actual = self.provider.add(name, data).await?;
+        if actual != expected {
+            return Err(AppError::Internal(format!(
+                "CIDが一致しません: {} expected={}, actual={}",
+                name, expected, actual
+            )));
+        }
+        Ok(expected)
+    }
+
+    pub async fn status(&self, cid: &str) -> AppResult<PinStatus> {
+        self.provider.status(cid).await
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// multipart/form-dataの本文を組み立てる
+struct Multipart {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl Multipart {
+    fn new() -> Self {
+        Self {
+            boundary: format!("----canvas-nft-{}", uuid::Uuid::new_v4()),
+            body: vec![],
+        }
+    }
+
+    fn text(mut self, name: &str, value: &str) -> Self {
+        self.body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                self.boundary, name, value
+            )
+            .as_bytes(),
+        );
+        self
+    }
+
+    fn file(mut self, name: &str, file_name: &str, data: &[u8]) -> Self {
+        self.body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+                self.boundary, name, file_name
+            )
+            .as_bytes(),
+        );
+        self.body.extend_from_slice(data);
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    /// (Content-Type, 本文)
+    fn finish(mut self) -> (String, Vec<u8>) {
+        self.body
+            .extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        (
+            format!("multipart/form-data; boundary={}", self.boundary),
+            self.body,
+        )
+    }
+}
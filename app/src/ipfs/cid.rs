@@ -0,0 +1,186 @@
+use ring::digest::{digest, SHA256};
+use std::fmt;
+
+// kuboの `ipfs add --cid-version=1` の既定値に合わせる
+const CHUNK_SIZE: usize = 262144;
+const MAX_LINKS: usize = 174;
+
+const CODEC_RAW: u64 = 0x55;
+const CODEC_DAG_PB: u64 = 0x70;
+const MULTIHASH_SHA2_256: u64 = 0x12;
+const UNIXFS_FILE: u64 = 2;
+
+const BASE32_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// CIDv1（sha2-256）
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cid {
+    codec: u64,
+    digest: Vec<u8>,
+}
+
+impl Cid {
+    fn of(codec: u64, data: &[u8]) -> Self {
+        Self {
+            codec,
+            digest: digest(&SHA256, data).as_ref().to_vec(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![];
+        put_varint(&mut out, 1);
+        put_varint(&mut out, self.codec);
+        put_varint(&mut out, MULTIHASH_SHA2_256);
+        put_varint(&mut out, self.digest.len() as u64);
+        out.extend_from_slice(&self.digest);
+        out
+    }
+}
+
+/// multibaseのbase32（小文字・パディングなし）
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.to_bytes();
+        let mut out = String::from("b");
+        let mut buffer: u32 = 0;
+        let mut bits = 0;
+        for byte in bytes {
+            // 未出力のビットは最大12bitなので下位16bitだけ残す
+            buffer = ((buffer << 8) | byte as u32) & 0xffff;
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+            }
+        }
+        if bits > 0 {
+            out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+        }
+        write!(f, "{}", out)
+    }
+}
+
+struct DagNode {
+    cid: Cid,
+    file_size: u64,
+    /// 配下のブロックを含めた大きさ
+    tsize: u64,
+}
+
+/// UnixFSのファイルとして追加したときのCIDを計算する
+/// 256KiBの固定長で分割してraw leavesにし、1ノード174リンクのbalanced layoutで束ねる
+pub fn compute(data: &[u8]) -> Cid {
+    let mut nodes: Vec<DagNode> = if data.is_empty() {
+        vec![leaf(data)]
+    } else {
+        data.chunks(CHUNK_SIZE).map(leaf).collect()
+    };
+    if nodes.len() == 1 {
+        return nodes.remove(0).cid;
+    }
+
+    while nodes.len() > 1 {
+        nodes = nodes.chunks(MAX_LINKS).map(parent).collect();
+    }
+    nodes.remove(0).cid
+}
+
+fn leaf(chunk: &[u8]) -> DagNode {
+    DagNode {
+        cid: Cid::of(CODEC_RAW, chunk),
+        file_size: chunk.len() as u64,
+        tsize: chunk.len() as u64,
+    }
+}
+
+/// dag-pbのノード（Links、Dataの順で書く）
+fn parent(children: &[DagNode]) -> DagNode {
+    let file_size: u64 = children.iter().map(|v| v.file_size).sum();
+
+    let mut unixfs = vec![];
+    put_varint_field(&mut unixfs, 1, UNIXFS_FILE);
+    put_varint_field(&mut unixfs, 3, file_size);
+    for child in children {
+        put_varint_field(&mut unixfs, 4, child.file_size);
+    }
+
+    let mut node = vec![];
+    for child in children {
+        let mut link = vec![];
+        put_bytes_field(&mut link, 1, &child.cid.to_bytes());
+        put_bytes_field(&mut link, 2, b"");
+        put_varint_field(&mut link, 3, child.tsize);
+        put_bytes_field(&mut node, 2, &link);
+    }
+    put_bytes_field(&mut node, 1, &unixfs);
+
+    DagNode {
+        cid: Cid::of(CODEC_DAG_PB, &node),
+        file_size,
+        tsize: node.len() as u64 + children.iter().map(|v| v.tsize).sum::<u64>(),
+    }
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(out, field << 3);
+    put_varint(out, value);
+}
+
+fn put_bytes_field(out: &mut Vec<u8>, field: u64, value: &[u8]) {
+    put_varint(out, (field << 3) | 2);
+    put_varint(out, value.len() as u64);
+    out.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn computes_single_block_cid() {
+        // `ipfs add --cid-version=1 --raw-leaves` の結果
+        assert_eq!(
+            compute(b"").to_string(),
+            "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+        );
+        assert_eq!(
+            compute(b"hello world").to_string(),
+            "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+        );
+        // ちょうど1チャンクならraw leafのまま
+        assert_eq!(
+            compute(&pattern(CHUNK_SIZE)).to_string(),
+            "bafkreibruh455iawsviqslif5c7uurdcfdemh22mtnytyzvnzn75kpejxy"
+        );
+    }
+
+    #[test]
+    fn computes_multi_chunk_cid() {
+        assert_eq!(
+            compute(&pattern(2 * CHUNK_SIZE + 1000)).to_string(),
+            "bafybeiedlmc6ukelkdnmjelnxg565gp6jy3ued6onc4pv7p5qiohksmq3e"
+        );
+    }
+
+    #[test]
+    fn computes_two_level_cid() {
+        // 174チャンクを超えると木が2段になる
+        assert_eq!(
+            compute(&pattern((MAX_LINKS + 1) * CHUNK_SIZE + 100)).to_string(),
+            "bafybeidwpjzq4iygyts7wi6ncqnk5gpnbi4ua2hapafbbcbhniu6a5sqze"
+        );
+    }
+}
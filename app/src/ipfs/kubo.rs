@@ -0,0 +1,80 @@
+use crate::domain::pin::PinStatus;
+use crate::ipfs::{Multipart, PinningProvider};
+use crate::AppResult;
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::header::CONTENT_TYPE;
+use serde::Deserialize;
+use std::env;
+
+/// kuboのHTTP API。ローカルのノードでも動かせる
+pub struct KuboProvider {
+    base_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddResponse {
+    #[serde(rename(deserialize = "Hash"))]
+    hash: String,
+}
+
+impl KuboProvider {
+    pub fn new() -> Self {
+        let base_url =
+            env::var("IPFS_API_URL").unwrap_or_else(|_| "http://127.0.0.1:5001".to_string());
+
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+impl Default for KuboProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl PinningProvider for KuboProvider {
+    fn name(&self) -> &'static str {
+        "kubo"
+    }
+
+    async fn add(&self, name: &str, data: Bytes) -> AppResult<String> {
+        let (content_type, body) = Multipart::new().file("file", name, &data).finish();
+
+        // CIDを手元の計算と合わせるため、cid-version=1とraw-leavesを指定する
+        let res: AddResponse = reqwest::Client::new()
+            .post(format!(
+                "{}/api/v0/add?cid-version=1&raw-leaves=true&pin=true",
+                self.base_url
+            ))
+            .header(CONTENT_TYPE, content_type)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(res.hash)
+    }
+
+    async fn status(&self, cid: &str) -> AppResult<PinStatus> {
+        let res = reqwest::Client::new()
+            .post(format!(
+                "{}/api/v0/pin/ls?arg={}&type=recursive",
+                self.base_url, cid
+            ))
+            .send()
+            .await?;
+
+        // ピン留めされていなければエラーが返る
+        if res.status().is_success() {
+            Ok(PinStatus::Pinned)
+        } else {
+            Ok(PinStatus::Failed)
+        }
+    }
+}
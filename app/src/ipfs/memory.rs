@@ -0,0 +1,42 @@
+use crate::domain::pin::PinStatus;
+use crate::ipfs::{compute, PinningProvider};
+use crate::AppResult;
+use async_trait::async_trait;
+use bytes::Bytes;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// 同じプロセスのApplication間で状態を共有する
+static SHARED: Lazy<Arc<MemoryProvider>> = Lazy::new(|| Arc::new(MemoryProvider::default()));
+
+pub fn shared() -> Arc<dyn super::PinningProvider> {
+    SHARED.clone()
+}
+
+/// ローカル開発や動作確認用の、メモリ上だけのピン留め
+#[derive(Default)]
+pub struct MemoryProvider {
+    blocks: Mutex<HashMap<String, Bytes>>,
+}
+
+#[async_trait]
+impl PinningProvider for MemoryProvider {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn add(&self, _name: &str, data: Bytes) -> AppResult<String> {
+        let cid = compute(&data).to_string();
+        self.blocks.lock().unwrap().insert(cid.clone(), data);
+        Ok(cid)
+    }
+
+    async fn status(&self, cid: &str) -> AppResult<PinStatus> {
+        if self.blocks.lock().unwrap().contains_key(cid) {
+            Ok(PinStatus::Pinned)
+        } else {
+            Ok(PinStatus::Failed)
+        }
+    }
+}
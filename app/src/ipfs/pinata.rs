@@ -0,0 +1,92 @@
+use crate::domain::pin::PinStatus;
+use crate::ipfs::{Multipart, PinningProvider};
+use crate::AppResult;
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use serde::Deserialize;
+use std::env;
+
+const BASE_URL: &str = "https://api.pinata.cloud";
+
+/// Pinataのピン留めサービス
+pub struct PinataProvider {
+    jwt: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PinFileResponse {
+    #[serde(rename(deserialize = "IpfsHash"))]
+    ipfs_hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PinListResponse {
+    count: u64,
+}
+
+impl PinataProvider {
+    pub fn new() -> Self {
+        let jwt = env::var("PINATA_JWT").expect("should set pinata jwt");
+
+        Self { jwt }
+    }
+}
+
+impl Default for PinataProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl PinningProvider for PinataProvider {
+    fn name(&self) -> &'static str {
+        "pinata"
+    }
+
+    async fn add(&self, name: &str, data: Bytes) -> AppResult<String> {
+        // cidVersion 1ではkuboと同じくraw leavesになる。食い違えばClient::addで検出する
+        let (content_type, body) = Multipart::new()
+            .file("file", name, &data)
+            .text("pinataOptions", r#"{"cidVersion":1}"#)
+            .text(
+                "pinataMetadata",
+                &serde_json::json!({ "name": name }).to_string(),
+            )
+            .finish();
+
+        let res: PinFileResponse = reqwest::Client::new()
+            .post(format!("{}/pinning/pinFileToIPFS", BASE_URL))
+            .header(AUTHORIZATION, format!("Bearer {}", self.jwt))
+            .header(CONTENT_TYPE, content_type)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(res.ipfs_hash)
+    }
+
+    async fn status(&self, cid: &str) -> AppResult<PinStatus> {
+        let res: PinListResponse = reqwest::Client::new()
+            .get(format!(
+                "{}/data/pinList?hashContains={}&status=pinned",
+                BASE_URL, cid
+            ))
+            .header(AUTHORIZATION, format!("Bearer {}", self.jwt))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if res.count > 0 {
+            Ok(PinStatus::Pinned)
+        } else {
+            Ok(PinStatus::Failed)
+        }
+    }
+}
@@ -1,12 +1,16 @@
 pub mod application;
+pub mod artwork;
 pub mod aws;
 mod csv_loader;
 pub mod dataloader;
+mod datetime;
 mod ddb;
 pub mod domain;
-mod ethereum;
+pub mod ethereum;
 pub mod internal_api;
-mod open_sea;
+pub mod ipfs;
+pub mod marketplace;
+pub mod open_sea;
 
 use aws_sdk_cognitoidentityprovider::error::{
     AdminCreateUserError, AdminGetUserError, AdminSetUserPasswordError,
@@ -16,7 +20,6 @@ use aws_sdk_dynamodb::error::{
     ScanError,
 };
 use aws_sdk_dynamodb::types::SdkError;
-use aws_sdk_lambda::error::InvokeError;
 use aws_sdk_s3::error::{GetObjectError, GetObjectErrorKind, PutObjectError};
 use aws_sdk_sesv2::error::SendEmailError;
 use aws_sdk_sns::error::PublishError;
@@ -27,6 +30,8 @@ pub const WORK_CSV_PATH_PREFIX: &str = "work_csv";
 pub const THUMBNAIL_CSV_PATH_PREFIX: &str = "thumbnail_csv";
 pub const ERC721_ASSET_PATH_PREFIX: &str = "721_asset";
 pub const ERC1155_ASSET_PATH_PREFIX: &str = "1155_asset";
+pub const CONTRACT_ARTIFACT_PATH_PREFIX: &str = "contract_artifact";
+pub const SALES_CSV_PATH_PREFIX: &str = "sales_csv";
 
 #[derive(ThisErr, Debug, PartialOrd, PartialEq, Clone)]
 pub enum AppError {
@@ -40,6 +45,8 @@ pub enum AppError {
     NotFound,
     #[error("サーバーエラーです: {0}")]
     Internal(String),
+    #[error("外部APIのレートリミットに達しました。{0}秒後に再試行してください")]
+    RateLimited(u64),
 }
 
 pub type AppResult<T> = Result<T, AppError>;
@@ -97,13 +104,6 @@ impl From<SdkError<DeleteItemError>> for AppError {
     }
 }
 
-impl From<SdkError<InvokeError>> for AppError {
-    fn from(e: SdkError<InvokeError>) -> Self {
-        let msg = format!("lambda invoke error: {:?}", e);
-        Self::Internal(msg)
-    }
-}
-
 impl From<SdkError<GetObjectError>> for AppError {
     fn from(e: SdkError<GetObjectError>) -> Self {
         match &e {
@@ -179,6 +179,9 @@ impl From<reqwest::Error> for AppError {
         if code == StatusCode::from_u16(404).unwrap() {
             return Self::NotFound;
         }
+        if code == StatusCode::from_u16(429).unwrap() {
+            return Self::RateLimited(0);
+        }
 
         let msg = format!("http error: {:?}", e);
         Self::Internal(msg)
@@ -213,6 +216,17 @@ impl From<jsonwebtoken::errors::Error> for AppError {
     }
 }
 
+impl From<image::ImageError> for AppError {
+    fn from(e: image::ImageError) -> Self {
+        match e {
+            image::ImageError::Decoding(_) | image::ImageError::Unsupported(_) => {
+                Self::BadRequest(format!("画像を読み込めません: {}", e))
+            }
+            _ => Self::Internal(format!("image error: {:?}", e)),
+        }
+    }
+}
+
 impl From<web3::Error> for AppError {
     fn from(e: web3::Error) -> Self {
         let msg = format!("web3 error: {:?}", e);
@@ -0,0 +1,78 @@
+#[cfg(any(test, feature = "memory-marketplace"))]
+mod memory;
+mod open_sea;
+
+use crate::domain::amount::Wei;
+use crate::domain::market::{Listing, ListingOrder, MarketAsset, Offer, SaleEvent, TokenRef};
+use crate::domain::user::User;
+use crate::ethereum::chain::ChainProfile;
+use crate::AppResult;
+use async_trait::async_trait;
+use std::env;
+use std::sync::Arc;
+
+#[cfg(any(test, feature = "memory-marketplace"))]
+pub use memory::MemoryMarketplace;
+pub use open_sea::OpenSeaMarketplace;
+
+/// NFTの売買を扱うマーケットプレイス
+#[async_trait]
+pub trait Marketplace: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// 売買ごとにマーケットプレイスが取る手数料（basis points）
+    fn fee_basis_points(&self) -> u64;
+
+    /// ERC20建ての代金を買い手から引き出すアドレス。承認が要らなければNone
+    fn payment_spender(&self) -> Option<String>;
+
+    async fn get_asset(&self, token: &TokenRef) -> AppResult<MarketAsset>;
+
+    /// priceは1つあたりの価格。期限の指定がなければマーケットプレイスのデフォルトの期間で出品する
+    async fn list_for_sale(
+        &self,
+        seller: &User,
+        token: &TokenRef,
+        price: Wei,
+        quantity: u64,
+        expires_at: Option<i64>,
+    ) -> AppResult<ListingOrder>;
+
+    async fn cancel_listing(
+        &self,
+        seller: &User,
+        token: &TokenRef,
+        order: &ListingOrder,
+    ) -> AppResult<()>;
+
+    async fn get_listings(&self, token: &TokenRef) -> AppResult<Vec<Listing>>;
+
+    async fn get_offers(&self, token: &TokenRef) -> AppResult<Vec<Offer>>;
+
+    /// 申し込みを出品者（保有者）のウォレットで約定し、トランザクションハッシュを返す
+    async fn accept_offer(
+        &self,
+        seller: &User,
+        token: &TokenRef,
+        offer_id: &str,
+    ) -> AppResult<String>;
+
+    async fn get_sales(&self, token: &TokenRef) -> AppResult<Vec<SaleEvent>>;
+
+    /// 指定の注文をその価格以下で買い手のウォレットで約定し、トランザクションハッシュを返す
+    async fn buy(&self, buyer: &User, token: &TokenRef, listing: &Listing) -> AppResult<String>;
+
+    /// metadataの再取得を依頼する。対応していなければfalse
+    async fn refresh_metadata(&self, token: &TokenRef) -> AppResult<bool>;
+}
+
+/// 環境変数 MARKETPLACE で使うマーケットプレイスを選ぶ。未指定ならOpenSea
+/// memoryはmemory-marketplaceのfeatureを有効にしたビルドでのみ使える
+pub fn from_env(chain: &ChainProfile) -> Arc<dyn Marketplace> {
+    match env::var("MARKETPLACE").unwrap_or_default().as_str() {
+        #[cfg(any(test, feature = "memory-marketplace"))]
+        "memory" => memory::shared(),
+        "" | "opensea" => Arc::new(OpenSeaMarketplace::new(chain)),
+        other => panic!("unsupported marketplace in this build: {}", other),
+    }
+}
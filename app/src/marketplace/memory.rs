@@ -0,0 +1,278 @@
+use crate::domain::amount::Wei;
+use crate::domain::market::{Listing, ListingOrder, MarketAsset, Offer, SaleEvent, TokenRef};
+use crate::domain::user::User;
+use crate::marketplace::Marketplace;
+use crate::{AppError, AppResult};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use web3::signing::keccak256;
+use web3::types::H256;
+
+const LISTING_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+// 同じプロセスのApplication間で状態を共有する
+static SHARED: Lazy<Arc<MemoryMarketplace>> = Lazy::new(|| Arc::new(MemoryMarketplace::new()));
+
+pub fn shared() -> Arc<dyn Marketplace> {
+    SHARED.clone()
+}
+
+#[derive(Default)]
+struct State {
+    assets: HashMap<TokenRef, MarketAsset>,
+    listings: HashMap<TokenRef, Vec<(String, Listing)>>,
+    offers: HashMap<TokenRef, Vec<Offer>>,
+    sales: HashMap<TokenRef, Vec<SaleEvent>>,
+    payment_spender: Option<String>,
+}
+
+/// ローカル開発や動作確認用の、メモリ上だけのマーケットプレイス
+#[derive(Default)]
+pub struct MemoryMarketplace {
+    state: Mutex<State>,
+}
+
+impl MemoryMarketplace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 出品・転送の対象になるアセットを登録する
+    pub fn put_asset(&self, token: TokenRef, asset: MarketAsset) {
+        self.state.lock().unwrap().assets.insert(token, asset);
+    }
+
+    /// ERC20の承認を確かめる相手を設定する。未設定なら確かめない
+    pub fn set_payment_spender(&self, spender: &str) {
+        self.state.lock().unwrap().payment_spender = Some(spender.to_string());
+    }
+
+    /// 他のウォレットの出品を登録する
+    pub fn put_listing(&self, token: TokenRef, listing: Listing) {
+        self.state
+            .lock()
+            .unwrap()
+            .listings
+            .entry(token)
+            .or_default()
+            .push((listing.id.clone(), listing));
+    }
+
+    /// 購入の申し込みを登録する
+    pub fn put_offer(&self, token: TokenRef, offer: Offer) {
+        self.state
+            .lock()
+            .unwrap()
+            .offers
+            .entry(token)
+            .or_default()
+            .push(offer);
+    }
+
+    /// 売買の成立を記録し、出品を取り下げて所有者を買い手にする
+    pub fn record_sale(&self, token: &TokenRef, sale: SaleEvent) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(listings) = state.listings.get_mut(token) {
+            listings.retain(|(_, v)| v.maker != sale.seller);
+        }
+        if let Some(asset) = state.assets.get_mut(token) {
+            asset.owners = vec![sale.buyer.clone()];
+            asset.price.last_sale_price = Some(sale.price.clone());
+            asset.price.currency = sale.currency.clone();
+        }
+        state.sales.entry(token.clone()).or_default().push(sale);
+    }
+}
+
+#[async_trait]
+impl Marketplace for MemoryMarketplace {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn fee_basis_points(&self) -> u64 {
+        0
+    }
+
+    fn payment_spender(&self) -> Option<String> {
+        self.state.lock().unwrap().payment_spender.clone()
+    }
+
+    async fn get_asset(&self, token: &TokenRef) -> AppResult<MarketAsset> {
+        let state = self.state.lock().unwrap();
+        let mut asset = state.assets.get(token).cloned().ok_or(AppError::NotFound)?;
+        asset.price.listing_price = state
+            .listings
+            .get(token)
+            .and_then(|v| v.iter().map(|(_, v)| v.price.clone()).next());
+        Ok(asset)
+    }
+
+    async fn list_for_sale(
+        &self,
+        seller: &User,
+        token: &TokenRef,
+        price: Wei,
+        quantity: u64,
+        expires_at: Option<i64>,
+    ) -> AppResult<ListingOrder> {
+        let mut state = self.state.lock().unwrap();
+        let asset = state.assets.get(token).ok_or(AppError::NotFound)?;
+        if !asset
+            .owners
+            .iter()
+            .any(|v| v.eq_ignore_ascii_case(&seller.wallet_address))
+        {
+            return Err(AppError::Forbidden);
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let order = ListingOrder {
+            id: format!(
+                "{:?}",
+                H256::from(keccak256(
+                    format!("{:?}:{}:{}", token, seller.wallet_address, now.as_nanos()).as_bytes()
+                ))
+            ),
+            expires_at: expires_at.unwrap_or(now.as_secs() as i64 + LISTING_SECONDS),
+            payload: "".to_string(),
+        };
+        state.listings.entry(token.clone()).or_default().push((
+            order.id.clone(),
+            Listing {
+                id: order.id.clone(),
+                maker: seller.wallet_address.clone(),
+                currency: "ETH".to_string(),
+                price: price
+                    .checked_mul(quantity.into())
+                    .ok_or_else(|| AppError::BadRequest("price overflow".to_string()))?
+                    .to_ether(),
+                payment_token: None,
+                quantity,
+                expires_at: Some(order.expires_at.to_string()),
+            },
+        ));
+        Ok(order)
+    }
+
+    async fn cancel_listing(
+        &self,
+        seller: &User,
+        token: &TokenRef,
+        order: &ListingOrder,
+    ) -> AppResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let listings = state.listings.get_mut(token).ok_or(AppError::NotFound)?;
+        let index = listings
+            .iter()
+            .position(|(id, _)| id == &order.id)
+            .ok_or(AppError::NotFound)?;
+        if listings[index].1.maker != seller.wallet_address {
+            return Err(AppError::Forbidden);
+        }
+        listings.remove(index);
+        Ok(())
+    }
+
+    async fn get_listings(&self, token: &TokenRef) -> AppResult<Vec<Listing>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .listings
+            .get(token)
+            .map(|v| v.iter().map(|(_, v)| v.clone()).collect())
+            .unwrap_or_default())
+    }
+
+    async fn get_offers(&self, token: &TokenRef) -> AppResult<Vec<Offer>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.offers.get(token).cloned().unwrap_or_default())
+    }
+
+    async fn accept_offer(
+        &self,
+        seller: &User,
+        token: &TokenRef,
+        offer_id: &str,
+    ) -> AppResult<String> {
+        let offer = {
+            let mut state = self.state.lock().unwrap();
+            let asset = state.assets.get(token).ok_or(AppError::NotFound)?;
+            if !asset
+                .owners
+                .iter()
+                .any(|v| v.eq_ignore_ascii_case(&seller.wallet_address))
+            {
+                return Err(AppError::Forbidden);
+            }
+            let offers = state.offers.get_mut(token).ok_or(AppError::NotFound)?;
+            let index = offers
+                .iter()
+                .position(|v| v.id == offer_id)
+                .ok_or(AppError::NotFound)?;
+            offers.remove(index)
+        };
+
+        let tx_hash = format!("memory:{}", uuid::Uuid::new_v4());
+        self.record_sale(
+            token,
+            SaleEvent {
+                seller: seller.wallet_address.clone(),
+                buyer: offer.maker,
+                currency: offer.currency,
+                price: offer.price,
+                quantity: offer.quantity,
+                tx_hash: tx_hash.clone(),
+                occurred_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as i64,
+            },
+        );
+        Ok(tx_hash)
+    }
+
+    async fn get_sales(&self, token: &TokenRef) -> AppResult<Vec<SaleEvent>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.sales.get(token).cloned().unwrap_or_default())
+    }
+
+    async fn buy(&self, buyer: &User, token: &TokenRef, listing: &Listing) -> AppResult<String> {
+        let found = self
+            .state
+            .lock()
+            .unwrap()
+            .listings
+            .get(token)
+            .map(|v| v.iter().any(|(_, v)| v == listing))
+            .unwrap_or_default();
+        if !found {
+            return Err(AppError::NotFound);
+        }
+
+        // オンチェーンの取引はないので、区別できる値にする
+        let tx_hash = format!("memory:{}", uuid::Uuid::new_v4());
+        self.record_sale(
+            token,
+            SaleEvent {
+                seller: listing.maker.clone(),
+                buyer: buyer.wallet_address.clone(),
+                currency: listing.currency.clone(),
+                price: listing.price.clone(),
+                quantity: listing.quantity,
+                tx_hash: tx_hash.clone(),
+                occurred_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as i64,
+            },
+        );
+        Ok(tx_hash)
+    }
+
+    async fn refresh_metadata(&self, _token: &TokenRef) -> AppResult<bool> {
+        Ok(false)
+    }
+}
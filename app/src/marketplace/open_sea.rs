@@ -0,0 +1,973 @@
+use crate::datetime::parse_iso8601;
+use crate::domain::amount::Wei;
+use crate::domain::market::{Listing, ListingOrder, MarketAsset, Offer, SaleEvent, TokenRef};
+use crate::domain::user::User;
+use crate::ethereum::chain::ChainProfile;
+use crate::ethereum::seaport::{ConsiderationItem, ItemType, OrderComponents};
+use crate::ethereum::unit::{format_decimals, parse_decimals, parse_units, Unit};
+use crate::marketplace::Marketplace;
+use crate::open_sea::{self, api};
+use crate::{ethereum, AppError, AppResult};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+use web3::signing::keccak256;
+use web3::types::{Address, H256, U256};
+
+// OpenSeaのconduit。出品するNFTはこのconduitにapproveしておく
+const CONDUIT_KEY: &str = "0x0000007b02230091a7ed01230072f7006a004d60a8d4e71d599b8104250f0000";
+const CONDUIT_ADDRESS: &str = "0x1E0049783F008A0085193E00003D00cd54003c71";
+const FEE_RECIPIENT: &str = "0x0000a26b00c1F0DF003000390027140000fAa719";
+const FEE_BASIS_POINTS: u64 = 250;
+const LISTING_DAYS: u64 = 30;
+
+/// 参照と出品はOpenSeaのAPIを使う
+/// 出品の注文への署名と、購入などの約定はこのプロセス内で行う
+pub struct OpenSeaMarketplace {
+    cli: open_sea::Client,
+    ethereum_cli: ethereum::Client,
+}
+
+impl OpenSeaMarketplace {
+    pub fn new(chain: &ChainProfile) -> Self {
+        Self {
+            cli: open_sea::Client::new(chain),
+            ethereum_cli: ethereum::Client::with_chain(chain.clone()),
+        }
+    }
+
+    fn parse_address(&self, address: String) -> AppResult<Address> {
+        self.ethereum_cli
+            .parse_address(address.clone())
+            .ok_or_else(|| AppError::BadRequest(format!("invalid address: {}", address)))
+    }
+
+    fn order_ref(&self, hash: &str) -> AppResult<api::get_fulfillment_data::OrderRef> {
+        Ok(api::get_fulfillment_data::OrderRef {
+            hash: hash.to_string(),
+            chain: self.ethereum_cli.chain().open_sea_chain.clone(),
+            protocol_address: format!("{:?}", self.ethereum_cli.seaport_address()?),
+        })
+    }
+
+    fn conduit_key(&self) -> AppResult<H256> {
+        env::var("OPEN_SEA_CONDUIT_KEY")
+            .unwrap_or_else(|_| CONDUIT_KEY.to_string())
+            .parse()
+            .map_err(|_e| AppError::Internal("invalid conduit key".to_string()))
+    }
+
+    fn conduit_address(&self) -> String {
+        env::var("OPEN_SEA_CONDUIT_ADDRESS").unwrap_or_else(|_| CONDUIT_ADDRESS.to_string())
+    }
+
+    /// 代金からOpenSeaの手数料を除いた額を出品者が受け取る
+    fn consideration(
+        &self,
+        seller: Address,
+        price: Wei,
+        quantity: u64,
+    ) -> AppResult<Vec<ConsiderationItem>> {
+        let price = price
+            .checked_mul(U256::from(quantity))
+            .ok_or_else(|| AppError::BadRequest("価格が大きすぎます".to_string()))?;
+        let fee = price.value() * U256::from(self.fee_basis_points()) / U256::from(10000);
+        if fee.is_zero() {
+            return Ok(vec![ConsiderationItem::native(price.value(), seller)]);
+        }
+
+        let recipient = self.parse_address(
+            env::var("OPEN_SEA_FEE_RECIPIENT").unwrap_or_else(|_| FEE_RECIPIENT.to_string()),
+        )?;
+        Ok(vec![
+            ConsiderationItem::native(price.value() - fee, seller),
+            ConsiderationItem::native(fee, recipient),
+        ])
+    }
+}
+
+#[async_trait]
+impl Marketplace for OpenSeaMarketplace {
+    fn name(&self) -> &'static str {
+        "opensea"
+    }
+
+    fn fee_basis_points(&self) -> u64 {
+        env::var("OPEN_SEA_FEE_BASIS_POINTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(FEE_BASIS_POINTS)
+    }
+
+    /// ERC20の代金はconduitが引き出す
+    fn payment_spender(&self) -> Option<String> {
+        Some(self.conduit_address())
+    }
+
+    async fn get_asset(&self, token: &TokenRef) -> AppResult<MarketAsset> {
+        let asset = self
+            .cli
+            .get_asset(api::get_asset::Input {
+                address: token.contract_address.clone(),
+                token_id: token.token_id.clone(),
+            })
+            .await?;
+
+        let price = asset.market_price();
+        Ok(MarketAsset {
+            name: asset.name,
+            description: asset.description,
+            image_url: asset.image_url,
+            image_preview_url: asset.image_preview_url,
+            permalink: asset.permalink,
+            owners: asset
+                .top_ownerships
+                .into_iter()
+                .map(|v| v.owner.address)
+                .collect(),
+            price,
+        })
+    }
+
+    async fn list_for_sale(
+        &self,
+        seller: &User,
+        token: &TokenRef,
+        price: Wei,
+        quantity: u64,
+        expires_at: Option<i64>,
+    ) -> AppResult<ListingOrder> {
+        let offerer = self.parse_address(seller.wallet_address.clone())?;
+        let conduit = self.parse_address(self.conduit_address())?;
+        let conduit_key = self.conduit_key()?;
+        let token_id = U256::from_dec_str(&token.token_id)
+            .map_err(|_e| AppError::BadRequest(format!("invalid token id: {}", token.token_id)))?;
+
+        self.ethereum_cli
+            .ensure_approval_for_all(token.schema, &seller.wallet_secret, conduit)
+            .await?;
+        let counter = self.ethereum_cli.get_seaport_counter(offerer).await?;
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let end_time = match expires_at {
+            Some(v) => u64::try_from(v)
+                .map_err(|_e| AppError::BadRequest(format!("invalid expires at: {}", v)))?,
+            None => now.as_secs() + LISTING_DAYS * 24 * 60 * 60,
+        };
+        let mut seed = offerer.as_bytes().to_vec();
+        seed.extend_from_slice(&now.as_nanos().to_be_bytes());
+        let order = OrderComponents::listing(
+            offerer,
+            token.schema,
+            self.parse_address(token.contract_address.clone())?,
+            token_id,
+            U256::from(quantity),
+            self.consideration(offerer, price, quantity)?,
+            now.as_secs(),
+            end_time,
+            U256::from_big_endian(&keccak256(&seed)),
+            conduit_key,
+            counter,
+        );
+        let signature = self
+            .ethereum_cli
+            .sign_seaport_order(&order, &seller.wallet_secret)?;
+
+        let listing = ListingOrder {
+            id: order.order_hash(),
+            expires_at: end_time as i64,
+            payload: serde_json::to_string(&order)?,
+        };
+
+        let output = self
+            .cli
+            .create_listing(api::create_listing::Input {
+                parameters: order,
+                signature,
+                protocol_address: format!("{:?}", self.ethereum_cli.seaport_address()?),
+            })
+            .await?;
+        println!("created listing: {:?}", output.order.order_hash);
+
+        Ok(listing)
+    }
+
+    /// zoneを使わない注文はオフチェーンで取り消せないので、Seaportのcancelを送る
+    async fn cancel_listing(
+        &self,
+        seller: &User,
+        _token: &TokenRef,
+        order: &ListingOrder,
+    ) -> AppResult<()> {
+        let components: OrderComponents = serde_json::from_str(&order.payload)?;
+        if components.order_hash() != order.id {
+            return Err(AppError::Internal(format!(
+                "order payload does not match: {}",
+                order.id
+            )));
+        }
+
+        let tx_hash = self
+            .ethereum_cli
+            .cancel_seaport_orders(&[components], &seller.wallet_secret)
+            .await?;
+        println!("cancelled listing: {}, tx: {:?}", order.id, tx_hash);
+        Ok(())
+    }
+
+    async fn get_listings(&self, token: &TokenRef) -> AppResult<Vec<Listing>> {
+        let output = self
+            .cli
+            .get_listings(api::get_listings::Input {
+                address: token.contract_address.clone(),
+                token_id: token.token_id.clone(),
+            })
+            .await?;
+
+        Ok(output
+            .listings
+            .into_iter()
+            .filter_map(|v| {
+                // 注文ハッシュがないものは指定して約定できない
+                let id = v.order_hash?;
+                let (currency, price) =
+                    to_decimal(&v.current_price, v.payment_token_contract.as_ref())?;
+                Some(Listing {
+                    id,
+                    maker: v.maker.address,
+                    currency,
+                    price,
+                    payment_token: v
+                        .payment_token_contract
+                        .and_then(|v| v.address)
+                        .filter(|v| {
+                            self.ethereum_cli
+                                .parse_address(v.clone())
+                                .is_some_and(|v| !v.is_zero())
+                        }),
+                    quantity: parse_quantity(v.quantity),
+                    expires_at: v.closing_date,
+                })
+            })
+            .collect())
+    }
+
+    async fn get_offers(&self, token: &TokenRef) -> AppResult<Vec<Offer>> {
+        let output = self
+            .cli
+            .get_offers(api::get_offers::Input {
+                address: token.contract_address.clone(),
+                token_id: token.token_id.clone(),
+            })
+            .await?;
+
+        // v2の注文には通貨の情報がないので、支払いのトークンから読む
+        let mut currencies: HashMap<String, (String, usize)> = HashMap::new();
+        let mut offers: Vec<Offer> = vec![];
+        for v in output.orders {
+            let (currency, decimals) = match v.payment_token() {
+                Some(address) => match currencies.get(address) {
+                    Some(v) => v.clone(),
+                    None => {
+                        let metadata = self
+                            .ethereum_cli
+                            .get_erc20_metadata(self.parse_address(address.to_string())?)
+                            .await?;
+                        currencies.insert(address.to_string(), metadata.clone());
+                        metadata
+                    }
+                },
+                None => (self.ethereum_cli.chain().native_currency.clone(), 18),
+            };
+            let (id, amount) = match (v.order_hash, parse_units(&v.current_price, Unit::Wei)) {
+                (Some(id), Ok(amount)) => (id, amount),
+                _ => continue,
+            };
+            offers.push(Offer {
+                id,
+                maker: v.maker.address,
+                currency,
+                price: format_decimals(amount, decimals),
+                quantity: v.remaining_quantity.unwrap_or(1),
+                expires_at: v.expiration_time,
+            });
+        }
+        Ok(offers)
+    }
+
+    /// 申し込みの注文を取得し、売るトークンが対象であることを確かめてから出品者のウォレットで約定する
+    async fn accept_offer(
+        &self,
+        seller: &User,
+        token: &TokenRef,
+        offer_id: &str,
+    ) -> AppResult<String> {
+        let output = self
+            .cli
+            .get_offer_fulfillment_data(api::get_fulfillment_data::OfferInput {
+                offer: self.order_ref(offer_id)?,
+                fulfiller: api::get_fulfillment_data::Fulfiller {
+                    address: seller.wallet_address.clone(),
+                },
+                consideration: api::get_fulfillment_data::Consideration {
+                    asset_contract_address: token.contract_address.clone(),
+                    token_id: token.token_id.clone(),
+                },
+            })
+            .await?;
+        let order = output.into_order()?;
+        let components = &order.parameters;
+        if !components.order_hash().eq_ignore_ascii_case(offer_id) {
+            return Err(AppError::Internal(format!(
+                "order does not match: {}",
+                offer_id
+            )));
+        }
+        let contract = self.parse_address(token.contract_address.clone())?;
+        let token_id = U256::from_dec_str(&token.token_id)
+            .map_err(|_e| AppError::BadRequest(format!("invalid token id: {}", token.token_id)))?;
+        if !components.consideration.iter().any(|v| {
+            v.item_type == token.schema.into()
+                && v.token == contract
+                && v.identifier_or_criteria == token_id
+        }) {
+            return Err(AppError::Internal(format!(
+                "order does not buy the token: {}",
+                offer_id
+            )));
+        }
+
+        // NFTと、手数料などERC20で払う分はconduitが移動する
+        let conduit = self.parse_address(self.conduit_address())?;
+        self.ethereum_cli
+            .ensure_approval_for_all(token.schema, &seller.wallet_secret, conduit)
+            .await?;
+        let mut fees: HashMap<Address, U256> = HashMap::new();
+        for v in components
+            .consideration
+            .iter()
+            .filter(|v| v.item_type == ItemType::Erc20)
+        {
+            *fees.entry(v.token).or_default() += v.start_amount.max(v.end_amount);
+        }
+        for (payment_token, amount) in fees {
+            self.ethereum_cli
+                .ensure_erc20_allowance(payment_token, &seller.wallet_secret, conduit, amount)
+                .await?;
+        }
+
+        let tx_hash = self
+            .ethereum_cli
+            .fulfill_seaport_order(
+                components,
+                &order.signature,
+                &seller.wallet_secret,
+                self.conduit_key()?,
+            )
+            .await?;
+        Ok(format!("{:?}", tx_hash))
+    }
+
+    async fn get_sales(&self, token: &TokenRef) -> AppResult<Vec<SaleEvent>> {
+        let output = self
+            .cli
+            .get_events(api::get_events::Input {
+                address: token.contract_address.clone(),
+                token_id: token.token_id.clone(),
+                event_type: "successful".to_string(),
+            })
+            .await?;
+
+        Ok(output
+            .asset_events
+            .into_iter()
+            .filter_map(|v| {
+                let (currency, price) =
+                    to_decimal(v.total_price.as_ref()?, v.payment_token.as_ref())?;
+                Some(SaleEvent {
+                    seller: v.seller.map(|v| v.address).unwrap_or_default(),
+                    buyer: v.winner_account.map(|v| v.address).unwrap_or_default(),
+                    currency,
+                    price,
+                    quantity: parse_quantity(v.quantity),
+                    tx_hash: v
+                        .transaction
+                        .map(|v| v.transaction_hash)
+                        .unwrap_or_default(),
+                    occurred_at: v
+                        .created_date
+                        .as_deref()
+                        .and_then(parse_iso8601)
+                        .unwrap_or_default(),
+                })
+            })
+            .collect())
+    }
+
+    /// 選んだ注文を取得し、その価格以下であることを確かめてから買い手のウォレットで約定する
+    async fn buy(&self, buyer: &User, token: &TokenRef, listing: &Listing) -> AppResult<String> {
+        let output = self
+            .cli
+            .get_listing_fulfillment_data(api::get_fulfillment_data::ListingInput {
+                listing: self.order_ref(&listing.id)?,
+                fulfiller: api::get_fulfillment_data::Fulfiller {
+                    address: buyer.wallet_address.clone(),
+                },
+            })
+            .await?;
+        let order = output.into_order()?;
+        let components = &order.parameters;
+        if !components.order_hash().eq_ignore_ascii_case(&listing.id) {
+            return Err(AppError::Internal(format!(
+                "order does not match: {}",
+                listing.id
+            )));
+        }
+        let contract = self.parse_address(token.contract_address.clone())?;
+        let token_id = U256::from_dec_str(&token.token_id)
+            .map_err(|_e| AppError::BadRequest(format!("invalid token id: {}", token.token_id)))?;
+        if !components
+            .offer
+            .iter()
+            .any(|v| v.token == contract && v.identifier_or_criteria == token_id)
+        {
+            return Err(AppError::Internal(format!(
+                "order does not sell the token: {}",
+                listing.id
+            )));
+        }
+
+        // 一覧で見た価格を上限にする
+        let (item_type, payment_token, decimals) = match &listing.payment_token {
+            Some(address) => {
+                let address = self.parse_address(address.clone())?;
+                let (_, decimals) = self.ethereum_cli.get_erc20_metadata(address).await?;
+                (ItemType::Erc20, address, decimals)
+            }
+            None => (ItemType::Native, Address::zero(), 18),
+        };
+        let max_price = parse_decimals(&listing.price, decimals)?;
+        let price = components.consideration_amount(item_type, payment_token);
+        if price > max_price {
+            return Err(AppError::BadRequest(format!(
+                "価格が変わっています: {} {}",
+                format_decimals(price, decimals),
+                listing.currency
+            )));
+        }
+
+        let tx_hash = self
+            .ethereum_cli
+            .fulfill_seaport_order(
+                components,
+                &order.signature,
+                &buyer.wallet_secret,
+                self.conduit_key()?,
+            )
+            .await?;
+        Ok(format!("{:?}", tx_hash))
+    }
+
+    async fn refresh_metadata(&self, token: &TokenRef) -> AppResult<bool> {
+        self.cli
+            .refresh_metadata(api::refresh_metadata::Input {
+                address: token.contract_address.clone(),
+                token_id: token.token_id.clone(),
+            })
+            .await?;
+        Ok(true)
+    }
+}
+
+// 通貨情報がなければETHとみなす
+fn to_decimal(
+    amount: &str,
+    token: Option<&api::get_asset::PaymentTokenContract>,
+) -> Option<(String, String)> {
+    let amount = parse_units(amount, Unit::Wei).ok()?;
+    Some((
+        token
+            .and_then(|v| v.symbol.clone())
+            .unwrap_or_else(|| "ETH".to_string()),
+        format_decimals(amount, token.and_then(|v| v.decimals).unwrap_or(18)),
+    ))
+}
+
+fn parse_quantity(quantity: Option<String>) -> u64 {
+    quantity.and_then(|v| v.parse().ok()).unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::market::TokenSchema;
+    use crate::ethereum::seaport::OfferItem;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server, StatusCode};
+    use serde_json::{json, Value};
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    // anvilの1番目のアカウント
+    const SELLER_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const SELLER: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+    const IS_APPROVED_FOR_ALL: &str = "0xe985e9c5";
+    const GET_COUNTER: &str = "0xf07ec373";
+    const SYMBOL: &str = "0x95d89b41";
+    const DECIMALS: &str = "0x313ce567";
+    const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    // anvilの2番目のアカウント
+    const BUYER_KEY: &str = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+    const BUYER: &str = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+    const FULFILL_ORDER: &str = "0xb3a34c4c";
+    const ALLOWANCE: &str = "0xdd62ed3e";
+    const APPROVE: &str = "0x095ea7b3";
+    const TX_HASH: &str = "0xabababababababababababababababababababababababababababababababab";
+
+    /// JSON-RPCとOpenSeaの注文APIを兼ねるサーバー。登録された注文と送られたトランザクションを記録する
+    #[derive(Clone)]
+    struct Mock {
+        orders: Arc<Mutex<Vec<Value>>>,
+        rejecting: bool,
+        fulfillment: Arc<Mutex<Value>>,
+        transactions: Arc<Mutex<Vec<Value>>>,
+    }
+
+    impl Mock {
+        fn start(rejecting: bool) -> (Self, String) {
+            let mock = Self {
+                orders: Arc::new(Mutex::new(vec![])),
+                rejecting,
+                fulfillment: Arc::new(Mutex::new(Value::Null)),
+                transactions: Arc::new(Mutex::new(vec![])),
+            };
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let state = mock.clone();
+            let make = make_service_fn(move |_| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(service_fn(move |req| state.clone().handle(req))) }
+            });
+            tokio::spawn(Server::from_tcp(listener).unwrap().serve(make));
+            (mock, url)
+        }
+
+        async fn handle(self, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let path = req.uri().path().to_string();
+            if path == "/v2/orders/test/seaport/offers" {
+                let query = req.uri().query().unwrap_or_default().to_string();
+                assert!(query.contains("token_ids=7"), "{}", query);
+                return Ok(Response::new(Body::from(offers().to_string())));
+            }
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            let body: Value = serde_json::from_slice(&body).unwrap();
+
+            if path == "/v2/listings/fulfillment_data" {
+                assert_eq!(body["fulfiller"]["address"], BUYER);
+                assert_eq!(body["listing"]["chain"], "test");
+                let res = json!({
+                    "protocol": "seaport1.5",
+                    "fulfillment_data": {"orders": [self.fulfillment.lock().unwrap().clone()]},
+                });
+                return Ok(Response::new(Body::from(res.to_string())));
+            }
+            if path == "/v2/offers/fulfillment_data" {
+                assert_eq!(body["fulfiller"]["address"], SELLER);
+                assert_eq!(body["consideration"]["token_id"], "7");
+                let res = json!({
+                    "protocol": "seaport1.5",
+                    "fulfillment_data": {"orders": [self.fulfillment.lock().unwrap().clone()]},
+                });
+                return Ok(Response::new(Body::from(res.to_string())));
+            }
+            if path == "/v2/orders/test/seaport/listings" {
+                if self.rejecting {
+                    return Ok(Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(Body::from("invalid signature"))
+                        .unwrap());
+                }
+                self.orders.lock().unwrap().push(body.clone());
+                let res = json!({"order": {"order_hash": null}});
+                return Ok(Response::new(Body::from(res.to_string())));
+            }
+
+            // 送信は常に成功し、レシートはすぐに返る
+            let result = match body["method"].as_str().unwrap() {
+                "eth_call" => None,
+                "eth_estimateGas" => {
+                    self.transactions
+                        .lock()
+                        .unwrap()
+                        .push(body["params"][0].clone());
+                    Some(json!("0x30000"))
+                }
+                "eth_getTransactionCount" => Some(json!("0x0")),
+                "eth_gasPrice" => Some(json!("0x3b9aca00")),
+                "eth_chainId" => Some(json!("0x1")),
+                "eth_sendRawTransaction" => Some(json!(TX_HASH)),
+                "eth_getTransactionReceipt" => Some(json!({
+                    "transactionHash": TX_HASH,
+                    "transactionIndex": "0x0",
+                    "blockNumber": "0x1",
+                    "from": BUYER,
+                    "cumulativeGasUsed": "0x30000",
+                    "gasUsed": "0x30000",
+                    "logs": [],
+                    "logsBloom": format!("0x{}", "0".repeat(512)),
+                    "status": "0x1",
+                })),
+                v => panic!("unexpected method: {}", v),
+            };
+            if let Some(result) = result {
+                let res = json!({"jsonrpc": "2.0", "id": body["id"], "result": result});
+                return Ok(Response::new(Body::from(res.to_string())));
+            }
+
+            // 承認済みで、カウンターは5。支払いのトークンはWETHで、conduitへの承認はない
+            let data = body["params"][0]["data"].as_str().unwrap_or_default();
+            let result = if data.starts_with(IS_APPROVED_FOR_ALL) {
+                format!("0x{:064x}", 1)
+            } else if data.starts_with(ALLOWANCE) {
+                format!("0x{:064x}", 0)
+            } else if data.starts_with(GET_COUNTER) {
+                format!("0x{:064x}", 5)
+            } else if data.starts_with(DECIMALS) {
+                format!("0x{:064x}", 18)
+            } else if data.starts_with(SYMBOL) {
+                format!("0x{:064x}{:064x}{:0<64}", 0x20, 4, "57455448")
+            } else {
+                panic!("unexpected call: {}", body);
+            };
+            let res = json!({
+                "jsonrpc": "2.0",
+                "id": body["id"],
+                "result": result,
+            });
+            Ok(Response::new(Body::from(res.to_string())))
+        }
+    }
+
+    /// v2のAPIの形式の申し込み。ハッシュのないものは約定できない
+    fn offers() -> Value {
+        let order = |hash: Value, price: &str, quantity: u64| {
+            json!({
+                "order_hash": hash,
+                "current_price": price,
+                "maker": {"address": "0x70997970c51812dc3a010c7d01b50e0d17dc79c8"},
+                "remaining_quantity": quantity,
+                "expiration_time": 1900000000,
+                "side": "bid",
+                "protocol_data": {
+                    "parameters": {
+                        "offer": [{
+                            "itemType": 1,
+                            "token": WETH,
+                            "identifierOrCriteria": "0",
+                            "startAmount": price,
+                            "endAmount": price
+                        }]
+                    },
+                    "signature": null
+                }
+            })
+        };
+        json!({
+            "orders": [
+                order(json!("0xaaaa"), "250000000000000000", 2),
+                order(Value::Null, "300000000000000000", 1),
+            ]
+        })
+    }
+
+    fn marketplace(url: &str) -> OpenSeaMarketplace {
+        let mut chain = ChainProfile::for_test(url, 1);
+        chain.open_sea_base_url = url.to_string();
+        chain.erc721_contract_address = "0x1111111111111111111111111111111111111111".to_string();
+        OpenSeaMarketplace::new(&chain)
+    }
+
+    fn seller() -> User {
+        User::new(
+            "seller".to_string(),
+            SELLER.to_string(),
+            SELLER_KEY.to_string(),
+        )
+    }
+
+    fn token() -> TokenRef {
+        TokenRef {
+            schema: TokenSchema::Erc721,
+            contract_address: "0x1111111111111111111111111111111111111111".to_string(),
+            token_id: "7".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn posts_signed_listing() {
+        let (mock, url) = Mock::start(false);
+        let marketplace = marketplace(&url);
+
+        let listing = marketplace
+            .list_for_sale(
+                &seller(),
+                &token(),
+                Wei::from(U256::exp10(18)),
+                1,
+                Some(1900000000),
+            )
+            .await
+            .unwrap();
+
+        let orders = mock.orders.lock().unwrap();
+        assert_eq!(orders.len(), 1);
+        let posted = &orders[0];
+        assert_eq!(
+            posted["protocol_address"],
+            format!("{:?}", marketplace.ethereum_cli.seaport_address().unwrap())
+        );
+
+        // 送った注文は保存した注文と同じで、offererの署名が付いている
+        let components: OrderComponents =
+            serde_json::from_value(posted["parameters"].clone()).unwrap();
+        assert_eq!(components.order_hash(), listing.id);
+        assert_eq!(listing.expires_at, 1900000000);
+        assert_eq!(components.counter, U256::from(5));
+        assert_eq!(components.offer[0].identifier_or_criteria, U256::from(7));
+        assert_eq!(components.consideration.len(), 2);
+        assert_eq!(posted["parameters"]["totalOriginalConsiderationItems"], 2);
+
+        let signature = posted["signature"].as_str().unwrap();
+        let bytes: Vec<u8> = (2..signature.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&signature[i..i + 2], 16).unwrap())
+            .collect();
+        let recovered = web3::signing::recover(
+            &components.digest(1, marketplace.ethereum_cli.seaport_address().unwrap()),
+            &bytes[..64],
+            bytes[64] as i32 - 27,
+        )
+        .unwrap();
+        assert_eq!(format!("{:?}", recovered), SELLER);
+    }
+
+    #[tokio::test]
+    async fn reads_offers_in_payment_token() {
+        let (_mock, url) = Mock::start(false);
+        let offers = marketplace(&url).get_offers(&token()).await.unwrap();
+
+        assert_eq!(
+            offers,
+            vec![Offer {
+                id: "0xaaaa".to_string(),
+                maker: "0x70997970c51812dc3a010c7d01b50e0d17dc79c8".to_string(),
+                currency: "WETH".to_string(),
+                price: "0.25".to_string(),
+                quantity: 2,
+                expires_at: Some(1900000000),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn surfaces_rejected_listing() {
+        let (_mock, url) = Mock::start(true);
+        let result = marketplace(&url)
+            .list_for_sale(&seller(), &token(), Wei::from(U256::exp10(18)), 1, None)
+            .await;
+
+        match result {
+            Err(AppError::BadRequest(message)) => assert!(message.contains("invalid signature")),
+            v => panic!("unexpected result: {:?}", v.map(|v| v.id)),
+        }
+    }
+
+    fn buyer() -> User {
+        User::new(
+            "buyer".to_string(),
+            BUYER.to_string(),
+            BUYER_KEY.to_string(),
+        )
+    }
+
+    /// 出品者が署名した1 ETHの注文をOpenSeaが返すようにし、一覧での見え方を返す
+    fn put_signed_listing(mock: &Mock, marketplace: &OpenSeaMarketplace) -> Listing {
+        let offerer: Address = SELLER.parse().unwrap();
+        let order = OrderComponents::listing(
+            offerer,
+            TokenSchema::Erc721,
+            token().contract_address.parse().unwrap(),
+            U256::from(7),
+            U256::one(),
+            marketplace
+                .consideration(offerer, Wei::from(U256::exp10(18)), 1)
+                .unwrap(),
+            1700000000,
+            1900000000,
+            U256::from(12345),
+            CONDUIT_KEY.parse().unwrap(),
+            U256::from(5),
+        );
+        let signature = marketplace
+            .ethereum_cli
+            .sign_seaport_order(&order, SELLER_KEY)
+            .unwrap();
+        *mock.fulfillment.lock().unwrap() = json!({"parameters": order, "signature": signature});
+
+        Listing {
+            id: order.order_hash(),
+            maker: SELLER.to_string(),
+            currency: "ETH".to_string(),
+            price: "1".to_string(),
+            payment_token: None,
+            quantity: 1,
+            expires_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn fulfils_listing_with_buyer_key() {
+        let (mock, url) = Mock::start(false);
+        let marketplace = marketplace(&url);
+        let listing = put_signed_listing(&mock, &marketplace);
+
+        let tx_hash = marketplace.buy(&buyer(), &token(), &listing).await.unwrap();
+        assert_eq!(tx_hash, TX_HASH);
+
+        // 代金をvalueとして、Seaportに買い手のアドレスから送る
+        let transactions = mock.transactions.lock().unwrap();
+        assert_eq!(transactions.len(), 1);
+        let tx = &transactions[0];
+        assert_eq!(tx["from"], BUYER);
+        assert_eq!(
+            tx["to"],
+            format!("{:?}", marketplace.ethereum_cli.seaport_address().unwrap())
+        );
+        assert_eq!(tx["value"], json!(U256::exp10(18)));
+        let data = tx["data"].as_str().unwrap();
+        assert!(data.starts_with(FULFILL_ORDER), "{}", data);
+        let signature = mock.fulfillment.lock().unwrap()["signature"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(data.contains(&signature[2..]));
+    }
+
+    #[tokio::test]
+    async fn refuses_order_above_listed_price() {
+        let (mock, url) = Mock::start(false);
+        let marketplace = marketplace(&url);
+        let mut listing = put_signed_listing(&mock, &marketplace);
+        listing.price = "0.5".to_string();
+
+        let result = marketplace.buy(&buyer(), &token(), &listing).await;
+        assert!(
+            matches!(result, Err(AppError::BadRequest(_))),
+            "{:?}",
+            result
+        );
+
+        // 一覧と違う注文が返ってきたときも送らない
+        listing.price = "1".to_string();
+        listing.id = format!("0x{}", "0".repeat(64));
+        let result = marketplace.buy(&buyer(), &token(), &listing).await;
+        assert!(matches!(result, Err(AppError::Internal(_))), "{:?}", result);
+        assert!(mock.transactions.lock().unwrap().is_empty());
+    }
+
+    /// 1 WETHでの申し込みで、2.5%の手数料をWETHで払う注文をOpenSeaが返すようにする
+    fn put_signed_offer(mock: &Mock, marketplace: &OpenSeaMarketplace) -> String {
+        let offerer: Address = BUYER.parse().unwrap();
+        let weth: Address = WETH.parse().unwrap();
+        let ether = U256::exp10(15);
+        let order = OrderComponents {
+            offerer,
+            zone: Address::zero(),
+            offer: vec![OfferItem {
+                item_type: ItemType::Erc20,
+                token: weth,
+                identifier_or_criteria: U256::zero(),
+                start_amount: ether * 1000,
+                end_amount: ether * 1000,
+            }],
+            consideration: vec![
+                ConsiderationItem {
+                    item_type: ItemType::Erc721,
+                    token: token().contract_address.parse().unwrap(),
+                    identifier_or_criteria: U256::from(7),
+                    start_amount: U256::one(),
+                    end_amount: U256::one(),
+                    recipient: offerer,
+                },
+                ConsiderationItem {
+                    item_type: ItemType::Erc20,
+                    token: weth,
+                    identifier_or_criteria: U256::zero(),
+                    start_amount: ether * 25,
+                    end_amount: ether * 25,
+                    recipient: FEE_RECIPIENT.parse().unwrap(),
+                },
+            ],
+            order_type: 0,
+            start_time: U256::from(1700000000),
+            end_time: U256::from(1900000000),
+            zone_hash: H256::zero(),
+            salt: U256::from(6789),
+            conduit_key: CONDUIT_KEY.parse().unwrap(),
+            counter: U256::zero(),
+            total_original_consideration_items: 2,
+        };
+        let signature = marketplace
+            .ethereum_cli
+            .sign_seaport_order(&order, BUYER_KEY)
+            .unwrap();
+        *mock.fulfillment.lock().unwrap() = json!({"parameters": order, "signature": signature});
+        order.order_hash()
+    }
+
+    #[tokio::test]
+    async fn fulfils_offer_with_seller_key() {
+        let (mock, url) = Mock::start(false);
+        let marketplace = marketplace(&url);
+        let offer_id = put_signed_offer(&mock, &marketplace);
+
+        let tx_hash = marketplace
+            .accept_offer(&seller(), &token(), &offer_id)
+            .await
+            .unwrap();
+        assert_eq!(tx_hash, TX_HASH);
+
+        // 手数料のWETHをconduitに承認してから、ETHを送らずに約定する
+        let transactions = mock.transactions.lock().unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0]["to"], WETH);
+        let approve = transactions[0]["data"].as_str().unwrap();
+        assert!(approve.starts_with(APPROVE), "{}", approve);
+        assert!(approve.contains(&CONDUIT_ADDRESS[2..].to_lowercase()));
+        assert!(approve.ends_with(&format!("{:064x}", U256::exp10(15) * 25)));
+
+        let tx = &transactions[1];
+        assert_eq!(tx["from"], SELLER);
+        assert_eq!(tx["value"], json!(U256::zero()));
+        assert!(tx["data"].as_str().unwrap().starts_with(FULFILL_ORDER));
+    }
+
+    #[tokio::test]
+    async fn refuses_offer_for_other_token() {
+        let (mock, url) = Mock::start(false);
+        let marketplace = marketplace(&url);
+        let offer_id = put_signed_offer(&mock, &marketplace);
+
+        let result = marketplace
+            .accept_offer(&seller(), &token(), "0xaaaa")
+            .await;
+        assert!(matches!(result, Err(AppError::Internal(_))), "{:?}", result);
+
+        let mut other = token();
+        other.contract_address = "0x2222222222222222222222222222222222222222".to_string();
+        let result = marketplace.accept_offer(&seller(), &other, &offer_id).await;
+        assert!(matches!(result, Err(AppError::Internal(_))), "{:?}", result);
+        assert!(mock.transactions.lock().unwrap().is_empty());
+    }
+}
@@ -1,21 +1,53 @@
 pub mod api;
+mod limiter;
 pub mod metadata;
 
+use crate::datetime::parse_http_date;
+use crate::ethereum::chain::ChainProfile;
+use crate::open_sea::limiter::TokenBucket;
 use crate::{AppError, AppResult};
-use reqwest::header::{HeaderMap, HeaderValue};
-use reqwest::{Body, Method, Response, Url};
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+use reqwest::{Method, Response, StatusCode, Url};
 use std::env;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-#[derive(Clone, Debug)]
+// OpenSeaのレートリミットはAPIキー単位なので、プロセス内の全てのClientで共有する
+static LIMITER: Lazy<Arc<TokenBucket>> = Lazy::new(|| {
+    let per_second = env::var("OPEN_SEA_RATE_PER_SEC")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2.0);
+    let burst = env::var("OPEN_SEA_RATE_BURST")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2);
+    Arc::new(TokenBucket::new(burst, per_second))
+});
+
+#[derive(Clone)]
 pub struct Client {
     base_url: Url,
+    chain: String,
+    api_key: Option<String>,
+    http: reqwest::Client,
+    limiter: Arc<TokenBucket>,
+    max_retries: usize,
 }
 
 impl Client {
-    pub fn new() -> Self {
-        let base_url = env::var("OPEN_SEA_BASE_URL").expect("should set open sea base url");
+    pub fn new(chain: &ChainProfile) -> Self {
         Client {
-            base_url: base_url.parse().unwrap(),
+            base_url: chain.open_sea_base_url.parse().unwrap(),
+            chain: chain.open_sea_chain.clone(),
+            api_key: env::var("OPEN_SEA_API_KEY").ok().filter(|v| !v.is_empty()),
+            http: reqwest::Client::new(),
+            limiter: LIMITER.clone(),
+            max_retries: env::var("OPEN_SEA_MAX_RETRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
         }
     }
 
@@ -28,31 +60,255 @@ impl Client {
         }
         println!("call api: {}", url.to_string());
 
-        let mut req = reqwest::Request::new(input.method, url);
-
         let mut headers = HeaderMap::new();
         headers.insert(
             "Content-Type",
             HeaderValue::from_str("application/json").unwrap(),
         );
-        *req.headers_mut() = headers;
+        if let Some(api_key) = &self.api_key {
+            headers.insert(
+                "X-API-KEY",
+                HeaderValue::from_str(api_key).map_err(|e| AppError::Internal(e.to_string()))?,
+            );
+        }
+
+        let mut attempt = 0;
+        loop {
+            self.limiter.acquire().await;
 
-        *req.body_mut() = input.body;
+            let mut req = reqwest::Request::new(input.method.clone(), url.clone());
+            *req.headers_mut() = headers.clone();
+            *req.body_mut() = input.body.clone().map(|v| v.into());
 
-        let cli = reqwest::Client::new();
-        let resp = cli.execute(req).await.map_err(|e| -> AppError {
-            println!("error: {}", e.to_string());
-            AppError::from(e)
-        })?;
+            let result = self.http.execute(req).await;
+            let retry_after = match &result {
+                Ok(resp) if is_retryable(resp.status()) => {
+                    Some(retry_after_of(resp).unwrap_or_else(|| backoff(attempt)))
+                }
+                Ok(_) => None,
+                Err(e) if e.is_timeout() || e.is_connect() => Some(backoff(attempt)),
+                Err(_) => None,
+            };
 
-        Ok(resp)
+            let wait = match retry_after {
+                Some(wait) => wait,
+                None => {
+                    return result.map_err(|e| -> AppError {
+                        println!("error: {}", e);
+                        AppError::from(e)
+                    })
+                }
+            };
+
+            let rate_limited =
+                matches!(&result, Ok(resp) if resp.status() == StatusCode::TOO_MANY_REQUESTS);
+            if rate_limited {
+                // 他のリクエストも含めてRetry-Afterの間は止める
+                self.limiter.pause(wait).await;
+            }
+
+            if attempt >= self.max_retries {
+                if rate_limited {
+                    return Err(AppError::RateLimited(wait.as_secs().max(1)));
+                }
+                return result.map_err(|e| -> AppError {
+                    println!("error: {}", e);
+                    AppError::from(e)
+                });
+            }
+
+            attempt += 1;
+            println!(
+                "retry open sea api after {:?}: {}, attempt: {}",
+                wait, url, attempt
+            );
+            if !rate_limited {
+                tokio::time::sleep(wait).await;
+            }
+        }
     }
 }
 
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn retry_after_of(resp: &Response) -> Option<Duration> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    parse_retry_after(resp.headers().get(RETRY_AFTER)?.to_str().ok()?, now)
+}
+
+/// Retry-Afterは秒数かHTTP-date。読めなければNone（backoffにする）
+fn parse_retry_after(value: &str, now: i64) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    // 過去の日時ならすぐに再試行する
+    let at = parse_http_date(value)?;
+    Some(Duration::from_secs((at - now).max(0) as u64))
+}
+
+fn backoff(attempt: usize) -> Duration {
+    Duration::from_millis(500 * 2u64.pow(attempt.min(5) as u32))
+}
+
 #[derive(Default)]
 pub struct CallInput {
     pub method: Method,
     pub path: String,
-    pub body: Option<Body>,
+    pub body: Option<String>,
     pub query: Vec<(String, String)>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Server};
+    use std::convert::Infallible;
+    use std::sync::Mutex;
+
+    /// 指定した順にステータスを返し、受けたリクエストの数を記録するサーバー
+    #[derive(Clone)]
+    struct Mock {
+        statuses: Arc<Mutex<Vec<u16>>>,
+        requests: Arc<Mutex<usize>>,
+    }
+
+    impl Mock {
+        fn start(statuses: Vec<u16>) -> (Self, String) {
+            let mock = Self {
+                statuses: Arc::new(Mutex::new(statuses)),
+                requests: Arc::new(Mutex::new(0)),
+            };
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let state = mock.clone();
+            let make = make_service_fn(move |_| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(service_fn(move |req| state.clone().handle(req))) }
+            });
+            tokio::spawn(Server::from_tcp(listener).unwrap().serve(make));
+            (mock, url)
+        }
+
+        async fn handle(self, _req: Request<Body>) -> Result<hyper::Response<Body>, Infallible> {
+            *self.requests.lock().unwrap() += 1;
+            let status = {
+                let mut statuses = self.statuses.lock().unwrap();
+                if statuses.len() > 1 {
+                    statuses.remove(0)
+                } else {
+                    statuses[0]
+                }
+            };
+            // backoffを待たないよう、すぐに再試行させる
+            Ok(hyper::Response::builder()
+                .status(status)
+                .header("Retry-After", "0")
+                .body(Body::from("{}"))
+                .unwrap())
+        }
+
+        fn requests(&self) -> usize {
+            *self.requests.lock().unwrap()
+        }
+    }
+
+    fn client(url: &str) -> Client {
+        Client {
+            base_url: url.parse().unwrap(),
+            chain: "test".to_string(),
+            api_key: None,
+            http: reqwest::Client::new(),
+            limiter: Arc::new(TokenBucket::new(100, 100.0)),
+            max_retries: 2,
+        }
+    }
+
+    fn input() -> CallInput {
+        CallInput {
+            method: Method::GET,
+            path: "/api/v1/asset".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let (mock, url) = Mock::start(vec![503, 502, 200]);
+
+        let resp = client(&url).call(input()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(mock.requests(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_rate_limited() {
+        let (mock, url) = Mock::start(vec![429]);
+
+        let res = client(&url).call(input()).await;
+        assert_eq!(res.err(), Some(AppError::RateLimited(1)));
+        // 最初の1回とmax_retries回
+        assert_eq!(mock.requests(), 3);
+    }
+
+    #[tokio::test]
+    async fn returns_server_error_after_retries() {
+        let (mock, url) = Mock::start(vec![500]);
+
+        let resp = client(&url).call(input()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(mock.requests(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (mock, url) = Mock::start(vec![404]);
+
+        let resp = client(&url).call(input()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(mock.requests(), 1);
+    }
+
+    #[test]
+    fn retries_only_rate_limits_and_server_errors() {
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(is_retryable(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable(StatusCode::OK));
+        assert!(!is_retryable(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn parses_retry_after() {
+        // Sun, 06 Nov 1994 08:49:37 GMT
+        let now = 784111777;
+
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse_retry_after(" 5 ", now), Some(Duration::from_secs(5)));
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:50:07 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("-1", now), None);
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        assert_eq!(backoff(0), Duration::from_millis(500));
+        assert_eq!(backoff(2), Duration::from_millis(2000));
+        assert_eq!(backoff(5), backoff(10));
+    }
+}
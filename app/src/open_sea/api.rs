@@ -1,7 +1,12 @@
+use crate::domain::amount::Wei;
+use crate::domain::asset::MarketPrice;
+use crate::ethereum::seaport::OrderComponents;
+use crate::ethereum::unit::{format_decimals, parse_decimals, parse_units, Unit};
 use crate::open_sea::{CallInput, Client};
 use crate::{AppError, AppResult};
 use reqwest::Method;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use web3::types::U256;
 
 impl Client {
     pub async fn get_asset(&self, input: get_asset::Input) -> AppResult<get_asset::Output> {
@@ -18,9 +23,7 @@ impl Client {
             method: Method::GET,
             path: format!("/api/v1/asset/{}/{}", input.address, input.token_id).to_string(),
             body: Some(
-                serde_json::to_string(&body)
-                    .map_err(|e| AppError::Internal(e.to_string()))?
-                    .into(),
+                serde_json::to_string(&body).map_err(|e| AppError::Internal(e.to_string()))?,
             ),
             query,
         })
@@ -30,6 +33,162 @@ impl Client {
         .await
         .map_err(AppError::from)
     }
+
+    pub async fn get_listings(
+        &self,
+        input: get_listings::Input,
+    ) -> AppResult<get_listings::Output> {
+        self.call(CallInput {
+            method: Method::GET,
+            path: format!(
+                "/api/v1/asset/{}/{}/listings",
+                input.address, input.token_id
+            ),
+            body: None,
+            query: vec![],
+        })
+        .await?
+        .error_for_status()?
+        .json::<get_listings::Output>()
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// 有効な申し込み（Seaportの注文）
+    pub async fn get_offers(&self, input: get_offers::Input) -> AppResult<get_offers::Output> {
+        self.call(CallInput {
+            method: Method::GET,
+            path: format!("/v2/orders/{}/seaport/offers", self.chain),
+            body: None,
+            query: vec![
+                ("asset_contract_address".to_string(), input.address),
+                ("token_ids".to_string(), input.token_id),
+            ],
+        })
+        .await?
+        .error_for_status()?
+        .json::<get_offers::Output>()
+        .await
+        .map_err(AppError::from)
+    }
+
+    pub async fn get_events(&self, input: get_events::Input) -> AppResult<get_events::Output> {
+        self.call(CallInput {
+            method: Method::GET,
+            path: "/api/v1/events".to_string(),
+            body: None,
+            query: vec![
+                ("asset_contract_address".to_string(), input.address),
+                ("token_id".to_string(), input.token_id),
+                ("event_type".to_string(), input.event_type),
+            ],
+        })
+        .await?
+        .error_for_status()?
+        .json::<get_events::Output>()
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// 署名済みのSeaportの注文を出品として登録する
+    pub async fn create_listing(
+        &self,
+        input: create_listing::Input,
+    ) -> AppResult<create_listing::Output> {
+        let resp = self
+            .call(CallInput {
+                method: Method::POST,
+                path: format!("/v2/orders/{}/seaport/listings", self.chain),
+                body: Some(serde_json::to_string(&input)?),
+                query: vec![],
+            })
+            .await?;
+
+        // 注文の内容が不正な場合は理由がbodyで返ってくる
+        if resp.status().is_client_error() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(AppError::BadRequest(format!(
+                "listing rejected ({}): {}",
+                status, body
+            )));
+        }
+
+        resp.error_for_status()?
+            .json::<create_listing::Output>()
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// 出品を買うための、署名付きの注文
+    pub async fn get_listing_fulfillment_data(
+        &self,
+        input: get_fulfillment_data::ListingInput,
+    ) -> AppResult<get_fulfillment_data::Output> {
+        self.post_fulfillment_data(
+            "/v2/listings/fulfillment_data",
+            serde_json::to_string(&input)?,
+        )
+        .await
+    }
+
+    /// 申し込みを承諾するための、署名付きの注文
+    pub async fn get_offer_fulfillment_data(
+        &self,
+        input: get_fulfillment_data::OfferInput,
+    ) -> AppResult<get_fulfillment_data::Output> {
+        self.post_fulfillment_data(
+            "/v2/offers/fulfillment_data",
+            serde_json::to_string(&input)?,
+        )
+        .await
+    }
+
+    async fn post_fulfillment_data(
+        &self,
+        path: &str,
+        body: String,
+    ) -> AppResult<get_fulfillment_data::Output> {
+        let resp = self
+            .call(CallInput {
+                method: Method::POST,
+                path: path.to_string(),
+                body: Some(body),
+                query: vec![],
+            })
+            .await?;
+
+        // 約定済みや取り消し済みの注文は理由がbodyで返ってくる
+        if resp.status().is_client_error() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(AppError::BadRequest(format!(
+                "order unavailable ({}): {}",
+                status, body
+            )));
+        }
+
+        resp.error_for_status()?
+            .json::<get_fulfillment_data::Output>()
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// metadataを取り直してもらう
+    pub async fn refresh_metadata(&self, input: refresh_metadata::Input) -> AppResult<()> {
+        self.call(CallInput {
+            method: Method::POST,
+            path: format!(
+                "/api/v2/chain/{}/contract/{}/nfts/{}/refresh",
+                self.chain, input.address, input.token_id
+            ),
+            body: None,
+            query: vec![],
+        })
+        .await?
+        .error_for_status()?;
+        Ok(())
+    }
 }
 
 pub mod get_asset {
@@ -51,6 +210,134 @@ pub mod get_asset {
         pub permalink: String,
         pub collection: Collection,
         pub top_ownerships: Vec<Owner>,
+        #[serde(default)]
+        pub sell_orders: Option<Vec<Order>>,
+        #[serde(default)]
+        pub last_sale: Option<Sale>,
+    }
+
+    impl Output {
+        /// 出品中の最安値と直近の売買価格を、支払い通貨の単位に直して返す
+        /// 通貨ごとに桁数が違うので、最安値はETH換算で比べる
+        pub fn market_price(&self) -> MarketPrice {
+            let orders: Vec<Amount> = self
+                .sell_orders
+                .iter()
+                .flatten()
+                .filter_map(|v| {
+                    let amount = parse_units(&v.current_price, Unit::Wei).ok()?;
+                    Some(Amount::new(amount, v.payment_token_contract.as_ref()))
+                })
+                .collect();
+            let cheapest = orders
+                .iter()
+                .filter_map(|v| Some((v.eth_value()?, v)))
+                .min_by_key(|(value, _)| *value)
+                .map(|(_, v)| v);
+            // 換算できる注文がなければ比べられないので、最初の注文にする
+            let listing = cheapest.or_else(|| orders.first()).cloned();
+            let last_sale = self.last_sale.as_ref().and_then(|v| {
+                let amount = parse_units(&v.total_price, Unit::Wei).ok()?;
+                Some(Amount::new(amount, v.payment_token.as_ref()))
+            });
+
+            let current = listing.as_ref().or(last_sale.as_ref());
+
+            MarketPrice {
+                currency: current.map(|v| v.symbol.clone()).unwrap_or_default(),
+                listing_price: listing.as_ref().map(|v| v.decimal.clone()),
+                last_sale_price: last_sale.as_ref().map(|v| v.decimal.clone()),
+                usd_price: current
+                    .and_then(|v| v.usd_value())
+                    .unwrap_or_else(|| "0".to_string()),
+                eth_price: current.and_then(|v| v.eth_value()).unwrap_or_default(),
+            }
+        }
+    }
+
+    /// 換算レートの桁数。レートは10進数の文字列のまま、この桁数の整数にして掛ける
+    const RATE_DECIMALS: usize = 18;
+
+    #[derive(Clone)]
+    struct Amount {
+        symbol: String,
+        decimal: String,
+        /// 支払い通貨の最小単位の値
+        value: U256,
+        decimals: usize,
+        usd_rate: Option<U256>,
+        /// ETH換算のレート。わからなければNone
+        eth_rate: Option<U256>,
+    }
+
+    impl Amount {
+        // 通貨情報がなければETHとみなす
+        fn new(amount: U256, token: Option<&PaymentTokenContract>) -> Self {
+            let symbol = token
+                .and_then(|v| v.symbol.clone())
+                .unwrap_or_else(|| "ETH".to_string());
+            let rate = |v: &Option<String>| {
+                v.as_ref()
+                    .and_then(|v| parse_decimals(v, RATE_DECIMALS).ok())
+            };
+            let eth_rate = match token.and_then(|v| rate(&v.eth_price)) {
+                Some(rate) => Some(rate),
+                None if symbol == "ETH" || symbol == "WETH" => Some(U256::exp10(RATE_DECIMALS)),
+                None => None,
+            };
+            let decimals = token.and_then(|v| v.decimals).unwrap_or(18);
+            Self {
+                decimal: format_decimals(amount, decimals),
+                value: amount,
+                decimals,
+                usd_rate: token.and_then(|v| rate(&v.usd_price)),
+                eth_rate,
+                symbol,
+            }
+        }
+
+        /// レートを掛けて、RATE_DECIMALS桁の値にする。端数は切り捨てる
+        fn convert(&self, rate: U256) -> Option<U256> {
+            let unit = U256::from(10).checked_pow(U256::from(self.decimals))?;
+            Some(self.value.checked_mul(rate)? / unit)
+        }
+
+        fn eth_value(&self) -> Option<Wei> {
+            // weiの桁数とRATE_DECIMALSは同じ
+            self.convert(self.eth_rate?).map(Wei::new)
+        }
+
+        fn usd_value(&self) -> Option<String> {
+            Some(format_decimals(
+                self.convert(self.usd_rate?)?,
+                RATE_DECIMALS,
+            ))
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Order {
+        pub current_price: String,
+        pub payment_token_contract: Option<PaymentTokenContract>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Sale {
+        pub total_price: String,
+        pub payment_token: Option<PaymentTokenContract>,
+    }
+
+    /// 注文や売買の支払い通貨。価格は文字列で返ってくることがある
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct PaymentTokenContract {
+        #[serde(default)]
+        pub address: Option<String>,
+        pub symbol: Option<String>,
+        pub decimals: Option<usize>,
+        #[serde(default, deserialize_with = "number_or_string")]
+        pub eth_price: Option<String>,
+        #[serde(default, deserialize_with = "number_or_string")]
+        pub usd_price: Option<String>,
     }
 
     #[derive(Debug, Serialize, Deserialize)]
@@ -61,8 +348,10 @@ pub mod get_asset {
     #[derive(Debug, Serialize, Deserialize)]
     pub struct PaymentToken {
         pub symbol: Option<String>,
-        pub eth_price: Option<f64>,
-        pub usd_price: Option<f64>,
+        #[serde(default, deserialize_with = "number_or_string")]
+        pub eth_price: Option<String>,
+        #[serde(default, deserialize_with = "number_or_string")]
+        pub usd_price: Option<String>,
     }
 
     #[derive(Debug, Serialize, Deserialize)]
@@ -75,3 +364,335 @@ pub mod get_asset {
         pub address: String,
     }
 }
+
+pub mod get_listings {
+    use super::get_asset::PaymentTokenContract;
+    use super::*;
+
+    #[derive(Debug, Serialize)]
+    pub struct Input {
+        pub address: String,
+        pub token_id: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Output {
+        pub listings: Vec<Listing>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Listing {
+        pub order_hash: Option<String>,
+        pub current_price: String,
+        pub maker: Account,
+        pub quantity: Option<String>,
+        pub closing_date: Option<String>,
+        pub payment_token_contract: Option<PaymentTokenContract>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Account {
+        pub address: String,
+    }
+}
+
+pub mod get_offers {
+    use super::get_listings::Account;
+    use super::*;
+
+    #[derive(Debug, Serialize)]
+    pub struct Input {
+        pub address: String,
+        pub token_id: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Output {
+        pub orders: Vec<Order>,
+    }
+
+    /// current_priceは支払い通貨の最小単位での注文全体の価格
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Order {
+        pub order_hash: Option<String>,
+        pub current_price: String,
+        pub maker: Account,
+        pub remaining_quantity: Option<u64>,
+        pub expiration_time: Option<i64>,
+        pub protocol_data: ProtocolData,
+    }
+
+    impl Order {
+        /// 支払いに使うERC20のアドレス。ネイティブ通貨ならNone
+        pub fn payment_token(&self) -> Option<&str> {
+            self.protocol_data
+                .parameters
+                .offer
+                .first()
+                .filter(|v| v.item_type == ITEM_TYPE_ERC20)
+                .map(|v| v.token.as_str())
+        }
+    }
+
+    const ITEM_TYPE_ERC20: u8 = 1;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct ProtocolData {
+        pub parameters: Parameters,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Parameters {
+        pub offer: Vec<OfferItem>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct OfferItem {
+        pub item_type: u8,
+        pub token: String,
+    }
+}
+
+pub mod get_events {
+    use super::get_asset::PaymentTokenContract;
+    use super::get_listings::Account;
+    use super::*;
+
+    #[derive(Debug, Serialize)]
+    pub struct Input {
+        pub address: String,
+        pub token_id: String,
+        pub event_type: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Output {
+        pub asset_events: Vec<Event>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Event {
+        pub total_price: Option<String>,
+        pub quantity: Option<String>,
+        pub payment_token: Option<PaymentTokenContract>,
+        pub seller: Option<Account>,
+        pub winner_account: Option<Account>,
+        pub transaction: Option<Transaction>,
+        pub created_date: Option<String>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Transaction {
+        pub transaction_hash: String,
+    }
+}
+
+pub mod create_listing {
+    use super::*;
+
+    #[derive(Debug, Serialize)]
+    pub struct Input {
+        pub parameters: OrderComponents,
+        pub signature: String,
+        pub protocol_address: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Output {
+        pub order: Order,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Order {
+        pub order_hash: Option<String>,
+    }
+}
+
+pub mod get_fulfillment_data {
+    use super::*;
+
+    #[derive(Debug, Serialize)]
+    pub struct ListingInput {
+        pub listing: OrderRef,
+        pub fulfiller: Fulfiller,
+    }
+
+    /// 売るトークンを指定する
+    #[derive(Debug, Serialize)]
+    pub struct OfferInput {
+        pub offer: OrderRef,
+        pub fulfiller: Fulfiller,
+        pub consideration: Consideration,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct Consideration {
+        pub asset_contract_address: String,
+        pub token_id: String,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct OrderRef {
+        pub hash: String,
+        pub chain: String,
+        pub protocol_address: String,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct Fulfiller {
+        pub address: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Output {
+        pub fulfillment_data: FulfillmentData,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct FulfillmentData {
+        pub orders: Vec<SignedOrder>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct SignedOrder {
+        pub parameters: OrderComponents,
+        pub signature: String,
+    }
+
+    impl Output {
+        /// 1つの注文だけを約定する
+        pub fn into_order(self) -> AppResult<SignedOrder> {
+            self.fulfillment_data
+                .orders
+                .into_iter()
+                .next()
+                .ok_or_else(|| AppError::Internal("no order to fulfill".to_string()))
+        }
+    }
+}
+
+pub mod refresh_metadata {
+    use super::*;
+
+    #[derive(Debug, Serialize)]
+    pub struct Input {
+        pub address: String,
+        pub token_id: String,
+    }
+}
+
+/// 10進数の文字列にそろえる。f64のDisplayは指数表記にならないので、そのまま桁を読める
+fn number_or_string<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Number(f64),
+        String(String),
+    }
+
+    Ok(match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::Number(v)) => Some(v.to_string()),
+        Some(Value::String(v)) => Some(v),
+        None => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_asset::Output;
+    use serde_json::json;
+
+    fn asset(sell_orders: serde_json::Value) -> Output {
+        output(sell_orders, serde_json::Value::Null)
+    }
+
+    fn asset_with_sale(last_sale: serde_json::Value) -> super::MarketPrice {
+        output(json!([]), last_sale).market_price()
+    }
+
+    fn output(sell_orders: serde_json::Value, last_sale: serde_json::Value) -> Output {
+        serde_json::from_value(json!({
+            "id": 1,
+            "image_url": "",
+            "image_preview_url": "",
+            "name": "",
+            "description": "",
+            "permalink": "",
+            "collection": {"payment_tokens": []},
+            "top_ownerships": [],
+            "sell_orders": sell_orders,
+            "last_sale": last_sale,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn compares_listings_across_currencies_in_eth() {
+        // 1000 USDC (0.5 ETH) と 0.3 WETH。桁数のまま比べるとUSDCが安く見える
+        let price = asset(json!([
+            {
+                "current_price": "1000000000",
+                "payment_token_contract": {"symbol": "USDC", "decimals": 6, "eth_price": "0.0005", "usd_price": "1.0"},
+            },
+            {
+                "current_price": "300000000000000000",
+                "payment_token_contract": {"symbol": "WETH", "decimals": 18, "eth_price": 1.0, "usd_price": 1600.0},
+            },
+        ]))
+        .market_price();
+
+        assert_eq!(price.currency, "WETH");
+        assert_eq!(price.listing_price, Some("0.3".to_string()));
+        assert_eq!(price.eth_price.to_ether(), "0.3");
+        assert_eq!(price.usd_price, "480");
+    }
+
+    #[test]
+    fn converts_prices_without_floats() {
+        // 1 USDC = 0.000333333333333333 ETH。f64では端数が丸められる
+        let price = asset(json!([
+            {
+                "current_price": "3000000",
+                "payment_token_contract": {"symbol": "USDC", "decimals": 6, "eth_price": "0.000333333333333333", "usd_price": 1.0},
+            },
+        ]))
+        .market_price();
+
+        assert_eq!(price.listing_price, Some("3".to_string()));
+        assert_eq!(price.eth_price.to_ether(), "0.000999999999999999");
+        assert_eq!(price.usd_price, "3");
+    }
+
+    #[test]
+    fn uses_last_sale_without_listings() {
+        let price = asset_with_sale(json!({
+            "total_price": "1500000000000000000",
+            "payment_token": {"symbol": "ETH", "decimals": 18, "usd_price": "1600.5"},
+        }));
+
+        assert_eq!(price.currency, "ETH");
+        assert_eq!(price.listing_price, None);
+        assert_eq!(price.last_sale_price, Some("1.5".to_string()));
+        assert_eq!(price.eth_price.to_ether(), "1.5");
+        assert_eq!(price.usd_price, "2400.75");
+    }
+
+    #[test]
+    fn falls_back_to_first_listing_without_rates() {
+        let price = asset(json!([
+            {
+                "current_price": "5000000",
+                "payment_token_contract": {"symbol": "USDC", "decimals": 6},
+            },
+        ]))
+        .market_price();
+
+        assert_eq!(price.currency, "USDC");
+        assert_eq!(price.listing_price, Some("5".to_string()));
+        assert!(price.eth_price.is_zero());
+        assert_eq!(price.usd_price, "0");
+    }
+}
@@ -0,0 +1,114 @@
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+struct State {
+    tokens: f64,
+    refilled_at: Instant,
+    paused_until: Option<Instant>,
+}
+
+/// 非同期のトークンバケット。待つ間はexecutorをブロックしない
+pub struct TokenBucket {
+    capacity: f64,
+    per_second: f64,
+    state: Mutex<State>,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, per_second: f64) -> Self {
+        Self {
+            capacity: capacity.max(1) as f64,
+            per_second: per_second.max(0.01),
+            state: Mutex::new(State {
+                tokens: capacity.max(1) as f64,
+                refilled_at: Instant::now(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    /// トークンを1つ取得できるまで待つ
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().await;
+                let now = Instant::now();
+
+                match state.paused_until {
+                    Some(until) if until > now => until - now,
+                    _ => {
+                        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+                        state.tokens =
+                            (state.tokens + elapsed * self.per_second).min(self.capacity);
+                        state.refilled_at = now;
+
+                        if state.tokens >= 1.0 {
+                            state.tokens -= 1.0;
+                            return;
+                        }
+                        Duration::from_secs_f64((1.0 - state.tokens) / self.per_second)
+                    }
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Retry-Afterなどで指定された間、全てのリクエストを止める
+    pub async fn pause(&self, duration: Duration) {
+        let mut state = self.state.lock().await;
+        let until = Instant::now() + duration;
+        if state.paused_until < Some(until) {
+            state.paused_until = Some(until);
+            state.refilled_at = until;
+        }
+        state.tokens = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn elapsed_of(bucket: &TokenBucket) -> Duration {
+        let started = Instant::now();
+        bucket.acquire().await;
+        started.elapsed()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_for_refill_after_burst() {
+        let bucket = TokenBucket::new(2, 2.0);
+
+        assert_eq!(elapsed_of(&bucket).await, Duration::ZERO);
+        assert_eq!(elapsed_of(&bucket).await, Duration::ZERO);
+        assert_eq!(elapsed_of(&bucket).await, Duration::from_millis(500));
+        assert_eq!(elapsed_of(&bucket).await, Duration::from_millis(500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refills_up_to_capacity() {
+        let bucket = TokenBucket::new(2, 1.0);
+        bucket.acquire().await;
+        bucket.acquire().await;
+
+        // 長く空いても貯まるのはcapacityまで
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(elapsed_of(&bucket).await, Duration::ZERO);
+        assert_eq!(elapsed_of(&bucket).await, Duration::ZERO);
+        assert_eq!(elapsed_of(&bucket).await, Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pauses_all_requests() {
+        let bucket = TokenBucket::new(5, 1.0);
+        bucket.pause(Duration::from_secs(3)).await;
+        // 短いpauseで先のpauseを縮めない
+        bucket.pause(Duration::from_secs(1)).await;
+
+        assert_eq!(elapsed_of(&bucket).await, Duration::from_secs(4));
+        // 止めている間の分は貯まらない
+        assert_eq!(elapsed_of(&bucket).await, Duration::from_secs(1));
+    }
+}
@@ -1,20 +1,116 @@
+use crate::domain::reveal::Placeholder;
+use crate::domain::royalty::Royalty;
+use crate::domain::template::RenderedMetadata;
+use crate::domain::work::{Work, WorkAttribute};
+use crate::AppResult;
 use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
 
+/// OpenSeaのmetadata standardの形式
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Metadata {
     pub description: String,
     pub external_url: String,
     pub name: String,
     pub image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animation_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Attribute>,
+    /// OpenSeaのコントラクトレベルのロイヤリティ表記に合わせる
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seller_fee_basis_points: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_recipient: Option<String>,
 }
 
 impl Metadata {
-    pub fn new(work_id: String, description: String, image_url: String) -> Self {
+    /// 名前・説明・URLはコレクションのテンプレートを展開したものを使う
+    pub fn new(
+        work: &Work,
+        rendered: RenderedMetadata,
+        image_url: String,
+        animation_url: Option<String>,
+        royalty: Option<&Royalty>,
+    ) -> Self {
         Self {
-            description,
-            external_url: format!("https://canvas-329810.web.app/{}", work_id.clone()),
-            name: work_id.clone(),
+            description: rendered.description,
+            external_url: rendered.external_url,
+            name: rendered.name,
             image: image_url,
+            animation_url,
+            background_color: work.background_color.clone(),
+            attributes: work
+                .attributes
+                .iter()
+                .chain(rendered.attributes.iter())
+                .map(Attribute::from)
+                .collect(),
+            seller_fee_basis_points: royalty.map(|v| v.basis_points),
+            fee_recipient: royalty.map(|v| v.receiver.clone()),
+        }
+    }
+}
+
+impl Metadata {
+    /// 公開前のトークンに返す、作品の内容を含まないもの
+    pub fn placeholder(placeholder: &Placeholder) -> Self {
+        Self {
+            description: placeholder.description.clone(),
+            external_url: "".to_string(),
+            name: placeholder.name.clone(),
+            image: placeholder.image_url.clone(),
+            animation_url: None,
+            background_color: None,
+            attributes: placeholder.attributes.iter().map(Attribute::from).collect(),
+            seller_fee_basis_points: None,
+            fee_recipient: None,
+        }
+    }
+
+    pub fn to_json(&self) -> AppResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Attribute {
+    pub trait_type: String,
+    pub value: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_type: Option<String>,
+}
+
+impl From<&WorkAttribute> for Attribute {
+    /// display_typeがある場合は数値として扱われるので、数値にできれば数値で出す
+    fn from(data: &WorkAttribute) -> Self {
+        let value = match (
+            &data.display_type,
+            data.value.parse::<i64>(),
+            data.value.parse::<f64>().ok().and_then(Number::from_f64),
+        ) {
+            (None, _, _) => Value::String(data.value.clone()),
+            (Some(_), Ok(v), _) => Value::from(v),
+            (Some(_), _, Some(v)) => Value::Number(v),
+            _ => Value::String(data.value.clone()),
+        };
+
+        Self {
+            trait_type: data.trait_type.clone(),
+            value,
+            display_type: data.display_type.clone(),
+        }
+    }
+}
+
+impl Attribute {
+    /// 数値もそのまま文字列にする
+    pub fn value_string(&self) -> String {
+        match &self.value {
+            Value::String(v) => v.clone(),
+            v => v.to_string(),
         }
     }
 }
@@ -1,17 +1,28 @@
 use app::aws::*;
+use app::domain::market::TokenSchema;
 use app::{application, AppError, AppResult};
 use lambda_runtime::{handler_fn, Context, Error};
 use serde_json::Value;
+use std::env;
+use std::str::FromStr;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     ssm::load_env().await;
+    app::ethereum::validate_chain().await?;
+
+    // 引数にイベントのJSONを渡した場合はローカルで1回だけ実行する
+    if let Some(payload) = env::args().nth(1) {
+        let event: Value = serde_json::from_str(&payload)?;
+        return exec(event, Context::default()).await;
+    }
+
     lambda_runtime::run(handler_fn(exec)).await?;
     Ok(())
 }
 
 async fn exec(event: Value, _: Context) -> Result<(), Error> {
-    let command = get_command_from_batch_event(event)
+    let command = get_command_from_batch_event(&event)
         .map_err(|e| simple_error::SimpleError::new(format!("error: {:?}", e)))?;
     println!("exec {:?}", command);
 
@@ -27,26 +38,99 @@ async fn exec(event: Value, _: Context) -> Result<(), Error> {
     }
 
     if command == "sync-nft-asset" {
-        let admin_nft_app = application::nft::Application::new("batch".to_string()).await;
+        let admin_nft_app = application::nft::Application::new("batch".to_string())
+            .await
+            .map_err(|e| simple_error::SimpleError::new(format!("error: {:?}", e)))?;
         admin_nft_app.sync_asset().await.map_err(|e| {
             println!("sync-nft-asset error: {:?}", e.clone());
             simple_error::SimpleError::new(format!("error: {:?}", e))
         })?;
     }
 
+    if command == "sync-pin-status" {
+        let admin_nft_app = application::nft::Application::new("batch".to_string())
+            .await
+            .map_err(|e| simple_error::SimpleError::new(format!("error: {:?}", e)))?;
+        admin_nft_app.sync_pin_status().await.map_err(|e| {
+            println!("sync-pin-status error: {:?}", e.clone());
+            simple_error::SimpleError::new(format!("error: {:?}", e))
+        })?;
+    }
+
+    if command == "reveal-collection" {
+        let schema = get_param_from_batch_event(&event, "schema")
+            .map_err(|e| simple_error::SimpleError::new(format!("error: {:?}", e)))?;
+        let schema = TokenSchema::from_str(&schema)
+            .map_err(|e| simple_error::SimpleError::new(format!("error: {:?}", e)))?;
+
+        let admin_nft_app = application::nft::Application::new("batch".to_string())
+            .await
+            .map_err(|e| simple_error::SimpleError::new(format!("error: {:?}", e)))?;
+        admin_nft_app.reveal(schema).await.map_err(|e| {
+            println!("reveal-collection error: {:?}", e.clone());
+            simple_error::SimpleError::new(format!("error: {:?}", e))
+        })?;
+    }
+
+    if command == "check-metadata" {
+        let admin_nft_app = application::nft::Application::new("batch".to_string())
+            .await
+            .map_err(|e| simple_error::SimpleError::new(format!("error: {:?}", e)))?;
+        admin_nft_app.check_metadata().await.map_err(|e| {
+            println!("check-metadata error: {:?}", e.clone());
+            simple_error::SimpleError::new(format!("error: {:?}", e))
+        })?;
+    }
+
+    if command == "export-sales" {
+        let month = event
+            .get("month")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string());
+
+        let sales_app = application::sales::Application::new("batch".to_string())
+            .await
+            .map_err(|e| simple_error::SimpleError::new(format!("error: {:?}", e)))?;
+        sales_app.export_csv(month).await.map_err(|e| {
+            println!("export-sales error: {:?}", e.clone());
+            simple_error::SimpleError::new(format!("error: {:?}", e))
+        })?;
+    }
+
+    if command == "deploy-contract" {
+        let contract = get_param_from_batch_event(&event, "contract")
+            .map_err(|e| simple_error::SimpleError::new(format!("error: {:?}", e)))?;
+        let signer_id = get_param_from_batch_event(&event, "signer_id")
+            .map_err(|e| simple_error::SimpleError::new(format!("error: {:?}", e)))?;
+        let force = matches!(event.get("force"), Some(Value::Bool(true)));
+
+        let contract_app = application::contract::Application::new("batch".to_string()).await;
+        contract_app
+            .deploy(contract, signer_id, force)
+            .await
+            .map_err(|e| {
+                println!("deploy-contract error: {:?}", e.clone());
+                simple_error::SimpleError::new(format!("error: {:?}", e))
+            })?;
+    }
+
     Ok(())
 }
 
-fn get_command_from_batch_event(event: Value) -> AppResult<String> {
+fn get_command_from_batch_event(event: &Value) -> AppResult<String> {
+    get_param_from_batch_event(event, "command")
+}
+
+fn get_param_from_batch_event(event: &Value, key: &str) -> AppResult<String> {
     match event {
         Value::Object(data) => {
-            let command = data.get("command");
-            if command.is_none() {
-                return Err(AppError::BadRequest(format!("command nil: {:?}", data)));
+            let param = data.get(key);
+            if param.is_none() {
+                return Err(AppError::BadRequest(format!("{} nil: {:?}", key, data)));
             }
-            match command.unwrap() {
+            match param.unwrap() {
                 Value::String(val) => Ok(val.to_string().clone()),
-                _ => Err(AppError::BadRequest(format!("command empty: {:?}", data))),
+                _ => Err(AppError::BadRequest(format!("{} empty: {:?}", key, data))),
             }
         }
         _ => Err(AppError::BadRequest(format!("parse error: {:?}", event))),
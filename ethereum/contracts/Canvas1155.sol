@@ -2,13 +2,18 @@ pragma solidity ^0.8.0;
 
 import "@openzeppelin/contracts/utils/Context.sol";
 import "@openzeppelin/contracts/token/ERC1155/ERC1155.sol";
+import "@openzeppelin/contracts/token/common/ERC2981.sol";
 import "@openzeppelin/contracts/access/Ownable.sol";
 
-contract Canvas1155 is Context, ERC1155, Ownable {
+contract Canvas1155 is Context, ERC1155, ERC2981, Ownable {
     mapping(string => uint256) private _name2token;
     mapping(uint256 => string) private _token2name;
     mapping(uint256 => string) private _token2ipfsHash;
     mapping(uint256 => string) private _token2s3Key;
+    string private _metadataBaseURI;
+    string public provenanceHash;
+
+    event ProvenanceHashSet(string hash);
 
     uint256 _localTokenId = 1;
 
@@ -22,7 +27,9 @@ contract Canvas1155 is Context, ERC1155, Ownable {
         string memory workId,
         uint256 amount,
         string memory ipfsHash,
-        string memory s3Key
+        string memory s3Key,
+        address royaltyReceiver,
+        uint96 royaltyBasisPoints
     ) public virtual onlyOwner {
         require(_name2token[workId] == 0, "already mint");
 
@@ -35,9 +42,58 @@ contract Canvas1155 is Context, ERC1155, Ownable {
 
         _mint(to, tokenId, amount, "");
 
+        if (royaltyBasisPoints > 0) {
+            _setTokenRoyalty(tokenId, royaltyReceiver, royaltyBasisPoints);
+        }
+
         _localTokenId += 1;
     }
 
+    function setDefaultRoyalty(address receiver, uint96 basisPoints)
+        public
+        virtual
+        onlyOwner
+    {
+        _setDefaultRoyalty(receiver, basisPoints);
+    }
+
+    function setTokenRoyalty(
+        string memory workId,
+        address receiver,
+        uint96 basisPoints
+    ) public virtual onlyOwner {
+        uint256 tokenId = _name2token[workId];
+        require(tokenId != 0, "unknown token");
+
+        _setTokenRoyalty(tokenId, receiver, basisPoints);
+    }
+
+    function setMetadataBaseURI(string memory baseURI)
+        public
+        virtual
+        onlyOwner
+    {
+        _metadataBaseURI = baseURI;
+    }
+
+    function setProvenanceHash(string memory hash) public virtual onlyOwner {
+        require(isEmptyString(provenanceHash), "provenance already set");
+        require(!isEmptyString(hash), "empty provenance");
+
+        provenanceHash = hash;
+        emit ProvenanceHashSet(hash);
+    }
+
+    function supportsInterface(bytes4 interfaceId)
+        public
+        view
+        virtual
+        override(ERC1155, ERC2981)
+        returns (bool)
+    {
+        return super.supportsInterface(interfaceId);
+    }
+
     function uri(uint256 tokenId)
         public
         view
@@ -45,6 +101,11 @@ contract Canvas1155 is Context, ERC1155, Ownable {
         override
         returns (string memory)
     {
+        if (!isEmptyString(_metadataBaseURI)) {
+            require(!isEmptyString(_token2name[tokenId]), "unknown token");
+            return _metadataBaseURI;
+        }
+
         string memory ipfsHash = _token2ipfsHash[tokenId];
         string memory s3Key = _token2s3Key[tokenId];
 
@@ -52,7 +113,7 @@ contract Canvas1155 is Context, ERC1155, Ownable {
             return
                 string(
                     abi.encodePacked(
-                        "https://ipfs.moralis.io:2053/ipfs/",
+                        "ipfs://",
                         ipfsHash
                     )
                 );
@@ -1,14 +1,20 @@
 pragma solidity ^0.8.0;
 
 import "@openzeppelin/contracts/utils/Context.sol";
+import "@openzeppelin/contracts/utils/Strings.sol";
 import "@openzeppelin/contracts/token/ERC721/extensions/ERC721Enumerable.sol";
+import "@openzeppelin/contracts/token/common/ERC2981.sol";
 import "@openzeppelin/contracts/access/Ownable.sol";
 
-contract Canvas721 is Context, ERC721Enumerable, Ownable {
+contract Canvas721 is Context, ERC721Enumerable, ERC2981, Ownable {
     mapping(string => uint256) private _name2token;
     mapping(uint256 => string) private _token2name;
     mapping(uint256 => string) private _token2ipfsHash;
     mapping(uint256 => string) private _token2s3Key;
+    string private _metadataBaseURI;
+    string public provenanceHash;
+
+    event ProvenanceHashSet(string hash);
 
     uint256 _localTokenId = 1;
 
@@ -18,7 +24,9 @@ contract Canvas721 is Context, ERC721Enumerable, Ownable {
         address to,
         string memory workId,
         string memory ipfsHash,
-        string memory s3Key
+        string memory s3Key,
+        address royaltyReceiver,
+        uint96 royaltyBasisPoints
     ) public virtual onlyOwner {
         require(_name2token[workId] == 0, "already mint");
 
@@ -31,9 +39,58 @@ contract Canvas721 is Context, ERC721Enumerable, Ownable {
 
         _mint(to, tokenId);
 
+        if (royaltyBasisPoints > 0) {
+            _setTokenRoyalty(tokenId, royaltyReceiver, royaltyBasisPoints);
+        }
+
         _localTokenId += 1;
     }
 
+    function setDefaultRoyalty(address receiver, uint96 basisPoints)
+        public
+        virtual
+        onlyOwner
+    {
+        _setDefaultRoyalty(receiver, basisPoints);
+    }
+
+    function setTokenRoyalty(
+        string memory workId,
+        address receiver,
+        uint96 basisPoints
+    ) public virtual onlyOwner {
+        uint256 tokenId = _name2token[workId];
+        require(tokenId != 0, "unknown token");
+
+        _setTokenRoyalty(tokenId, receiver, basisPoints);
+    }
+
+    function setMetadataBaseURI(string memory baseURI)
+        public
+        virtual
+        onlyOwner
+    {
+        _metadataBaseURI = baseURI;
+    }
+
+    function setProvenanceHash(string memory hash) public virtual onlyOwner {
+        require(isEmptyString(provenanceHash), "provenance already set");
+        require(!isEmptyString(hash), "empty provenance");
+
+        provenanceHash = hash;
+        emit ProvenanceHashSet(hash);
+    }
+
+    function supportsInterface(bytes4 interfaceId)
+        public
+        view
+        virtual
+        override(ERC721Enumerable, ERC2981)
+        returns (bool)
+    {
+        return super.supportsInterface(interfaceId);
+    }
+
     function tokenURI(uint256 tokenId)
         public
         view
@@ -41,6 +98,14 @@ contract Canvas721 is Context, ERC721Enumerable, Ownable {
         override
         returns (string memory)
     {
+        if (!isEmptyString(_metadataBaseURI)) {
+            require(_exists(tokenId), "unknown token");
+            return
+                string(
+                    abi.encodePacked(_metadataBaseURI, Strings.toString(tokenId))
+                );
+        }
+
         string memory ipfsHash = _token2ipfsHash[tokenId];
         string memory s3Key = _token2s3Key[tokenId];
 
@@ -48,7 +113,7 @@ contract Canvas721 is Context, ERC721Enumerable, Ownable {
             return
                 string(
                     abi.encodePacked(
-                        "https://ipfs.moralis.io:2053/ipfs/",
+                        "ipfs://",
                         ipfsHash
                     )
                 );
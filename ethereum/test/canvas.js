@@ -5,7 +5,14 @@ contract("Canvas721", (accounts) => {
   it("canvas methods test", async () => {
     const contract = await Canvas721.deployed();
 
-    await contract.mint(accounts[0], "A", "", "721_asset/A.metadata.json");
+    await contract.mint(
+      accounts[0],
+      "A",
+      "",
+      "721_asset/A.metadata.json",
+      accounts[2],
+      500
+    );
     const tokenURI1 = await contract.tokenURI(1);
     assert.equal(
       tokenURI1,
@@ -17,22 +24,35 @@ contract("Canvas721", (accounts) => {
       accounts[0],
       "B",
       "Qme4nCCQgRSeiprzEAmKuVxEjmmAfHhwhWJw4xe1pG7mhD",
-      ""
+      "",
+      "0x0000000000000000000000000000000000000000",
+      0
     );
     const tokenURI2 = await contract.tokenURI(2);
     assert.equal(
       tokenURI2,
-      "https://ipfs.moralis.io:2053/ipfs/Qme4nCCQgRSeiprzEAmKuVxEjmmAfHhwhWJw4xe1pG7mhD",
+      "ipfs://Qme4nCCQgRSeiprzEAmKuVxEjmmAfHhwhWJw4xe1pG7mhD",
       "error"
     );
 
     try {
-      await contract.mint(accounts[1], "B", "", "");
+      await contract.mint(accounts[1], "B", "", "", "0x0000000000000000000000000000000000000000", 0);
       throw new Error();
     } catch (error) {
       assert.equal(error.reason, "already mint", "error");
     }
 
+    await contract.setMetadataBaseURI(
+      "https://example.com/default/metadata/" + contract.address + "/"
+    );
+    const tokenURI3 = await contract.tokenURI(2);
+    assert.equal(
+      tokenURI3,
+      "https://example.com/default/metadata/" + contract.address + "/2",
+      "error"
+    );
+    await contract.setMetadataBaseURI("");
+
     const address1 = await contract.ownerAddressOf("A");
     assert.equal(address1, accounts[0], "error");
 
@@ -65,6 +85,40 @@ contract("Canvas721", (accounts) => {
     assert.equal(names[0], "A", "error");
     assert.equal(names[1], "B", "error");
     assert.equal(names.length, 2, "error");
+
+    const royalty1 = await contract.royaltyInfo(1, 10000);
+    assert.equal(royalty1[0], accounts[2], "error");
+    assert.equal(royalty1[1], 500, "error");
+
+    const royalty2 = await contract.royaltyInfo(2, 10000);
+    assert.equal(royalty2[1], 0, "error");
+
+    await contract.setDefaultRoyalty(accounts[3], 250);
+    const royalty3 = await contract.royaltyInfo(2, 10000);
+    assert.equal(royalty3[0], accounts[3], "error");
+    assert.equal(royalty3[1], 250, "error");
+
+    await contract.setTokenRoyalty("B", accounts[2], 1000);
+    const royalty4 = await contract.royaltyInfo(2, 10000);
+    assert.equal(royalty4[0], accounts[2], "error");
+    assert.equal(royalty4[1], 1000, "error");
+
+    const erc2981 = await contract.supportsInterface("0x2a55205a");
+    assert.equal(erc2981, true, "error");
+
+    const provenance1 = await contract.provenanceHash();
+    assert.equal(provenance1, "", "error");
+
+    await contract.setProvenanceHash("abc123");
+    const provenance2 = await contract.provenanceHash();
+    assert.equal(provenance2, "abc123", "error");
+
+    try {
+      await contract.setProvenanceHash("def456");
+      throw new Error();
+    } catch (error) {
+      assert.equal(error.reason, "provenance already set", "error");
+    }
   });
 });
 
@@ -72,7 +126,15 @@ contract("Canvas1155", (accounts) => {
   it("canvas methods test", async () => {
     const contract = await Canvas1155.deployed();
 
-    await contract.mint(accounts[0], "A", 10, "", "1155_asset/A.metadata.json");
+    await contract.mint(
+      accounts[0],
+      "A",
+      10,
+      "",
+      "1155_asset/A.metadata.json",
+      accounts[2],
+      500
+    );
     const tokenURI1 = await contract.uri(1);
     assert.equal(
       tokenURI1,
@@ -85,22 +147,35 @@ contract("Canvas1155", (accounts) => {
       "B",
       10,
       "Qme4nCCQgRSeiprzEAmKuVxEjmmAfHhwhWJw4xe1pG7mhD",
-      ""
+      "",
+      "0x0000000000000000000000000000000000000000",
+      0
     );
     const tokenURI2 = await contract.uri(2);
     assert.equal(
       tokenURI2,
-      "https://ipfs.moralis.io:2053/ipfs/Qme4nCCQgRSeiprzEAmKuVxEjmmAfHhwhWJw4xe1pG7mhD",
+      "ipfs://Qme4nCCQgRSeiprzEAmKuVxEjmmAfHhwhWJw4xe1pG7mhD",
       "error"
     );
 
     try {
-      await contract.mint(accounts[1], "B", 10, "", "");
+      await contract.mint(accounts[1], "B", 10, "", "", "0x0000000000000000000000000000000000000000", 0);
       throw new Error();
     } catch (error) {
       assert.equal(error.reason, "already mint", "error");
     }
 
+    await contract.setMetadataBaseURI(
+      "https://example.com/default/metadata/1155/{id}.json"
+    );
+    const baseTokenURI = await contract.uri(2);
+    assert.equal(
+      baseTokenURI,
+      "https://example.com/default/metadata/1155/{id}.json",
+      "error"
+    );
+    await contract.setMetadataBaseURI("");
+
     const tokenId1 = await contract.tokenIdOf("A");
     assert.equal(tokenId1, 1, "error");
 
@@ -114,5 +189,31 @@ contract("Canvas1155", (accounts) => {
     assert.equal(names[0], "A", "error");
     assert.equal(names[1], "B", "error");
     assert.equal(names.length, 2, "error");
+
+    const royalty1 = await contract.royaltyInfo(1, 10000);
+    assert.equal(royalty1[0], accounts[2], "error");
+    assert.equal(royalty1[1], 500, "error");
+
+    await contract.setTokenRoyalty("B", accounts[2], 1000);
+    const royalty2 = await contract.royaltyInfo(2, 10000);
+    assert.equal(royalty2[0], accounts[2], "error");
+    assert.equal(royalty2[1], 1000, "error");
+
+    const erc2981 = await contract.supportsInterface("0x2a55205a");
+    assert.equal(erc2981, true, "error");
+
+    const provenance1 = await contract.provenanceHash();
+    assert.equal(provenance1, "", "error");
+
+    await contract.setProvenanceHash("abc123");
+    const provenance2 = await contract.provenanceHash();
+    assert.equal(provenance2, "abc123", "error");
+
+    try {
+      await contract.setProvenanceHash("def456");
+      throw new Error();
+    } catch (error) {
+      assert.equal(error.reason, "provenance already set", "error");
+    }
   });
 });
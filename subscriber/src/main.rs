@@ -6,6 +6,7 @@ use serde_json::Value;
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     ssm::load_env().await;
+    app::ethereum::validate_chain().await?;
     lambda_runtime::run(handler_fn(exec)).await?;
     Ok(())
 }
@@ -98,9 +99,15 @@ async fn exec(event: Value, _: Context) -> Result<(), Error> {
 
             let email = cognito::get_email(data.executor_id.clone()).await?;
 
-            let admin_nft_app = application::nft::Application::new(data.executor_id.clone()).await;
+            let admin_nft_app =
+                application::nft::Application::new(data.executor_id.clone()).await?;
             let res = admin_nft_app
-                .mint_erc721(data.work_id.clone(), data.ipfs_hash, data.s3_key)
+                .mint_erc721(
+                    data.chain_id,
+                    data.work_id.clone(),
+                    data.ipfs_hash,
+                    data.s3_key,
+                )
                 .await
                 .map_err(|e| simple_error::SimpleError::new(format!("error: {:?}", e)));
 
@@ -134,9 +141,11 @@ async fn exec(event: Value, _: Context) -> Result<(), Error> {
 
             let email = cognito::get_email(data.executor_id.clone()).await?;
 
-            let admin_nft_app = application::nft::Application::new(data.executor_id.clone()).await;
+            let admin_nft_app =
+                application::nft::Application::new(data.executor_id.clone()).await?;
             let res = admin_nft_app
                 .mint_erc1155(
+                    data.chain_id,
                     data.work_id.clone(),
                     data.amount.clone(),
                     data.ipfs_hash,